@@ -0,0 +1,364 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::collections::VecDeque;
+
+use crate::color::Color;
+use crate::dimen::Vec2;
+use crate::error::{BacktraceError, ErrorMessage};
+use crate::font::{Font, TextLayout, TextOptions};
+use crate::shape::Rectangle;
+use crate::time::Stopwatch;
+use crate::Graphics2D;
+
+/// The corner of the window in which a [DebugOverlay] is drawn.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum DebugOverlayCorner
+{
+    /// The top left corner. This is the default.
+    #[default]
+    TopLeft,
+
+    /// The top right corner.
+    TopRight,
+
+    /// The bottom left corner.
+    BottomLeft,
+
+    /// The bottom right corner.
+    BottomRight
+}
+
+/// Draws the frame rate, a graph of recent frame times, and the renderer's
+/// [crate::FrameStatistics] in a corner of the window.
+///
+/// When using a [crate::window::WindowHandler], the overlay can be enabled
+/// using [crate::window::WindowHelper::set_debug_overlay], and it will be
+/// drawn on top of each frame automatically. Otherwise, call
+/// [DebugOverlay::draw] at the end of each frame.
+///
+/// The frame time is measured between consecutive calls to `draw()`, so the
+/// window must be redrawn continuously for the values to be meaningful.
+///
+/// ```rust,no_run
+/// # use speedy2d::debug_overlay::{DebugOverlay, DebugOverlayCorner};
+/// # use speedy2d::font::Font;
+/// # use speedy2d::window::{WindowHandler, WindowHelper, WindowStartupInfo};
+/// struct MyHandler
+/// {
+///     font: Font
+/// }
+///
+/// impl WindowHandler for MyHandler
+/// {
+///     fn on_start(&mut self, helper: &mut WindowHelper, info: WindowStartupInfo)
+///     {
+///         let overlay = DebugOverlay::new(&self.font)
+///             .unwrap()
+///             .with_corner(DebugOverlayCorner::TopRight);
+///
+///         helper.set_debug_overlay(Some(overlay));
+///     }
+/// }
+/// ```
+pub struct DebugOverlay
+{
+    font: Font,
+    corner: DebugOverlayCorner,
+    stopwatch: Stopwatch,
+    last_draw_secs: Option<f64>,
+    frame_times_secs: VecDeque<f64>
+}
+
+impl DebugOverlay
+{
+    /// The number of frames shown in the graph.
+    const HISTORY_LEN: usize = 100;
+
+    const MARGIN: f32 = 8.0;
+    const PADDING: f32 = 6.0;
+    const FONT_SIZE: f32 = 14.0;
+    const BAR_WIDTH: f32 = 2.0;
+    const GRAPH_HEIGHT: f32 = 50.0;
+
+    /// The frame time shown at the top of the graph.
+    const GRAPH_MAX_SECS: f64 = 2.0 / 60.0;
+
+    /// Creates a new overlay, which draws text using the specified font.
+    pub fn new(font: &Font) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        Ok(DebugOverlay {
+            font: font.clone(),
+            corner: DebugOverlayCorner::TopLeft,
+            stopwatch: Stopwatch::new()?,
+            last_draw_secs: None,
+            frame_times_secs: VecDeque::with_capacity(Self::HISTORY_LEN)
+        })
+    }
+
+    /// Sets the corner of the window in which the overlay is drawn. The
+    /// default is [DebugOverlayCorner::TopLeft].
+    #[inline]
+    #[must_use]
+    pub fn with_corner(mut self, corner: DebugOverlayCorner) -> Self
+    {
+        self.corner = corner;
+        self
+    }
+
+    /// Returns the average time between recent frames, in seconds, or `None`
+    /// if fewer than two frames have been drawn.
+    pub fn average_frame_time_secs(&self) -> Option<f64>
+    {
+        if self.frame_times_secs.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.frame_times_secs.iter().sum::<f64>()
+                / self.frame_times_secs.len() as f64
+        )
+    }
+
+    /// Returns the average number of frames per second over recent frames, or
+    /// `None` if fewer than two frames have been drawn.
+    pub fn fps(&self) -> Option<f64>
+    {
+        self.average_frame_time_secs()
+            .filter(|secs| *secs > 0.0)
+            .map(|secs| 1.0 / secs)
+    }
+
+    /// Records the time since the previous call, and draws the overlay. This
+    /// should be called after everything else in the frame has been drawn.
+    ///
+    /// The overlay is always drawn in pixels, using the default blend mode
+    /// and shader, and isn't affected by any transforms, clip area, or
+    /// virtual resolution set by the caller. That state is restored
+    /// afterwards.
+    pub fn draw(&mut self, graphics: &mut Graphics2D)
+    {
+        self.record_frame(self.stopwatch.secs_elapsed());
+
+        let saved = graphics.renderer.reset_drawing_state();
+        graphics.with_layer(i32::MAX, |graphics| self.draw_contents(graphics));
+        graphics.renderer.restore_drawing_state(saved);
+    }
+
+    fn record_frame(&mut self, now_secs: f64)
+    {
+        if let Some(last_draw_secs) = self.last_draw_secs {
+            if self.frame_times_secs.len() == Self::HISTORY_LEN {
+                self.frame_times_secs.pop_front();
+            }
+
+            self.frame_times_secs.push_back(now_secs - last_draw_secs);
+        }
+
+        self.last_draw_secs = Some(now_secs);
+    }
+
+    fn draw_contents(&self, graphics: &mut Graphics2D)
+    {
+        let statistics = graphics.last_frame_statistics();
+
+        let summary = match (self.fps(), self.average_frame_time_secs()) {
+            (Some(fps), Some(secs)) => {
+                format!("{:.1} FPS ({:.2} ms)", fps, secs * 1000.0)
+            }
+            _ => "-- FPS".to_string()
+        };
+
+        let text = format!(
+            "{}\nDraw calls: {}, texture binds: {}\nVertices: {}\nGlyphs: {} hits, {} \
+             misses\nFlush: {:.2} ms",
+            summary,
+            statistics.draw_calls(),
+            statistics.texture_binds(),
+            statistics.vertices_uploaded(),
+            statistics.glyph_cache_hits(),
+            statistics.glyph_cache_misses(),
+            statistics.flush_time_secs() * 1000.0
+        );
+
+        let text = self
+            .font
+            .layout_text(&text, Self::FONT_SIZE, TextOptions::new());
+
+        let graph_width = Self::HISTORY_LEN as f32 * Self::BAR_WIDTH;
+
+        let size = Vec2::new(
+            graph_width.max(text.width()) + Self::PADDING * 2.0,
+            text.height() + Self::GRAPH_HEIGHT + Self::PADDING * 3.0
+        );
+
+        let area = graphics.renderer.drawable_size();
+
+        let left = match self.corner {
+            DebugOverlayCorner::TopLeft | DebugOverlayCorner::BottomLeft => Self::MARGIN,
+            DebugOverlayCorner::TopRight | DebugOverlayCorner::BottomRight => {
+                area.x - Self::MARGIN - size.x
+            }
+        };
+
+        let top = match self.corner {
+            DebugOverlayCorner::TopLeft | DebugOverlayCorner::TopRight => Self::MARGIN,
+            DebugOverlayCorner::BottomLeft | DebugOverlayCorner::BottomRight => {
+                area.y - Self::MARGIN - size.y
+            }
+        };
+
+        let top_left = Vec2::new(left, top);
+
+        graphics.draw_rectangle(
+            Rectangle::new(top_left, top_left + size),
+            Color::from_rgba(0.0, 0.0, 0.0, 0.7)
+        );
+
+        let text_position = top_left + Vec2::new(Self::PADDING, Self::PADDING);
+        graphics.draw_text(text_position, Color::WHITE, &text);
+
+        let graph_bottom_left = Vec2::new(
+            top_left.x + Self::PADDING,
+            top_left.y + size.y - Self::PADDING
+        );
+
+        let bar_height = |secs: f64| {
+            (secs / Self::GRAPH_MAX_SECS).min(1.0) as f32 * Self::GRAPH_HEIGHT
+        };
+
+        for (i, secs) in self.frame_times_secs.iter().enumerate() {
+            let color = if *secs <= 1.05 / 60.0 {
+                Color::GREEN
+            } else if *secs <= 1.05 / 30.0 {
+                Color::YELLOW
+            } else {
+                Color::RED
+            };
+
+            let left = graph_bottom_left.x + i as f32 * Self::BAR_WIDTH;
+
+            graphics.draw_rectangle(
+                Rectangle::from_tuples(
+                    (left, graph_bottom_left.y - bar_height(*secs)),
+                    (left + Self::BAR_WIDTH, graph_bottom_left.y)
+                ),
+                color
+            );
+        }
+
+        // A line showing the frame time at 60 FPS
+        let target_y = graph_bottom_left.y - bar_height(1.0 / 60.0);
+
+        graphics.draw_rectangle(
+            Rectangle::from_tuples(
+                (graph_bottom_left.x, target_y),
+                (graph_bottom_left.x + graph_width, target_y + 1.0)
+            ),
+            Color::from_rgba(1.0, 1.0, 1.0, 0.5)
+        );
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::paint::BlendMode;
+    use crate::test_utils::{pixel, render_frame};
+    use crate::transform::Transform;
+    use crate::GLRenderer;
+
+    fn create_overlay() -> DebugOverlay
+    {
+        let font =
+            Font::new(include_bytes!("../assets/fonts/NotoSans-Regular.ttf")).unwrap();
+
+        DebugOverlay::new(&font).unwrap()
+    }
+
+    #[test]
+    fn frame_times()
+    {
+        let mut overlay = create_overlay();
+
+        assert_eq!(None, overlay.fps());
+
+        overlay.record_frame(1.0);
+        assert_eq!(None, overlay.fps());
+
+        overlay.record_frame(1.01);
+        overlay.record_frame(1.04);
+
+        assert!((overlay.average_frame_time_secs().unwrap() - 0.02).abs() < 0.0001);
+        assert!((overlay.fps().unwrap() - 50.0).abs() < 0.01);
+
+        for i in 0..1000 {
+            overlay.record_frame(2.0 + i as f64 * 0.1);
+        }
+
+        assert_eq!(DebugOverlay::HISTORY_LEN, overlay.frame_times_secs.len());
+        assert!((overlay.fps().unwrap() - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn draw_in_corner()
+    {
+        let mut overlay = create_overlay().with_corner(DebugOverlayCorner::BottomRight);
+        let mut renderer = GLRenderer::new_software((400, 300)).unwrap();
+
+        let (_, image) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            overlay.draw(graphics)
+        });
+
+        // The background darkens the bottom right corner only
+        assert!(pixel(&image, 390, 290)[0] < 100);
+        assert_eq!(255, pixel(&image, 10, 10)[0]);
+        assert_eq!(255, pixel(&image, 396, 296)[0]);
+    }
+
+    #[test]
+    fn draw_ignores_caller_state()
+    {
+        let mut overlay = create_overlay().with_corner(DebugOverlayCorner::BottomRight);
+        let mut renderer = GLRenderer::new_software((400, 300)).unwrap();
+
+        let transform = Transform::translate((-1000.0, 0.0));
+
+        let (_, image) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.push_transform(transform);
+            graphics.set_clip(Some(Rectangle::from_tuples((0, 0), (10, 10))));
+            graphics.set_blend_mode(BlendMode::Additive);
+
+            overlay.draw(graphics);
+
+            assert_eq!(transform, graphics.current_transform());
+            assert_eq!(BlendMode::Additive, graphics.blend_mode());
+
+            // The clip area is restored after drawing the overlay
+            graphics.pop_transform();
+            graphics.set_blend_mode(BlendMode::Normal);
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 0.0), (400.0, 300.0)),
+                Color::RED
+            );
+        });
+
+        assert!(pixel(&image, 390, 290)[0] < 100);
+        assert_eq!([255, 0, 0, 255], pixel(&image, 5, 5));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 20, 20));
+    }
+}
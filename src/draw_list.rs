@@ -0,0 +1,1824 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::convert::TryInto;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::color::Color;
+use crate::dimen::{IVec2, UVec2, Vec2};
+use crate::error::{BacktraceError, Context, ErrorMessage};
+use crate::font::{Font, FontId, FormattedGlyph, FormattedTextBlock, FormattedTextLine};
+use crate::image::{
+    ImageDataType,
+    ImageHandle,
+    ImageOptions,
+    ImageSmoothingMode,
+    ImageWrapMode,
+    RenderTarget
+};
+use crate::paint::{BlendMode, Gradient, GradientShape};
+use crate::renderer2d::{Renderer2D, SavedRenderState};
+use crate::shader::{CustomShader, ShaderUniform};
+use crate::shape::{Rect, Rectangle};
+use crate::transform::Transform;
+
+/// A single operation in a [DrawList]. Images and shaders are referred to by
+/// their index in the list's tables.
+#[derive(Clone)]
+pub(crate) enum DrawCommand
+{
+    ClearScreen(Color),
+
+    Triangle
+    {
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3]
+    },
+
+    TriangleImage
+    {
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3],
+        vertex_texture_coords_clockwise: [Vec2; 3],
+        image: usize
+    },
+
+    CircleSection
+    {
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3],
+        vertex_normalized_circle_coords_clockwise: [Vec2; 3],
+        normalized_inner_radius: Vec2
+    },
+
+    Text
+    {
+        position: Vec2,
+        color: Color,
+        text: FormattedTextBlock
+    },
+
+    TextCropped
+    {
+        position: Vec2,
+        crop_window: Rect,
+        color: Color,
+        text: FormattedTextBlock
+    },
+
+    SetGradient(Option<Rc<Gradient>>),
+    SetBlendMode(BlendMode),
+    SetShader(Option<usize>),
+    PushTransform(Transform),
+    PopTransform,
+    SetClip(Option<Rectangle<i32>>),
+    PushClipRectangle(Rect),
+    BeginStencilClip,
+    EndStencilClip,
+    PopClip,
+    BeginRenderTarget(usize),
+    EndRenderTarget,
+    SetLayer(i32)
+}
+
+/// An image which is drawn by a [DrawList], or a render target which is drawn
+/// into. See [DrawList::images].
+#[derive(Debug, Clone)]
+pub struct DrawListImage
+{
+    size: UVec2,
+    smoothing_mode: ImageSmoothingMode,
+    wrap_mode: ImageWrapMode,
+    mipmaps: bool,
+    is_render_target: bool,
+    image: Option<ImageHandle>,
+    render_target: Option<RenderTarget>,
+
+    /// The RGBA contents of the image, which are saved with the list.
+    pixels: Option<Rc<[u8]>>
+}
+
+impl DrawListImage
+{
+    /// Returns the size of the image in pixels.
+    #[inline]
+    pub fn size(&self) -> UVec2
+    {
+        self.size
+    }
+
+    /// Returns the smoothing mode of the image.
+    #[inline]
+    pub fn smoothing_mode(&self) -> ImageSmoothingMode
+    {
+        self.smoothing_mode
+    }
+
+    /// Returns the wrap mode of the image.
+    #[inline]
+    pub fn wrap_mode(&self) -> ImageWrapMode
+    {
+        self.wrap_mode
+    }
+
+    /// Returns true if the image has mipmaps.
+    #[inline]
+    pub fn has_mipmaps(&self) -> bool
+    {
+        self.mipmaps
+    }
+
+    /// Returns true if the list draws into this image using
+    /// [crate::Graphics2D::draw_to_render_target].
+    #[inline]
+    pub fn is_render_target(&self) -> bool
+    {
+        self.is_render_target
+    }
+
+    /// Returns the image which will be drawn when the list is replayed. This
+    /// is `None` for images in a list which was loaded from a file, unless
+    /// one was provided using [DrawList::set_image].
+    #[inline]
+    pub fn image(&self) -> Option<&ImageHandle>
+    {
+        self.image.as_ref()
+    }
+
+    /// Returns the contents of the image in RGBA format, as they were when
+    /// the list was recorded. This is `None` for render targets which the
+    /// list draws into, as their contents are drawn again on replay.
+    #[inline]
+    pub fn pixels(&self) -> Option<&[u8]>
+    {
+        self.pixels.as_deref()
+    }
+
+    fn options(&self) -> ImageOptions
+    {
+        ImageOptions::new(self.smoothing_mode)
+            .with_wrap_mode(self.wrap_mode)
+            .with_mipmaps(self.mipmaps)
+    }
+
+    /// Returns the image and render target to use when replaying the list.
+    /// Render targets and images which were loaded from a file are created
+    /// again.
+    fn resolve(&self, renderer: &mut Renderer2D) -> Option<ResolvedImage>
+    {
+        if let Some(render_target) = &self.render_target {
+            return Some(ResolvedImage {
+                image: render_target.image().clone(),
+                render_target: Some(render_target.clone())
+            });
+        }
+
+        if let Some(image) = &self.image {
+            return Some(ResolvedImage {
+                image: image.clone(),
+                render_target: None
+            });
+        }
+
+        if !self.is_render_target {
+            let pixels = self.pixels.as_ref()?;
+
+            return match renderer.create_image_from_raw_pixels(
+                ImageDataType::RGBA,
+                self.options(),
+                self.size,
+                pixels
+            ) {
+                Ok(image) => Some(ResolvedImage {
+                    image,
+                    render_target: None
+                }),
+                Err(err) => {
+                    log::error!("Failed to create image for replay: {:?}", err);
+                    None
+                }
+            };
+        }
+
+        match renderer.create_render_target(self.size, self.options()) {
+            Ok(render_target) => Some(ResolvedImage {
+                image: render_target.image().clone(),
+                render_target: Some(render_target)
+            }),
+            Err(err) => {
+                log::error!("Failed to create render target for replay: {:?}", err);
+                None
+            }
+        }
+    }
+}
+
+struct ResolvedImage
+{
+    image: ImageHandle,
+    render_target: Option<RenderTarget>
+}
+
+#[derive(Clone)]
+enum DrawListUniform
+{
+    Value(ShaderUniform),
+    Image(usize)
+}
+
+#[derive(Clone)]
+enum DrawListShader
+{
+    Recorded(Rc<CustomShader>),
+
+    /// A shader loaded from a file, which is compiled when the list is
+    /// replayed.
+    Loaded
+    {
+        source: String,
+        uniforms: Vec<(String, DrawListUniform)>
+    }
+}
+
+impl DrawListShader
+{
+    fn resolve(
+        &self,
+        renderer: &Renderer2D,
+        images: &[Option<ResolvedImage>]
+    ) -> Option<Rc<CustomShader>>
+    {
+        let (source, uniforms) = match self {
+            DrawListShader::Recorded(shader) => return Some(shader.clone()),
+            DrawListShader::Loaded { source, uniforms } => (source, uniforms)
+        };
+
+        let mut shader = match renderer.create_custom_shader(source) {
+            Ok(shader) => shader,
+            Err(err) => {
+                log::error!("Failed to create custom shader for replay: {:?}", err);
+                return None;
+            }
+        };
+
+        for (name, value) in uniforms {
+            match value {
+                DrawListUniform::Value(value) => shader.set_uniform(name, value.clone()),
+                DrawListUniform::Image(index) => {
+                    if let Some(Some(resolved)) = images.get(*index) {
+                        shader.set_uniform(name, &resolved.image);
+                    }
+                }
+            }
+        }
+
+        Some(Rc::new(shader))
+    }
+}
+
+/// A recording of drawing operations, which can be replayed onto any
+/// renderer, and saved to a compact binary format. This is useful for
+/// attaching the contents of a frame to a bug report, or for drawing the same
+/// content many times.
+///
+/// Record a `DrawList` using [crate::Graphics2D::record], and replay it using
+/// [crate::Graphics2D::replay]. Shapes are recorded after they have been
+/// broken down into triangles, so the list is replayed exactly, relative to
+/// the transform which is active at the time.
+///
+/// The pixels of each image drawn by the list are read back when the
+/// recording ends, and are included when the list is saved, so that a loaded
+/// list can create the images again. Text (including the font) and custom
+/// shaders are also saved in full, and render targets are created again when
+/// the list is replayed. If an image's pixels couldn't be read, a loaded list
+/// draws the image as a rectangle of its tint color, and
+/// [DrawList::set_image] may be used to provide it again.
+///
+/// ```rust,no_run
+/// # use speedy2d::color::Color;
+/// # use speedy2d::draw_list::DrawList;
+/// # use speedy2d::Graphics2D;
+/// # fn draw(graphics: &mut Graphics2D) {
+/// let recording = graphics.record(|graphics| {
+///     graphics.clear_screen(Color::WHITE);
+///     graphics.draw_circle((100.0, 100.0), 75.0, Color::BLUE);
+/// });
+///
+/// recording.save_to_file_path("frame.s2dl").unwrap();
+///
+/// // Later, possibly on another machine
+/// let recording = DrawList::load_from_file_path("frame.s2dl").unwrap();
+/// graphics.replay(&recording);
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct DrawList
+{
+    commands: Vec<DrawCommand>,
+    images: Vec<DrawListImage>,
+    shaders: Vec<DrawListShader>
+}
+
+impl DrawList
+{
+    const MAGIC: &'static [u8; 4] = b"S2DL";
+    const FORMAT_VERSION: u32 = 2;
+
+    /// Creates an empty list.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Returns the number of operations in the list.
+    #[inline]
+    pub fn len(&self) -> usize
+    {
+        self.commands.len()
+    }
+
+    /// Returns true if the list contains no operations.
+    #[inline]
+    pub fn is_empty(&self) -> bool
+    {
+        self.commands.is_empty()
+    }
+
+    /// Returns the images which are drawn by this list, and the render
+    /// targets which are drawn into.
+    #[inline]
+    pub fn images(&self) -> &[DrawListImage]
+    {
+        &self.images
+    }
+
+    /// Replaces the image at the specified index in [DrawList::images]. This
+    /// allows the images to be provided again after a list has been loaded.
+    pub fn set_image(&mut self, index: usize, image: &ImageHandle)
+    {
+        match self.images.get_mut(index) {
+            None => log::warn!("Ignoring set_image(): index {} out of range", index),
+            Some(entry) => {
+                entry.image = Some(image.clone());
+                entry.render_target = None;
+                entry.pixels = None;
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, command: DrawCommand)
+    {
+        self.commands.push(command);
+    }
+
+    pub(crate) fn image_index(&mut self, image: &ImageHandle) -> usize
+    {
+        if let Some(index) = self
+            .images
+            .iter()
+            .position(|entry| entry.image.as_ref() == Some(image))
+        {
+            return index;
+        }
+
+        self.images.push(DrawListImage {
+            size: image.size,
+            smoothing_mode: image.smoothing_mode,
+            wrap_mode: image.wrap_mode,
+            mipmaps: image.mipmaps,
+            is_render_target: false,
+            image: Some(image.clone()),
+            render_target: None,
+            pixels: None
+        });
+
+        self.images.len() - 1
+    }
+
+    /// Reads back the contents of the images drawn by the list, so that they
+    /// can be saved. Render targets which the list draws into are skipped.
+    pub(crate) fn read_image_pixels(&mut self, renderer: &mut Renderer2D)
+    {
+        for entry in self.images.iter_mut() {
+            if entry.is_render_target || entry.pixels.is_some() {
+                continue;
+            }
+
+            let image = match &entry.image {
+                None => continue,
+                Some(image) => image
+            };
+
+            match renderer.read_image(image, ImageDataType::RGBA) {
+                Ok(pixels) => entry.pixels = Some(Rc::from(pixels.into_data())),
+                Err(err) => log::error!("Failed to read image for draw list: {:?}", err)
+            }
+        }
+    }
+
+    pub(crate) fn render_target_index(&mut self, render_target: &RenderTarget) -> usize
+    {
+        let index = self.image_index(render_target.image());
+        let entry = &mut self.images[index];
+
+        entry.is_render_target = true;
+        entry.render_target = Some(render_target.clone());
+
+        index
+    }
+
+    pub(crate) fn shader_index(&mut self, shader: &Rc<CustomShader>) -> usize
+    {
+        let existing = self.shaders.iter().position(|existing| match existing {
+            DrawListShader::Recorded(existing) => **existing == **shader,
+            DrawListShader::Loaded { .. } => false
+        });
+
+        if let Some(index) = existing {
+            return index;
+        }
+
+        // Register the textures now, so that they can be saved later
+        for (_, value) in shader.uniforms.iter() {
+            if let ShaderUniform::Texture(image) = value {
+                self.image_index(image);
+            }
+        }
+
+        self.shaders.push(DrawListShader::Recorded(shader.clone()));
+        self.shaders.len() - 1
+    }
+
+    /// Draws the contents of the list using the specified renderer. Any
+    /// transforms, clip areas, and render targets which the list leaves
+    /// active are removed afterwards, and the previous blend mode and shader
+    /// are restored.
+    pub(crate) fn replay(&self, renderer: &mut Renderer2D)
+    {
+        let previous_blend_mode = renderer.blend_mode();
+        let previous_shader = renderer.custom_shader();
+        let previous_gradient = renderer.paint_gradient();
+
+        let images: Vec<Option<ResolvedImage>> = self
+            .images
+            .iter()
+            .map(|image| image.resolve(renderer))
+            .collect();
+
+        let shaders: Vec<Option<Rc<CustomShader>>> = self
+            .shaders
+            .iter()
+            .map(|shader| shader.resolve(renderer, &images))
+            .collect();
+
+        // Each render target has its own transform and clip stacks, so the
+        // depth of each is tracked separately.
+        let mut levels = vec![ReplayLevel::default()];
+        let mut skipped_render_targets = 0;
+
+        // Clip shapes can't be added to a static mesh, as they would be
+        // drawn into the mesh instead of the stencil buffer
+        let mut skipping_stencil_clip = false;
+
+        for command in self.commands.iter() {
+            if skipping_stencil_clip {
+                skipping_stencil_clip = !matches!(command, DrawCommand::EndStencilClip);
+                continue;
+            }
+
+            if skipped_render_targets > 0 {
+                match command {
+                    DrawCommand::BeginRenderTarget(_) => skipped_render_targets += 1,
+                    DrawCommand::EndRenderTarget => skipped_render_targets -= 1,
+                    _ => {}
+                }
+                continue;
+            }
+
+            let level = levels.last_mut().unwrap();
+
+            match command {
+                DrawCommand::ClearScreen(color) => renderer.clear_screen(*color),
+
+                DrawCommand::Triangle {
+                    vertex_positions_clockwise,
+                    vertex_colors_clockwise
+                } => renderer.draw_triangle_three_color(
+                    *vertex_positions_clockwise,
+                    *vertex_colors_clockwise
+                ),
+
+                DrawCommand::TriangleImage {
+                    vertex_positions_clockwise,
+                    vertex_colors_clockwise,
+                    vertex_texture_coords_clockwise,
+                    image
+                } => match &images[*image] {
+                    Some(resolved) => renderer.draw_triangle_image_tinted(
+                        *vertex_positions_clockwise,
+                        *vertex_colors_clockwise,
+                        *vertex_texture_coords_clockwise,
+                        &resolved.image
+                    ),
+                    None => renderer.draw_triangle_three_color(
+                        *vertex_positions_clockwise,
+                        *vertex_colors_clockwise
+                    )
+                },
+
+                DrawCommand::CircleSection {
+                    vertex_positions_clockwise,
+                    vertex_colors_clockwise,
+                    vertex_normalized_circle_coords_clockwise,
+                    normalized_inner_radius
+                } => renderer.draw_circle_section(
+                    *vertex_positions_clockwise,
+                    *vertex_colors_clockwise,
+                    *vertex_normalized_circle_coords_clockwise,
+                    *normalized_inner_radius
+                ),
+
+                DrawCommand::Text {
+                    position,
+                    color,
+                    text
+                } => renderer.draw_text(*position, *color, text),
+
+                DrawCommand::TextCropped {
+                    position,
+                    crop_window,
+                    color,
+                    text
+                } => renderer.draw_text_cropped(
+                    *position,
+                    crop_window.clone(),
+                    *color,
+                    text
+                ),
+
+                DrawCommand::SetGradient(gradient) => {
+                    renderer.set_paint_gradient(gradient.clone())
+                }
+
+                DrawCommand::SetBlendMode(blend_mode) => {
+                    renderer.set_blend_mode(*blend_mode)
+                }
+
+                DrawCommand::SetLayer(layer) => renderer.set_layer(*layer),
+
+                DrawCommand::SetShader(shader) => {
+                    renderer.set_custom_shader(
+                        shader.and_then(|index| shaders[index].clone())
+                    );
+                }
+
+                DrawCommand::PushTransform(transform) => {
+                    renderer.push_transform(transform);
+                    level.transforms += 1;
+                }
+
+                DrawCommand::PopTransform => {
+                    if level.transforms > 0 {
+                        renderer.pop_transform();
+                        level.transforms -= 1;
+                    }
+                }
+
+                DrawCommand::SetClip(rect) => renderer.set_clip(rect.clone()),
+
+                DrawCommand::PushClipRectangle(rect) => {
+                    // If the rectangle isn't axis-aligned under the current
+                    // transform, it must be added to the stencil buffer.
+                    if !renderer.push_clip_rectangle(rect) {
+                        let shader = renderer.set_custom_shader(None);
+                        renderer.begin_stencil_clip();
+
+                        let (tl, tr) = (*rect.top_left(), rect.top_right());
+                        let (br, bl) = (*rect.bottom_right(), rect.bottom_left());
+
+                        renderer
+                            .draw_triangle_three_color([tl, tr, br], [Color::WHITE; 3]);
+                        renderer
+                            .draw_triangle_three_color([br, bl, tl], [Color::WHITE; 3]);
+
+                        renderer.end_stencil_clip();
+                        renderer.set_custom_shader(shader);
+                    }
+
+                    level.clips += 1;
+                }
+
+                DrawCommand::BeginStencilClip => {
+                    if renderer.is_building_mesh() {
+                        log::warn!(
+                            "Clip shapes can't be added to a static mesh, ignoring"
+                        );
+                        skipping_stencil_clip = true;
+                    } else {
+                        renderer.begin_stencil_clip();
+                    }
+                }
+
+                DrawCommand::EndStencilClip => {
+                    renderer.end_stencil_clip();
+                    level.clips += 1;
+                }
+
+                DrawCommand::PopClip => {
+                    if level.clips > 0 {
+                        renderer.pop_clip();
+                        level.clips -= 1;
+                    }
+                }
+
+                DrawCommand::BeginRenderTarget(index) => {
+                    let render_target = images[*index]
+                        .as_ref()
+                        .and_then(|resolved| resolved.render_target.as_ref());
+
+                    match render_target {
+                        None => skipped_render_targets += 1,
+                        Some(render_target) => levels.push(ReplayLevel {
+                            saved: Some(renderer.begin_render_target(render_target)),
+                            ..ReplayLevel::default()
+                        })
+                    }
+                }
+
+                DrawCommand::EndRenderTarget => {
+                    if levels.len() > 1 {
+                        levels.pop().unwrap().finish(renderer);
+                    }
+                }
+            }
+        }
+
+        while let Some(level) = levels.pop() {
+            level.finish(renderer);
+        }
+
+        renderer.set_blend_mode(previous_blend_mode);
+        renderer.set_custom_shader(previous_shader);
+        renderer.set_paint_gradient(previous_gradient);
+    }
+
+    /// Encodes the list in a compact binary format, which can be loaded using
+    /// [DrawList::from_bytes]. See [DrawList] for details of what is saved.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BacktraceError<ErrorMessage>>
+    {
+        let mut fonts: Vec<(FontId, &[u8])> = Vec::new();
+        let mut commands = Writer::default();
+
+        commands.write_len(self.commands.len());
+
+        for command in self.commands.iter() {
+            self.write_command(&mut commands, &mut fonts, command)?;
+        }
+
+        let mut out = Writer::default();
+
+        out.bytes.extend_from_slice(DrawList::MAGIC);
+        out.write_u32(DrawList::FORMAT_VERSION);
+
+        out.write_len(self.images.len());
+
+        for image in self.images.iter() {
+            out.write_u32(image.size.x);
+            out.write_u32(image.size.y);
+            out.write_u8(match image.smoothing_mode {
+                ImageSmoothingMode::NearestNeighbor => 0,
+                ImageSmoothingMode::Linear => 1,
+                ImageSmoothingMode::Trilinear => 2
+            });
+            out.write_u8(match image.wrap_mode {
+                ImageWrapMode::ClampToEdge => 0,
+                ImageWrapMode::Repeat => 1,
+                ImageWrapMode::MirroredRepeat => 2
+            });
+            out.write_bool(image.mipmaps);
+            out.write_bool(image.is_render_target);
+            out.write_bool(image.pixels.is_some());
+
+            if let Some(pixels) = &image.pixels {
+                out.write_data(pixels);
+            }
+        }
+
+        out.write_len(self.shaders.len());
+
+        for shader in self.shaders.iter() {
+            self.write_shader(&mut out, shader);
+        }
+
+        out.write_len(fonts.len());
+
+        for (_, data) in fonts.iter() {
+            out.write_data(data);
+        }
+
+        out.bytes.extend_from_slice(&commands.bytes);
+
+        Ok(out.bytes)
+    }
+
+    /// Loads a list which was encoded using [DrawList::to_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.read_slice(DrawList::MAGIC.len())? != DrawList::MAGIC {
+            return Err(ErrorMessage::msg("Not a draw list"));
+        }
+
+        let version = reader.read_u32()?;
+
+        if version != DrawList::FORMAT_VERSION {
+            return Err(ErrorMessage::msg(format!(
+                "Unsupported draw list version {}",
+                version
+            )));
+        }
+
+        let mut list = DrawList::new();
+
+        for _ in 0..reader.read_len()? {
+            let size = UVec2::new(reader.read_u32()?, reader.read_u32()?);
+
+            let smoothing_mode = match reader.read_u8()? {
+                0 => ImageSmoothingMode::NearestNeighbor,
+                1 => ImageSmoothingMode::Linear,
+                2 => ImageSmoothingMode::Trilinear,
+                value => return Err(invalid_value("smoothing mode", value))
+            };
+
+            let wrap_mode = match reader.read_u8()? {
+                0 => ImageWrapMode::ClampToEdge,
+                1 => ImageWrapMode::Repeat,
+                2 => ImageWrapMode::MirroredRepeat,
+                value => return Err(invalid_value("wrap mode", value))
+            };
+
+            let mipmaps = reader.read_bool()?;
+            let is_render_target = reader.read_bool()?;
+
+            let pixels = match reader.read_bool()? {
+                false => None,
+                true => {
+                    let pixels = reader.read_data()?;
+
+                    let expected_len = (size.x as usize)
+                        .checked_mul(size.y as usize)
+                        .and_then(|len| len.checked_mul(4));
+
+                    if expected_len != Some(pixels.len()) {
+                        return Err(ErrorMessage::msg(format!(
+                            "Invalid pixel data for {}x{} image",
+                            size.x, size.y
+                        )));
+                    }
+
+                    Some(Rc::from(pixels))
+                }
+            };
+
+            list.images.push(DrawListImage {
+                size,
+                smoothing_mode,
+                wrap_mode,
+                mipmaps,
+                is_render_target,
+                image: None,
+                render_target: None,
+                pixels
+            });
+        }
+
+        for _ in 0..reader.read_len()? {
+            let shader = list.read_shader(&mut reader)?;
+            list.shaders.push(shader);
+        }
+
+        let mut fonts = Vec::new();
+
+        for _ in 0..reader.read_len()? {
+            fonts.push(Font::new(reader.read_data()?).context("Failed to load font")?);
+        }
+
+        for _ in 0..reader.read_len()? {
+            let command = list.read_command(&mut reader, &fonts)?;
+            list.commands.push(command);
+        }
+
+        Ok(list)
+    }
+
+    /// Saves the list to the specified file. See [DrawList::to_bytes].
+    pub fn save_to_file_path<P: AsRef<Path>>(
+        &self,
+        path: P
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        std::fs::write(path.as_ref(), self.to_bytes()?)
+            .context(format!("Failed to write file '{:?}'", path.as_ref()))
+    }
+
+    /// Loads a list from the specified file. See [DrawList::from_bytes].
+    pub fn load_from_file_path<P: AsRef<Path>>(
+        path: P
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let bytes = std::fs::read(path.as_ref())
+            .context(format!("Failed to read file '{:?}'", path.as_ref()))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    fn image_index_for_saving(&self, image: &ImageHandle) -> Option<usize>
+    {
+        self.images
+            .iter()
+            .position(|entry| entry.image.as_ref() == Some(image))
+    }
+
+    fn write_shader(&self, out: &mut Writer, shader: &DrawListShader)
+    {
+        match shader {
+            DrawListShader::Recorded(shader) => {
+                out.write_data(shader.source.as_bytes());
+
+                let uniforms: Vec<(&String, DrawListUniform)> = shader
+                    .uniforms
+                    .iter()
+                    .filter_map(|(name, value)| match value {
+                        ShaderUniform::Texture(image) => self
+                            .image_index_for_saving(image)
+                            .map(|index| (name, DrawListUniform::Image(index))),
+                        value => Some((name, DrawListUniform::Value(value.clone())))
+                    })
+                    .collect();
+
+                out.write_len(uniforms.len());
+
+                for (name, value) in uniforms.iter() {
+                    out.write_data(name.as_bytes());
+                    out.write_uniform(value);
+                }
+            }
+
+            DrawListShader::Loaded { source, uniforms } => {
+                out.write_data(source.as_bytes());
+                out.write_len(uniforms.len());
+
+                for (name, value) in uniforms.iter() {
+                    out.write_data(name.as_bytes());
+                    out.write_uniform(value);
+                }
+            }
+        }
+    }
+
+    fn read_shader(
+        &self,
+        reader: &mut Reader
+    ) -> Result<DrawListShader, BacktraceError<ErrorMessage>>
+    {
+        let source = reader.read_string()?;
+        let mut uniforms = Vec::new();
+
+        for _ in 0..reader.read_len()? {
+            let name = reader.read_string()?;
+
+            let value = match reader.read_u8()? {
+                0 => DrawListUniform::Value(ShaderUniform::Float(reader.read_f32()?)),
+                1 => DrawListUniform::Value(ShaderUniform::Vec2(reader.read_vec2()?)),
+                2 => DrawListUniform::Value(ShaderUniform::Vec3(
+                    reader.read_f32()?,
+                    reader.read_f32()?,
+                    reader.read_f32()?
+                )),
+                3 => DrawListUniform::Value(ShaderUniform::Vec4(
+                    reader.read_f32()?,
+                    reader.read_f32()?,
+                    reader.read_f32()?,
+                    reader.read_f32()?
+                )),
+                4 => DrawListUniform::Value(ShaderUniform::Color(reader.read_color()?)),
+                5 => DrawListUniform::Image(self.read_image_index(reader)?),
+                value => return Err(invalid_value("uniform type", value))
+            };
+
+            uniforms.push((name, value));
+        }
+
+        Ok(DrawListShader::Loaded { source, uniforms })
+    }
+
+    fn read_image_index(
+        &self,
+        reader: &mut Reader
+    ) -> Result<usize, BacktraceError<ErrorMessage>>
+    {
+        let index = reader.read_len()?;
+
+        if index >= self.images.len() {
+            return Err(ErrorMessage::msg(format!("Invalid image index {}", index)));
+        }
+
+        Ok(index)
+    }
+
+    fn read_shader_index(
+        &self,
+        reader: &mut Reader
+    ) -> Result<usize, BacktraceError<ErrorMessage>>
+    {
+        let index = reader.read_len()?;
+
+        if index >= self.shaders.len() {
+            return Err(ErrorMessage::msg(format!("Invalid shader index {}", index)));
+        }
+
+        Ok(index)
+    }
+
+    fn write_command<'a>(
+        &self,
+        out: &mut Writer,
+        fonts: &mut Vec<(FontId, &'a [u8])>,
+        command: &'a DrawCommand
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        match command {
+            DrawCommand::ClearScreen(color) => {
+                out.write_u8(0);
+                out.write_color(color);
+            }
+
+            DrawCommand::Triangle {
+                vertex_positions_clockwise,
+                vertex_colors_clockwise
+            } => {
+                out.write_u8(1);
+                out.write_vec2s(vertex_positions_clockwise);
+                out.write_colors(vertex_colors_clockwise);
+            }
+
+            DrawCommand::TriangleImage {
+                vertex_positions_clockwise,
+                vertex_colors_clockwise,
+                vertex_texture_coords_clockwise,
+                image
+            } => {
+                out.write_u8(2);
+                out.write_vec2s(vertex_positions_clockwise);
+                out.write_colors(vertex_colors_clockwise);
+                out.write_vec2s(vertex_texture_coords_clockwise);
+                out.write_len(*image);
+            }
+
+            DrawCommand::CircleSection {
+                vertex_positions_clockwise,
+                vertex_colors_clockwise,
+                vertex_normalized_circle_coords_clockwise,
+                normalized_inner_radius
+            } => {
+                out.write_u8(3);
+                out.write_vec2s(vertex_positions_clockwise);
+                out.write_colors(vertex_colors_clockwise);
+                out.write_vec2s(vertex_normalized_circle_coords_clockwise);
+                out.write_vec2(normalized_inner_radius);
+            }
+
+            DrawCommand::Text {
+                position,
+                color,
+                text
+            } => {
+                out.write_u8(4);
+                out.write_vec2(position);
+                out.write_color(color);
+                out.write_text(fonts, text)?;
+            }
+
+            DrawCommand::TextCropped {
+                position,
+                crop_window,
+                color,
+                text
+            } => {
+                out.write_u8(5);
+                out.write_vec2(position);
+                out.write_vec2(crop_window.top_left());
+                out.write_vec2(crop_window.bottom_right());
+                out.write_color(color);
+                out.write_text(fonts, text)?;
+            }
+
+            DrawCommand::SetGradient(gradient) => {
+                out.write_u8(6);
+                out.write_bool(gradient.is_some());
+
+                if let Some(gradient) = gradient {
+                    match &gradient.shape {
+                        GradientShape::Linear { start, end } => {
+                            out.write_u8(0);
+                            out.write_vec2(start);
+                            out.write_vec2(end);
+                        }
+                        GradientShape::Radial { center, radius } => {
+                            out.write_u8(1);
+                            out.write_vec2(center);
+                            out.write_f32(*radius);
+                        }
+                    }
+
+                    out.write_len(gradient.stops.len());
+
+                    for (offset, color) in gradient.stops.iter() {
+                        out.write_f32(*offset);
+                        out.write_color(color);
+                    }
+                }
+            }
+
+            DrawCommand::SetBlendMode(blend_mode) => {
+                out.write_u8(7);
+                out.write_u8(match blend_mode {
+                    BlendMode::Normal => 0,
+                    BlendMode::Additive => 1,
+                    BlendMode::Multiply => 2,
+                    BlendMode::Screen => 3,
+                    BlendMode::Premultiplied => 4,
+                    BlendMode::Replace => 5
+                });
+            }
+
+            DrawCommand::SetShader(shader) => {
+                out.write_u8(8);
+                out.write_bool(shader.is_some());
+
+                if let Some(shader) = shader {
+                    out.write_len(*shader);
+                }
+            }
+
+            DrawCommand::PushTransform(transform) => {
+                out.write_u8(9);
+
+                for value in transform.to_matrix().iter() {
+                    out.write_f32(*value);
+                }
+            }
+
+            DrawCommand::PopTransform => out.write_u8(10),
+
+            DrawCommand::SetClip(rect) => {
+                out.write_u8(11);
+                out.write_bool(rect.is_some());
+
+                if let Some(rect) = rect {
+                    out.write_i32(rect.top_left().x);
+                    out.write_i32(rect.top_left().y);
+                    out.write_i32(rect.bottom_right().x);
+                    out.write_i32(rect.bottom_right().y);
+                }
+            }
+
+            DrawCommand::PushClipRectangle(rect) => {
+                out.write_u8(12);
+                out.write_vec2(rect.top_left());
+                out.write_vec2(rect.bottom_right());
+            }
+
+            DrawCommand::BeginStencilClip => out.write_u8(13),
+            DrawCommand::EndStencilClip => out.write_u8(14),
+            DrawCommand::PopClip => out.write_u8(15),
+
+            DrawCommand::BeginRenderTarget(image) => {
+                out.write_u8(16);
+                out.write_len(*image);
+            }
+
+            DrawCommand::EndRenderTarget => out.write_u8(17),
+
+            DrawCommand::SetLayer(layer) => {
+                out.write_u8(18);
+                out.write_i32(*layer);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_command(
+        &self,
+        reader: &mut Reader,
+        fonts: &[Font]
+    ) -> Result<DrawCommand, BacktraceError<ErrorMessage>>
+    {
+        Ok(match reader.read_u8()? {
+            0 => DrawCommand::ClearScreen(reader.read_color()?),
+
+            1 => DrawCommand::Triangle {
+                vertex_positions_clockwise: reader.read_vec2s()?,
+                vertex_colors_clockwise: reader.read_colors()?
+            },
+
+            2 => DrawCommand::TriangleImage {
+                vertex_positions_clockwise: reader.read_vec2s()?,
+                vertex_colors_clockwise: reader.read_colors()?,
+                vertex_texture_coords_clockwise: reader.read_vec2s()?,
+                image: self.read_image_index(reader)?
+            },
+
+            3 => DrawCommand::CircleSection {
+                vertex_positions_clockwise: reader.read_vec2s()?,
+                vertex_colors_clockwise: reader.read_colors()?,
+                vertex_normalized_circle_coords_clockwise: reader.read_vec2s()?,
+                normalized_inner_radius: reader.read_vec2()?
+            },
+
+            4 => DrawCommand::Text {
+                position: reader.read_vec2()?,
+                color: reader.read_color()?,
+                text: reader.read_text(fonts)?
+            },
+
+            5 => DrawCommand::TextCropped {
+                position: reader.read_vec2()?,
+                crop_window: Rect::new(reader.read_vec2()?, reader.read_vec2()?),
+                color: reader.read_color()?,
+                text: reader.read_text(fonts)?
+            },
+
+            6 => DrawCommand::SetGradient(
+                if reader.read_bool()? {
+                    let shape = match reader.read_u8()? {
+                        0 => GradientShape::Linear {
+                            start: reader.read_vec2()?,
+                            end: reader.read_vec2()?
+                        },
+                        1 => GradientShape::Radial {
+                            center: reader.read_vec2()?,
+                            radius: reader.read_f32()?
+                        },
+                        value => return Err(invalid_value("gradient shape", value))
+                    };
+
+                    let stop_count = reader.read_len()?;
+
+                    // Gradients without any stops are never recorded
+                    if stop_count == 0 || stop_count > Gradient::MAX_STOPS {
+                        return Err(ErrorMessage::msg(format!(
+                            "Invalid gradient stop count in draw list: {}",
+                            stop_count
+                        )));
+                    }
+
+                    // The stops are added in the same way as the public API,
+                    // so that they are clamped and sorted
+                    let mut gradient = Gradient {
+                        shape,
+                        stops: Vec::with_capacity(stop_count)
+                    };
+
+                    for _ in 0..stop_count {
+                        gradient =
+                            gradient.with_stop(reader.read_f32()?, reader.read_color()?);
+                    }
+
+                    Some(Rc::new(gradient))
+                } else {
+                    None
+                }
+            ),
+
+            7 => DrawCommand::SetBlendMode(match reader.read_u8()? {
+                0 => BlendMode::Normal,
+                1 => BlendMode::Additive,
+                2 => BlendMode::Multiply,
+                3 => BlendMode::Screen,
+                4 => BlendMode::Premultiplied,
+                5 => BlendMode::Replace,
+                value => return Err(invalid_value("blend mode", value))
+            }),
+
+            8 => DrawCommand::SetShader(
+                if reader.read_bool()? {
+                    Some(self.read_shader_index(reader)?)
+                } else {
+                    None
+                }
+            ),
+
+            9 => DrawCommand::PushTransform(Transform::from_matrix(
+                reader.read_f32()?,
+                reader.read_f32()?,
+                reader.read_f32()?,
+                reader.read_f32()?,
+                reader.read_f32()?,
+                reader.read_f32()?
+            )),
+
+            10 => DrawCommand::PopTransform,
+
+            11 => DrawCommand::SetClip(
+                if reader.read_bool()? {
+                    Some(Rectangle::new(
+                        IVec2::new(reader.read_i32()?, reader.read_i32()?),
+                        IVec2::new(reader.read_i32()?, reader.read_i32()?)
+                    ))
+                } else {
+                    None
+                }
+            ),
+
+            12 => DrawCommand::PushClipRectangle(Rect::new(
+                reader.read_vec2()?,
+                reader.read_vec2()?
+            )),
+
+            13 => DrawCommand::BeginStencilClip,
+            14 => DrawCommand::EndStencilClip,
+            15 => DrawCommand::PopClip,
+
+            16 => {
+                let index = self.read_image_index(reader)?;
+
+                if !self.images[index].is_render_target {
+                    return Err(ErrorMessage::msg(format!(
+                        "Image {} is not a render target",
+                        index
+                    )));
+                }
+
+                DrawCommand::BeginRenderTarget(index)
+            }
+
+            17 => DrawCommand::EndRenderTarget,
+
+            18 => DrawCommand::SetLayer(reader.read_i32()?),
+
+            value => return Err(invalid_value("command", value))
+        })
+    }
+}
+
+#[derive(Default)]
+struct ReplayLevel
+{
+    saved: Option<SavedRenderState>,
+    transforms: usize,
+    clips: usize
+}
+
+impl ReplayLevel
+{
+    /// Pops any transforms and clips which are still active, and returns to
+    /// the previous render target.
+    fn finish(self, renderer: &mut Renderer2D)
+    {
+        for _ in 0..self.clips {
+            renderer.pop_clip();
+        }
+
+        for _ in 0..self.transforms {
+            renderer.pop_transform();
+        }
+
+        if let Some(saved) = self.saved {
+            renderer.end_render_target(saved);
+        }
+    }
+}
+
+fn invalid_value(name: &str, value: u8) -> BacktraceError<ErrorMessage>
+{
+    ErrorMessage::msg(format!("Invalid {} in draw list: {}", name, value))
+}
+
+/// Appends little-endian values to a buffer.
+#[derive(Default)]
+struct Writer
+{
+    bytes: Vec<u8>
+}
+
+impl Writer
+{
+    fn write_u8(&mut self, value: u8)
+    {
+        self.bytes.push(value);
+    }
+
+    fn write_bool(&mut self, value: bool)
+    {
+        self.write_u8(value as u8);
+    }
+
+    fn write_u32(&mut self, value: u32)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, value: i32)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_len(&mut self, value: usize)
+    {
+        self.write_u32(value as u32);
+    }
+
+    fn write_data(&mut self, data: &[u8])
+    {
+        self.write_len(data.len());
+        self.bytes.extend_from_slice(data);
+    }
+
+    fn write_vec2(&mut self, value: &Vec2)
+    {
+        self.write_f32(value.x);
+        self.write_f32(value.y);
+    }
+
+    fn write_vec2s(&mut self, values: &[Vec2; 3])
+    {
+        for value in values.iter() {
+            self.write_vec2(value);
+        }
+    }
+
+    fn write_color(&mut self, color: &Color)
+    {
+        self.write_f32(color.r());
+        self.write_f32(color.g());
+        self.write_f32(color.b());
+        self.write_f32(color.a());
+    }
+
+    fn write_colors(&mut self, colors: &[Color; 3])
+    {
+        for color in colors.iter() {
+            self.write_color(color);
+        }
+    }
+
+    fn write_uniform(&mut self, value: &DrawListUniform)
+    {
+        match value {
+            DrawListUniform::Value(ShaderUniform::Float(value)) => {
+                self.write_u8(0);
+                self.write_f32(*value);
+            }
+            DrawListUniform::Value(ShaderUniform::Vec2(value)) => {
+                self.write_u8(1);
+                self.write_vec2(value);
+            }
+            DrawListUniform::Value(ShaderUniform::Vec3(x, y, z)) => {
+                self.write_u8(2);
+                self.write_f32(*x);
+                self.write_f32(*y);
+                self.write_f32(*z);
+            }
+            DrawListUniform::Value(ShaderUniform::Vec4(x, y, z, w)) => {
+                self.write_u8(3);
+                self.write_f32(*x);
+                self.write_f32(*y);
+                self.write_f32(*z);
+                self.write_f32(*w);
+            }
+            DrawListUniform::Value(ShaderUniform::Color(color)) => {
+                self.write_u8(4);
+                self.write_color(color);
+            }
+            DrawListUniform::Image(index) => {
+                self.write_u8(5);
+                self.write_len(*index);
+            }
+            DrawListUniform::Value(ShaderUniform::Texture(_)) => {
+                unreachable!("Textures are replaced with image indices")
+            }
+        }
+    }
+
+    fn write_text<'a>(
+        &mut self,
+        fonts: &mut Vec<(FontId, &'a [u8])>,
+        text: &'a FormattedTextBlock
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.write_f32(text.width());
+        self.write_f32(text.height());
+        self.write_len(text.iter_lines().len());
+
+        for line in text.iter_lines() {
+            self.write_f32(line.baseline_position());
+            self.write_f32(line.width());
+            self.write_f32(line.ascent());
+            self.write_f32(line.descent());
+            self.write_f32(line.line_gap());
+            self.write_len(line.iter_glyphs().len());
+
+            for glyph in line.iter_glyphs() {
+                let font_index =
+                    match fonts.iter().position(|(id, _)| *id == glyph.font_id()) {
+                        Some(index) => index,
+                        None => {
+                            let data = glyph.font_data().ok_or_else(|| {
+                                ErrorMessage::msg("Font data is not available")
+                            })?;
+
+                            fonts.push((glyph.font_id(), data));
+                            fonts.len() - 1
+                        }
+                    };
+
+                let scale = glyph.glyph().scale();
+                let position = glyph.glyph().position();
+
+                self.write_len(font_index);
+                self.write_u32(glyph.glyph().id().0 as u32);
+                self.write_f32(scale.x);
+                self.write_f32(scale.y);
+                self.write_f32(position.x);
+                self.write_f32(position.y);
+                self.write_u32(glyph.user_index());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads little-endian values from a buffer.
+struct Reader<'a>
+{
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a>
+{
+    fn read_slice(&mut self, len: usize)
+        -> Result<&'a [u8], BacktraceError<ErrorMessage>>
+    {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| ErrorMessage::msg("Unexpected end of draw list"))?;
+
+        let result = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(result)
+    }
+
+    fn read_array<const N: usize>(
+        &mut self
+    ) -> Result<[u8; N], BacktraceError<ErrorMessage>>
+    {
+        Ok(self.read_slice(N)?.try_into().unwrap())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, BacktraceError<ErrorMessage>>
+    {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_bool(&mut self) -> Result<bool, BacktraceError<ErrorMessage>>
+    {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(invalid_value("boolean", value))
+        }
+    }
+
+    fn read_u32(&mut self) -> Result<u32, BacktraceError<ErrorMessage>>
+    {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, BacktraceError<ErrorMessage>>
+    {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_f32(&mut self) -> Result<f32, BacktraceError<ErrorMessage>>
+    {
+        Ok(f32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_len(&mut self) -> Result<usize, BacktraceError<ErrorMessage>>
+    {
+        Ok(self.read_u32()? as usize)
+    }
+
+    fn read_data(&mut self) -> Result<&'a [u8], BacktraceError<ErrorMessage>>
+    {
+        let len = self.read_len()?;
+        self.read_slice(len)
+    }
+
+    fn read_string(&mut self) -> Result<String, BacktraceError<ErrorMessage>>
+    {
+        let data = self.read_data()?;
+
+        String::from_utf8(data.to_vec()).map_err(|err| {
+            ErrorMessage::msg_with_cause("Invalid string in draw list", err)
+        })
+    }
+
+    fn read_vec2(&mut self) -> Result<Vec2, BacktraceError<ErrorMessage>>
+    {
+        Ok(Vec2::new(self.read_f32()?, self.read_f32()?))
+    }
+
+    fn read_vec2s(&mut self) -> Result<[Vec2; 3], BacktraceError<ErrorMessage>>
+    {
+        Ok([self.read_vec2()?, self.read_vec2()?, self.read_vec2()?])
+    }
+
+    fn read_color(&mut self) -> Result<Color, BacktraceError<ErrorMessage>>
+    {
+        Ok(Color::from_rgba(
+            self.read_f32()?,
+            self.read_f32()?,
+            self.read_f32()?,
+            self.read_f32()?
+        ))
+    }
+
+    fn read_colors(&mut self) -> Result<[Color; 3], BacktraceError<ErrorMessage>>
+    {
+        Ok([self.read_color()?, self.read_color()?, self.read_color()?])
+    }
+
+    fn read_text(
+        &mut self,
+        fonts: &[Font]
+    ) -> Result<FormattedTextBlock, BacktraceError<ErrorMessage>>
+    {
+        let width = self.read_f32()?;
+        let height = self.read_f32()?;
+        let mut lines = Vec::new();
+
+        for _ in 0..self.read_len()? {
+            let baseline = self.read_f32()?;
+            let line_width = self.read_f32()?;
+            let ascent = self.read_f32()?;
+            let descent = self.read_f32()?;
+            let line_gap = self.read_f32()?;
+            let mut glyphs = Vec::new();
+
+            for _ in 0..self.read_len()? {
+                let font_index = self.read_len()?;
+
+                let font = fonts.get(font_index).ok_or_else(|| {
+                    ErrorMessage::msg(format!("Invalid font index {}", font_index))
+                })?;
+
+                let glyph_id = self.read_u32()?.try_into().map_err(|err| {
+                    ErrorMessage::msg_with_cause("Invalid glyph ID", err)
+                })?;
+
+                let scale = self.read_vec2()?;
+                let position = self.read_vec2()?;
+                let user_index = self.read_u32()?;
+
+                glyphs.push(FormattedGlyph::new(
+                    font, glyph_id, scale, position, user_index
+                ));
+            }
+
+            lines.push(FormattedTextLine::new(
+                glyphs, baseline, line_width, ascent, descent, line_gap
+            ));
+        }
+
+        Ok(FormattedTextBlock::new(lines, width, height))
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::font::{TextLayout, TextOptions};
+    use crate::image::ImageDataType;
+    use crate::paint::Paint;
+    use crate::test_utils::render_frame;
+    use crate::{GLRenderer, Graphics2D};
+
+    fn draw_scene(graphics: &mut Graphics2D)
+    {
+        let font =
+            Font::new(include_bytes!("../assets/fonts/NotoSans-Regular.ttf")).unwrap();
+
+        let target = graphics
+            .create_render_target(ImageSmoothingMode::NearestNeighbor, (20, 20))
+            .unwrap();
+
+        graphics.draw_to_render_target(&target, |graphics| {
+            graphics.clear_screen(Color::GREEN);
+        });
+
+        graphics.with_transform(Transform::translate((10.0, 0.0)), |graphics| {
+            graphics.draw_rectangle_with_paint(
+                Rect::from_tuples((0.0, 0.0), (40.0, 40.0)),
+                &Paint::Gradient(
+                    Gradient::linear((0.0, 0.0), (40.0, 0.0))
+                        .with_stop(0.0, Color::RED)
+                        .with_stop(1.0, Color::BLUE)
+                )
+            );
+        });
+
+        graphics.with_clip(Rect::from_tuples((50.0, 50.0), (70.0, 70.0)), |graphics| {
+            graphics.draw_circle((60.0, 60.0), 20.0, Color::MAGENTA);
+        });
+
+        graphics.draw_image((0.0, 80.0), target.image());
+
+        graphics.draw_text(
+            (50.0, 0.0),
+            Color::BLACK,
+            &font.layout_text("Hi", 32.0, TextOptions::new())
+        );
+    }
+
+    #[test]
+    fn replay_matches_original()
+    {
+        let mut renderer = GLRenderer::new_software((100, 100)).unwrap();
+        let (list, original) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.record(draw_scene)
+        });
+
+        assert!(!list.is_empty());
+
+        let (_, replayed) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.replay(&list)
+        });
+        assert_eq!(original.data(), replayed.data());
+
+        let loaded = DrawList::from_bytes(&list.to_bytes().unwrap()).unwrap();
+        assert_eq!(list.len(), loaded.len());
+        assert!(loaded.images()[0].is_render_target());
+
+        let (_, loaded) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.replay(&loaded)
+        });
+        assert_eq!(original.data(), loaded.data());
+    }
+
+    #[test]
+    fn images_are_saved()
+    {
+        let pixels = [
+            255, 0, 0, 255, 0, 255, 0, 255, //
+            0, 0, 255, 255, 255, 255, 0, 128
+        ];
+
+        let mut renderer = GLRenderer::new_software((20, 20)).unwrap();
+
+        let (list, original) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            let image = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGBA,
+                    ImageSmoothingMode::NearestNeighbor,
+                    (2, 2),
+                    &pixels
+                )
+                .unwrap();
+
+            graphics.record(|graphics| {
+                graphics.draw_rectangle_image(
+                    Rect::from_tuples((0.0, 0.0), (20.0, 20.0)),
+                    &image
+                );
+            })
+        });
+
+        let loaded = DrawList::from_bytes(&list.to_bytes().unwrap()).unwrap();
+
+        assert!(loaded.images()[0].image().is_none());
+        assert_eq!(Some(&pixels[..]), loaded.images()[0].pixels());
+
+        let (_, replayed) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.replay(&loaded)
+        });
+        assert_eq!(original.data(), replayed.data());
+    }
+
+    #[test]
+    fn missing_images_are_drawn_with_tint()
+    {
+        let mut renderer = GLRenderer::new_software((10, 10)).unwrap();
+
+        let (mut list, _) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            let image = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGB,
+                    ImageSmoothingMode::Linear,
+                    (1, 1),
+                    &[0, 255, 0]
+                )
+                .unwrap();
+
+            graphics.record(|graphics| {
+                graphics.draw_rectangle_image_tinted(
+                    Rect::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                    Color::RED,
+                    &image
+                );
+            })
+        });
+
+        // As if the pixels couldn't be read back
+        list.images[0].pixels = None;
+
+        let loaded = DrawList::from_bytes(&list.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(1, loaded.images().len());
+        assert_eq!(UVec2::new(1, 1), loaded.images()[0].size());
+        assert!(loaded.images()[0].image().is_none());
+
+        let (_, image) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.replay(&loaded)
+        });
+        assert_eq!(&[255, 0, 0, 255], &image.data()[0..4]);
+    }
+
+    #[test]
+    fn invalid_data()
+    {
+        let bytes = DrawList::new().to_bytes().unwrap();
+
+        assert!(DrawList::from_bytes(&bytes).unwrap().is_empty());
+        assert!(DrawList::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(DrawList::from_bytes(b"PNG").is_err());
+    }
+
+    #[test]
+    fn invalid_gradient_stops()
+    {
+        let with_stops = |count: usize| {
+            let mut list = DrawList::new();
+
+            list.commands
+                .push(DrawCommand::SetGradient(Some(Rc::new(Gradient {
+                    shape: GradientShape::Linear {
+                        start: Vec2::ZERO,
+                        end: Vec2::new(10.0, 0.0)
+                    },
+                    stops: (0..count)
+                        .map(|i| (1.0 - i as f32 / 10.0, Color::RED))
+                        .collect()
+                }))));
+
+            DrawList::from_bytes(&list.to_bytes().unwrap())
+        };
+
+        assert!(with_stops(0).is_err());
+        assert!(with_stops(Gradient::MAX_STOPS + 1).is_err());
+
+        // Stops which are out of order are sorted
+        let loaded = with_stops(Gradient::MAX_STOPS).unwrap();
+
+        match &loaded.commands[0] {
+            DrawCommand::SetGradient(Some(gradient)) => {
+                let offsets: Vec<f32> =
+                    gradient.stops.iter().map(|(offset, _)| *offset).collect();
+
+                assert_eq!(Gradient::MAX_STOPS, offsets.len());
+                assert!(offsets.windows(2).all(|pair| pair[0] <= pair[1]));
+            }
+            _ => panic!("Expected a gradient command")
+        }
+    }
+}
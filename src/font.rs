@@ -908,6 +908,30 @@ pub struct FormattedGlyph
 
 impl FormattedGlyph
 {
+    /// Creates a glyph from the specified font, at the specified scale and
+    /// position.
+    pub(crate) fn new(
+        font: &Font,
+        glyph_id: u16,
+        scale: Vec2,
+        position: Vec2,
+        user_index: UserGlyphIndex
+    ) -> Self
+    {
+        FormattedGlyph {
+            glyph: font
+                .font()
+                .glyph(rusttype::GlyphId(glyph_id))
+                .scaled(Scale {
+                    x: scale.x,
+                    y: scale.y
+                })
+                .positioned(rusttype::point(position.x, position.y)),
+            font_id: font.id(),
+            user_index
+        }
+    }
+
     #[inline]
     #[must_use]
     pub(crate) fn glyph(&self) -> &rusttype::PositionedGlyph<'static>
@@ -915,6 +939,15 @@ impl FormattedGlyph
         &self.glyph
     }
 
+    /// The data of the font file which this glyph was loaded from.
+    pub(crate) fn font_data(&self) -> Option<&[u8]>
+    {
+        match self.glyph.font() {
+            rusttype::Font::Owned(face) => Some(face.as_slice()),
+            rusttype::Font::Ref(_) => None
+        }
+    }
+
     /// The identifier of the font which was used to render this glyph.
     #[inline]
     #[must_use]
@@ -997,6 +1030,15 @@ pub struct FormattedTextBlock
 
 impl FormattedTextBlock
 {
+    pub(crate) fn new(lines: Vec<FormattedTextLine>, width: f32, height: f32) -> Self
+    {
+        FormattedTextBlock {
+            lines: Arc::new(lines.into_iter().collect()),
+            width,
+            height
+        }
+    }
+
     /// Iterate over the lines of text in this block.
     #[inline]
     pub fn iter_lines(&self) -> Iter<'_, FormattedTextLine>
@@ -1044,6 +1086,26 @@ pub struct FormattedTextLine
 
 impl FormattedTextLine
 {
+    pub(crate) fn new(
+        glyphs: Vec<FormattedGlyph>,
+        baseline_vertical_position: f32,
+        width: f32,
+        ascent: f32,
+        descent: f32,
+        line_gap: f32
+    ) -> Self
+    {
+        FormattedTextLine {
+            glyphs: Arc::new(glyphs.into_iter().collect()),
+            baseline_vertical_position,
+            width,
+            height: ascent - descent,
+            ascent,
+            descent,
+            line_gap
+        }
+    }
+
     /// Iterate over the glyphs in this line.
     #[inline]
     pub fn iter_glyphs(&self) -> Iter<'_, FormattedGlyph>
@@ -28,7 +28,8 @@ use crate::glwrapper::{
     GLContextManager,
     GLTexture,
     GLTextureImageFormatU8,
-    GLTextureSmoothing
+    GLTextureSmoothing,
+    GLTextureWrap
 };
 use crate::numeric::RoundFloat;
 use crate::renderer2d::{Renderer2DAction, Renderer2DVertex};
@@ -103,13 +104,23 @@ impl GlyphCacheKey
     }
 }
 
+/// Counters for the work done by the glyph cache.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct GlyphCacheStatistics
+{
+    pub hits: usize,
+    pub misses: usize,
+    pub textures_allocated: usize
+}
+
 pub(crate) struct GlyphCache
 {
     last_frame: HashSet<GlyphCacheKey>,
     this_frame: HashSet<GlyphCacheKey>,
 
     cache_entries: HashMap<GlyphCacheKey, GlyphCacheEntry>,
-    textures: Vec<GlyphCacheTexture>
+    textures: Vec<GlyphCacheTexture>,
+    statistics: GlyphCacheStatistics
 }
 
 impl GlyphCache
@@ -206,21 +217,30 @@ impl GlyphCache
                     texture_coord: *texture_region.top_left(),
                     color,
                     texture_mix: 1.0,
-                    circle_mix: 0.0
+                    circle_mix: 0.0,
+                    circle_inner_radius: Vec2::ZERO,
+                    gradient_coord: Vec2::ZERO,
+                    gradient_type: 0.0
                 },
                 Renderer2DVertex {
                     position: screen_region.top_right(),
                     texture_coord: texture_region.top_right(),
                     color,
                     texture_mix: 1.0,
-                    circle_mix: 0.0
+                    circle_mix: 0.0,
+                    circle_inner_radius: Vec2::ZERO,
+                    gradient_coord: Vec2::ZERO,
+                    gradient_type: 0.0
                 },
                 Renderer2DVertex {
                     position: *screen_region.bottom_right(),
                     texture_coord: *texture_region.bottom_right(),
                     color,
                     texture_mix: 1.0,
-                    circle_mix: 0.0
+                    circle_mix: 0.0,
+                    circle_inner_radius: Vec2::ZERO,
+                    gradient_coord: Vec2::ZERO,
+                    gradient_type: 0.0
                 }
             ]
         });
@@ -233,21 +253,30 @@ impl GlyphCache
                     texture_coord: *texture_region.bottom_right(),
                     color,
                     texture_mix: 1.0,
-                    circle_mix: 0.0
+                    circle_mix: 0.0,
+                    circle_inner_radius: Vec2::ZERO,
+                    gradient_coord: Vec2::ZERO,
+                    gradient_type: 0.0
                 },
                 Renderer2DVertex {
                     position: screen_region.bottom_left(),
                     texture_coord: texture_region.bottom_left(),
                     color,
                     texture_mix: 1.0,
-                    circle_mix: 0.0
+                    circle_mix: 0.0,
+                    circle_inner_radius: Vec2::ZERO,
+                    gradient_coord: Vec2::ZERO,
+                    gradient_type: 0.0
                 },
                 Renderer2DVertex {
                     position: *screen_region.top_left(),
                     texture_coord: *texture_region.top_left(),
                     color,
                     texture_mix: 1.0,
-                    circle_mix: 0.0
+                    circle_mix: 0.0,
+                    circle_inner_radius: Vec2::ZERO,
+                    gradient_coord: Vec2::ZERO,
+                    gradient_type: 0.0
                 }
             ]
         });
@@ -273,6 +302,7 @@ impl GlyphCache
         match cache_entries.entry(key.clone()) {
             Entry::Occupied(_) => {
                 // Already in the cache, nothing to do
+                self.statistics.hits += 1;
             }
 
             Entry::Vacant(entry) => {
@@ -320,10 +350,19 @@ impl GlyphCache
                     ),
                     texture_id: None
                 });
+
+                self.statistics.misses += 1;
             }
         }
     }
 
+    /// Returns the work done since the previous call to this function, and
+    /// resets the counters to zero.
+    pub(crate) fn take_statistics(&mut self) -> GlyphCacheStatistics
+    {
+        std::mem::take(&mut self.statistics)
+    }
+
     pub(crate) fn on_new_frame_start(&mut self)
     {
         self.last_frame.clear();
@@ -374,7 +413,8 @@ impl GlyphCache
                     &mut self.textures,
                     &mut cleared_textures,
                     key,
-                    &entry.glyph_bitmap
+                    &entry.glyph_bitmap,
+                    &mut self.statistics.textures_allocated
                 )
                 .map_err(|err| {
                     ErrorMessage::msg_with_cause("Glyph rearrangement failed", err)
@@ -404,7 +444,8 @@ impl GlyphCache
             last_frame: HashSet::new(),
             this_frame: HashSet::new(),
             cache_entries: HashMap::new(),
-            textures: Vec::new()
+            textures: Vec::new(),
+            statistics: GlyphCacheStatistics::default()
         }
     }
 
@@ -449,7 +490,8 @@ impl GlyphCache
         current_textures: &mut Vec<GlyphCacheTexture>,
         previous_textures: &mut Vec<GlyphCacheTexture>,
         key: &GlyphCacheKey,
-        glyph_bitmap: &Rc<BitmapRGBA>
+        glyph_bitmap: &Rc<BitmapRGBA>,
+        textures_allocated: &mut usize
     ) -> Result<usize, BacktraceError<ErrorMessage>>
     {
         for (i, texture) in current_textures.iter_mut().enumerate() {
@@ -477,7 +519,10 @@ impl GlyphCache
         );
 
         current_textures.push(match GlyphCacheTexture::new(context) {
-            Ok(texture) => texture,
+            Ok(texture) => {
+                *textures_allocated += 1;
+                texture
+            }
             Err(err) => {
                 return Err(ErrorMessage::msg_with_cause(
                     "Failed to create new texture",
@@ -577,8 +622,9 @@ impl BitmapRGBA
             context,
             GLTextureImageFormatU8::RGBA,
             GLTextureSmoothing::NearestNeighbour,
+            GLTextureWrap::ClampToEdge,
             &self.size,
-            self.data.as_slice()
+            Some(self.data.as_slice())
         )
     }
 }
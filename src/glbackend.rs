@@ -34,17 +34,22 @@ pub mod types
     pub type GLTypeProgram = glow::Program;
     pub type GLTypeBuffer = glow::Buffer;
     pub type GLTypeTexture = glow::Texture;
+    pub type GLTypeFramebuffer = glow::Framebuffer;
+    pub type GLTypeRenderbuffer = glow::Renderbuffer;
     pub type GLTypeUniformLocation = glow::UniformLocation;
+    pub type GLActiveUniform = glow::ActiveUniform;
 }
 
 pub mod constants
 {
     use crate::glbackend::types::GLenum;
 
-    #[allow(dead_code)]
     pub const GL_VERSION: GLenum = glow::VERSION;
+    pub const GL_EXTENSIONS: GLenum = glow::EXTENSIONS;
 
     pub const GL_TEXTURE0: GLenum = glow::TEXTURE0;
+    pub const GL_MAX_TEXTURE_IMAGE_UNITS: GLenum = glow::MAX_TEXTURE_IMAGE_UNITS;
+    pub const GL_SAMPLER_2D: GLenum = glow::SAMPLER_2D;
 
     pub const GL_TEXTURE_2D: GLenum = glow::TEXTURE_2D;
 
@@ -52,20 +57,37 @@ pub mod constants
 
     pub const GL_SCISSOR_TEST: GLenum = glow::SCISSOR_TEST;
 
+    pub const GL_STENCIL_TEST: GLenum = glow::STENCIL_TEST;
+    pub const GL_KEEP: GLenum = glow::KEEP;
+    pub const GL_INCR: GLenum = glow::INCR;
+    pub const GL_REPLACE: GLenum = glow::REPLACE;
+    pub const GL_EQUAL: GLenum = glow::EQUAL;
+    pub const GL_LESS: GLenum = glow::LESS;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_ALWAYS: GLenum = glow::ALWAYS;
+
+    pub const GL_ZERO: GLenum = glow::ZERO;
     pub const GL_ONE: GLenum = glow::ONE;
+    pub const GL_DST_COLOR: GLenum = glow::DST_COLOR;
+    pub const GL_ONE_MINUS_SRC_COLOR: GLenum = glow::ONE_MINUS_SRC_COLOR;
     pub const GL_SRC_ALPHA: GLenum = glow::SRC_ALPHA;
     pub const GL_ONE_MINUS_SRC_ALPHA: GLenum = glow::ONE_MINUS_SRC_ALPHA;
 
     pub const GL_NEAREST: GLenum = glow::NEAREST;
     pub const GL_LINEAR: GLenum = glow::LINEAR;
+    pub const GL_NEAREST_MIPMAP_NEAREST: GLenum = glow::NEAREST_MIPMAP_NEAREST;
+    pub const GL_LINEAR_MIPMAP_NEAREST: GLenum = glow::LINEAR_MIPMAP_NEAREST;
+    pub const GL_LINEAR_MIPMAP_LINEAR: GLenum = glow::LINEAR_MIPMAP_LINEAR;
 
     pub const GL_ARRAY_BUFFER: GLenum = glow::ARRAY_BUFFER;
     pub const GL_ELEMENT_ARRAY_BUFFER: GLenum = glow::ELEMENT_ARRAY_BUFFER;
 
     pub const GL_DYNAMIC_DRAW: GLenum = glow::DYNAMIC_DRAW;
+    pub const GL_STATIC_DRAW: GLenum = glow::STATIC_DRAW;
 
     pub const GL_FLOAT: GLenum = glow::FLOAT;
     pub const GL_UNSIGNED_BYTE: GLenum = glow::UNSIGNED_BYTE;
+    pub const GL_UNSIGNED_INT: GLenum = glow::UNSIGNED_INT;
 
     pub const GL_R8: GLenum = glow::R8;
     pub const GL_RGB8: GLenum = glow::RGB8;
@@ -80,10 +102,13 @@ pub mod constants
     pub const GL_TEXTURE_MIN_FILTER: GLenum = glow::TEXTURE_MIN_FILTER;
     pub const GL_TEXTURE_MAG_FILTER: GLenum = glow::TEXTURE_MAG_FILTER;
     pub const GL_CLAMP_TO_EDGE: GLenum = glow::CLAMP_TO_EDGE;
+    pub const GL_REPEAT: GLenum = glow::REPEAT;
+    pub const GL_MIRRORED_REPEAT: GLenum = glow::MIRRORED_REPEAT;
 
     pub const GL_TRIANGLES: GLenum = glow::TRIANGLES;
 
     pub const GL_COLOR_BUFFER_BIT: GLenum = glow::COLOR_BUFFER_BIT;
+    pub const GL_STENCIL_BUFFER_BIT: GLenum = glow::STENCIL_BUFFER_BIT;
 
     pub const GL_NO_ERROR: GLenum = glow::NO_ERROR;
     pub const GL_INVALID_ENUM: GLenum = glow::INVALID_ENUM;
@@ -102,6 +127,17 @@ pub mod constants
     pub const GL_INFO_LOG_LENGTH: GLenum = glow::INFO_LOG_LENGTH;
 
     pub const GL_UNPACK_ALIGNMENT: GLenum = glow::UNPACK_ALIGNMENT;
+    pub const GL_PACK_ALIGNMENT: GLenum = glow::PACK_ALIGNMENT;
+
+    pub const GL_FRAMEBUFFER: GLenum = glow::FRAMEBUFFER;
+    pub const GL_COLOR_ATTACHMENT0: GLenum = glow::COLOR_ATTACHMENT0;
+    pub const GL_FRAMEBUFFER_COMPLETE: GLenum = glow::FRAMEBUFFER_COMPLETE;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT: GLenum =
+        glow::FRAMEBUFFER_INCOMPLETE_ATTACHMENT;
+    pub const GL_RENDERBUFFER: GLenum = glow::RENDERBUFFER;
+    pub const GL_DEPTH_STENCIL_ATTACHMENT: GLenum = glow::DEPTH_STENCIL_ATTACHMENT;
+    pub const GL_DEPTH24_STENCIL8: GLenum = glow::DEPTH24_STENCIL8;
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
@@ -148,8 +184,44 @@ pub trait GLBackend
     unsafe fn gl_delete_shader(&self, handle: GLTypeShader);
     unsafe fn gl_delete_buffer(&self, handle: GLTypeBuffer);
     unsafe fn gl_delete_texture(&self, handle: GLTypeTexture);
+    unsafe fn gl_delete_framebuffer(&self, handle: GLTypeFramebuffer);
+    unsafe fn gl_delete_renderbuffer(&self, handle: GLTypeRenderbuffer);
     unsafe fn gl_active_texture(&self, unit: GLenum);
     unsafe fn gl_bind_texture(&self, target: GLenum, handle: GLTypeTexture);
+    unsafe fn gl_bind_framebuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeFramebuffer>
+    );
+    unsafe fn gl_framebuffer_texture_2d(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        texture_target: GLenum,
+        texture: Option<GLTypeTexture>,
+        level: GLint
+    );
+    unsafe fn gl_bind_renderbuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeRenderbuffer>
+    );
+    unsafe fn gl_renderbuffer_storage(
+        &self,
+        target: GLenum,
+        internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei
+    );
+    unsafe fn gl_framebuffer_renderbuffer(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: Option<GLTypeRenderbuffer>
+    );
+    #[must_use]
+    unsafe fn gl_check_framebuffer_status(&self, target: GLenum) -> GLenum;
     unsafe fn gl_enable(&self, cap: GLenum);
     unsafe fn gl_disable(&self, cap: GLenum);
     #[allow(dead_code)]
@@ -166,14 +238,32 @@ pub trait GLBackend
     unsafe fn gl_disable_vertex_attrib_array(&self, handle: GLuint);
     unsafe fn gl_uniform_1f(&self, handle: &GLTypeUniformLocation, value: f32);
     unsafe fn gl_uniform_1i(&self, handle: &GLTypeUniformLocation, value: GLint);
+    unsafe fn gl_uniform_1fv(&self, handle: &GLTypeUniformLocation, values: &[f32]);
+    unsafe fn gl_uniform_2fv(&self, handle: &GLTypeUniformLocation, values: &[f32]);
+    unsafe fn gl_uniform_3fv(&self, handle: &GLTypeUniformLocation, values: &[f32]);
+    unsafe fn gl_uniform_4fv(&self, handle: &GLTypeUniformLocation, values: &[f32]);
     unsafe fn gl_attach_shader(&self, program: GLTypeProgram, shader: GLTypeShader);
     unsafe fn gl_link_program(&self, program: GLTypeProgram);
+    unsafe fn gl_bind_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint,
+        name: &str
+    );
     unsafe fn gl_shader_source(&self, handle: GLTypeShader, source: &str);
     unsafe fn gl_compile_shader(&self, handle: GLTypeShader);
     unsafe fn gl_tex_parameter_i(&self, target: GLenum, parameter: GLenum, value: GLint);
+    unsafe fn gl_generate_mipmap(&self, target: GLenum);
     unsafe fn gl_bind_buffer(&self, target: GLenum, handle: GLTypeBuffer);
     unsafe fn gl_buffer_data(&self, target: GLenum, data: &[u8], usage: GLenum);
     unsafe fn gl_draw_arrays(&self, mode: GLenum, first: GLint, count: GLsizei);
+    unsafe fn gl_draw_elements(
+        &self,
+        mode: GLenum,
+        count: GLsizei,
+        element_type: GLenum,
+        offset: GLint
+    );
     unsafe fn gl_clear_color(&self, r: f32, g: f32, b: f32, a: f32);
     unsafe fn gl_clear(&self, mask: GLenum);
     #[allow(dead_code)]
@@ -182,6 +272,15 @@ pub trait GLBackend
     unsafe fn gl_get_string(&self, parameter: GLenum) -> String;
     unsafe fn gl_viewport(&self, x: i32, y: i32, width: i32, height: i32);
     unsafe fn gl_scissor(&self, x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+    unsafe fn gl_clear_stencil(&self, value: GLint);
+    unsafe fn gl_stencil_func(&self, func: GLenum, reference: GLint, mask: GLuint);
+    unsafe fn gl_stencil_op(
+        &self,
+        stencil_fail: GLenum,
+        depth_fail: GLenum,
+        pass: GLenum
+    );
+    unsafe fn gl_color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool);
     unsafe fn gl_pixel_store_i(&self, param: GLenum, value: GLint);
 
     unsafe fn gl_vertex_attrib_pointer_f32(
@@ -208,7 +307,7 @@ pub trait GLBackend
         pixels: Option<&[u8]>
     );
 
-    #[allow(clippy::too_many_arguments, dead_code)]
+    #[allow(clippy::too_many_arguments)]
     unsafe fn gl_tex_sub_image_2d(
         &self,
         target: GLenum,
@@ -237,6 +336,14 @@ pub trait GLBackend
         &self
     ) -> Result<GLTypeTexture, BacktraceError<ErrorMessage>>;
 
+    unsafe fn gl_gen_framebuffer(
+        &self
+    ) -> Result<GLTypeFramebuffer, BacktraceError<ErrorMessage>>;
+
+    unsafe fn gl_gen_renderbuffer(
+        &self
+    ) -> Result<GLTypeRenderbuffer, BacktraceError<ErrorMessage>>;
+
     #[must_use]
     unsafe fn gl_get_error(&self) -> GLenum;
 
@@ -257,6 +364,19 @@ pub trait GLBackend
     #[must_use]
     unsafe fn gl_get_program_link_status(&self, program: GLTypeProgram) -> bool;
 
+    #[must_use]
+    unsafe fn gl_get_active_uniforms(&self, program: GLTypeProgram) -> GLuint;
+
+    #[must_use]
+    unsafe fn gl_get_active_uniform(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint
+    ) -> Option<GLActiveUniform>;
+
+    #[must_use]
+    unsafe fn gl_get_parameter_i32(&self, parameter: GLenum) -> GLint;
+
     #[must_use]
     unsafe fn gl_get_shader_compile_status(&self, shader: GLTypeShader) -> bool;
 
@@ -310,6 +430,16 @@ pub trait GLBackend
         self.gl_buffer_data(target, data, usage)
     }
 
+    unsafe fn gl_buffer_data_u32(&self, target: GLenum, data: &[u32], usage: GLenum)
+    {
+        let data = std::slice::from_raw_parts(
+            data.as_ptr() as *const u8,
+            std::mem::size_of_val(data)
+        );
+
+        self.gl_buffer_data(target, data, usage)
+    }
+
     #[allow(clippy::too_many_arguments)]
     unsafe fn gl_read_pixels(
         &self,
@@ -359,6 +489,16 @@ impl GLBackend for GLBackendGlow
         self.context.delete_texture(handle)
     }
 
+    unsafe fn gl_delete_framebuffer(&self, handle: GLTypeFramebuffer)
+    {
+        self.context.delete_framebuffer(handle)
+    }
+
+    unsafe fn gl_delete_renderbuffer(&self, handle: GLTypeRenderbuffer)
+    {
+        self.context.delete_renderbuffer(handle)
+    }
+
     unsafe fn gl_active_texture(&self, unit: GLenum)
     {
         self.context.active_texture(unit)
@@ -369,6 +509,75 @@ impl GLBackend for GLBackendGlow
         self.context.bind_texture(target, Some(handle))
     }
 
+    unsafe fn gl_bind_framebuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeFramebuffer>
+    )
+    {
+        self.context.bind_framebuffer(target, handle)
+    }
+
+    unsafe fn gl_framebuffer_texture_2d(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        texture_target: GLenum,
+        texture: Option<GLTypeTexture>,
+        level: GLint
+    )
+    {
+        self.context.framebuffer_texture_2d(
+            target,
+            attachment,
+            texture_target,
+            texture,
+            level
+        )
+    }
+
+    unsafe fn gl_bind_renderbuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeRenderbuffer>
+    )
+    {
+        self.context.bind_renderbuffer(target, handle)
+    }
+
+    unsafe fn gl_renderbuffer_storage(
+        &self,
+        target: GLenum,
+        internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei
+    )
+    {
+        self.context
+            .renderbuffer_storage(target, internal_format, width, height)
+    }
+
+    unsafe fn gl_framebuffer_renderbuffer(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: Option<GLTypeRenderbuffer>
+    )
+    {
+        self.context.framebuffer_renderbuffer(
+            target,
+            attachment,
+            renderbuffer_target,
+            renderbuffer
+        )
+    }
+
+    unsafe fn gl_check_framebuffer_status(&self, target: GLenum) -> GLenum
+    {
+        self.context.check_framebuffer_status(target)
+    }
+
     unsafe fn gl_enable(&self, cap: GLenum)
     {
         self.context.enable(cap)
@@ -421,6 +630,26 @@ impl GLBackend for GLBackendGlow
         self.context.uniform_1_i32(Some(handle), value)
     }
 
+    unsafe fn gl_uniform_1fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.context.uniform_1_f32_slice(Some(handle), values)
+    }
+
+    unsafe fn gl_uniform_2fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.context.uniform_2_f32_slice(Some(handle), values)
+    }
+
+    unsafe fn gl_uniform_3fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.context.uniform_3_f32_slice(Some(handle), values)
+    }
+
+    unsafe fn gl_uniform_4fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.context.uniform_4_f32_slice(Some(handle), values)
+    }
+
     unsafe fn gl_attach_shader(&self, program: GLTypeProgram, shader: GLTypeShader)
     {
         self.context.attach_shader(program, shader)
@@ -431,6 +660,16 @@ impl GLBackend for GLBackendGlow
         self.context.link_program(program)
     }
 
+    unsafe fn gl_bind_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint,
+        name: &str
+    )
+    {
+        self.context.bind_attrib_location(program, index, name)
+    }
+
     unsafe fn gl_shader_source(&self, handle: GLTypeShader, source: &str)
     {
         self.context.shader_source(handle, source)
@@ -446,6 +685,11 @@ impl GLBackend for GLBackendGlow
         self.context.tex_parameter_i32(target, parameter, value)
     }
 
+    unsafe fn gl_generate_mipmap(&self, target: u32)
+    {
+        self.context.generate_mipmap(target)
+    }
+
     unsafe fn gl_bind_buffer(&self, target: u32, handle: GLTypeBuffer)
     {
         self.context.bind_buffer(target, Some(handle))
@@ -461,6 +705,18 @@ impl GLBackend for GLBackendGlow
         self.context.draw_arrays(mode, first, count)
     }
 
+    unsafe fn gl_draw_elements(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32
+    )
+    {
+        self.context
+            .draw_elements(mode, count, element_type, offset)
+    }
+
     unsafe fn gl_clear_color(&self, r: f32, g: f32, b: f32, a: f32)
     {
         self.context.clear_color(r, g, b, a)
@@ -516,6 +772,26 @@ impl GLBackend for GLBackendGlow
         self.context.scissor(x, y, width, height);
     }
 
+    unsafe fn gl_clear_stencil(&self, value: GLint)
+    {
+        self.context.clear_stencil(value)
+    }
+
+    unsafe fn gl_stencil_func(&self, func: GLenum, reference: GLint, mask: GLuint)
+    {
+        self.context.stencil_func(func, reference, mask)
+    }
+
+    unsafe fn gl_stencil_op(&self, stencil_fail: GLenum, depth_fail: GLenum, pass: GLenum)
+    {
+        self.context.stencil_op(stencil_fail, depth_fail, pass)
+    }
+
+    unsafe fn gl_color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        self.context.color_mask(red, green, blue, alpha)
+    }
+
     unsafe fn gl_pixel_store_i(&self, param: u32, value: i32)
     {
         self.context.pixel_store_i32(param, value)
@@ -629,6 +905,28 @@ impl GLBackend for GLBackendGlow
         Ok(handle)
     }
 
+    unsafe fn gl_gen_framebuffer(
+        &self
+    ) -> Result<GLTypeFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        let handle = self.context.create_framebuffer().map_err(|err| {
+            ErrorMessage::msg(format!("Failed to create framebuffer: {err}"))
+        })?;
+
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_renderbuffer(
+        &self
+    ) -> Result<GLTypeRenderbuffer, BacktraceError<ErrorMessage>>
+    {
+        let handle = self.context.create_renderbuffer().map_err(|err| {
+            ErrorMessage::msg(format!("Failed to create renderbuffer: {err}"))
+        })?;
+
+        Ok(handle)
+    }
+
     unsafe fn gl_get_error(&self) -> GLenum
     {
         self.context.get_error()
@@ -657,6 +955,25 @@ impl GLBackend for GLBackendGlow
         self.context.get_program_link_status(program)
     }
 
+    unsafe fn gl_get_active_uniforms(&self, program: GLTypeProgram) -> GLuint
+    {
+        self.context.get_active_uniforms(program)
+    }
+
+    unsafe fn gl_get_active_uniform(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint
+    ) -> Option<GLActiveUniform>
+    {
+        self.context.get_active_uniform(program, index)
+    }
+
+    unsafe fn gl_get_parameter_i32(&self, parameter: GLenum) -> GLint
+    {
+        self.context.get_parameter_i32(parameter)
+    }
+
     unsafe fn gl_get_shader_compile_status(&self, shader: GLTypeShader) -> bool
     {
         self.context.get_shader_compile_status(shader)
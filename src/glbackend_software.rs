@@ -0,0 +1,2306 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::mem::MaybeUninit;
+
+use crate::dimen::UVec2;
+use crate::error::{BacktraceError, ErrorMessage};
+use crate::glbackend::constants::*;
+use crate::glbackend::types::*;
+use crate::glbackend::GLBackend;
+use crate::renderer2d::Renderer2D;
+
+const MAX_VERTEX_ATTRIBUTES: usize = 16;
+
+const MAX_GRADIENT_STOPS: usize = 8;
+
+/// The only fragment shaders which the software renderer is able to run.
+const BUILTIN_FRAGMENT_SHADERS: [&str; 2] = [
+    include_str!("shaders/r2d_fragment_v110.glsl"),
+    include_str!("shaders/r2d_fragment_v300es.glsl")
+];
+
+struct SoftwareShader
+{
+    source: String
+}
+
+/// The attribute locations used by the built-in shaders, which are known once
+/// the program has been linked.
+#[derive(Clone, Copy)]
+struct BuiltinAttributes
+{
+    position: usize,
+    color: usize,
+    texture_coord: usize,
+    texture_mix: usize,
+    circle_mix: usize,
+    circle_inner_radius: usize,
+    gradient_coord: usize,
+    gradient_type: usize
+}
+
+#[derive(Default)]
+struct SoftwareProgram
+{
+    shaders: Vec<GLTypeShader>,
+    attribute_bindings: HashMap<String, GLuint>,
+    attributes: Option<BuiltinAttributes>,
+    info_log: String,
+    uniforms: HashMap<String, Vec<f32>>
+}
+
+impl SoftwareProgram
+{
+    fn uniform(&self, name: &str, index: usize) -> f32
+    {
+        self.uniforms
+            .get(name)
+            .and_then(|values| values.get(index))
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct VertexAttribPointer
+{
+    buffer: GLTypeBuffer,
+    size: usize,
+    stride: usize,
+    offset: usize
+}
+
+struct SoftwareTexture
+{
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+    mag_filter: GLenum,
+    min_filter: GLenum,
+    wrap_s: GLenum,
+    wrap_t: GLenum,
+
+    /// Mipmap levels 1 and above, which are only present after calling
+    /// `gl_generate_mipmap()`.
+    mipmaps: Vec<SoftwareMipmap>
+}
+
+struct SoftwareMipmap
+{
+    width: usize,
+    height: usize,
+    data: Vec<u8>
+}
+
+impl SoftwareMipmap
+{
+    /// Creates the next mipmap level by averaging each 2x2 block of pixels.
+    fn downsample(width: usize, height: usize, data: &[u8]) -> Self
+    {
+        let result_width = (width / 2).max(1);
+        let result_height = (height / 2).max(1);
+
+        let mut result = Vec::with_capacity(result_width * result_height * 4);
+
+        for y in 0..result_height {
+            for x in 0..result_width {
+                let mut sum = [0u32; 4];
+
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let src_x = (x * 2 + dx).min(width - 1);
+                    let src_y = (y * 2 + dy).min(height - 1);
+                    let start = (src_y * width + src_x) * 4;
+
+                    for (c, value) in sum.iter_mut().enumerate() {
+                        *value += u32::from(data[start + c]);
+                    }
+                }
+
+                result.extend(sum.map(|value| ((value + 2) / 4) as u8));
+            }
+        }
+
+        SoftwareMipmap {
+            width: result_width,
+            height: result_height,
+            data: result
+        }
+    }
+}
+
+/// Maps a texel coordinate which may be outside the texture to one inside it,
+/// using the specified wrap mode.
+fn wrap_texel_coord(coord: isize, size: usize, wrap: GLenum) -> usize
+{
+    let size = size as isize;
+
+    let coord = match wrap {
+        GL_REPEAT => coord.rem_euclid(size),
+        GL_MIRRORED_REPEAT => {
+            let coord = coord.rem_euclid(size * 2);
+
+            if coord < size {
+                coord
+            } else {
+                size * 2 - 1 - coord
+            }
+        }
+        _ => coord.clamp(0, size - 1)
+    };
+
+    coord as usize
+}
+
+impl SoftwareTexture
+{
+    /// Returns the size and pixels of the specified mipmap level, where level
+    /// 0 is the texture itself. Levels which don't exist are replaced with
+    /// level 0.
+    fn level(&self, level: usize) -> (usize, usize, &[u8])
+    {
+        match level
+            .checked_sub(1)
+            .and_then(|index| self.mipmaps.get(index))
+        {
+            None => (self.width, self.height, &self.data),
+            Some(mipmap) => (mipmap.width, mipmap.height, &mipmap.data)
+        }
+    }
+
+    fn generate_mipmaps(&mut self)
+    {
+        self.mipmaps.clear();
+
+        let (mut width, mut height) = (self.width, self.height);
+
+        while width > 1 || height > 1 {
+            let (level_width, level_height, data) = self.level(self.mipmaps.len());
+            let mipmap = SoftwareMipmap::downsample(level_width, level_height, data);
+
+            width = mipmap.width;
+            height = mipmap.height;
+
+            self.mipmaps.push(mipmap);
+        }
+    }
+
+    fn texel(&self, level: usize, x: isize, y: isize) -> [f32; 4]
+    {
+        let (width, height, data) = self.level(level);
+
+        let x = wrap_texel_coord(x, width, self.wrap_s);
+        let y = wrap_texel_coord(y, height, self.wrap_t);
+        let start = (y * width + x) * 4;
+
+        let mut result = [0.0; 4];
+
+        for (i, value) in result.iter_mut().enumerate() {
+            *value = f32::from(data[start + i]) / 255.0;
+        }
+
+        result
+    }
+
+    /// Samples the texture using its wrap modes. The `lod` is the base 2
+    /// logarithm of the number of texels covered by each pixel, and selects
+    /// between the magnification filter and the minification filter, and
+    /// between mipmap levels.
+    fn sample(&self, coord: [f32; 2], lod: f32) -> [f32; 4]
+    {
+        if self.width == 0 || self.height == 0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+
+        if lod <= 0.0 {
+            return self.sample_level(0, coord, self.mag_filter);
+        }
+
+        let max_level = self.mipmaps.len() as f32;
+        let nearest_level = ((lod + 0.5).ceil() - 1.0).min(max_level) as usize;
+
+        match self.min_filter {
+            GL_NEAREST_MIPMAP_NEAREST => {
+                self.sample_level(nearest_level, coord, GL_NEAREST)
+            }
+            GL_LINEAR_MIPMAP_NEAREST => {
+                self.sample_level(nearest_level, coord, GL_LINEAR)
+            }
+            GL_LINEAR_MIPMAP_LINEAR => {
+                let lod = lod.min(max_level);
+                let lower_level = lod.floor();
+                let amount = lod - lower_level;
+
+                let lower = self.sample_level(lower_level as usize, coord, GL_LINEAR);
+
+                if amount == 0.0 {
+                    return lower;
+                }
+
+                let upper = self.sample_level(lower_level as usize + 1, coord, GL_LINEAR);
+
+                [0, 1, 2, 3].map(|c| mix(lower[c], upper[c], amount))
+            }
+            filter => self.sample_level(0, coord, filter)
+        }
+    }
+
+    fn sample_level(&self, level: usize, coord: [f32; 2], filter: GLenum) -> [f32; 4]
+    {
+        let (width, height, _) = self.level(level);
+
+        let x = coord[0] * width as f32;
+        let y = coord[1] * height as f32;
+
+        if filter == GL_NEAREST {
+            return self.texel(level, x.floor() as isize, y.floor() as isize);
+        }
+
+        let x = x - 0.5;
+        let y = y - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+
+        let fx = x - x0;
+        let fy = y - y0;
+
+        let x0 = x0 as isize;
+        let y0 = y0 as isize;
+
+        let top_left = self.texel(level, x0, y0);
+        let top_right = self.texel(level, x0 + 1, y0);
+        let bottom_left = self.texel(level, x0, y0 + 1);
+        let bottom_right = self.texel(level, x0 + 1, y0 + 1);
+
+        let mut result = [0.0; 4];
+
+        for i in 0..4 {
+            let top = mix(top_left[i], top_right[i], fx);
+            let bottom = mix(bottom_left[i], bottom_right[i], fx);
+            result[i] = mix(top, bottom, fy);
+        }
+
+        result
+    }
+}
+
+struct SoftwareRenderbuffer
+{
+    width: usize,
+    height: usize,
+    data: Vec<u8>
+}
+
+#[derive(Default)]
+struct SoftwareFramebuffer
+{
+    color: Option<GLTypeTexture>,
+    stencil: Option<GLTypeRenderbuffer>
+}
+
+/// The pixels being drawn to, temporarily taken out of the texture or
+/// renderbuffer which owns them.
+struct RenderTarget
+{
+    width: usize,
+    height: usize,
+    color: Vec<u8>,
+    stencil: Option<Vec<u8>>
+}
+
+#[derive(Clone, Copy)]
+struct ClipRect
+{
+    min_x: i64,
+    min_y: i64,
+    max_x: i64,
+    max_y: i64
+}
+
+impl ClipRect
+{
+    fn new(x: i32, y: i32, width: i32, height: i32) -> Self
+    {
+        ClipRect {
+            min_x: x.into(),
+            min_y: y.into(),
+            max_x: i64::from(x) + i64::from(width),
+            max_y: i64::from(y) + i64::from(height)
+        }
+    }
+
+    fn intersect(&self, other: &ClipRect) -> ClipRect
+    {
+        ClipRect {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y)
+        }
+    }
+}
+
+/// The values passed from the vertex shader to the fragment shader.
+#[derive(Clone, Copy, Default)]
+struct Varyings
+{
+    color: [f32; 4],
+    texture_coord: [f32; 2],
+    texture_mix: f32,
+    circle_mix: f32,
+    circle_inner_radius: [f32; 2],
+    gradient_coord: [f32; 2],
+    gradient_type: f32
+}
+
+impl Varyings
+{
+    fn interpolate(vertices: [&Varyings; 3], weights: [f32; 3]) -> Varyings
+    {
+        let blend = |get: &dyn Fn(&Varyings) -> f32| {
+            get(vertices[0]) * weights[0]
+                + get(vertices[1]) * weights[1]
+                + get(vertices[2]) * weights[2]
+        };
+
+        Varyings {
+            color: [
+                blend(&|v| v.color[0]),
+                blend(&|v| v.color[1]),
+                blend(&|v| v.color[2]),
+                blend(&|v| v.color[3])
+            ],
+            texture_coord: [
+                blend(&|v| v.texture_coord[0]),
+                blend(&|v| v.texture_coord[1])
+            ],
+            texture_mix: blend(&|v| v.texture_mix),
+            circle_mix: blend(&|v| v.circle_mix),
+            circle_inner_radius: [
+                blend(&|v| v.circle_inner_radius[0]),
+                blend(&|v| v.circle_inner_radius[1])
+            ],
+            gradient_coord: [
+                blend(&|v| v.gradient_coord[0]),
+                blend(&|v| v.gradient_coord[1])
+            ],
+            gradient_type: blend(&|v| v.gradient_type)
+        }
+    }
+}
+
+struct ShadedVertex
+{
+    x: f64,
+    y: f64,
+    varyings: Varyings
+}
+
+/// The uniforms used by the built-in fragment shader, read once per draw
+/// call.
+struct FragmentUniforms<'a>
+{
+    texture: Option<&'a SoftwareTexture>,
+    gradient_stop_count: usize,
+    gradient_stop_offsets: [f32; MAX_GRADIENT_STOPS],
+    gradient_stop_colors: [[f32; 4]; MAX_GRADIENT_STOPS],
+    premultiply_alpha: f32,
+    texture_premultiplied: f32
+}
+
+impl FragmentUniforms<'_>
+{
+    fn gradient_color(&self, offset: f32) -> [f32; 4]
+    {
+        let mut result = self.gradient_stop_colors[0];
+
+        for i in 1..self.gradient_stop_count.min(MAX_GRADIENT_STOPS) {
+            let previous_offset = self.gradient_stop_offsets[i - 1];
+            let segment_length = self.gradient_stop_offsets[i] - previous_offset;
+
+            let blend = ((offset - previous_offset) / segment_length.max(0.00001))
+                .clamp(0.0, 1.0);
+
+            for (c, value) in result.iter_mut().enumerate() {
+                *value = mix(*value, self.gradient_stop_colors[i][c], blend);
+            }
+        }
+
+        result
+    }
+
+    /// Equivalent to the `main()` function of the built-in fragment shader.
+    /// Returns `None` if the fragment is discarded.
+    fn shade(&self, v: &Varyings, lod: f32) -> Option<[f32; 4]>
+    {
+        let tex_col = match self.texture {
+            None => [0.0, 0.0, 0.0, 1.0],
+            Some(texture) => texture.sample(v.texture_coord, lod)
+        };
+
+        let [tx, ty] = v.texture_coord;
+        let [ix, iy] = v.circle_inner_radius;
+
+        let tex_coord_mag_squared = tx * tx + ty * ty;
+
+        let inner_scaled = [tx * iy, ty * ix];
+        let inner_radius_product = ix * iy;
+
+        let inner_alpha = step(
+            inner_radius_product * inner_radius_product,
+            inner_scaled[0] * inner_scaled[0] + inner_scaled[1] * inner_scaled[1]
+        );
+
+        let circle_alpha = (1.0 - step(1.0, tex_coord_mag_squared)) * inner_alpha;
+
+        if v.circle_mix * (1.0 - circle_alpha) > 0.5 {
+            return None;
+        }
+
+        let gradient_mix = step(0.5, v.gradient_type);
+
+        let paint_color = if gradient_mix > 0.0 {
+            let [gx, gy] = v.gradient_coord;
+
+            self.gradient_color(mix(
+                gx,
+                (gx * gx + gy * gy).sqrt(),
+                step(1.5, v.gradient_type)
+            ))
+        } else {
+            [1.0; 4]
+        };
+
+        let plain_mix = 1.0 - v.texture_mix - v.circle_mix;
+
+        let mut color = [0.0; 4];
+
+        for c in 0..4 {
+            let circle = if c == 3 { circle_alpha } else { 1.0 };
+
+            color[c] = v.color[c]
+                * paint_color[c]
+                * (plain_mix + tex_col[c] * v.texture_mix + circle * v.circle_mix);
+        }
+
+        let alpha_factor = mix(
+            1.0,
+            mix(
+                color[3],
+                v.color[3] * paint_color[3] * mix(1.0, circle_alpha, v.circle_mix),
+                self.texture_premultiplied
+            ),
+            self.premultiply_alpha
+        );
+
+        for value in color.iter_mut().take(3) {
+            *value *= alpha_factor;
+        }
+
+        Some(color.map(|value| value.clamp(0.0, 1.0)))
+    }
+}
+
+struct SoftwareState
+{
+    next_handle: u32,
+
+    shaders: HashMap<GLTypeShader, SoftwareShader>,
+    programs: HashMap<GLTypeProgram, SoftwareProgram>,
+    uniform_locations: HashMap<GLTypeUniformLocation, (GLTypeProgram, String)>,
+    buffers: HashMap<GLTypeBuffer, Vec<u8>>,
+    textures: HashMap<GLTypeTexture, SoftwareTexture>,
+    framebuffers: HashMap<GLTypeFramebuffer, SoftwareFramebuffer>,
+    renderbuffers: HashMap<GLTypeRenderbuffer, SoftwareRenderbuffer>,
+
+    default_framebuffer: RenderTarget,
+
+    active_program: Option<GLTypeProgram>,
+    active_texture_unit: u32,
+    bound_textures: HashMap<u32, GLTypeTexture>,
+    bound_array_buffer: Option<GLTypeBuffer>,
+    bound_element_array_buffer: Option<GLTypeBuffer>,
+    bound_framebuffer: Option<GLTypeFramebuffer>,
+    bound_renderbuffer: Option<GLTypeRenderbuffer>,
+
+    attrib_pointers: [Option<VertexAttribPointer>; MAX_VERTEX_ATTRIBUTES],
+    attrib_enabled: [bool; MAX_VERTEX_ATTRIBUTES],
+
+    viewport: ClipRect,
+    scissor: ClipRect,
+
+    blend_enabled: bool,
+    scissor_enabled: bool,
+    stencil_enabled: bool,
+
+    blend_factors: [GLenum; 4],
+    stencil_func: (GLenum, u8, u8),
+    stencil_ops: (GLenum, GLenum),
+    color_mask: [bool; 4],
+
+    clear_color: [f32; 4],
+    clear_stencil: u8,
+    unpack_alignment: usize
+}
+
+impl SoftwareState
+{
+    fn new_handle(&mut self) -> u32
+    {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    fn bound_texture_mut(&mut self) -> Option<&mut SoftwareTexture>
+    {
+        match self.bound_textures.get(&self.active_texture_unit) {
+            None => None,
+            Some(handle) => self.textures.get_mut(handle)
+        }
+    }
+
+    fn set_uniform(&mut self, location: &GLTypeUniformLocation, values: &[f32])
+    {
+        if let Some((program, name)) = self.uniform_locations.get(location) {
+            if let Some(program) = self.programs.get_mut(program) {
+                program.uniforms.insert(name.clone(), values.to_vec());
+            }
+        }
+    }
+
+    /// Moves the pixels of the current draw target out of the state, so that
+    /// they can be modified while textures are being sampled. They must be
+    /// returned using `restore_render_target()`.
+    fn take_render_target(&mut self) -> Option<RenderTarget>
+    {
+        let framebuffer = match self.bound_framebuffer {
+            None => {
+                let target = &mut self.default_framebuffer;
+
+                return Some(RenderTarget {
+                    width: target.width,
+                    height: target.height,
+                    color: std::mem::take(&mut target.color),
+                    stencil: target.stencil.take()
+                });
+            }
+            Some(handle) => self.framebuffers.get(&handle)?
+        };
+
+        let stencil_handle = framebuffer.stencil;
+        let texture = self.textures.get_mut(&framebuffer.color?)?;
+        let renderbuffers = &mut self.renderbuffers;
+
+        let stencil = stencil_handle
+            .and_then(|handle| renderbuffers.get_mut(&handle))
+            .filter(|renderbuffer| {
+                renderbuffer.width == texture.width
+                    && renderbuffer.height == texture.height
+            })
+            .map(|renderbuffer| std::mem::take(&mut renderbuffer.data));
+
+        Some(RenderTarget {
+            width: texture.width,
+            height: texture.height,
+            color: std::mem::take(&mut texture.data),
+            stencil
+        })
+    }
+
+    fn restore_render_target(&mut self, target: RenderTarget)
+    {
+        let (color_handle, stencil_handle) = match self.bound_framebuffer {
+            None => {
+                self.default_framebuffer = target;
+                return;
+            }
+            Some(handle) => match self.framebuffers.get(&handle) {
+                None => return,
+                Some(framebuffer) => (framebuffer.color, framebuffer.stencil)
+            }
+        };
+
+        if let Some(texture) =
+            color_handle.and_then(|handle| self.textures.get_mut(&handle))
+        {
+            texture.data = target.color;
+        }
+
+        if let (Some(renderbuffer), Some(stencil)) = (
+            stencil_handle.and_then(|handle| self.renderbuffers.get_mut(&handle)),
+            target.stencil
+        ) {
+            renderbuffer.data = stencil;
+        }
+    }
+
+    fn clip_rect(&self, target: &RenderTarget) -> ClipRect
+    {
+        let clip = ClipRect::new(0, 0, target.width as i32, target.height as i32)
+            .intersect(&self.viewport);
+
+        match self.scissor_enabled {
+            true => clip.intersect(&self.scissor),
+            false => clip
+        }
+    }
+
+    fn attribute(&self, index: usize, vertex: usize) -> [f32; 4]
+    {
+        let mut result = [0.0, 0.0, 0.0, 1.0];
+
+        if !self.attrib_enabled[index] {
+            return result;
+        }
+
+        let pointer = match self.attrib_pointers[index] {
+            None => return result,
+            Some(pointer) => pointer
+        };
+
+        let data = match self.buffers.get(&pointer.buffer) {
+            None => return result,
+            Some(data) => data
+        };
+
+        let value_size = std::mem::size_of::<f32>();
+
+        let stride = match pointer.stride {
+            0 => pointer.size * value_size,
+            stride => stride
+        };
+
+        let start = pointer.offset + vertex * stride;
+
+        for (i, value) in result.iter_mut().take(pointer.size).enumerate() {
+            let value_start = start + i * value_size;
+
+            if let Some(bytes) = data.get(value_start..value_start + value_size) {
+                *value = f32::from_ne_bytes(bytes.try_into().unwrap());
+            }
+        }
+
+        result
+    }
+
+    /// Equivalent to the built-in vertex shader, followed by the viewport
+    /// transformation.
+    fn shade_vertex(
+        &self,
+        program: &SoftwareProgram,
+        attributes: &BuiltinAttributes,
+        vertex: usize
+    ) -> ShadedVertex
+    {
+        let position = self.attribute(attributes.position, vertex);
+
+        let transform_row = |name: &str| {
+            program.uniform(name, 0) * position[0]
+                + program.uniform(name, 1) * position[1]
+                + program.uniform(name, 2)
+        };
+
+        let position = [
+            transform_row(Renderer2D::UNIFORM_NAME_TRANSFORM_X),
+            transform_row(Renderer2D::UNIFORM_NAME_TRANSFORM_Y)
+        ];
+
+        let mut color = self.attribute(attributes.color, vertex);
+
+        for (i, value) in color.iter_mut().enumerate() {
+            *value *= program.uniform(Renderer2D::UNIFORM_NAME_TINT, i);
+        }
+
+        let scale_x = program.uniform(Renderer2D::UNIFORM_NAME_SCALE_X, 0);
+        let scale_y = program.uniform(Renderer2D::UNIFORM_NAME_SCALE_Y, 0);
+        let offset_y = program.uniform(Renderer2D::UNIFORM_NAME_OFFSET_Y, 0);
+
+        let ndc_x = f64::from(position[0] * scale_x - 1.0);
+        let ndc_y = f64::from(position[1] * scale_y + offset_y);
+
+        let viewport = &self.viewport;
+
+        let two_values = |index: usize| {
+            let value = self.attribute(index, vertex);
+            [value[0], value[1]]
+        };
+
+        let x = viewport.min_x as f64
+            + (ndc_x + 1.0) * (viewport.max_x - viewport.min_x) as f64 / 2.0;
+        let y = viewport.min_y as f64
+            + (ndc_y + 1.0) * (viewport.max_y - viewport.min_y) as f64 / 2.0;
+
+        ShadedVertex {
+            x: snap_to_subpixel(x),
+            y: snap_to_subpixel(y),
+            varyings: Varyings {
+                color,
+                texture_coord: two_values(attributes.texture_coord),
+                texture_mix: self.attribute(attributes.texture_mix, vertex)[0],
+                circle_mix: self.attribute(attributes.circle_mix, vertex)[0],
+                circle_inner_radius: two_values(attributes.circle_inner_radius),
+                gradient_coord: two_values(attributes.gradient_coord),
+                gradient_type: self.attribute(attributes.gradient_type, vertex)[0]
+            }
+        }
+    }
+
+    fn fragment_uniforms(&self, program: &SoftwareProgram) -> FragmentUniforms<'_>
+    {
+        let texture_unit = program.uniform(Renderer2D::UNIFORM_NAME_TEXTURE, 0) as u32;
+
+        let mut uniforms = FragmentUniforms {
+            texture: self
+                .bound_textures
+                .get(&texture_unit)
+                .and_then(|handle| self.textures.get(handle)),
+            gradient_stop_count: program
+                .uniform(Renderer2D::UNIFORM_NAME_GRADIENT_STOP_COUNT, 0)
+                as usize,
+            gradient_stop_offsets: [0.0; MAX_GRADIENT_STOPS],
+            gradient_stop_colors: [[0.0; 4]; MAX_GRADIENT_STOPS],
+            premultiply_alpha: program
+                .uniform(Renderer2D::UNIFORM_NAME_PREMULTIPLY_ALPHA, 0),
+            texture_premultiplied: program
+                .uniform(Renderer2D::UNIFORM_NAME_TEXTURE_PREMULTIPLIED, 0)
+        };
+
+        for i in 0..MAX_GRADIENT_STOPS {
+            uniforms.gradient_stop_offsets[i] =
+                program.uniform(Renderer2D::UNIFORM_NAME_GRADIENT_STOP_OFFSETS, i);
+
+            for c in 0..4 {
+                uniforms.gradient_stop_colors[i][c] = program
+                    .uniform(Renderer2D::UNIFORM_NAME_GRADIENT_STOP_COLORS, i * 4 + c);
+            }
+        }
+
+        uniforms
+    }
+
+    fn stencil_test(&self, value: u8) -> bool
+    {
+        if !self.stencil_enabled {
+            return true;
+        }
+
+        let (func, reference, mask) = self.stencil_func;
+
+        let reference = reference & mask;
+        let value = value & mask;
+
+        match func {
+            GL_EQUAL => reference == value,
+            GL_LESS => reference < value,
+            // Other functions aren't used by the renderer
+            _ => true
+        }
+    }
+
+    fn stencil_op(&self, op: GLenum, value: u8) -> u8
+    {
+        if !self.stencil_enabled {
+            return value;
+        }
+
+        match op {
+            GL_ZERO => 0,
+            GL_INCR => value.saturating_add(1),
+            GL_REPLACE => self.stencil_func.1,
+            _ => value
+        }
+    }
+
+    fn blend_factor(factor: GLenum, src: &[f32; 4], dst: &[f32; 4], c: usize) -> f32
+    {
+        match factor {
+            GL_ZERO => 0.0,
+            GL_SRC_ALPHA => src[3],
+            GL_ONE_MINUS_SRC_ALPHA => 1.0 - src[3],
+            GL_DST_COLOR => dst[c],
+            GL_ONE_MINUS_SRC_COLOR => 1.0 - src[c],
+            _ => 1.0
+        }
+    }
+
+    fn write_fragment(&self, color: &mut [u8], src: [f32; 4])
+    {
+        let dst = [
+            f32::from(color[0]) / 255.0,
+            f32::from(color[1]) / 255.0,
+            f32::from(color[2]) / 255.0,
+            f32::from(color[3]) / 255.0
+        ];
+
+        for c in 0..4 {
+            if !self.color_mask[c] {
+                continue;
+            }
+
+            let value = if self.blend_enabled {
+                let (src_factor, dst_factor) = match c {
+                    3 => (self.blend_factors[2], self.blend_factors[3]),
+                    _ => (self.blend_factors[0], self.blend_factors[1])
+                };
+
+                src[c] * Self::blend_factor(src_factor, &src, &dst, c)
+                    + dst[c] * Self::blend_factor(dst_factor, &src, &dst, c)
+            } else {
+                src[c]
+            };
+
+            color[c] = to_u8(value);
+        }
+    }
+
+    fn draw_triangle(
+        &self,
+        target: &mut RenderTarget,
+        clip: &ClipRect,
+        uniforms: &FragmentUniforms,
+        vertices: [&ShadedVertex; 3]
+    )
+    {
+        let [v0, mut v1, mut v2] = vertices;
+
+        let mut area = edge(v0, v1, v2.x, v2.y);
+
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+
+        // Use a consistent winding order, so that the edge functions are
+        // positive inside the triangle
+        if area < 0.0 {
+            std::mem::swap(&mut v1, &mut v2);
+            area = -area;
+        }
+
+        let min_x = (v0.x.min(v1.x).min(v2.x).floor() as i64).max(clip.min_x);
+        let min_y = (v0.y.min(v1.y).min(v2.y).floor() as i64).max(clip.min_y);
+        let max_x = (v0.x.max(v1.x).max(v2.x).ceil() as i64).min(clip.max_x);
+        let max_y = (v0.y.max(v1.y).max(v2.y).ceil() as i64).min(clip.max_y);
+
+        let edges = [(v1, v2), (v2, v0), (v0, v1)];
+
+        let lod = uniforms
+            .texture
+            .map(|texture| texture_lod(texture, [v0, v1, v2]))
+            .unwrap_or(0.0);
+
+        for y in min_y..max_y {
+            let py = y as f64 + 0.5;
+
+            for x in min_x..max_x {
+                let px = x as f64 + 0.5;
+
+                let mut weights = [0.0; 3];
+                let mut inside = true;
+
+                for (i, (a, b)) in edges.iter().enumerate() {
+                    let value = edge(a, b, px, py);
+
+                    if value < 0.0 || (value == 0.0 && !is_top_left(a, b)) {
+                        inside = false;
+                        break;
+                    }
+
+                    weights[i] = (value / area) as f32;
+                }
+
+                if !inside {
+                    continue;
+                }
+
+                let varyings = Varyings::interpolate(
+                    [&v0.varyings, &v1.varyings, &v2.varyings],
+                    weights
+                );
+
+                let color = match uniforms.shade(&varyings, lod) {
+                    None => continue,
+                    Some(color) => color
+                };
+
+                let index = y as usize * target.width + x as usize;
+
+                if let Some(stencil) = &mut target.stencil {
+                    let (fail_op, pass_op) = self.stencil_ops;
+
+                    if !self.stencil_test(stencil[index]) {
+                        stencil[index] = self.stencil_op(fail_op, stencil[index]);
+                        continue;
+                    }
+
+                    stencil[index] = self.stencil_op(pass_op, stencil[index]);
+                }
+
+                self.write_fragment(&mut target.color[index * 4..index * 4 + 4], color);
+            }
+        }
+    }
+
+    /// Draws triangles using the vertices at the specified indices.
+    fn draw_vertices(&mut self, indices: &[usize])
+    {
+        let program = match self
+            .active_program
+            .and_then(|handle| self.programs.get(&handle))
+        {
+            None => return,
+            Some(program) => program
+        };
+
+        let attributes = match &program.attributes {
+            None => return,
+            Some(attributes) => *attributes
+        };
+
+        let vertices: Vec<ShadedVertex> = indices
+            .iter()
+            .map(|vertex| self.shade_vertex(program, &attributes, *vertex))
+            .collect();
+
+        let mut target = match self.take_render_target() {
+            None => return,
+            Some(target) => target
+        };
+
+        let program = &self.programs[&self.active_program.unwrap()];
+        let uniforms = self.fragment_uniforms(program);
+        let clip = self.clip_rect(&target);
+
+        for triangle in vertices.chunks_exact(3) {
+            self.draw_triangle(
+                &mut target,
+                &clip,
+                &uniforms,
+                [&triangle[0], &triangle[1], &triangle[2]]
+            );
+        }
+
+        self.restore_render_target(target);
+    }
+
+    fn clear(&mut self, mask: GLenum)
+    {
+        let mut target = match self.take_render_target() {
+            None => return,
+            Some(target) => target
+        };
+
+        let clip = self.clip_rect(&target);
+        let clear_color = self.clear_color.map(to_u8);
+
+        for y in clip.min_y.max(0)..clip.max_y {
+            for x in clip.min_x.max(0)..clip.max_x {
+                let index = y as usize * target.width + x as usize;
+
+                if mask & GL_COLOR_BUFFER_BIT != 0 {
+                    let pixel = &mut target.color[index * 4..index * 4 + 4];
+
+                    for (c, value) in pixel.iter_mut().enumerate() {
+                        if self.color_mask[c] {
+                            *value = clear_color[c];
+                        }
+                    }
+                }
+
+                if mask & GL_STENCIL_BUFFER_BIT != 0 {
+                    if let Some(stencil) = &mut target.stencil {
+                        stencil[index] = self.clear_stencil;
+                    }
+                }
+            }
+        }
+
+        self.restore_render_target(target);
+    }
+
+    fn link_program(&mut self, handle: GLTypeProgram)
+    {
+        let fragment_shader_is_builtin = match self.programs.get(&handle) {
+            None => return,
+            Some(program) => program.shaders.iter().any(|shader| {
+                self.shaders.get(shader).is_some_and(|shader| {
+                    BUILTIN_FRAGMENT_SHADERS.contains(&shader.source.as_str())
+                })
+            })
+        };
+
+        let program = self.programs.get_mut(&handle).unwrap();
+
+        if !fragment_shader_is_builtin {
+            program.attributes = None;
+            program.info_log =
+                "Custom shaders are not supported by the software renderer".to_string();
+            return;
+        }
+
+        let location = |name: &str| {
+            program
+                .attribute_bindings
+                .get(name)
+                .map(|index| *index as usize)
+                .filter(|index| *index < MAX_VERTEX_ATTRIBUTES)
+        };
+
+        let attributes = (|| {
+            Some(BuiltinAttributes {
+                position: location(Renderer2D::ATTR_NAME_POSITION)?,
+                color: location(Renderer2D::ATTR_NAME_COLOR)?,
+                texture_coord: location(Renderer2D::ATTR_NAME_TEXTURE_COORD)?,
+                texture_mix: location(Renderer2D::ATTR_NAME_TEXTURE_MIX)?,
+                circle_mix: location(Renderer2D::ATTR_NAME_CIRCLE_MIX)?,
+                circle_inner_radius: location(Renderer2D::ATTR_NAME_CIRCLE_INNER_RADIUS)?,
+                gradient_coord: location(Renderer2D::ATTR_NAME_GRADIENT_COORD)?,
+                gradient_type: location(Renderer2D::ATTR_NAME_GRADIENT_TYPE)?
+            })
+        })();
+
+        program.info_log = match attributes {
+            None => "Missing attribute location binding".to_string(),
+            Some(_) => String::new()
+        };
+
+        program.attributes = attributes;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn upload_texture_pixels(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        format: GLenum,
+        pixels: &[u8]
+    )
+    {
+        let bytes_per_pixel = match format {
+            GL_RED => 1,
+            GL_RGB => 3,
+            _ => 4
+        };
+
+        let alignment = self.unpack_alignment.max(1);
+        let row_bytes = (width * bytes_per_pixel).div_ceil(alignment) * alignment;
+
+        let texture = match self.bound_texture_mut() {
+            None => return,
+            Some(texture) => texture
+        };
+
+        for row in 0..height.min(texture.height.saturating_sub(y)) {
+            for column in 0..width.min(texture.width.saturating_sub(x)) {
+                let src = row * row_bytes + column * bytes_per_pixel;
+
+                let rgba = match pixels.get(src..src + bytes_per_pixel) {
+                    None => continue,
+                    Some([r]) => [*r, 0, 0, 255],
+                    Some([r, g, b]) => [*r, *g, *b, 255],
+                    Some(rgba) => [rgba[0], rgba[1], rgba[2], rgba[3]]
+                };
+
+                let dst = ((y + row) * texture.width + x + column) * 4;
+                texture.data[dst..dst + 4].copy_from_slice(&rgba);
+            }
+        }
+    }
+}
+
+/// A [GLBackend] which draws using the CPU instead of a GPU. Only the
+/// built-in shaders are supported, and the contents of the default
+/// framebuffer are kept in memory, where they can be obtained using
+/// `gl_read_pixels()`.
+///
+/// The default framebuffer behaves like a window which is resized whenever
+/// the viewport is changed.
+pub struct GLBackendSoftware
+{
+    state: RefCell<SoftwareState>
+}
+
+impl GLBackendSoftware
+{
+    #[must_use]
+    pub fn new(size: UVec2) -> Self
+    {
+        let width = size.x as usize;
+        let height = size.y as usize;
+
+        GLBackendSoftware {
+            state: RefCell::new(SoftwareState {
+                next_handle: 0,
+                shaders: HashMap::new(),
+                programs: HashMap::new(),
+                uniform_locations: HashMap::new(),
+                buffers: HashMap::new(),
+                textures: HashMap::new(),
+                framebuffers: HashMap::new(),
+                renderbuffers: HashMap::new(),
+                default_framebuffer: RenderTarget {
+                    width,
+                    height,
+                    color: vec![0; width * height * 4],
+                    stencil: Some(vec![0; width * height])
+                },
+                active_program: None,
+                active_texture_unit: 0,
+                bound_textures: HashMap::new(),
+                bound_array_buffer: None,
+                bound_element_array_buffer: None,
+                bound_framebuffer: None,
+                bound_renderbuffer: None,
+                attrib_pointers: [None; MAX_VERTEX_ATTRIBUTES],
+                attrib_enabled: [false; MAX_VERTEX_ATTRIBUTES],
+                viewport: ClipRect::new(0, 0, size.x as i32, size.y as i32),
+                scissor: ClipRect::new(0, 0, size.x as i32, size.y as i32),
+                blend_enabled: false,
+                scissor_enabled: false,
+                stencil_enabled: false,
+                blend_factors: [GL_ONE, GL_ZERO, GL_ONE, GL_ZERO],
+                stencil_func: (GL_ALWAYS, 0, 0xFF),
+                stencil_ops: (GL_KEEP, GL_KEEP),
+                color_mask: [true; 4],
+                clear_color: [0.0; 4],
+                clear_stencil: 0,
+                unpack_alignment: 4
+            })
+        }
+    }
+}
+
+impl GLBackend for GLBackendSoftware
+{
+    unsafe fn gl_delete_program(&self, handle: GLTypeProgram)
+    {
+        let mut state = self.state.borrow_mut();
+        state.programs.remove(&handle);
+        state
+            .uniform_locations
+            .retain(|_, (program, _)| *program != handle);
+    }
+
+    unsafe fn gl_delete_shader(&self, handle: GLTypeShader)
+    {
+        self.state.borrow_mut().shaders.remove(&handle);
+    }
+
+    unsafe fn gl_delete_buffer(&self, handle: GLTypeBuffer)
+    {
+        self.state.borrow_mut().buffers.remove(&handle);
+    }
+
+    unsafe fn gl_delete_texture(&self, handle: GLTypeTexture)
+    {
+        let mut state = self.state.borrow_mut();
+        state.textures.remove(&handle);
+        state.bound_textures.retain(|_, bound| *bound != handle);
+    }
+
+    unsafe fn gl_delete_framebuffer(&self, handle: GLTypeFramebuffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.framebuffers.remove(&handle);
+
+        if state.bound_framebuffer == Some(handle) {
+            state.bound_framebuffer = None;
+        }
+    }
+
+    unsafe fn gl_delete_renderbuffer(&self, handle: GLTypeRenderbuffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.renderbuffers.remove(&handle);
+
+        if state.bound_renderbuffer == Some(handle) {
+            state.bound_renderbuffer = None;
+        }
+    }
+
+    unsafe fn gl_active_texture(&self, unit: GLenum)
+    {
+        self.state.borrow_mut().active_texture_unit = unit - GL_TEXTURE0;
+    }
+
+    unsafe fn gl_bind_texture(&self, _target: GLenum, handle: GLTypeTexture)
+    {
+        let mut state = self.state.borrow_mut();
+        let unit = state.active_texture_unit;
+
+        match handle {
+            0 => state.bound_textures.remove(&unit),
+            handle => state.bound_textures.insert(unit, handle)
+        };
+    }
+
+    unsafe fn gl_bind_framebuffer(
+        &self,
+        _target: GLenum,
+        handle: Option<GLTypeFramebuffer>
+    )
+    {
+        self.state.borrow_mut().bound_framebuffer = handle;
+    }
+
+    unsafe fn gl_framebuffer_texture_2d(
+        &self,
+        _target: GLenum,
+        _attachment: GLenum,
+        _texture_target: GLenum,
+        texture: Option<GLTypeTexture>,
+        _level: GLint
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(handle) = state.bound_framebuffer {
+            if let Some(framebuffer) = state.framebuffers.get_mut(&handle) {
+                framebuffer.color = texture;
+            }
+        }
+    }
+
+    unsafe fn gl_bind_renderbuffer(
+        &self,
+        _target: GLenum,
+        handle: Option<GLTypeRenderbuffer>
+    )
+    {
+        self.state.borrow_mut().bound_renderbuffer = handle;
+    }
+
+    unsafe fn gl_renderbuffer_storage(
+        &self,
+        _target: GLenum,
+        _internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(handle) = state.bound_renderbuffer {
+            let width = width.max(0) as usize;
+            let height = height.max(0) as usize;
+
+            state.renderbuffers.insert(
+                handle,
+                SoftwareRenderbuffer {
+                    width,
+                    height,
+                    data: vec![0; width * height]
+                }
+            );
+        }
+    }
+
+    unsafe fn gl_framebuffer_renderbuffer(
+        &self,
+        _target: GLenum,
+        attachment: GLenum,
+        _renderbuffer_target: GLenum,
+        renderbuffer: Option<GLTypeRenderbuffer>
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if attachment != GL_DEPTH_STENCIL_ATTACHMENT {
+            return;
+        }
+
+        if let Some(handle) = state.bound_framebuffer {
+            if let Some(framebuffer) = state.framebuffers.get_mut(&handle) {
+                framebuffer.stencil = renderbuffer;
+            }
+        }
+    }
+
+    unsafe fn gl_check_framebuffer_status(&self, _target: GLenum) -> GLenum
+    {
+        let state = self.state.borrow();
+
+        let framebuffer = match state.bound_framebuffer {
+            None => return GL_FRAMEBUFFER_COMPLETE,
+            Some(handle) => match state.framebuffers.get(&handle) {
+                None => return GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT,
+                Some(framebuffer) => framebuffer
+            }
+        };
+
+        let texture = match framebuffer.color.and_then(|h| state.textures.get(&h)) {
+            None => return GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT,
+            Some(texture) => texture
+        };
+
+        if let Some(handle) = framebuffer.stencil {
+            match state.renderbuffers.get(&handle) {
+                Some(renderbuffer)
+                    if renderbuffer.width == texture.width
+                        && renderbuffer.height == texture.height => {}
+                _ => return GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT
+            }
+        }
+
+        GL_FRAMEBUFFER_COMPLETE
+    }
+
+    unsafe fn gl_enable(&self, cap: GLenum)
+    {
+        let mut state = self.state.borrow_mut();
+
+        match cap {
+            GL_BLEND => state.blend_enabled = true,
+            GL_SCISSOR_TEST => state.scissor_enabled = true,
+            GL_STENCIL_TEST => state.stencil_enabled = true,
+            _ => {}
+        }
+    }
+
+    unsafe fn gl_disable(&self, cap: GLenum)
+    {
+        let mut state = self.state.borrow_mut();
+
+        match cap {
+            GL_BLEND => state.blend_enabled = false,
+            GL_SCISSOR_TEST => state.scissor_enabled = false,
+            GL_STENCIL_TEST => state.stencil_enabled = false,
+            _ => {}
+        }
+    }
+
+    unsafe fn gl_blend_func(&self, sfactor: GLenum, dfactor: GLenum)
+    {
+        self.gl_blend_func_separate(sfactor, dfactor, sfactor, dfactor)
+    }
+
+    unsafe fn gl_blend_func_separate(
+        &self,
+        sfactor: GLenum,
+        dfactor: GLenum,
+        sfactor_alpha: GLenum,
+        dfactor_alpha: GLenum
+    )
+    {
+        self.state.borrow_mut().blend_factors =
+            [sfactor, dfactor, sfactor_alpha, dfactor_alpha];
+    }
+
+    unsafe fn gl_use_program(&self, handle: GLTypeProgram)
+    {
+        self.state.borrow_mut().active_program = Some(handle);
+    }
+
+    unsafe fn gl_enable_vertex_attrib_array(&self, handle: GLuint)
+    {
+        if let Some(enabled) = self
+            .state
+            .borrow_mut()
+            .attrib_enabled
+            .get_mut(handle as usize)
+        {
+            *enabled = true;
+        }
+    }
+
+    unsafe fn gl_disable_vertex_attrib_array(&self, handle: GLuint)
+    {
+        if let Some(enabled) = self
+            .state
+            .borrow_mut()
+            .attrib_enabled
+            .get_mut(handle as usize)
+        {
+            *enabled = false;
+        }
+    }
+
+    unsafe fn gl_uniform_1f(&self, handle: &GLTypeUniformLocation, value: f32)
+    {
+        self.state.borrow_mut().set_uniform(handle, &[value]);
+    }
+
+    unsafe fn gl_uniform_1i(&self, handle: &GLTypeUniformLocation, value: GLint)
+    {
+        self.state.borrow_mut().set_uniform(handle, &[value as f32]);
+    }
+
+    unsafe fn gl_uniform_1fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.state.borrow_mut().set_uniform(handle, values);
+    }
+
+    unsafe fn gl_uniform_2fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.state.borrow_mut().set_uniform(handle, values);
+    }
+
+    unsafe fn gl_uniform_3fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.state.borrow_mut().set_uniform(handle, values);
+    }
+
+    unsafe fn gl_uniform_4fv(&self, handle: &GLTypeUniformLocation, values: &[f32])
+    {
+        self.state.borrow_mut().set_uniform(handle, values);
+    }
+
+    unsafe fn gl_attach_shader(&self, program: GLTypeProgram, shader: GLTypeShader)
+    {
+        if let Some(program) = self.state.borrow_mut().programs.get_mut(&program) {
+            program.shaders.push(shader);
+        }
+    }
+
+    unsafe fn gl_link_program(&self, program: GLTypeProgram)
+    {
+        self.state.borrow_mut().link_program(program);
+    }
+
+    unsafe fn gl_bind_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint,
+        name: &str
+    )
+    {
+        if let Some(program) = self.state.borrow_mut().programs.get_mut(&program) {
+            program.attribute_bindings.insert(name.to_string(), index);
+        }
+    }
+
+    unsafe fn gl_shader_source(&self, handle: GLTypeShader, source: &str)
+    {
+        if let Some(shader) = self.state.borrow_mut().shaders.get_mut(&handle) {
+            shader.source = source.to_string();
+        }
+    }
+
+    unsafe fn gl_compile_shader(&self, _handle: GLTypeShader) {}
+
+    unsafe fn gl_tex_parameter_i(&self, _target: GLenum, parameter: GLenum, value: GLint)
+    {
+        if let Some(texture) = self.state.borrow_mut().bound_texture_mut() {
+            match parameter {
+                GL_TEXTURE_MAG_FILTER => texture.mag_filter = value as GLenum,
+                GL_TEXTURE_MIN_FILTER => texture.min_filter = value as GLenum,
+                GL_TEXTURE_WRAP_S => texture.wrap_s = value as GLenum,
+                GL_TEXTURE_WRAP_T => texture.wrap_t = value as GLenum,
+                _ => {}
+            }
+        }
+    }
+
+    unsafe fn gl_generate_mipmap(&self, _target: GLenum)
+    {
+        if let Some(texture) = self.state.borrow_mut().bound_texture_mut() {
+            texture.generate_mipmaps();
+        }
+    }
+
+    unsafe fn gl_bind_buffer(&self, target: GLenum, handle: GLTypeBuffer)
+    {
+        let mut state = self.state.borrow_mut();
+
+        match target {
+            GL_ARRAY_BUFFER => state.bound_array_buffer = Some(handle),
+            GL_ELEMENT_ARRAY_BUFFER => state.bound_element_array_buffer = Some(handle),
+            _ => {}
+        }
+    }
+
+    unsafe fn gl_buffer_data(&self, target: GLenum, data: &[u8], _usage: GLenum)
+    {
+        let mut state = self.state.borrow_mut();
+
+        let bound = match target {
+            GL_ARRAY_BUFFER => state.bound_array_buffer,
+            GL_ELEMENT_ARRAY_BUFFER => state.bound_element_array_buffer,
+            _ => None
+        };
+
+        if let Some(handle) = bound {
+            state.buffers.insert(handle, data.to_vec());
+        }
+    }
+
+    unsafe fn gl_draw_arrays(&self, mode: GLenum, first: GLint, count: GLsizei)
+    {
+        if mode != GL_TRIANGLES {
+            log::error!("Software renderer only supports drawing triangles");
+            return;
+        }
+
+        let first = first.max(0) as usize;
+        let count = count.max(0) as usize;
+
+        self.state
+            .borrow_mut()
+            .draw_vertices(&(first..first + count).collect::<Vec<_>>());
+    }
+
+    unsafe fn gl_draw_elements(
+        &self,
+        mode: GLenum,
+        count: GLsizei,
+        element_type: GLenum,
+        offset: GLint
+    )
+    {
+        if mode != GL_TRIANGLES || element_type != GL_UNSIGNED_INT {
+            log::error!(
+                "Software renderer only supports drawing triangles with u32 indices"
+            );
+            return;
+        }
+
+        let mut state = self.state.borrow_mut();
+
+        let indices = match state
+            .bound_element_array_buffer
+            .and_then(|handle| state.buffers.get(&handle))
+        {
+            None => return,
+            Some(data) => data
+                .chunks_exact(std::mem::size_of::<u32>())
+                .skip(offset.max(0) as usize / std::mem::size_of::<u32>())
+                .take(count.max(0) as usize)
+                .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()) as usize)
+                .collect::<Vec<_>>()
+        };
+
+        state.draw_vertices(&indices);
+    }
+
+    unsafe fn gl_clear_color(&self, r: f32, g: f32, b: f32, a: f32)
+    {
+        self.state.borrow_mut().clear_color = [r, g, b, a];
+    }
+
+    unsafe fn gl_clear(&self, mask: GLenum)
+    {
+        self.state.borrow_mut().clear(mask);
+    }
+
+    unsafe fn gl_enable_debug_message_callback(&self) {}
+
+    unsafe fn gl_get_string(&self, parameter: GLenum) -> String
+    {
+        match parameter {
+            // Framebuffers and mipmap generation are supported, as in OpenGL 3.0
+            GL_VERSION => "3.0 Speedy2D software renderer".to_string(),
+            _ => String::new()
+        }
+    }
+
+    unsafe fn gl_viewport(&self, x: i32, y: i32, width: i32, height: i32)
+    {
+        let mut state = self.state.borrow_mut();
+
+        state.viewport = ClipRect::new(x, y, width, height);
+
+        let width = width.max(0) as usize;
+        let height = height.max(0) as usize;
+
+        let resized = state.default_framebuffer.width != width
+            || state.default_framebuffer.height != height;
+
+        if state.bound_framebuffer.is_none() && resized {
+            state.default_framebuffer = RenderTarget {
+                width,
+                height,
+                color: vec![0; width * height * 4],
+                stencil: Some(vec![0; width * height])
+            };
+        }
+    }
+
+    unsafe fn gl_scissor(&self, x: GLint, y: GLint, width: GLsizei, height: GLsizei)
+    {
+        self.state.borrow_mut().scissor = ClipRect::new(x, y, width, height);
+    }
+
+    unsafe fn gl_clear_stencil(&self, value: GLint)
+    {
+        self.state.borrow_mut().clear_stencil = value as u8;
+    }
+
+    unsafe fn gl_stencil_func(&self, func: GLenum, reference: GLint, mask: GLuint)
+    {
+        self.state.borrow_mut().stencil_func = (func, reference as u8, mask as u8);
+    }
+
+    unsafe fn gl_stencil_op(
+        &self,
+        stencil_fail: GLenum,
+        _depth_fail: GLenum,
+        pass: GLenum
+    )
+    {
+        // There is no depth buffer, so the depth test always passes
+        self.state.borrow_mut().stencil_ops = (stencil_fail, pass);
+    }
+
+    unsafe fn gl_color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        self.state.borrow_mut().color_mask = [red, green, blue, alpha];
+    }
+
+    unsafe fn gl_pixel_store_i(&self, param: GLenum, value: GLint)
+    {
+        if param == GL_UNPACK_ALIGNMENT {
+            self.state.borrow_mut().unpack_alignment = value.max(1) as usize;
+        }
+    }
+
+    unsafe fn gl_vertex_attrib_pointer_f32(
+        &self,
+        index: GLuint,
+        size: GLsizei,
+        _data_type: GLenum,
+        _normalized: bool,
+        stride: GLsizei,
+        offset: GLsizei
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        let buffer = match state.bound_array_buffer {
+            None => return,
+            Some(buffer) => buffer
+        };
+
+        if let Some(pointer) = state.attrib_pointers.get_mut(index as usize) {
+            *pointer = Some(VertexAttribPointer {
+                buffer,
+                size: size.clamp(0, 4) as usize,
+                stride: stride.max(0) as usize,
+                offset: offset.max(0) as usize
+            });
+        }
+    }
+
+    unsafe fn gl_tex_image_2d(
+        &self,
+        _target: GLenum,
+        level: GLint,
+        _internal_format: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        _border: GLint,
+        format: GLenum,
+        _data_type: GLenum,
+        pixels: Option<&[u8]>
+    )
+    {
+        if level != 0 {
+            return;
+        }
+
+        let mut state = self.state.borrow_mut();
+
+        let handle = match state.bound_textures.get(&state.active_texture_unit) {
+            None => return,
+            Some(handle) => *handle
+        };
+
+        let width = width.max(0) as usize;
+        let height = height.max(0) as usize;
+
+        let (mag_filter, min_filter, wrap_s, wrap_t) = state
+            .textures
+            .get(&handle)
+            .map(|texture| {
+                (
+                    texture.mag_filter,
+                    texture.min_filter,
+                    texture.wrap_s,
+                    texture.wrap_t
+                )
+            })
+            .unwrap_or((GL_LINEAR, GL_LINEAR, GL_CLAMP_TO_EDGE, GL_CLAMP_TO_EDGE));
+
+        state.textures.insert(
+            handle,
+            SoftwareTexture {
+                width,
+                height,
+                data: vec![0; width * height * 4],
+                mag_filter,
+                min_filter,
+                wrap_s,
+                wrap_t,
+                mipmaps: Vec::new()
+            }
+        );
+
+        if let Some(pixels) = pixels {
+            state.upload_texture_pixels(0, 0, width, height, format, pixels);
+        }
+    }
+
+    unsafe fn gl_tex_sub_image_2d(
+        &self,
+        _target: GLenum,
+        level: GLint,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        _data_type: GLenum,
+        pixels: &[u8]
+    )
+    {
+        if level != 0 {
+            return;
+        }
+
+        self.state.borrow_mut().upload_texture_pixels(
+            x.max(0) as usize,
+            y.max(0) as usize,
+            width.max(0) as usize,
+            height.max(0) as usize,
+            format,
+            pixels
+        );
+    }
+
+    unsafe fn gl_create_program(
+        &self
+    ) -> Result<GLTypeProgram, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state.programs.insert(handle, SoftwareProgram::default());
+        Ok(handle)
+    }
+
+    unsafe fn gl_create_shader(
+        &self,
+        _shader_type: GLenum
+    ) -> Result<GLTypeShader, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state.shaders.insert(
+            handle,
+            SoftwareShader {
+                source: String::new()
+            }
+        );
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_buffer(&self) -> Result<GLTypeBuffer, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state.buffers.insert(handle, Vec::new());
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_texture(&self)
+        -> Result<GLTypeTexture, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state.textures.insert(
+            handle,
+            SoftwareTexture {
+                width: 0,
+                height: 0,
+                data: Vec::new(),
+                mag_filter: GL_LINEAR,
+                min_filter: GL_LINEAR,
+                wrap_s: GL_CLAMP_TO_EDGE,
+                wrap_t: GL_CLAMP_TO_EDGE,
+                mipmaps: Vec::new()
+            }
+        );
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_framebuffer(
+        &self
+    ) -> Result<GLTypeFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state
+            .framebuffers
+            .insert(handle, SoftwareFramebuffer::default());
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_renderbuffer(
+        &self
+    ) -> Result<GLTypeRenderbuffer, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state.renderbuffers.insert(
+            handle,
+            SoftwareRenderbuffer {
+                width: 0,
+                height: 0,
+                data: Vec::new()
+            }
+        );
+        Ok(handle)
+    }
+
+    unsafe fn gl_get_error(&self) -> GLenum
+    {
+        GL_NO_ERROR
+    }
+
+    unsafe fn gl_get_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        name: &str
+    ) -> Option<GLuint>
+    {
+        self.state
+            .borrow()
+            .programs
+            .get(&program)?
+            .attribute_bindings
+            .get(name)
+            .copied()
+    }
+
+    unsafe fn gl_get_uniform_location(
+        &self,
+        program: GLTypeProgram,
+        name: &str
+    ) -> Option<GLTypeUniformLocation>
+    {
+        let mut state = self.state.borrow_mut();
+
+        state.programs.get(&program)?.attributes?;
+
+        if let Some((location, _)) = state.uniform_locations.iter().find(
+            |(_, (existing_program, existing_name))| {
+                *existing_program == program && existing_name == name
+            }
+        ) {
+            return Some(*location);
+        }
+
+        let is_builtin_uniform = [
+            Renderer2D::UNIFORM_NAME_SCALE_X,
+            Renderer2D::UNIFORM_NAME_SCALE_Y,
+            Renderer2D::UNIFORM_NAME_OFFSET_Y,
+            Renderer2D::UNIFORM_NAME_TRANSFORM_X,
+            Renderer2D::UNIFORM_NAME_TRANSFORM_Y,
+            Renderer2D::UNIFORM_NAME_TINT,
+            Renderer2D::UNIFORM_NAME_TEXTURE,
+            Renderer2D::UNIFORM_NAME_GRADIENT_STOP_COUNT,
+            Renderer2D::UNIFORM_NAME_GRADIENT_STOP_OFFSETS,
+            Renderer2D::UNIFORM_NAME_GRADIENT_STOP_COLORS,
+            Renderer2D::UNIFORM_NAME_PREMULTIPLY_ALPHA,
+            Renderer2D::UNIFORM_NAME_TEXTURE_PREMULTIPLIED
+        ]
+        .contains(&name);
+
+        if !is_builtin_uniform {
+            return None;
+        }
+
+        let location = state.new_handle();
+
+        state
+            .uniform_locations
+            .insert(location, (program, name.to_string()));
+
+        Some(location)
+    }
+
+    unsafe fn gl_get_program_link_status(&self, program: GLTypeProgram) -> bool
+    {
+        self.state
+            .borrow()
+            .programs
+            .get(&program)
+            .is_some_and(|program| program.attributes.is_some())
+    }
+
+    unsafe fn gl_get_active_uniforms(&self, program: GLTypeProgram) -> GLuint
+    {
+        // Only the built-in program can be linked, and the only uniform which
+        // needs to be inspected is its texture sampler
+        match self.gl_get_program_link_status(program) {
+            true => 1,
+            false => 0
+        }
+    }
+
+    unsafe fn gl_get_active_uniform(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint
+    ) -> Option<GLActiveUniform>
+    {
+        if index >= self.gl_get_active_uniforms(program) {
+            return None;
+        }
+
+        Some(GLActiveUniform {
+            size: 1,
+            utype: GL_SAMPLER_2D,
+            name: Renderer2D::UNIFORM_NAME_TEXTURE.to_string()
+        })
+    }
+
+    unsafe fn gl_get_parameter_i32(&self, parameter: GLenum) -> GLint
+    {
+        match parameter {
+            // The minimum required by OpenGL ES 2.0
+            GL_MAX_TEXTURE_IMAGE_UNITS => 8,
+            _ => 0
+        }
+    }
+
+    unsafe fn gl_get_shader_compile_status(&self, _shader: GLTypeShader) -> bool
+    {
+        true
+    }
+
+    unsafe fn gl_get_program_info_log(
+        &self,
+        program: GLTypeProgram
+    ) -> Result<String, BacktraceError<ErrorMessage>>
+    {
+        Ok(self
+            .state
+            .borrow()
+            .programs
+            .get(&program)
+            .map(|program| program.info_log.clone())
+            .unwrap_or_default())
+    }
+
+    unsafe fn gl_get_shader_info_log(
+        &self,
+        _shader: GLTypeShader
+    ) -> Result<String, BacktraceError<ErrorMessage>>
+    {
+        Ok(String::new())
+    }
+
+    unsafe fn gl_read_pixels(
+        &self,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        _data_type: GLenum,
+        data: &mut [MaybeUninit<u8>]
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        let target = match state.take_render_target() {
+            None => return,
+            Some(target) => target
+        };
+
+        let bytes_per_pixel = match format {
+            GL_RGB => 3,
+            _ => 4
+        };
+
+        let width = width.max(0) as usize;
+        let height = height.max(0) as usize;
+
+        // The contents of pixels outside the framebuffer are undefined in GL,
+        // so they are read as zero. The position may be negative, so it's
+        // converted before checking the bounds.
+        let source_offset = |position: i64, size: usize| -> Option<usize> {
+            let position: usize = position.try_into().ok()?;
+            Some(position).filter(|position| *position < size)
+        };
+
+        for row in 0..height {
+            let src_y = source_offset(y as i64 + row as i64, target.height);
+
+            for column in 0..width {
+                let src_x = source_offset(x as i64 + column as i64, target.width);
+
+                let src = match (src_x, src_y) {
+                    (Some(src_x), Some(src_y)) => {
+                        Some((src_y * target.width + src_x) * 4)
+                    }
+                    _ => None
+                };
+
+                let dst = (row * width + column) * bytes_per_pixel;
+
+                for c in 0..bytes_per_pixel {
+                    if let Some(byte) = data.get_mut(dst + c) {
+                        byte.write(match src {
+                            Some(src) => target.color[src + c],
+                            None => 0
+                        });
+                    }
+                }
+            }
+        }
+
+        state.restore_render_target(target);
+    }
+}
+
+/// Returns the base 2 logarithm of the number of texels covered by each pixel
+/// of the triangle. This is constant across the triangle, as the texture
+/// coordinates are interpolated linearly.
+fn texture_lod(texture: &SoftwareTexture, vertices: [&ShadedVertex; 3]) -> f32
+{
+    let [v0, v1, v2] = vertices;
+
+    let (x1, y1) = (v1.x - v0.x, v1.y - v0.y);
+    let (x2, y2) = (v2.x - v0.x, v2.y - v0.y);
+
+    let determinant = x1 * y2 - x2 * y1;
+
+    // Returns the derivatives of the texture coordinate, measured in texels,
+    // with respect to the x and y screen coordinates
+    let derivatives = |component: usize, size: usize| {
+        let get = |v: &ShadedVertex| f64::from(v.varyings.texture_coord[component]);
+
+        let f1 = (get(v1) - get(v0)) * size as f64;
+        let f2 = (get(v2) - get(v0)) * size as f64;
+
+        (
+            (f1 * y2 - f2 * y1) / determinant,
+            (f2 * x1 - f1 * x2) / determinant
+        )
+    };
+
+    let (du_dx, du_dy) = derivatives(0, texture.width);
+    let (dv_dx, dv_dy) = derivatives(1, texture.height);
+
+    du_dx.hypot(dv_dx).max(du_dy.hypot(dv_dy)).log2() as f32
+}
+
+fn mix(a: f32, b: f32, amount: f32) -> f32
+{
+    a * (1.0 - amount) + b * amount
+}
+
+fn step(edge: f32, value: f32) -> f32
+{
+    if value < edge {
+        0.0
+    } else {
+        1.0
+    }
+}
+
+fn to_u8(value: f32) -> u8
+{
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Like GPUs, positions are snapped to a grid of subpixels, so that rounding
+/// errors in the projection don't change which pixels lie inside a shape.
+fn snap_to_subpixel(value: f64) -> f64
+{
+    const SUBPIXELS: f64 = 256.0;
+    (value * SUBPIXELS).round() / SUBPIXELS
+}
+
+/// Positive if `(x, y)` is to the left of the line from `a` to `b`.
+fn edge(a: &ShadedVertex, b: &ShadedVertex, x: f64, y: f64) -> f64
+{
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/// Pixels which lie exactly on an edge are only drawn for top and left edges,
+/// so that pixels on an edge shared by two triangles are drawn once.
+fn is_top_left(a: &ShadedVertex, b: &ShadedVertex) -> bool
+{
+    (a.y == b.y && b.x < a.x) || b.y < a.y
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::color::Color;
+    use crate::font::{Font, TextLayout, TextOptions};
+    use crate::image::{ImageDataType, ImageSmoothingMode};
+    use crate::shape::{Ellipse, Rectangle};
+    use crate::test_utils::{pixel, render};
+    use crate::GLRenderer;
+
+    #[test]
+    fn shapes()
+    {
+        let image = render(50, 50, |graphics| {
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 0.0), (20.0, 10.0)),
+                Color::RED
+            );
+            graphics.draw_circle(
+                (35.0, 35.0),
+                10.0,
+                Color::from_rgba(0.0, 0.0, 1.0, 0.5)
+            );
+        });
+
+        assert_eq!([255, 0, 0, 255], pixel(&image, 0, 0));
+        assert_eq!([255, 0, 0, 255], pixel(&image, 19, 9));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 20, 9));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 19, 10));
+
+        assert_eq!([128, 128, 255, 255], pixel(&image, 35, 35));
+
+        // Outside the circle, but inside its quad
+        assert_eq!([255, 255, 255, 255], pixel(&image, 26, 26));
+    }
+
+    #[test]
+    fn textures()
+    {
+        let image = render(40, 40, |graphics| {
+            let pixels = [
+                255, 0, 0, 255, 0, 255, 0, 255, //
+                0, 0, 255, 255, 0, 0, 0, 0
+            ];
+
+            let handle = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGBA,
+                    ImageSmoothingMode::NearestNeighbor,
+                    (2, 2),
+                    &pixels
+                )
+                .unwrap();
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((0.0, 0.0), (40.0, 40.0)),
+                &handle
+            );
+        });
+
+        assert_eq!([255, 0, 0, 255], pixel(&image, 5, 5));
+        assert_eq!([0, 255, 0, 255], pixel(&image, 35, 5));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 5, 35));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 35, 35));
+    }
+
+    #[test]
+    fn render_targets_and_clipping()
+    {
+        let image = render(100, 100, |graphics| {
+            let target = graphics
+                .create_render_target(ImageSmoothingMode::NearestNeighbor, (50, 50))
+                .unwrap();
+
+            graphics.draw_to_render_target(&target, |graphics| {
+                graphics.clear_screen(Color::GREEN);
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (50.0, 10.0)),
+                    Color::BLUE
+                );
+            });
+
+            graphics.draw_image((0.0, 0.0), target.image());
+
+            graphics.with_clip(Ellipse::new((75.0, 75.0), (20.0, 10.0)), |graphics| {
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (100.0, 100.0)),
+                    Color::RED
+                );
+            });
+        });
+
+        assert_eq!([0, 0, 255, 255], pixel(&image, 25, 5));
+        assert_eq!([0, 255, 0, 255], pixel(&image, 25, 45));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 75, 25));
+
+        assert_eq!([255, 0, 0, 255], pixel(&image, 90, 75));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 75, 90));
+    }
+
+    #[test]
+    fn text()
+    {
+        let font =
+            Font::new(include_bytes!("../assets/fonts/NotoSans-Regular.ttf")).unwrap();
+
+        let text = font.layout_text("Hello", 32.0, TextOptions::new());
+
+        let image = render(100, 50, |graphics| {
+            graphics.draw_text((0.0, 0.0), Color::BLACK, &text);
+        });
+
+        let dark_pixels = image
+            .data()
+            .chunks_exact(4)
+            .filter(|pixel| pixel[0] < 128)
+            .count();
+
+        assert!(dark_pixels > 100, "Only {} dark pixels", dark_pixels);
+        assert_eq!([255, 255, 255, 255], pixel(&image, 99, 49));
+    }
+
+    #[test]
+    fn custom_shaders_unsupported()
+    {
+        let mut renderer = GLRenderer::new_software((10, 10)).unwrap();
+
+        assert!(renderer
+            .create_custom_shader("void main(void) { gl_FragColor = vec4(1.0); }")
+            .is_err());
+    }
+
+    #[test]
+    fn read_pixels_outside_framebuffer()
+    {
+        let backend = GLBackendSoftware::new(UVec2::new(4, 4));
+        let mut data = [MaybeUninit::new(255); 16];
+
+        unsafe {
+            backend.gl_clear_color(1.0, 0.0, 0.0, 1.0);
+            backend.gl_clear(GL_COLOR_BUFFER_BIT);
+
+            // Only the last two pixels of the row are inside the framebuffer
+            backend.gl_read_pixels(-2, 3, 4, 1, GL_RGBA, GL_UNSIGNED_BYTE, &mut data);
+        }
+
+        let data: Vec<u8> = data
+            .iter()
+            .map(|byte| unsafe { byte.assume_init() })
+            .collect();
+
+        assert_eq!(
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 0, //
+                255, 0, 0, 255, 255, 0, 0, 255
+            ],
+            data
+        );
+    }
+}
@@ -29,7 +29,9 @@ use crate::error::{BacktraceError, Context, ErrorMessage};
 use crate::glbackend::constants::*;
 use crate::glbackend::types::{
     GLTypeBuffer,
+    GLTypeFramebuffer,
     GLTypeProgram,
+    GLTypeRenderbuffer,
     GLTypeShader,
     GLTypeTexture,
     GLTypeUniformLocation,
@@ -38,6 +40,8 @@ use crate::glbackend::types::{
     GLuint
 };
 use crate::glbackend::GLBackend;
+use crate::paint::BlendMode;
+use crate::shape::URect;
 use crate::{ImageDataType, RawBitmapData};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -79,7 +83,9 @@ enum GLHandleType
     Program,
     Shader,
     Buffer,
-    Texture
+    Texture,
+    Framebuffer,
+    Renderbuffer
 }
 
 trait GLHandleId: Debug + Hash + PartialEq + Eq
@@ -112,6 +118,18 @@ struct GLHandleTypeTexture
     handle: GLTypeTexture
 }
 
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct GLHandleTypeFramebuffer
+{
+    handle: GLTypeFramebuffer
+}
+
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct GLHandleTypeRenderbuffer
+{
+    handle: GLTypeRenderbuffer
+}
+
 struct GLHandle<HandleType: GLHandleId>
 {
     context: Weak<RefCell<GLContextManagerState>>,
@@ -177,6 +195,8 @@ impl<HandleType: GLHandleId> GLHandle<HandleType>
             GLHandleType::Shader => gl_clear_and_log_old_error(context),
             GLHandleType::Buffer => {}
             GLHandleType::Texture => {}
+            GLHandleType::Framebuffer => {}
+            GLHandleType::Renderbuffer => {}
         }
 
         let handle = handle_creator().context("Handle creation failed")?;
@@ -186,6 +206,8 @@ impl<HandleType: GLHandleId> GLHandle<HandleType>
             GLHandleType::Shader => gl_check_error_always(context)?,
             GLHandleType::Buffer => {}
             GLHandleType::Texture => {}
+            GLHandleType::Framebuffer => {}
+            GLHandleType::Renderbuffer => {}
         }
 
         Ok(GLHandle {
@@ -257,6 +279,30 @@ impl GLHandleId for GLHandleTypeTexture
     }
 }
 
+impl GLHandleId for GLHandleTypeFramebuffer
+{
+    type HandleRawType = GLTypeFramebuffer;
+
+    fn delete(&self, context: &GLContextManager)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_delete_framebuffer(self.handle)
+        });
+    }
+}
+
+impl GLHandleId for GLHandleTypeRenderbuffer
+{
+    type HandleRawType = GLTypeRenderbuffer;
+
+    fn delete(&self, context: &GLContextManager)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_delete_renderbuffer(self.handle)
+        });
+    }
+}
+
 #[derive(Debug)]
 pub struct GLProgram
 {
@@ -335,7 +381,20 @@ impl GLProgram
         program.attach_shader(context, vertex_shader)?;
         program.attach_shader(context, fragment_shader)?;
 
+        let attribute_names: Vec<&'static str> =
+            attribute_names.into_iter().copied().collect();
+
+        // Bind the attributes to fixed locations, so that every program
+        // linked with the same attributes can share the same vertex buffers.
         context.with_gl_backend(|backend| unsafe {
+            for (index, attribute_name) in attribute_names.iter().enumerate() {
+                backend.gl_bind_attrib_location(
+                    program.get_handle(),
+                    index as GLuint,
+                    attribute_name
+                );
+            }
+
             backend.gl_link_program(program.get_handle());
         });
 
@@ -354,10 +413,15 @@ impl GLProgram
 
         gl_check_error_always(context)?;
 
-        for attribute_name in attribute_names.into_iter() {
+        // Custom shaders might not use every attribute, in which case the
+        // attribute is inactive. It's still safe to enable it at its bound
+        // location, so there's no need to look up the location here.
+        for (index, attribute_name) in attribute_names.into_iter().enumerate() {
             program.attribute_handles.insert(
-                attribute_name.as_ref(),
-                program.get_attribute_handle(attribute_name.as_ref())?
+                attribute_name,
+                GLAttributeHandle {
+                    handle: index as GLuint
+                }
             );
         }
 
@@ -437,6 +501,26 @@ impl GLProgram
             Some(handle) => Ok(GLUniformHandle { handle })
         }
     }
+
+    /// Returns the names of the active `sampler2D` uniforms in the program,
+    /// along with their array sizes.
+    pub fn get_sampler_uniforms(&self, context: &GLContextManager)
+        -> Vec<(String, usize)>
+    {
+        if !context.is_valid() {
+            return Vec::new();
+        }
+
+        context.with_gl_backend(|backend| unsafe {
+            (0..backend.gl_get_active_uniforms(self.get_handle()))
+                .filter_map(|index| {
+                    backend.gl_get_active_uniform(self.get_handle(), index)
+                })
+                .filter(|uniform| uniform.utype == GL_SAMPLER_2D)
+                .map(|uniform| (uniform.name, uniform.size.max(0) as usize))
+                .collect()
+        })
+    }
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
@@ -543,6 +627,34 @@ impl GLUniformHandle
             backend.gl_uniform_1i(&self.handle, value)
         })
     }
+
+    pub fn set_value_float_array(&self, context: &GLContextManager, values: &[f32])
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_1fv(&self.handle, values)
+        })
+    }
+
+    pub fn set_value_vec2(&self, context: &GLContextManager, values: [f32; 2])
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_2fv(&self.handle, &values)
+        })
+    }
+
+    pub fn set_value_vec3(&self, context: &GLContextManager, values: [f32; 3])
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_3fv(&self.handle, &values)
+        })
+    }
+
+    pub fn set_value_vec4_array(&self, context: &GLContextManager, values: &[f32])
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_4fv(&self.handle, values)
+        })
+    }
 }
 
 pub enum GLBufferTarget
@@ -579,6 +691,21 @@ impl GLHandleOwner<GLHandleTypeBuffer> for GLBuffer
     }
 }
 
+fn new_buffer_handle(
+    context: &GLContextManager
+) -> Result<GLHandle<GLHandleTypeBuffer>, BacktraceError<ErrorMessage>>
+{
+    gl_clear_and_log_old_error(context);
+
+    GLHandle::wrap(context, GLHandleType::Buffer, || {
+        context.with_gl_backend(|backend| unsafe {
+            Ok(GLHandleTypeBuffer {
+                handle: backend.gl_gen_buffer()?
+            })
+        })
+    })
+}
+
 impl GLBuffer
 {
     fn new(
@@ -588,15 +715,7 @@ impl GLBuffer
         attrib_index: GLAttributeHandle
     ) -> Result<Self, BacktraceError<ErrorMessage>>
     {
-        gl_clear_and_log_old_error(context);
-
-        let handle = GLHandle::wrap(context, GLHandleType::Buffer, || {
-            context.with_gl_backend(|backend| unsafe {
-                Ok(GLHandleTypeBuffer {
-                    handle: backend.gl_gen_buffer()?
-                })
-            })
-        })?;
+        let handle = new_buffer_handle(context)?;
 
         Ok(GLBuffer {
             handle,
@@ -607,6 +726,23 @@ impl GLBuffer
     }
 
     pub fn set_data(&mut self, context: &GLContextManager, data: &[f32])
+    {
+        self.set_data_with_usage(context, data, GL_DYNAMIC_DRAW);
+    }
+
+    /// Uploads data which is expected to be drawn many times without
+    /// changing.
+    pub fn set_static_data(&mut self, context: &GLContextManager, data: &[f32])
+    {
+        self.set_data_with_usage(context, data, GL_STATIC_DRAW);
+    }
+
+    fn set_data_with_usage(
+        &mut self,
+        context: &GLContextManager,
+        data: &[f32],
+        usage: GLenum
+    )
     {
         if !context.is_valid() {
             log::warn!("Ignoring buffer set_data: invalid GL context");
@@ -615,8 +751,22 @@ impl GLBuffer
 
         context.with_gl_backend(|backend| unsafe {
             backend.gl_bind_buffer(self.target.gl_constant(), self.get_handle());
+            backend.gl_buffer_data_f32(self.target.gl_constant(), data, usage);
+        });
+
+        self.bind(context);
+    }
+
+    /// Uses the data previously uploaded to this buffer for its attribute.
+    pub fn bind(&self, context: &GLContextManager)
+    {
+        if !context.is_valid() {
+            log::warn!("Ignoring buffer bind: invalid GL context");
+            return;
+        }
 
-            backend.gl_buffer_data_f32(self.target.gl_constant(), data, GL_DYNAMIC_DRAW);
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_buffer(self.target.gl_constant(), self.get_handle());
 
             backend.gl_vertex_attrib_pointer_f32(
                 self.attrib_index.handle,
@@ -630,11 +780,183 @@ impl GLBuffer
     }
 }
 
+/// A buffer containing the values of several attributes for each vertex,
+/// interleaved in the order given when it was created.
+pub struct GLInterleavedBuffer
+{
+    handle: GLHandle<GLHandleTypeBuffer>,
+    attributes: Vec<(GLAttributeHandle, GLint)>
+}
+
+impl GLHandleOwner<GLHandleTypeBuffer> for GLInterleavedBuffer
+{
+    fn get_handle(&self) -> <GLHandleTypeBuffer as GLHandleId>::HandleRawType
+    {
+        self.handle.handle.handle
+    }
+}
+
+impl GLInterleavedBuffer
+{
+    fn new(
+        context: &GLContextManager,
+        attributes: Vec<(GLAttributeHandle, GLint)>
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        Ok(GLInterleavedBuffer {
+            handle: new_buffer_handle(context)?,
+            attributes
+        })
+    }
+
+    /// Returns the number of `f32` values for each vertex.
+    pub fn components_per_vertex(&self) -> usize
+    {
+        self.attributes
+            .iter()
+            .map(|(_, components)| *components as usize)
+            .sum()
+    }
+
+    pub fn set_data(&mut self, context: &GLContextManager, data: &[f32])
+    {
+        if !context.is_valid() {
+            log::warn!("Ignoring buffer set_data: invalid GL context");
+            return;
+        }
+
+        let stride = (self.components_per_vertex() * std::mem::size_of::<f32>())
+            .try_into()
+            .unwrap();
+
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_buffer(GL_ARRAY_BUFFER, self.get_handle());
+            backend.gl_buffer_data_f32(GL_ARRAY_BUFFER, data, GL_DYNAMIC_DRAW);
+
+            let mut offset = 0;
+
+            for (attribute, components) in self.attributes.iter() {
+                backend.gl_vertex_attrib_pointer_f32(
+                    attribute.handle,
+                    *components,
+                    GL_FLOAT,
+                    false,
+                    stride,
+                    offset
+                );
+
+                offset += *components * std::mem::size_of::<f32>() as GLint;
+            }
+        });
+    }
+}
+
+/// A buffer of `u32` vertex indices, used by
+/// [GLContextManager::draw_indexed_triangles].
+pub struct GLIndexBuffer
+{
+    handle: GLHandle<GLHandleTypeBuffer>,
+    len: usize
+}
+
+impl GLHandleOwner<GLHandleTypeBuffer> for GLIndexBuffer
+{
+    fn get_handle(&self) -> <GLHandleTypeBuffer as GLHandleId>::HandleRawType
+    {
+        self.handle.handle.handle
+    }
+}
+
+impl GLIndexBuffer
+{
+    fn new(context: &GLContextManager) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        Ok(GLIndexBuffer {
+            handle: new_buffer_handle(context)?,
+            len: 0
+        })
+    }
+
+    /// Returns the number of indices in the buffer.
+    pub fn len(&self) -> usize
+    {
+        self.len
+    }
+
+    pub fn set_data(&mut self, context: &GLContextManager, data: &[u32])
+    {
+        if !context.is_valid() {
+            log::warn!("Ignoring buffer set_data: invalid GL context");
+            return;
+        }
+
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_buffer(GL_ELEMENT_ARRAY_BUFFER, self.get_handle());
+            backend.gl_buffer_data_u32(GL_ELEMENT_ARRAY_BUFFER, data, GL_STATIC_DRAW);
+        });
+
+        self.len = data.len();
+    }
+
+    pub fn bind(&self, context: &GLContextManager)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_buffer(GL_ELEMENT_ARRAY_BUFFER, self.get_handle());
+        });
+    }
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub enum GLTextureSmoothing
 {
     NearestNeighbour,
-    Linear
+    Linear,
+    NearestNeighbourMipmap,
+    LinearMipmap,
+    Trilinear
+}
+
+impl GLTextureSmoothing
+{
+    fn min_filter(&self) -> GLenum
+    {
+        match self {
+            GLTextureSmoothing::NearestNeighbour => GL_NEAREST,
+            GLTextureSmoothing::Linear => GL_LINEAR,
+            GLTextureSmoothing::NearestNeighbourMipmap => GL_NEAREST_MIPMAP_NEAREST,
+            GLTextureSmoothing::LinearMipmap => GL_LINEAR_MIPMAP_NEAREST,
+            GLTextureSmoothing::Trilinear => GL_LINEAR_MIPMAP_LINEAR
+        }
+    }
+
+    fn mag_filter(&self) -> GLenum
+    {
+        match self {
+            GLTextureSmoothing::NearestNeighbour
+            | GLTextureSmoothing::NearestNeighbourMipmap => GL_NEAREST,
+            GLTextureSmoothing::Linear
+            | GLTextureSmoothing::LinearMipmap
+            | GLTextureSmoothing::Trilinear => GL_LINEAR
+        }
+    }
+
+    fn has_mipmaps(&self) -> bool
+    {
+        match self {
+            GLTextureSmoothing::NearestNeighbour | GLTextureSmoothing::Linear => false,
+            GLTextureSmoothing::NearestNeighbourMipmap
+            | GLTextureSmoothing::LinearMipmap
+            | GLTextureSmoothing::Trilinear => true
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub enum GLTextureWrap
+{
+    ClampToEdge,
+    Repeat,
+    MirroredRepeat
 }
 
 #[allow(clippy::upper_case_acronyms)]
@@ -691,7 +1013,8 @@ impl GLTextureImageFormatU8
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct GLTexture
 {
-    handle: Rc<GLHandle<GLHandleTypeTexture>>
+    handle: Rc<GLHandle<GLHandleTypeTexture>>,
+    premultiplied_alpha: bool
 }
 
 impl GLHandleOwner<GLHandleTypeTexture> for GLTexture
@@ -715,17 +1038,33 @@ impl GLTexture
         })?;
 
         Ok(GLTexture {
-            handle: Rc::new(handle)
+            handle: Rc::new(handle),
+            premultiplied_alpha: false
         })
     }
 
+    /// Marks the texture as containing colors which have been multiplied by
+    /// their alpha component, such as the contents of a render target.
+    pub fn with_premultiplied_alpha(mut self) -> Self
+    {
+        self.premultiplied_alpha = true;
+        self
+    }
+
+    #[inline]
+    pub fn has_premultiplied_alpha(&self) -> bool
+    {
+        self.premultiplied_alpha
+    }
+
     pub fn set_image_data(
         &self,
         context: &GLContextManager,
         format: GLTextureImageFormatU8,
         smoothing: GLTextureSmoothing,
+        wrap: GLTextureWrap,
         size: &UVec2,
-        data: &[u8]
+        data: Option<&[u8]>
     ) -> Result<(), BacktraceError<ErrorMessage>>
     {
         if !context.is_valid() {
@@ -733,24 +1072,21 @@ impl GLTexture
             return Ok(());
         }
 
-        let smoothing_constant = match smoothing {
-            GLTextureSmoothing::NearestNeighbour => GL_NEAREST,
-            GLTextureSmoothing::Linear => GL_LINEAR
+        if smoothing.has_mipmaps() {
+            context
+                .ensure_framebuffers_supported()
+                .context("Mipmaps are unsupported")?;
+        }
+
+        let wrap_constant = match wrap {
+            GLTextureWrap::ClampToEdge => GL_CLAMP_TO_EDGE,
+            GLTextureWrap::Repeat => GL_REPEAT,
+            GLTextureWrap::MirroredRepeat => GL_MIRRORED_REPEAT
         } as GLint;
 
         context.bind_texture(self);
 
-        let width_stride_bytes = size.x as usize * format.get_bytes_per_pixel();
-
-        let unpack_alignment = if width_stride_bytes.is_multiple_of(8) {
-            8
-        } else if width_stride_bytes.is_multiple_of(4) {
-            4
-        } else if width_stride_bytes.is_multiple_of(2) {
-            2
-        } else {
-            1
-        };
+        let unpack_alignment = GLTexture::unpack_alignment(&format, size.x);
 
         context.with_gl_backend::<Result<(), BacktraceError<ErrorMessage>>, _>(
             |backend| unsafe {
@@ -758,22 +1094,22 @@ impl GLTexture
                 backend.gl_tex_parameter_i(
                     GL_TEXTURE_2D,
                     GL_TEXTURE_WRAP_S,
-                    GL_CLAMP_TO_EDGE as GLint
+                    wrap_constant
                 );
                 backend.gl_tex_parameter_i(
                     GL_TEXTURE_2D,
                     GL_TEXTURE_WRAP_T,
-                    GL_CLAMP_TO_EDGE as GLint
+                    wrap_constant
                 );
                 backend.gl_tex_parameter_i(
                     GL_TEXTURE_2D,
                     GL_TEXTURE_MIN_FILTER,
-                    smoothing_constant
+                    smoothing.min_filter() as GLint
                 );
                 backend.gl_tex_parameter_i(
                     GL_TEXTURE_2D,
                     GL_TEXTURE_MAG_FILTER,
-                    smoothing_constant
+                    smoothing.mag_filter() as GLint
                 );
 
                 backend.gl_tex_image_2d(
@@ -788,55 +1124,284 @@ impl GLTexture
                     0,
                     format.get_format(),
                     GL_UNSIGNED_BYTE,
-                    Some(data)
+                    data
                 );
 
+                if smoothing.has_mipmaps() {
+                    backend.gl_generate_mipmap(GL_TEXTURE_2D);
+                }
+
                 Ok(())
             }
         )
     }
-}
 
-#[must_use]
-fn obtain_context_if_valid(
-    state: &RefCell<GLContextManagerState>
-) -> Option<GLContextManager>
-{
-    let state = state.borrow_mut();
+    /// Generates the mipmap levels of the texture from its current contents.
+    /// This must be called again after the contents change.
+    pub fn generate_mipmaps(&self, context: &GLContextManager)
+    {
+        if !context.is_valid() {
+            log::warn!("Ignoring texture generate_mipmaps: invalid GL context");
+            return;
+        }
 
-    if state.is_valid {
-        Some(GLContextManager {
-            state: state.weak_ref_to_self.upgrade().unwrap()
-        })
-    } else {
-        None
-    }
-}
+        context.bind_texture(self);
 
-#[inline]
-#[must_use]
-fn obtain_context_from_weak_if_valid(
-    state: &Weak<RefCell<GLContextManagerState>>
-) -> Option<GLContextManager>
-{
-    match state.upgrade() {
-        None => None,
-        Some(state) => obtain_context_if_valid(&state)
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_generate_mipmap(GL_TEXTURE_2D);
+        });
     }
-}
-
-struct GLContextManagerState
-{
-    is_valid: bool,
-    active_texture: Option<GLTexture>,
-    active_program: Option<Rc<GLProgram>>,
-    active_blend_mode: Option<GLBlendEnabled>,
-    viewport_size: Option<UVec2>,
-    scissor_enabled: bool,
-    gl_backend: Rc<dyn GLBackend + 'static>,
-    gl_version: GLVersion,
-    weak_ref_to_self: Weak<RefCell<GLContextManagerState>>
-}
+
+    /// Replaces the pixels in part of the texture, without changing its size
+    /// or parameters. The area must be inside the existing texture.
+    pub fn set_sub_image_data(
+        &self,
+        context: &GLContextManager,
+        format: GLTextureImageFormatU8,
+        offset: &UVec2,
+        size: &UVec2,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        if !context.is_valid() {
+            log::warn!("Ignoring texture set_sub_image_data: invalid GL context");
+            return Ok(());
+        }
+
+        context.bind_texture(self);
+
+        let unpack_alignment = GLTexture::unpack_alignment(&format, size.x);
+
+        context.with_gl_backend::<Result<(), BacktraceError<ErrorMessage>>, _>(
+            |backend| unsafe {
+                backend.gl_pixel_store_i(GL_UNPACK_ALIGNMENT, unpack_alignment);
+
+                backend.gl_tex_sub_image_2d(
+                    GL_TEXTURE_2D,
+                    0,
+                    offset.x.try_into()?,
+                    offset.y.try_into()?,
+                    size.x.try_into()?,
+                    size.y.try_into()?,
+                    format.get_format(),
+                    GL_UNSIGNED_BYTE,
+                    data
+                );
+
+                Ok(())
+            }
+        )
+    }
+
+    fn unpack_alignment(format: &GLTextureImageFormatU8, width: u32) -> GLint
+    {
+        let width_stride_bytes = width as usize * format.get_bytes_per_pixel();
+
+        if width_stride_bytes.is_multiple_of(8) {
+            8
+        } else if width_stride_bytes.is_multiple_of(4) {
+            4
+        } else if width_stride_bytes.is_multiple_of(2) {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// A framebuffer object which renders into a texture. If supported, the
+/// framebuffer also has a stencil buffer.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct GLFramebuffer
+{
+    handle: Rc<GLHandle<GLHandleTypeFramebuffer>>,
+    stencil_buffer: Option<Rc<GLHandle<GLHandleTypeRenderbuffer>>>
+}
+
+impl GLHandleOwner<GLHandleTypeFramebuffer> for GLFramebuffer
+{
+    fn get_handle(&self) -> <GLHandleTypeFramebuffer as GLHandleId>::HandleRawType
+    {
+        self.handle.handle.handle
+    }
+}
+
+impl GLFramebuffer
+{
+    fn new(
+        context: &GLContextManager,
+        texture: &GLTexture,
+        size: &UVec2
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let width = size.x.try_into()?;
+        let height = size.y.try_into()?;
+
+        let handle = GLHandle::wrap(context, GLHandleType::Framebuffer, || {
+            context.with_gl_backend(|backend| unsafe {
+                Ok(GLHandleTypeFramebuffer {
+                    handle: backend.gl_gen_framebuffer()?
+                })
+            })
+        })?;
+
+        let stencil_buffer = GLHandle::wrap(context, GLHandleType::Renderbuffer, || {
+            context.with_gl_backend(|backend| unsafe {
+                Ok(GLHandleTypeRenderbuffer {
+                    handle: backend.gl_gen_renderbuffer()?
+                })
+            })
+        })?;
+
+        let mut framebuffer = GLFramebuffer {
+            handle: Rc::new(handle),
+            stencil_buffer: None
+        };
+
+        let previous = RefCell::borrow(&context.state).active_framebuffer.clone();
+
+        context.bind_framebuffer(Some(&framebuffer));
+
+        let (status, has_stencil) = context.with_gl_backend(|backend| unsafe {
+            backend.gl_framebuffer_texture_2d(
+                GL_FRAMEBUFFER,
+                GL_COLOR_ATTACHMENT0,
+                GL_TEXTURE_2D,
+                Some(texture.get_handle()),
+                0
+            );
+
+            backend.gl_bind_renderbuffer(
+                GL_RENDERBUFFER,
+                Some(stencil_buffer.handle.handle)
+            );
+            backend.gl_renderbuffer_storage(
+                GL_RENDERBUFFER,
+                GL_DEPTH24_STENCIL8,
+                width,
+                height
+            );
+            backend.gl_bind_renderbuffer(GL_RENDERBUFFER, None);
+
+            backend.gl_framebuffer_renderbuffer(
+                GL_FRAMEBUFFER,
+                GL_DEPTH_STENCIL_ATTACHMENT,
+                GL_RENDERBUFFER,
+                Some(stencil_buffer.handle.handle)
+            );
+
+            let status = backend.gl_check_framebuffer_status(GL_FRAMEBUFFER);
+
+            if status == GL_FRAMEBUFFER_COMPLETE {
+                return (status, true);
+            }
+
+            // Some implementations can't combine these formats, so fall back
+            // to a framebuffer without a stencil buffer.
+            log::warn!(
+                "Framebuffer with stencil buffer is incomplete (status 0x{status:X}), \
+                 clipping to shapes will be unavailable"
+            );
+
+            backend.gl_framebuffer_renderbuffer(
+                GL_FRAMEBUFFER,
+                GL_DEPTH_STENCIL_ATTACHMENT,
+                GL_RENDERBUFFER,
+                None
+            );
+
+            (backend.gl_check_framebuffer_status(GL_FRAMEBUFFER), false)
+        });
+
+        context.bind_framebuffer(previous.as_ref());
+
+        if has_stencil {
+            framebuffer.stencil_buffer = Some(Rc::new(stencil_buffer));
+        }
+
+        if status != GL_FRAMEBUFFER_COMPLETE {
+            return Err(ErrorMessage::msg(format!(
+                "Framebuffer is incomplete (status 0x{status:X})"
+            )));
+        }
+
+        Ok(framebuffer)
+    }
+
+    /// Creates a framebuffer with no attachments, which can be used to read
+    /// back the contents of textures using `GLContextManager::read_texture()`.
+    fn new_for_reading(
+        context: &GLContextManager
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let handle = GLHandle::wrap(context, GLHandleType::Framebuffer, || {
+            context.with_gl_backend(|backend| unsafe {
+                Ok(GLHandleTypeFramebuffer {
+                    handle: backend.gl_gen_framebuffer()?
+                })
+            })
+        })?;
+
+        Ok(GLFramebuffer {
+            handle: Rc::new(handle),
+            stencil_buffer: None
+        })
+    }
+}
+
+#[must_use]
+fn obtain_context_if_valid(
+    state: &RefCell<GLContextManagerState>
+) -> Option<GLContextManager>
+{
+    let state = state.borrow_mut();
+
+    if state.is_valid {
+        Some(GLContextManager {
+            state: state.weak_ref_to_self.upgrade().unwrap()
+        })
+    } else {
+        None
+    }
+}
+
+#[inline]
+#[must_use]
+fn obtain_context_from_weak_if_valid(
+    state: &Weak<RefCell<GLContextManagerState>>
+) -> Option<GLContextManager>
+{
+    match state.upgrade() {
+        None => None,
+        Some(state) => obtain_context_if_valid(&state)
+    }
+}
+
+/// Counters for the work done by a [GLContextManager].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GLStatistics
+{
+    pub draw_calls: usize,
+    pub vertices_uploaded: usize,
+    pub texture_binds: usize
+}
+
+struct GLContextManagerState
+{
+    is_valid: bool,
+    active_texture: Option<GLTexture>,
+    active_program: Option<Rc<GLProgram>>,
+    active_blend_mode: Option<GLBlendEnabled>,
+    active_stencil_mode: Option<GLStencilMode>,
+    active_framebuffer: Option<GLFramebuffer>,
+    viewport_size: Option<UVec2>,
+    scissor_enabled: bool,
+    statistics: GLStatistics,
+    gl_backend: Rc<dyn GLBackend + 'static>,
+    gl_version: GLVersion,
+    framebuffers_supported: bool,
+    weak_ref_to_self: Weak<RefCell<GLContextManagerState>>
+}
 
 impl PartialEq for GLContextManagerState
 {
@@ -859,16 +1424,30 @@ impl GLContextManager
         gl_version: GLVersion
     ) -> Result<Self, BacktraceError<ErrorMessage>>
     {
+        let framebuffers_supported =
+            GLContextManager::detect_framebuffer_support(&gl_backend, gl_version);
+
+        if !framebuffers_supported {
+            log::warn!(
+                "Framebuffer objects are unsupported, so render targets, mipmaps, and \
+                 reading back images will be unavailable"
+            );
+        }
+
         let manager = GLContextManager {
             state: Rc::new(RefCell::new(GLContextManagerState {
                 is_valid: true,
                 active_texture: None,
                 active_program: None,
                 active_blend_mode: None,
+                active_stencil_mode: None,
+                active_framebuffer: None,
                 viewport_size: None,
                 scissor_enabled: false,
+                statistics: GLStatistics::default(),
                 gl_backend,
                 gl_version,
+                framebuffers_supported,
                 weak_ref_to_self: Weak::new()
             }))
         };
@@ -881,6 +1460,57 @@ impl GLContextManager
         Ok(manager)
     }
 
+    /// Framebuffer objects and glGenerateMipmap are only part of the core
+    /// profile from OpenGL 3.0 onwards. Earlier versions require the
+    /// GL_ARB_framebuffer_object extension.
+    fn detect_framebuffer_support(
+        gl_backend: &Rc<dyn GLBackend>,
+        gl_version: GLVersion
+    ) -> bool
+    {
+        match gl_version {
+            GLVersion::WebGL2_0 => true,
+            GLVersion::OpenGL2_0 => {
+                let version = unsafe { gl_backend.gl_get_string(GL_VERSION) };
+
+                let major_version = version
+                    .split('.')
+                    .next()
+                    .and_then(|major| major.trim().parse::<u32>().ok());
+
+                if major_version.is_some_and(|major| major >= 3) {
+                    return true;
+                }
+
+                let extensions = unsafe { gl_backend.gl_get_string(GL_EXTENSIONS) };
+
+                extensions
+                    .split_whitespace()
+                    .any(|extension| extension == "GL_ARB_framebuffer_object")
+            }
+        }
+    }
+
+    /// Returns true if framebuffer objects and mipmap generation are
+    /// available.
+    #[inline]
+    pub fn supports_framebuffers(&self) -> bool
+    {
+        RefCell::borrow(&self.state).framebuffers_supported
+    }
+
+    fn ensure_framebuffers_supported(&self) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        if !self.supports_framebuffers() {
+            return Err(ErrorMessage::msg(
+                "Framebuffer objects require OpenGL 3.0 or the \
+                 GL_ARB_framebuffer_object extension"
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn mark_invalid(&self)
     {
         log::info!("GL context manager is now inactive");
@@ -898,6 +1528,22 @@ impl GLContextManager
         GLBuffer::new(self, target, components_per_vertex, attrib_index)
     }
 
+    pub fn new_interleaved_buffer(
+        &self,
+        attributes: Vec<(GLAttributeHandle, GLint)>
+    ) -> Result<GLInterleavedBuffer, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+        GLInterleavedBuffer::new(self, attributes)
+    }
+
+    pub fn new_index_buffer(&self)
+        -> Result<GLIndexBuffer, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+        GLIndexBuffer::new(self)
+    }
+
     pub fn new_shader(
         &self,
         shader_type: GLShaderType,
@@ -931,6 +1577,56 @@ impl GLContextManager
         GLTexture::new(self)
     }
 
+    pub fn new_framebuffer(
+        &self,
+        texture: &GLTexture,
+        size: &UVec2
+    ) -> Result<GLFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+        self.ensure_framebuffers_supported()?;
+        GLFramebuffer::new(self, texture, size)
+    }
+
+    /// Creates a framebuffer which can be reused to read back the contents of
+    /// any texture, using `read_texture()`.
+    pub fn new_readback_framebuffer(
+        &self
+    ) -> Result<GLFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+        self.ensure_framebuffers_supported()?;
+        GLFramebuffer::new_for_reading(self)
+    }
+
+    /// Binds the specified framebuffer for subsequent drawing operations, or
+    /// the default framebuffer if `None` is specified.
+    pub fn bind_framebuffer(&self, framebuffer: Option<&GLFramebuffer>)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring bind_framebuffer: invalid GL context");
+            return;
+        }
+
+        if RefCell::borrow(&self.state).active_framebuffer.as_ref() == framebuffer {
+            // Already bound
+            return;
+        }
+
+        // Drop separately to avoid a duplicate borrow of `state`.
+        let old_framebuffer = RefCell::borrow_mut(&self.state).active_framebuffer.take();
+        drop(old_framebuffer);
+
+        RefCell::borrow_mut(&self.state).active_framebuffer = framebuffer.cloned();
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_framebuffer(
+                GL_FRAMEBUFFER,
+                framebuffer.map(|framebuffer| framebuffer.get_handle())
+            );
+        });
+    }
+
     pub fn set_viewport_size(&self, size: UVec2)
     {
         if !self.is_valid() {
@@ -963,7 +1659,11 @@ impl GLContextManager
         let old_active_texture = RefCell::borrow_mut(&self.state).active_texture.take();
         drop(old_active_texture);
 
-        RefCell::borrow_mut(&self.state).active_texture = Some(texture.clone());
+        {
+            let mut state = RefCell::borrow_mut(&self.state);
+            state.active_texture = Some(texture.clone());
+            state.statistics.texture_binds += 1;
+        }
 
         self.with_gl_backend(|backend| unsafe {
             backend.gl_active_texture(GL_TEXTURE0);
@@ -971,6 +1671,22 @@ impl GLContextManager
         });
     }
 
+    /// Binds a texture to an additional texture unit, for use by a custom
+    /// shader. Unit 0 is reserved for the texture bound by `bind_texture()`.
+    pub fn bind_texture_to_unit(&self, unit: u32, texture: &GLTexture)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring bind_texture_to_unit: invalid GL context");
+            return;
+        }
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_active_texture(GL_TEXTURE0 + unit);
+            backend.gl_bind_texture(GL_TEXTURE_2D, texture.get_handle());
+            backend.gl_active_texture(GL_TEXTURE0);
+        });
+    }
+
     pub fn unbind_texture(&self)
     {
         #[cfg(not(target_arch = "wasm32"))]
@@ -1012,7 +1728,10 @@ impl GLContextManager
             return;
         }
 
-        if let Some(existing_program) = &RefCell::borrow_mut(&self.state).active_program {
+        // Take separately to avoid a duplicate borrow of `state`.
+        let existing_program = RefCell::borrow_mut(&self.state).active_program.take();
+
+        if let Some(existing_program) = existing_program {
             existing_program.disable(self);
         }
 
@@ -1029,17 +1748,15 @@ impl GLContextManager
         RefCell::borrow_mut(&self.state).active_blend_mode = Some(blend_mode.clone());
 
         match blend_mode {
-            GLBlendEnabled::Enabled(mode) => match mode {
-                GLBlendMode::OneMinusSrcAlpha => self.with_gl_backend(|backend| unsafe {
+            GLBlendEnabled::Enabled(mode) => {
+                let (src_rgb, dst_rgb, src_alpha, dst_alpha) = mode.factors();
+
+                self.with_gl_backend(|backend| unsafe {
                     backend.gl_enable(GL_BLEND);
-                    backend.gl_blend_func_separate(
-                        GL_SRC_ALPHA,
-                        GL_ONE_MINUS_SRC_ALPHA,
-                        GL_ONE,
-                        GL_ONE_MINUS_SRC_ALPHA
-                    );
+                    backend
+                        .gl_blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
                 })
-            },
+            }
 
             GLBlendEnabled::Disabled => self.with_gl_backend(|backend| unsafe {
                 backend.gl_disable(GL_BLEND);
@@ -1060,18 +1777,110 @@ impl GLContextManager
         }
     }
 
+    pub fn set_stencil_mode(&self, mode: GLStencilMode)
+    {
+        if RefCell::borrow(&self.state).active_stencil_mode == Some(mode) {
+            return;
+        }
+
+        RefCell::borrow_mut(&self.state).active_stencil_mode = Some(mode);
+
+        let (func, reference, pass_op, write_color) = match mode {
+            GLStencilMode::Disabled => {
+                self.with_gl_backend(|backend| unsafe {
+                    backend.gl_disable(GL_STENCIL_TEST);
+                    backend.gl_color_mask(true, true, true, true);
+                });
+                return;
+            }
+            GLStencilMode::Test(value) => (GL_EQUAL, value, GL_KEEP, true),
+            GLStencilMode::Increment(value) => (GL_EQUAL, value, GL_INCR, false),
+            GLStencilMode::ReduceTo(value) => (GL_LESS, value, GL_REPLACE, false)
+        };
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_enable(GL_STENCIL_TEST);
+            backend.gl_stencil_func(func, reference.into(), 0xFF);
+            backend.gl_stencil_op(GL_KEEP, GL_KEEP, pass_op);
+            backend.gl_color_mask(write_color, write_color, write_color, write_color);
+        });
+    }
+
+    /// Resets the stencil buffer to zero. This is affected by the scissor
+    /// test, if enabled.
+    pub fn clear_stencil(&self)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring clear_stencil: invalid GL context");
+            return;
+        }
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_clear_stencil(0);
+            backend.gl_clear(GL_STENCIL_BUFFER_BIT);
+        });
+    }
+
     pub fn set_clip(&self, x: i32, y: i32, width: i32, height: i32)
     {
         let vp_height = match self.state.borrow().viewport_size {
             None => panic!("Call to set_clip before viewport size set"),
             Some(viewport_size) => viewport_size.y as i32
         };
+
+        // Framebuffers are rendered with the Y axis flipped, so that the
+        // first row of the texture is at the top.
+        let y = match self.state.borrow().active_framebuffer {
+            None => vp_height - y - height,
+            Some(_) => y
+        };
+
         self.with_gl_backend(|backend| unsafe {
-            backend.gl_scissor(x, vp_height - y - height, width, height);
+            backend.gl_scissor(x, y, width, height);
         });
     }
 
     pub fn draw_triangles(&self, blend_mode: GLBlendEnabled, vertex_count: usize)
+    {
+        self.draw_triangles_from(blend_mode, 0, vertex_count);
+    }
+
+    /// Draws triangles using the first `index_count` indices of the
+    /// specified index buffer.
+    pub fn draw_indexed_triangles(
+        &self,
+        blend_mode: GLBlendEnabled,
+        indices: &GLIndexBuffer,
+        index_count: usize
+    )
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring draw_indexed_triangles: invalid GL context");
+            return;
+        }
+
+        self.set_blend_mode(blend_mode);
+        indices.bind(self);
+
+        let index_count = index_count.min(indices.len());
+        RefCell::borrow_mut(&self.state).statistics.draw_calls += 1;
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_draw_elements(
+                GL_TRIANGLES,
+                index_count.try_into().unwrap(),
+                GL_UNSIGNED_INT,
+                0
+            );
+        });
+    }
+
+    pub fn draw_triangles_from(
+        &self,
+        blend_mode: GLBlendEnabled,
+        first_vertex: usize,
+        vertex_count: usize
+    )
     {
         if !self.is_valid() {
             log::warn!("Ignoring draw_triangles: invalid GL context");
@@ -1079,9 +1888,14 @@ impl GLContextManager
         }
 
         self.set_blend_mode(blend_mode);
+        RefCell::borrow_mut(&self.state).statistics.draw_calls += 1;
 
         self.with_gl_backend(|backend| unsafe {
-            backend.gl_draw_arrays(GL_TRIANGLES, 0, vertex_count.try_into().unwrap());
+            backend.gl_draw_arrays(
+                GL_TRIANGLES,
+                first_vertex.try_into().unwrap(),
+                vertex_count.try_into().unwrap()
+            );
         });
     }
 
@@ -1098,6 +1912,21 @@ impl GLContextManager
         });
     }
 
+    /// Records that the specified number of vertices were uploaded to the GPU.
+    pub fn count_vertices_uploaded(&self, vertex_count: usize)
+    {
+        RefCell::borrow_mut(&self.state)
+            .statistics
+            .vertices_uploaded += vertex_count;
+    }
+
+    /// Returns the work done since the previous call to this function, and
+    /// resets the counters to zero.
+    pub fn take_statistics(&self) -> GLStatistics
+    {
+        std::mem::take(&mut RefCell::borrow_mut(&self.state).statistics)
+    }
+
     fn with_gl_backend<Return, F>(&self, callback: F) -> Return
     where
         F: FnOnce(&Rc<dyn GLBackend>) -> Return
@@ -1125,6 +1954,19 @@ impl GLContextManager
         self.state.borrow().gl_version
     }
 
+    /// Returns the number of texture units which a fragment shader can use.
+    pub fn max_texture_units(&self) -> usize
+    {
+        if !self.is_valid() {
+            return 0;
+        }
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_get_parameter_i32(GL_MAX_TEXTURE_IMAGE_UNITS)
+        })
+        .max(0) as usize
+    }
+
     pub fn capture(&mut self, format: ImageDataType) -> RawBitmapData
     {
         let viewport_size = match self.state.borrow().viewport_size {
@@ -1132,8 +1974,133 @@ impl GLContextManager
             Some(value) => value
         };
 
-        let width: usize = viewport_size.x.try_into().unwrap();
-        let height: usize = viewport_size.y.try_into().unwrap();
+        self.capture_region(&URect::new(UVec2::ZERO, viewport_size), format)
+    }
+
+    /// Reads the pixels in the specified area of the viewport, measured from
+    /// the top left. The area is cropped to the viewport.
+    pub fn capture_region(
+        &mut self,
+        region: &URect,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        let viewport_size = match self.state.borrow().viewport_size {
+            None => return RawBitmapData::new(vec![], (0, 0), format),
+            Some(value) => value
+        };
+
+        let region = match region.intersect(&URect::new(UVec2::ZERO, viewport_size)) {
+            None => return RawBitmapData::new(vec![], (0, 0), format),
+            Some(region) => region
+        };
+
+        if self.state.borrow().active_framebuffer.is_some() {
+            // Framebuffer rows are already stored from top to bottom
+            let buf = self.read_pixels(*region.top_left(), region.size(), format);
+            return RawBitmapData::new(buf, region.size(), format);
+        }
+
+        let position = UVec2::new(
+            region.top_left().x,
+            viewport_size.y - region.bottom_right().y
+        );
+
+        let buf = self.read_pixels(position, region.size(), format);
+
+        let mut result = RawBitmapData::new(buf, region.size(), format);
+        result.flip_vertically();
+        result
+    }
+
+    /// Reads the contents of the specified framebuffer, which must have the
+    /// specified size. The previously bound framebuffer is restored
+    /// afterwards.
+    pub fn read_framebuffer(
+        &self,
+        framebuffer: &GLFramebuffer,
+        size: UVec2,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring read_framebuffer: invalid GL context");
+            return RawBitmapData::new(vec![], (0, 0), format);
+        }
+
+        let previous = RefCell::borrow(&self.state).active_framebuffer.clone();
+
+        self.bind_framebuffer(Some(framebuffer));
+
+        // Framebuffer rows are already stored from top to bottom
+        let buf = self.read_pixels(UVec2::ZERO, size, format);
+
+        self.bind_framebuffer(previous.as_ref());
+
+        RawBitmapData::new(buf, size, format)
+    }
+
+    /// Reads the contents of the specified texture, which must have the
+    /// specified size, by attaching it to `framebuffer` while the pixels are
+    /// read. The framebuffer must have been created using
+    /// `new_readback_framebuffer()`.
+    pub fn read_texture(
+        &self,
+        framebuffer: &GLFramebuffer,
+        texture: &GLTexture,
+        size: UVec2,
+        format: ImageDataType
+    ) -> Result<RawBitmapData, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+
+        let previous = RefCell::borrow(&self.state).active_framebuffer.clone();
+
+        self.bind_framebuffer(Some(framebuffer));
+
+        let attach = |texture: Option<&GLTexture>| {
+            self.with_gl_backend(|backend| unsafe {
+                backend.gl_framebuffer_texture_2d(
+                    GL_FRAMEBUFFER,
+                    GL_COLOR_ATTACHMENT0,
+                    GL_TEXTURE_2D,
+                    texture.map(|texture| texture.get_handle()),
+                    0
+                );
+            });
+        };
+
+        attach(Some(texture));
+
+        let status = self.with_gl_backend(|backend| unsafe {
+            backend.gl_check_framebuffer_status(GL_FRAMEBUFFER)
+        });
+
+        let result = if status == GL_FRAMEBUFFER_COMPLETE {
+            // Framebuffer rows are already stored from top to bottom
+            let buf = self.read_pixels(UVec2::ZERO, size, format);
+            Ok(RawBitmapData::new(buf, size, format))
+        } else {
+            Err(ErrorMessage::msg(format!(
+                "Framebuffer is incomplete (status 0x{status:X})"
+            )))
+        };
+
+        // Detach the texture, so that the framebuffer doesn't keep it alive
+        attach(None);
+
+        self.bind_framebuffer(previous.as_ref());
+
+        result
+    }
+
+    /// Reads an area of the currently bound framebuffer, with no padding
+    /// between rows. The position is measured in GL coordinates.
+    fn read_pixels(&self, position: UVec2, size: UVec2, format: ImageDataType)
+        -> Vec<u8>
+    {
+        let width: usize = size.x.try_into().unwrap();
+        let height: usize = size.y.try_into().unwrap();
 
         let gl_format = GLTextureImageFormatU8::from(format);
 
@@ -1145,9 +2112,10 @@ impl GLContextManager
         let mut buf: Vec<u8> = Vec::with_capacity(bytes);
 
         self.with_gl_backend(|backend| unsafe {
+            backend.gl_pixel_store_i(GL_PACK_ALIGNMENT, 1);
             backend.gl_read_pixels(
-                0,
-                0,
+                position.x.try_into().unwrap(),
+                position.y.try_into().unwrap(),
                 width.try_into().unwrap(),
                 height.try_into().unwrap(),
                 gl_format,
@@ -1160,39 +2128,100 @@ impl GLContextManager
             buf.set_len(bytes);
         }
 
-        let row_bytes = width * bpp;
-
-        let buf_ptr = buf.as_mut_ptr();
-
-        for row in 0..(height / 2) {
-            let bottom_row = height - row - 1;
+        buf
+    }
+}
 
-            let top_start = row * row_bytes;
-            let bottom_start = bottom_row * row_bytes;
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub enum GLBlendMode
+{
+    OneMinusSrcAlpha,
+    Additive,
+    // The multiply and screen modes expect the shader to output premultiplied
+    // colors.
+    Multiply,
+    Screen,
+    // The shader output is already premultiplied, so the source color is
+    // added without scaling it by its alpha.
+    Premultiplied,
+    AdditivePremultiplied
+}
 
-            unsafe {
-                ptr::swap_nonoverlapping(
-                    buf_ptr.add(top_start),
-                    buf_ptr.add(bottom_start),
-                    row_bytes
-                );
+impl GLBlendMode
+{
+    /// Returns the source and destination factors for the RGB components,
+    /// followed by those for the alpha component.
+    fn factors(&self) -> (GLenum, GLenum, GLenum, GLenum)
+    {
+        match self {
+            GLBlendMode::OneMinusSrcAlpha => (
+                GL_SRC_ALPHA,
+                GL_ONE_MINUS_SRC_ALPHA,
+                GL_ONE,
+                GL_ONE_MINUS_SRC_ALPHA
+            ),
+            GLBlendMode::Additive => (GL_SRC_ALPHA, GL_ONE, GL_ZERO, GL_ONE),
+            GLBlendMode::Multiply => {
+                (GL_DST_COLOR, GL_ONE_MINUS_SRC_ALPHA, GL_ZERO, GL_ONE)
             }
+            GLBlendMode::Screen => (
+                GL_ONE,
+                GL_ONE_MINUS_SRC_COLOR,
+                GL_ONE,
+                GL_ONE_MINUS_SRC_ALPHA
+            ),
+            GLBlendMode::Premultiplied => (
+                GL_ONE,
+                GL_ONE_MINUS_SRC_ALPHA,
+                GL_ONE,
+                GL_ONE_MINUS_SRC_ALPHA
+            ),
+            GLBlendMode::AdditivePremultiplied => (GL_ONE, GL_ONE, GL_ZERO, GL_ONE)
         }
-
-        RawBitmapData::new(buf, viewport_size, format)
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone)]
-pub enum GLBlendMode
+/// The stencil test and stencil buffer update performed for each pixel.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum GLStencilMode
 {
-    OneMinusSrcAlpha
+    Disabled,
+    /// Draw only where the stencil value is equal to the given value.
+    Test(u8),
+    /// Increment the stencil value where it is equal to the given value,
+    /// without drawing any color.
+    Increment(u8),
+    /// Replace stencil values greater than the given value with that value,
+    /// without drawing any color.
+    ReduceTo(u8)
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub enum GLBlendEnabled
 {
     Enabled(GLBlendMode),
-    #[allow(dead_code)]
     Disabled
 }
+
+impl GLBlendEnabled
+{
+    /// Returns the blending used for `blend_mode`. If the shader output has
+    /// been premultiplied (see [BlendMode::requires_premultiplied_output]),
+    /// the source color must not be multiplied by its alpha again.
+    pub fn new(blend_mode: BlendMode, texture_premultiplied: bool) -> Self
+    {
+        match (blend_mode, texture_premultiplied) {
+            (BlendMode::Normal, false) => Self::Enabled(GLBlendMode::OneMinusSrcAlpha),
+            (BlendMode::Normal, true) | (BlendMode::Premultiplied, _) => {
+                Self::Enabled(GLBlendMode::Premultiplied)
+            }
+            (BlendMode::Additive, false) => Self::Enabled(GLBlendMode::Additive),
+            (BlendMode::Additive, true) => {
+                Self::Enabled(GLBlendMode::AdditivePremultiplied)
+            }
+            (BlendMode::Multiply, _) => Self::Enabled(GLBlendMode::Multiply),
+            (BlendMode::Screen, _) => Self::Enabled(GLBlendMode::Screen),
+            (BlendMode::Replace, _) => Self::Disabled
+        }
+    }
+}
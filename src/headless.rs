@@ -0,0 +1,129 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::ffi::CString;
+use std::num::NonZeroU32;
+
+use glutin::api::egl::context::PossiblyCurrentContext;
+use glutin::api::egl::device::Device;
+use glutin::api::egl::display::Display;
+use glutin::api::egl::surface::Surface;
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::context::{
+    ContextApi,
+    ContextAttributesBuilder,
+    NotCurrentGlContext,
+    Version
+};
+use glutin::display::GlDisplay;
+use glutin::surface::{PbufferSurface, SurfaceAttributesBuilder};
+
+use crate::dimen::UVec2;
+use crate::error::{BacktraceError, Context, ErrorMessage};
+
+/// An OpenGL context which renders into an offscreen EGL pbuffer, without
+/// requiring a window or a display server.
+pub(crate) struct HeadlessContext
+{
+    display: Display,
+    _context: PossiblyCurrentContext,
+    _surface: Surface<PbufferSurface>
+}
+
+impl HeadlessContext
+{
+    /// Creates a context with a pbuffer of the specified size, using the
+    /// first EGL device which supports it, and makes it current on this
+    /// thread.
+    pub(crate) fn new(size: UVec2) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let width = NonZeroU32::new(size.x)
+            .ok_or_else(|| ErrorMessage::msg("Width must be greater than zero"))?;
+
+        let height = NonZeroU32::new(size.y)
+            .ok_or_else(|| ErrorMessage::msg("Height must be greater than zero"))?;
+
+        let devices = Device::query_devices().context("Failed to query EGL devices")?;
+
+        for device in devices {
+            log::info!("Trying EGL device: {:?}", device.name());
+
+            match Self::new_for_device(&device, width, height) {
+                Ok(context) => return Ok(context),
+                Err(err) => log::info!("Failed with error: {:?}", err)
+            }
+        }
+
+        Err(ErrorMessage::msg(
+            "None of the available EGL devices support offscreen rendering"
+        ))
+    }
+
+    fn new_for_device(
+        device: &Device,
+        width: NonZeroU32,
+        height: NonZeroU32
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let display = unsafe { Display::with_device(device, None) }
+            .context("Failed to create EGL display")?;
+
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::PBUFFER)
+            .with_alpha_size(8)
+            .with_stencil_size(8)
+            .build();
+
+        let config = unsafe { display.find_configs(template) }
+            .context("Failed to find EGL configs")?
+            .next()
+            .ok_or_else(|| ErrorMessage::msg("No suitable EGL config found"))?;
+
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::OpenGl(Some(Version::new(2, 0))))
+            .build(None);
+
+        let context = unsafe { display.create_context(&config, &context_attributes) }
+            .context("Failed to create GL context")?;
+
+        let surface = unsafe {
+            display.create_pbuffer_surface(
+                &config,
+                &SurfaceAttributesBuilder::<PbufferSurface>::new().build(width, height)
+            )
+        }
+        .context("Failed to create pbuffer surface")?;
+
+        let context = context
+            .make_current(&surface)
+            .context("Failed to make context current")?;
+
+        Ok(HeadlessContext {
+            display,
+            _context: context,
+            _surface: surface
+        })
+    }
+
+    /// Returns the address of the specified GL function.
+    pub(crate) fn get_proc_address(&self, name: &str) -> *const std::os::raw::c_void
+    {
+        match CString::new(name) {
+            Ok(name) => self.display.get_proc_address(name.as_c_str()),
+            Err(_) => std::ptr::null()
+        }
+    }
+}
@@ -14,8 +14,14 @@
  *  limitations under the License.
  */
 
+#[cfg(any(feature = "image-loading", doc, doctest))]
+use {
+    crate::error::{BacktraceError, Context, ErrorMessage},
+    std::path::Path
+};
+
 use crate::dimen::UVec2;
-use crate::glwrapper::GLTexture;
+use crate::glwrapper::{GLFramebuffer, GLTexture};
 
 /// The data type of the pixels making up the raw image data.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -30,6 +36,18 @@ pub enum ImageDataType
     RGBA
 }
 
+impl ImageDataType
+{
+    #[inline]
+    pub(crate) fn bytes_per_pixel(self) -> usize
+    {
+        match self {
+            ImageDataType::RGB => 3,
+            ImageDataType::RGBA => 4
+        }
+    }
+}
+
 /// Represents a handle for a loaded image.
 ///
 /// Note: this handle can only be used in the graphics context in which it was
@@ -38,6 +56,9 @@ pub enum ImageDataType
 pub struct ImageHandle
 {
     pub(crate) size: UVec2,
+    pub(crate) smoothing_mode: ImageSmoothingMode,
+    pub(crate) wrap_mode: ImageWrapMode,
+    pub(crate) mipmaps: bool,
     pub(crate) texture: GLTexture
 }
 
@@ -48,6 +69,72 @@ impl ImageHandle
     {
         &self.size
     }
+
+    /// Returns the smoothing mode which was specified when the image was
+    /// created.
+    pub fn smoothing_mode(&self) -> ImageSmoothingMode
+    {
+        self.smoothing_mode
+    }
+
+    /// Returns the wrap mode which was specified when the image was created.
+    pub fn wrap_mode(&self) -> ImageWrapMode
+    {
+        self.wrap_mode
+    }
+
+    /// Returns true if mipmaps were generated for the image. See
+    /// [ImageOptions::with_mipmaps].
+    pub fn has_mipmaps(&self) -> bool
+    {
+        self.mipmaps
+    }
+}
+
+/// An offscreen image which can be drawn into using the full
+/// [crate::Graphics2D] API, and then drawn to the screen (or to another render
+/// target) like any other image.
+///
+/// Create a render target using [crate::Graphics2D::create_render_target],
+/// and draw into it using [crate::Graphics2D::draw_to_render_target]. To draw
+/// the result, pass [RenderTarget::image] to a function such as
+/// [crate::Graphics2D::draw_image].
+///
+/// The contents of the render target are preserved until they are drawn over
+/// or cleared. Colors are stored with premultiplied alpha, and this is taken
+/// into account when the image is drawn, so translucent content looks the
+/// same as if it had been drawn directly.
+///
+/// Note: this handle can only be used in the graphics context in which it was
+/// created.
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct RenderTarget
+{
+    image: ImageHandle,
+    pub(crate) framebuffer: GLFramebuffer
+}
+
+impl RenderTarget
+{
+    pub(crate) fn new(image: ImageHandle, framebuffer: GLFramebuffer) -> Self
+    {
+        RenderTarget { image, framebuffer }
+    }
+
+    /// Returns the size of the render target in pixels.
+    pub fn size(&self) -> &UVec2
+    {
+        self.image.size()
+    }
+
+    /// Returns a handle to the contents of the render target, which may be
+    /// drawn like any other image.
+    ///
+    /// The image must not be drawn into the render target itself.
+    pub fn image(&self) -> &ImageHandle
+    {
+        &self.image
+    }
 }
 
 /// `ImageSmoothingMode` defines how images are rendered when the pixels of the
@@ -66,7 +153,160 @@ pub enum ImageSmoothingMode
     /// nearest pixels in the source image. This produces a smoother result
     /// than `NearestNeighbor`, but in cases where the image is intended to
     /// be pixel-aligned it may cause unnecessary blurriness.
-    Linear
+    Linear,
+
+    /// The same as `Linear` when the image is enlarged. When the image is
+    /// drawn smaller than its original size, pixels are interpolated between
+    /// the two nearest mipmap levels, which avoids the shimmering and
+    /// aliasing caused by skipping over pixels of the source image.
+    ///
+    /// Mipmaps are always generated for images using this mode. See
+    /// [ImageOptions::with_mipmaps].
+    Trilinear
+}
+
+/// `ImageWrapMode` defines which pixels are drawn when texture coordinates
+/// outside the image are used, for example by
+/// [crate::Graphics2D::draw_rectangle_image_tiled].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ImageWrapMode
+{
+    /// The pixels at the edge of the image are extended outwards. This is the
+    /// default.
+    #[default]
+    ClampToEdge,
+
+    /// The image is repeated.
+    Repeat,
+
+    /// The image is repeated, with every other copy flipped, so that the edges
+    /// of adjacent copies match.
+    MirroredRepeat
+}
+
+/// The options used when creating an image, controlling how the image is
+/// sampled when it's drawn.
+///
+/// Any function which accepts `ImageOptions` also accepts an
+/// [ImageSmoothingMode], in which case the default options are used for
+/// everything else.
+///
+/// ```rust,no_run
+/// # use speedy2d::Graphics2D;
+/// # use speedy2d::image::{ImageDataType, ImageOptions, ImageSmoothingMode};
+/// # use speedy2d::image::ImageWrapMode;
+/// # fn draw(graphics: &mut Graphics2D, pixels: &[u8]) {
+/// let pattern = graphics
+///     .create_image_from_raw_pixels(
+///         ImageDataType::RGBA,
+///         ImageOptions::new(ImageSmoothingMode::Linear)
+///             .with_wrap_mode(ImageWrapMode::Repeat),
+///         (32, 32),
+///         pixels
+///     )
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct ImageOptions
+{
+    smoothing_mode: ImageSmoothingMode,
+    wrap_mode: ImageWrapMode,
+    mipmaps: bool
+}
+
+impl ImageOptions
+{
+    /// Creates a new set of options with the specified smoothing mode, and
+    /// [ImageWrapMode::ClampToEdge].
+    #[inline]
+    #[must_use]
+    pub fn new(smoothing_mode: ImageSmoothingMode) -> Self
+    {
+        ImageOptions {
+            smoothing_mode,
+            wrap_mode: ImageWrapMode::ClampToEdge,
+            mipmaps: false
+        }
+    }
+
+    /// Sets the wrap mode, used when drawing parts of the image outside its
+    /// bounds.
+    #[inline]
+    #[must_use]
+    pub fn with_wrap_mode(mut self, wrap_mode: ImageWrapMode) -> Self
+    {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    /// Sets whether mipmaps are generated for the image. Mipmaps are
+    /// successively smaller copies of the image, which are used when the
+    /// image is drawn smaller than its original size to reduce aliasing.
+    /// They use an extra third of the image's memory, and are updated
+    /// automatically when the image changes.
+    ///
+    /// With [ImageSmoothingMode::NearestNeighbor] or
+    /// [ImageSmoothingMode::Linear], the nearest mipmap level is used. Mipmaps
+    /// are always generated when using [ImageSmoothingMode::Trilinear].
+    ///
+    /// Generating mipmaps requires OpenGL 3.0 or later (or the
+    /// `GL_ARB_framebuffer_object` extension), WebGL 2, or the software
+    /// renderer. Otherwise, creating the image returns an error.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::image::{ImageDataType, ImageOptions, ImageSmoothingMode};
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D, pixels: &[u8]) {
+    /// // A map tile which is drawn at many different zoom levels
+    /// let tile = graphics
+    ///     .create_image_from_raw_pixels(
+    ///         ImageDataType::RGBA,
+    ///         ImageOptions::new(ImageSmoothingMode::Trilinear),
+    ///         (256, 256),
+    ///         pixels
+    ///     )
+    ///     .unwrap();
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self
+    {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Returns the smoothing mode.
+    #[inline]
+    pub fn smoothing_mode(&self) -> ImageSmoothingMode
+    {
+        self.smoothing_mode
+    }
+
+    /// Returns the wrap mode.
+    #[inline]
+    pub fn wrap_mode(&self) -> ImageWrapMode
+    {
+        self.wrap_mode
+    }
+
+    /// Returns true if mipmaps will be generated, either because they were
+    /// requested using [ImageOptions::with_mipmaps], or because the smoothing
+    /// mode is [ImageSmoothingMode::Trilinear].
+    #[inline]
+    pub fn mipmaps(&self) -> bool
+    {
+        self.mipmaps || self.smoothing_mode == ImageSmoothingMode::Trilinear
+    }
+}
+
+impl From<ImageSmoothingMode> for ImageOptions
+{
+    fn from(smoothing_mode: ImageSmoothingMode) -> Self
+    {
+        ImageOptions::new(smoothing_mode)
+    }
 }
 
 /// Supported image formats.
@@ -103,6 +343,28 @@ pub enum ImageFileFormat
     Farbfeld
 }
 
+impl ImageFileFormat
+{
+    #[cfg(any(feature = "image-loading", doc, doctest))]
+    pub(crate) fn to_image_format(self) -> image::ImageFormat
+    {
+        match self {
+            ImageFileFormat::PNG => image::ImageFormat::Png,
+            ImageFileFormat::JPEG => image::ImageFormat::Jpeg,
+            ImageFileFormat::GIF => image::ImageFormat::Gif,
+            ImageFileFormat::BMP => image::ImageFormat::Bmp,
+            ImageFileFormat::ICO => image::ImageFormat::Ico,
+            ImageFileFormat::TIFF => image::ImageFormat::Tiff,
+            ImageFileFormat::WebP => image::ImageFormat::WebP,
+            ImageFileFormat::AVIF => image::ImageFormat::Avif,
+            ImageFileFormat::PNM => image::ImageFormat::Pnm,
+            ImageFileFormat::DDS => image::ImageFormat::Dds,
+            ImageFileFormat::TGA => image::ImageFormat::Tga,
+            ImageFileFormat::Farbfeld => image::ImageFormat::Farbfeld
+        }
+    }
+}
+
 /// A type to represent some raw pixel data, with an associated width and height
 /// in pixels.
 #[derive(Clone)]
@@ -151,4 +413,169 @@ impl RawBitmapData
     {
         self.data
     }
+
+    /// Reverses the order of the rows, so that the top row becomes the
+    /// bottom row.
+    pub fn flip_vertically(&mut self)
+    {
+        let row_bytes = self.size.x as usize * self.format.bytes_per_pixel();
+
+        if row_bytes == 0 {
+            return;
+        }
+
+        let rows = self.data.len() / row_bytes;
+
+        for row in 0..(rows / 2) {
+            let (top, bottom) = self.data.split_at_mut((rows - row - 1) * row_bytes);
+            top[row * row_bytes..(row + 1) * row_bytes]
+                .swap_with_slice(&mut bottom[..row_bytes]);
+        }
+    }
+
+    /// Converts RGBA data with premultiplied alpha into non-premultiplied
+    /// data in the specified format.
+    pub(crate) fn unpremultiply_alpha(self, format: ImageDataType) -> Self
+    {
+        debug_assert_eq!(ImageDataType::RGBA, self.format);
+
+        let mut data = Vec::with_capacity(
+            self.size.x as usize * self.size.y as usize * format.bytes_per_pixel()
+        );
+
+        for pixel in self.data.chunks_exact(4) {
+            let alpha = pixel[3] as u32;
+
+            for component in &pixel[..3] {
+                data.push(match alpha {
+                    0 => 0,
+                    _ => ((*component as u32 * 255 + alpha / 2) / alpha).min(255) as u8
+                });
+            }
+
+            if format == ImageDataType::RGBA {
+                data.push(pixel[3]);
+            }
+        }
+
+        Self::new(data, self.size, format)
+    }
+
+    /// Encodes the pixels in the specified file format, returning the bytes
+    /// of the file.
+    ///
+    /// Not every format which can be loaded can also be encoded. An error is
+    /// returned if the format isn't supported.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::image::{ImageDataType, ImageFileFormat};
+    /// # use speedy2d::shape::URect;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// // Save a screenshot of one panel of the window
+    /// graphics
+    ///     .capture_region(URect::from_tuples((0, 0), (200, 100)), ImageDataType::RGBA)
+    ///     .save_to_file("panel.png", ImageFileFormat::PNG)
+    ///     .unwrap();
+    /// # }
+    /// ```
+    #[cfg(any(feature = "image-loading", doc, doctest))]
+    pub fn encode(
+        &self,
+        format: ImageFileFormat
+    ) -> Result<Vec<u8>, BacktraceError<ErrorMessage>>
+    {
+        let (width, height) = (self.size.x, self.size.y);
+
+        let image = match self.format {
+            ImageDataType::RGB => {
+                image::RgbImage::from_raw(width, height, self.data.clone())
+                    .map(image::DynamicImage::ImageRgb8)
+            }
+            ImageDataType::RGBA => {
+                image::RgbaImage::from_raw(width, height, self.data.clone())
+                    .map(image::DynamicImage::ImageRgba8)
+            }
+        }
+        .ok_or_else(|| ErrorMessage::msg("Pixel data is smaller than the image size"))?;
+
+        let mut bytes = Vec::new();
+
+        image
+            .write_to(&mut bytes, format.to_image_format())
+            .context(format!("Failed to encode image as {:?}", format))?;
+
+        Ok(bytes)
+    }
+
+    /// Encodes the pixels in the specified file format, and writes them to
+    /// the specified path. See [RawBitmapData::encode].
+    #[cfg(any(feature = "image-loading", doc, doctest))]
+    pub fn save_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: ImageFileFormat
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        let bytes = self.encode(format)?;
+
+        std::fs::write(path.as_ref(), bytes)
+            .context(format!("Failed to write file '{:?}'", path.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn flip_vertically()
+    {
+        let mut bitmap = RawBitmapData::new(
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            (1, 3),
+            ImageDataType::RGB
+        );
+
+        bitmap.flip_vertically();
+
+        assert_eq!(&vec![7, 8, 9, 4, 5, 6, 1, 2, 3], bitmap.data());
+    }
+
+    #[test]
+    fn unpremultiply_alpha()
+    {
+        let bitmap = RawBitmapData::new(
+            vec![128, 64, 0, 128, 0, 0, 0, 0, 10, 20, 30, 255],
+            (3, 1),
+            ImageDataType::RGBA
+        );
+
+        let rgba = bitmap.clone().unpremultiply_alpha(ImageDataType::RGBA);
+        let rgb = bitmap.unpremultiply_alpha(ImageDataType::RGB);
+
+        assert_eq!(
+            &vec![255, 128, 0, 128, 0, 0, 0, 0, 10, 20, 30, 255],
+            rgba.data()
+        );
+        assert_eq!(&vec![255, 128, 0, 0, 0, 0, 10, 20, 30], rgb.data());
+        assert_eq!(ImageDataType::RGB, rgb.format());
+    }
+
+    #[test]
+    #[cfg(feature = "image-loading")]
+    fn encode_png()
+    {
+        let data = vec![255, 0, 0, 255, 0, 255, 0, 128];
+        let bitmap = RawBitmapData::new(data.clone(), (2, 1), ImageDataType::RGBA);
+
+        let bytes = bitmap.encode(ImageFileFormat::PNG).unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap().into_rgba8();
+
+        assert_eq!((2, 1), decoded.dimensions());
+        assert_eq!(data, decoded.into_raw());
+
+        assert!(bitmap.encode(ImageFileFormat::DDS).is_err());
+    }
 }
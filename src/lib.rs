@@ -309,15 +309,42 @@ use {
 
 use crate::color::Color;
 use crate::dimen::{UVec2, Vec2};
+use crate::draw_list::DrawList;
 use crate::error::{BacktraceError, ErrorMessage};
 use crate::font::FormattedTextBlock;
 use crate::glbackend::GLBackend;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::glbackend::GLBackendGlow;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::glbackend_software::GLBackendSoftware;
 use crate::glwrapper::{GLContextManager, GLVersion};
-use crate::image::{ImageDataType, ImageHandle, ImageSmoothingMode, RawBitmapData};
+#[cfg(all(feature = "headless", egl_backend))]
+use crate::headless::HeadlessContext;
+use crate::image::{
+    ImageDataType,
+    ImageHandle,
+    ImageOptions,
+    RawBitmapData,
+    RenderTarget
+};
+use crate::mesh::StaticMesh;
+use crate::paint::{BlendMode, Paint};
 use crate::renderer2d::Renderer2D;
-use crate::shape::{Polygon, Rect, Rectangle, RoundedRectangle};
+use crate::shader::{CustomShader, ShaderLanguage};
+use crate::shape::{
+    ClipShape,
+    Ellipse,
+    Polygon,
+    Rect,
+    Rectangle,
+    RoundedRectangle,
+    StrokeStyle,
+    URect
+};
+use crate::sprite::SpriteBatch;
+use crate::texture_atlas::SubImageHandle;
+use crate::transform::Transform;
+use crate::viewport::VirtualResolution;
 #[cfg(target_arch = "wasm32")]
 use crate::web::WebCanvasElement;
 #[cfg(any(doc, doctest, feature = "windowing"))]
@@ -347,6 +374,33 @@ pub mod color;
 /// Types representing shapes.
 pub mod shape;
 
+/// Types describing how shapes are filled, including gradients.
+pub mod paint;
+
+/// Types representing 2D transformations, such as rotation and scaling.
+pub mod transform;
+
+/// Types for drawing to a fixed logical resolution, scaled to fit the window.
+pub mod viewport;
+
+/// Support for custom fragment shaders.
+pub mod shader;
+
+/// Recording and replaying drawing operations.
+pub mod draw_list;
+
+/// Static geometry which is uploaded to the GPU once, and drawn many times.
+pub mod mesh;
+
+/// Types for drawing large numbers of sprites efficiently.
+pub mod sprite;
+
+/// An overlay showing the frame rate and renderer statistics.
+pub mod debug_overlay;
+
+/// Packing many small images into shared textures.
+pub mod texture_atlas;
+
 /// Components for loading fonts and laying out text.
 pub mod font;
 
@@ -387,10 +441,17 @@ mod web;
 
 mod font_cache;
 mod glbackend;
+#[cfg(not(target_arch = "wasm32"))]
+mod glbackend_software;
 #[cfg(all(feature = "windowing", not(target_arch = "wasm32")))]
 mod glutin_winit;
 mod glwrapper;
+#[cfg(all(feature = "headless", egl_backend))]
+mod headless;
 mod renderer2d;
+mod tessellate;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test_utils;
 mod texture_packer;
 mod utils;
 
@@ -436,6 +497,105 @@ impl Display for GLRendererCreationError
     }
 }
 
+/// Controls whether queued items may be drawn in a different order to reduce
+/// the number of draw calls. Set the batching mode using
+/// [Graphics2D::set_batching_mode].
+///
+/// Each time the texture, gradient, blend mode, or shader changes between
+/// consecutive items, the renderer must issue a separate draw call. When
+/// drawing text interleaved with images, for example, this can result in
+/// one draw call per item.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum BatchingMode
+{
+    /// Items are drawn in exactly the order they were submitted. This is the
+    /// default.
+    #[default]
+    Ordered,
+
+    /// Items sharing the same texture, gradient, blend mode, and shader are
+    /// grouped together and drawn using a single draw call. An item is only
+    /// moved ahead of earlier items which it doesn't overlap, so the visual
+    /// result is the same as [BatchingMode::Ordered].
+    ///
+    /// In this mode, items are also sorted by their layer, set using
+    /// [Graphics2D::set_layer]. Items on a higher layer are drawn on top of
+    /// items on a lower layer, regardless of the order they were submitted.
+    Grouped
+}
+
+/// Statistics about the rendering work done during a single frame. See
+/// [GLRenderer::last_frame_statistics] and [Graphics2D::last_frame_statistics].
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct FrameStatistics
+{
+    draw_calls: usize,
+    vertices_uploaded: usize,
+    texture_binds: usize,
+    glyph_cache_hits: usize,
+    glyph_cache_misses: usize,
+    glyph_textures_allocated: usize,
+    flush_time_secs: f64
+}
+
+impl FrameStatistics
+{
+    /// Returns the number of draw calls made. Each change of texture,
+    /// gradient, blend mode, or shader requires a separate draw call, as does
+    /// each static mesh and sprite batch. See [BatchingMode].
+    #[inline]
+    pub fn draw_calls(&self) -> usize
+    {
+        self.draw_calls
+    }
+
+    /// Returns the number of vertices uploaded to the GPU.
+    #[inline]
+    pub fn vertices_uploaded(&self) -> usize
+    {
+        self.vertices_uploaded
+    }
+
+    /// Returns the number of times a different texture was bound.
+    #[inline]
+    pub fn texture_binds(&self) -> usize
+    {
+        self.texture_binds
+    }
+
+    /// Returns the number of glyphs drawn which were already present in the
+    /// glyph cache.
+    #[inline]
+    pub fn glyph_cache_hits(&self) -> usize
+    {
+        self.glyph_cache_hits
+    }
+
+    /// Returns the number of glyphs which had to be rendered and added to
+    /// the glyph cache.
+    #[inline]
+    pub fn glyph_cache_misses(&self) -> usize
+    {
+        self.glyph_cache_misses
+    }
+
+    /// Returns the number of new textures created to hold the glyph cache.
+    #[inline]
+    pub fn glyph_textures_allocated(&self) -> usize
+    {
+        self.glyph_textures_allocated
+    }
+
+    /// Returns the CPU time spent preparing queued items and sending them to
+    /// the GPU, in seconds. This does not include the time taken by the GPU
+    /// to draw them.
+    #[inline]
+    pub fn flush_time_secs(&self) -> f64
+    {
+        self.flush_time_secs
+    }
+}
+
 /// A graphics renderer using an OpenGL backend.
 ///
 /// Note: There is no need to use this struct if you are letting Speedy2D create
@@ -443,7 +603,9 @@ impl Display for GLRendererCreationError
 pub struct GLRenderer
 {
     context: GLContextManager,
-    renderer: Graphics2D
+    renderer: Graphics2D,
+    #[cfg(all(feature = "headless", egl_backend))]
+    headless_context: Option<HeadlessContext>
 }
 
 impl GLRenderer
@@ -457,6 +619,9 @@ impl GLRenderer
     /// Note: This function must not be called if you are letting Speedy2D
     /// create a window for you.
     ///
+    /// To use [Graphics2D::push_clip] with shapes other than rectangles, the
+    /// context must have a stencil buffer.
+    ///
     /// # Safety
     ///
     /// While a `GLRenderer` object is active, you must not make any changes to
@@ -483,6 +648,106 @@ impl GLRenderer
         )
     }
 
+    /// Creates a `GLRenderer` which draws into memory using the CPU, rather
+    /// than using OpenGL. This doesn't require a GPU or a window, so it can
+    /// be used to generate images on a server, or in automated tests. Use
+    /// [Graphics2D::capture] to obtain the pixels which have been drawn.
+    ///
+    /// All drawing operations are supported, apart from custom shaders:
+    /// [Graphics2D::create_custom_shader] will return an error. The output
+    /// isn't antialiased, so may differ slightly from a GL context with
+    /// multisampling enabled.
+    ///
+    /// ```rust
+    /// use speedy2d::color::Color;
+    /// use speedy2d::image::ImageDataType;
+    /// use speedy2d::GLRenderer;
+    ///
+    /// let mut renderer = GLRenderer::new_software((64, 64)).unwrap();
+    ///
+    /// let image = renderer.draw_frame(|graphics| {
+    ///     graphics.clear_screen(Color::WHITE);
+    ///     graphics.draw_circle((32.0, 32.0), 16.0, Color::RED);
+    ///     graphics.capture(ImageDataType::RGBA)
+    /// });
+    ///
+    /// let center = (32 * 64 + 32) * 4;
+    /// assert_eq!(&[255, 0, 0, 255], &image.data()[center..center + 4]);
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_software<V: Into<UVec2>>(
+        viewport_size_pixels: V
+    ) -> Result<Self, BacktraceError<GLRendererCreationError>>
+    {
+        let viewport_size_pixels = viewport_size_pixels.into();
+
+        Self::new_with_gl_backend(
+            viewport_size_pixels,
+            Rc::new(GLBackendSoftware::new(viewport_size_pixels)),
+            GLVersion::OpenGL2_0
+        )
+    }
+
+    /// Creates a `GLRenderer` which draws into an offscreen OpenGL context,
+    /// without requiring a window or a display server. This is useful for
+    /// generating images in batch tools, or on a server. Use
+    /// [Graphics2D::capture] to obtain the pixels which have been drawn.
+    ///
+    /// The context is created using EGL, rendering into a pbuffer of the
+    /// specified size, so the viewport should not be made larger than this.
+    /// The context remains current on the calling thread until the renderer
+    /// is dropped, so only one headless renderer should be in use on each
+    /// thread at a time.
+    ///
+    /// This requires the `headless` feature, and is currently supported on
+    /// platforms which provide EGL device enumeration, such as Linux with
+    /// Mesa or the NVIDIA driver. If no GPU is available,
+    /// [GLRenderer::new_software] may be used instead.
+    ///
+    /// ```rust,no_run
+    /// use speedy2d::color::Color;
+    /// use speedy2d::image::ImageDataType;
+    /// use speedy2d::GLRenderer;
+    ///
+    /// let mut renderer = GLRenderer::new_headless((640, 480)).unwrap();
+    ///
+    /// let image = renderer.draw_frame(|graphics| {
+    ///     graphics.clear_screen(Color::WHITE);
+    ///     graphics.draw_circle((320.0, 240.0), 100.0, Color::BLUE);
+    ///     graphics.capture(ImageDataType::RGBA)
+    /// });
+    /// ```
+    #[cfg(all(feature = "headless", egl_backend))]
+    pub fn new_headless<V: Into<UVec2>>(
+        size_pixels: V
+    ) -> Result<Self, BacktraceError<GLRendererCreationError>>
+    {
+        let size_pixels = size_pixels.into();
+
+        let headless_context = HeadlessContext::new(size_pixels).map_err(|err| {
+            GLRendererCreationError::msg_with_cause(
+                "Failed to create headless context",
+                err
+            )
+        })?;
+
+        let backend = GLBackendGlow::new(unsafe {
+            glow::Context::from_loader_function(|name| {
+                headless_context.get_proc_address(name)
+            })
+        });
+
+        let mut renderer = Self::new_with_gl_backend(
+            size_pixels,
+            Rc::new(backend),
+            GLVersion::OpenGL2_0
+        )?;
+
+        renderer.headless_context = Some(headless_context);
+
+        Ok(renderer)
+    }
+
     /// Creates a `GLRenderer` for the specified HTML canvas. The canvas
     /// will be found based on the specified ID.
     ///
@@ -527,7 +792,12 @@ impl GLRenderer
             })?
         };
 
-        Ok(GLRenderer { context, renderer })
+        Ok(GLRenderer {
+            context,
+            renderer,
+            #[cfg(all(feature = "headless", egl_backend))]
+            headless_context: None
+        })
     }
 
     /// Sets the renderer viewport to the specified pixel size, in response to a
@@ -539,6 +809,26 @@ impl GLRenderer
             .set_viewport_size_pixels(viewport_size_pixels)
     }
 
+    /// Sets a logical coordinate space which is scaled to fit the viewport,
+    /// or `None` to draw in viewport pixels. See
+    /// [Graphics2D::set_virtual_resolution].
+    pub fn set_virtual_resolution(
+        &mut self,
+        virtual_resolution: Option<VirtualResolution>
+    )
+    {
+        self.renderer.set_virtual_resolution(virtual_resolution)
+    }
+
+    /// Converts a position in viewport pixels (such as the mouse position)
+    /// into the logical coordinate space set using
+    /// [GLRenderer::set_virtual_resolution]. If no virtual resolution is set,
+    /// the position is returned unchanged.
+    pub fn window_to_logical(&self, position: impl Into<Vec2>) -> Vec2
+    {
+        self.renderer.window_to_logical(position)
+    }
+
     /// Creates a new [ImageHandle] from the specified raw pixel data.
     ///
     /// The data provided in the `data` parameter must be in the format
@@ -549,13 +839,68 @@ impl GLRenderer
     pub fn create_image_from_raw_pixels(
         &mut self,
         data_type: ImageDataType,
-        smoothing_mode: ImageSmoothingMode,
+        options: impl Into<ImageOptions>,
         size: UVec2,
         data: &[u8]
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         self.renderer
-            .create_image_from_raw_pixels(data_type, smoothing_mode, size, data)
+            .create_image_from_raw_pixels(data_type, options, size, data)
+    }
+
+    /// Creates a new [RenderTarget] of the specified size in pixels. See
+    /// [Graphics2D::create_render_target].
+    ///
+    /// The returned [RenderTarget] is valid only for the current graphics
+    /// context.
+    pub fn create_render_target(
+        &mut self,
+        options: impl Into<ImageOptions>,
+        size: UVec2
+    ) -> Result<RenderTarget, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_render_target(options, size)
+    }
+
+    /// Compiles a [CustomShader] from the provided fragment shader source.
+    /// See [Graphics2D::create_custom_shader].
+    ///
+    /// The returned [CustomShader] is valid only for the current graphics
+    /// context.
+    pub fn create_custom_shader(
+        &mut self,
+        fragment_shader_source: &str
+    ) -> Result<CustomShader, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_custom_shader(fragment_shader_source)
+    }
+
+    /// Draws into the specified [RenderTarget]. A `Graphics2D` object will be
+    /// provided to the callback, and all drawing operations will be
+    /// redirected into the render target until the callback returns.
+    ///
+    /// This may be called outside of [GLRenderer::draw_frame], for example
+    /// to generate a thumbnail.
+    pub fn draw_to_render_target<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        target: &RenderTarget,
+        callback: F
+    ) -> R
+    {
+        self.renderer.draw_to_render_target(target, callback)
+    }
+
+    /// Creates a [StaticMesh] from the shapes and images drawn by the
+    /// callback. See [Graphics2D::create_static_mesh].
+    ///
+    /// The returned [StaticMesh] is valid only for the current graphics
+    /// context.
+    pub fn create_static_mesh<F: FnOnce(&mut Graphics2D)>(
+        &mut self,
+        callback: F
+    ) -> Result<StaticMesh, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_static_mesh(callback)
     }
 
     /// Loads an image from the specified file path.
@@ -571,12 +916,12 @@ impl GLRenderer
     pub fn create_image_from_file_path<S: AsRef<Path>>(
         &mut self,
         data_type: Option<ImageFileFormat>,
-        smoothing_mode: ImageSmoothingMode,
+        options: impl Into<ImageOptions>,
         path: S
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         self.renderer
-            .create_image_from_file_path(data_type, smoothing_mode, path)
+            .create_image_from_file_path(data_type, options, path)
     }
 
     /// Loads an image from the provided encoded image file data.
@@ -615,12 +960,61 @@ impl GLRenderer
     pub fn create_image_from_file_bytes<R: Seek + BufRead>(
         &mut self,
         data_type: Option<ImageFileFormat>,
-        smoothing_mode: ImageSmoothingMode,
+        options: impl Into<ImageOptions>,
         file_bytes: R
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         self.renderer
-            .create_image_from_file_bytes(data_type, smoothing_mode, file_bytes)
+            .create_image_from_file_bytes(data_type, options, file_bytes)
+    }
+
+    /// Replaces the pixels in part of an existing image. See
+    /// [Graphics2D::update_image_region].
+    pub fn update_image_region(
+        &mut self,
+        image: &ImageHandle,
+        data_type: ImageDataType,
+        region: impl AsRef<URect>,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.renderer
+            .update_image_region(image, data_type, region, data)
+    }
+
+    /// Replaces all the pixels in an existing image. See
+    /// [Graphics2D::update_image].
+    pub fn update_image(
+        &mut self,
+        image: &ImageHandle,
+        data_type: ImageDataType,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.renderer.update_image(image, data_type, data)
+    }
+
+    /// Reads back the current contents of an image. See
+    /// [Graphics2D::read_image_pixels].
+    pub fn read_image_pixels(
+        &mut self,
+        image: &ImageHandle,
+        format: ImageDataType
+    ) -> Result<RawBitmapData, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.read_image_pixels(image, format)
+    }
+
+    /// Reads back the current contents of a render target. See
+    /// [Graphics2D::read_render_target_pixels].
+    pub fn read_render_target_pixels(
+        &mut self,
+        render_target: &RenderTarget,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        self.renderer
+            .read_render_target_pixels(render_target, format)
     }
 
     /// Starts the process of drawing a frame. A `Graphics2D` object will be
@@ -637,6 +1031,27 @@ impl GLRenderer
         self.renderer.renderer.finish_frame();
         result
     }
+
+    /// Returns statistics about the rendering work done by the most recent
+    /// call to [GLRenderer::draw_frame].
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// let mut renderer = GLRenderer::new_software((640, 480)).unwrap();
+    ///
+    /// renderer.draw_frame(|graphics| {
+    ///     graphics.clear_screen(Color::WHITE);
+    ///     graphics.draw_circle((100.0, 100.0), 75.0, Color::BLUE);
+    /// });
+    ///
+    /// assert_eq!(1, renderer.last_frame_statistics().draw_calls());
+    /// ```
+    #[inline]
+    pub fn last_frame_statistics(&self) -> FrameStatistics
+    {
+        self.renderer.last_frame_statistics()
+    }
 }
 
 impl Drop for GLRenderer
@@ -654,6 +1069,26 @@ impl Drop for GLRenderer
 ///
 /// If you are managing the GL context yourself, you must invoke
 /// [GLRenderer::draw_frame] to obtain an instance.
+///
+/// # Outlines and strokes
+///
+/// There are two families of functions for drawing outlines:
+///
+///  * [Graphics2D::draw_line] and the `draw_*_outline()` functions, such as
+///    [Graphics2D::draw_rectangle_outline], take a simple `thickness`. The
+///    outlines are drawn inside the edges of the shape, so that they line up
+///    with the filled shape of the same size, and are drawn using a small,
+///    fixed number of triangles. These are the fastest way to draw solid
+///    outlines.
+///  * [Graphics2D::stroke_path] and the other `stroke_*()` functions, such as
+///    [Graphics2D::stroke_rectangle], take a [shape::StrokeStyle]. The outlines
+///    are centered on the edges of the shape, and support dashes, line joins,
+///    and line caps. They are tessellated on the CPU, so they are slower to
+///    draw.
+///
+/// The two families are kept separate because their outlines are placed
+/// differently, and changing the existing functions to use a
+/// [shape::StrokeStyle] would change how existing code is drawn.
 pub struct Graphics2D
 {
     renderer: Renderer2D
@@ -666,24 +1101,50 @@ impl Graphics2D
     /// The data provided in the `data` parameter must be in the format
     /// specified by `data_type`.
     ///
+    /// The `options` may be an [image::ImageSmoothingMode], or [ImageOptions]
+    /// to also set the [image::ImageWrapMode].
+    ///
     /// The returned [ImageHandle] is valid only for the current graphics
     /// context.
     pub fn create_image_from_raw_pixels<S: Into<UVec2>>(
         &mut self,
         data_type: ImageDataType,
-        smoothing_mode: ImageSmoothingMode,
+        options: impl Into<ImageOptions>,
         size: S,
         data: &[u8]
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         self.renderer.create_image_from_raw_pixels(
             data_type,
-            smoothing_mode,
+            options.into(),
             size.into(),
             data
         )
     }
 
+    /// Creates a new [RenderTarget] of the specified size in pixels. The
+    /// render target is initially transparent.
+    ///
+    /// Use [Graphics2D::draw_to_render_target] to draw into it, and
+    /// [RenderTarget::image] to draw the result.
+    ///
+    /// Render targets require framebuffer objects, which are available in
+    /// OpenGL 3.0 or later (or with the `GL_ARB_framebuffer_object`
+    /// extension), WebGL 2, and the software renderer. Otherwise, an error is
+    /// returned.
+    ///
+    /// The returned [RenderTarget] is valid only for the current graphics
+    /// context.
+    pub fn create_render_target<S: Into<UVec2>>(
+        &mut self,
+        options: impl Into<ImageOptions>,
+        size: S
+    ) -> Result<RenderTarget, BacktraceError<ErrorMessage>>
+    {
+        self.renderer
+            .create_render_target(size.into(), options.into())
+    }
+
     /// Loads an image from the specified file path.
     ///
     /// If no `data_type` is provided, an attempt will be made to guess the file
@@ -697,12 +1158,12 @@ impl Graphics2D
     pub fn create_image_from_file_path<S: AsRef<Path>>(
         &mut self,
         data_type: Option<ImageFileFormat>,
-        smoothing_mode: ImageSmoothingMode,
+        options: impl Into<ImageOptions>,
         path: S
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         self.renderer
-            .create_image_from_file_path(data_type, smoothing_mode, path)
+            .create_image_from_file_path(data_type, options.into(), path)
     }
 
     /// Loads an image from the provided encoded image file data.
@@ -743,12 +1204,75 @@ impl Graphics2D
     pub fn create_image_from_file_bytes<R: Seek + BufRead>(
         &mut self,
         data_type: Option<ImageFileFormat>,
-        smoothing_mode: ImageSmoothingMode,
+        options: impl Into<ImageOptions>,
         file_bytes: R
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         self.renderer
-            .create_image_from_file_bytes(data_type, smoothing_mode, file_bytes)
+            .create_image_from_file_bytes(data_type, options.into(), file_bytes)
+    }
+
+    /// Replaces the pixels in part of an existing image, without allocating a
+    /// new texture. This is useful for images which change every frame, such
+    /// as video frames or procedurally generated textures.
+    ///
+    /// The `region` is measured in pixels from the top left of the image, and
+    /// must be entirely inside the image. The data provided in the `data`
+    /// parameter must be in the format specified by `data_type`, and must
+    /// contain exactly enough pixels to fill `region`.
+    ///
+    /// Anything drawn using the image earlier in the frame still uses the
+    /// previous contents.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::image::{ImageDataType, ImageHandle};
+    /// # use speedy2d::shape::URect;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D, canvas: &ImageHandle) {
+    /// // Paint a 4x4 red square at (10, 10)
+    /// let pixels = [255, 0, 0].repeat(16);
+    ///
+    /// graphics
+    ///     .update_image_region(
+    ///         canvas,
+    ///         ImageDataType::RGB,
+    ///         URect::from_tuples((10, 10), (14, 14)),
+    ///         &pixels
+    ///     )
+    ///     .unwrap();
+    ///
+    /// graphics.draw_image((0.0, 0.0), canvas);
+    /// # }
+    /// ```
+    pub fn update_image_region(
+        &mut self,
+        image: &ImageHandle,
+        data_type: ImageDataType,
+        region: impl AsRef<URect>,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.renderer
+            .update_image_region(image, data_type, region.as_ref(), data)
+    }
+
+    /// Replaces all the pixels in an existing image, without allocating a new
+    /// texture. The data provided in the `data` parameter must be in the
+    /// format specified by `data_type`, and must be the same size as the
+    /// image. See [Graphics2D::update_image_region].
+    pub fn update_image(
+        &mut self,
+        image: &ImageHandle,
+        data_type: ImageDataType,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.update_image_region(
+            image,
+            data_type,
+            URect::new(UVec2::ZERO, *image.size()),
+            data
+        )
     }
 
     /// Fills the screen with the specified color.
@@ -805,6 +1329,43 @@ impl Graphics2D
             .draw_text_cropped(position, crop_window, color, text);
     }
 
+    /// Draws the provided block of text at the specified position, filled
+    /// using the specified [Paint]. Gradient coordinates are relative to the
+    /// render window, rather than to `position`.
+    ///
+    /// See the documentation for [Graphics2D::draw_text] for more details.
+    pub fn draw_text_with_paint<V: Into<Vec2>>(
+        &mut self,
+        position: V,
+        paint: &Paint,
+        text: &FormattedTextBlock
+    )
+    {
+        self.with_paint(paint, |graphics, color| {
+            graphics.draw_text(position, color, text)
+        });
+    }
+
+    /// Runs `callback` with the renderer set up to fill shapes using `paint`.
+    /// The callback is given the color which should be used as the vertex
+    /// color for each shape.
+    fn with_paint<F: FnOnce(&mut Self, Color)>(&mut self, paint: &Paint, callback: F)
+    {
+        match paint {
+            Paint::Solid(color) => callback(self, *color),
+            Paint::Gradient(gradient) if gradient.stops.is_empty() => {
+                log::warn!("Gradient has no color stops, drawing in solid black instead");
+                callback(self, Color::BLACK)
+            }
+            Paint::Gradient(gradient) => {
+                self.renderer
+                    .set_paint_gradient(Some(Rc::new(gradient.clone())));
+                callback(self, Color::WHITE);
+                self.renderer.set_paint_gradient(None);
+            }
+        }
+    }
+
     /// Draws a polygon with a single color, with the specified offset in
     /// pixels.
     pub fn draw_polygon<V: Into<Vec2>>(
@@ -817,58 +1378,206 @@ impl Graphics2D
         self.renderer.draw_polygon(polygon, offset, color)
     }
 
-    /// Draws a triangle with the specified colors (one color for each corner).
+    /// Draws a polygon, filled using the specified [Paint], with the specified
+    /// offset in pixels. Gradient coordinates are relative to the render
+    /// window, rather than to `offset`.
     ///
-    /// The vertex positions (and associated colors) must be provided in
-    /// clockwise order.
-    pub fn draw_triangle_three_color(
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::paint::{Gradient, Paint};
+    /// # use speedy2d::shape::Polygon;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let triangle = Polygon::new(&[(100.0, 100.0), (200.0, 100.0), (150.0, 200.0)]);
+    ///
+    /// let paint = Paint::Gradient(
+    ///     Gradient::radial((150.0, 130.0), 80.0)
+    ///         .with_stop(0.0, Color::WHITE)
+    ///         .with_stop(1.0, Color::BLUE)
+    /// );
+    ///
+    /// graphics.draw_polygon_with_paint(&triangle, (0.0, 0.0), &paint);
+    /// # }
+    /// ```
+    pub fn draw_polygon_with_paint<V: Into<Vec2>>(
         &mut self,
-        vertex_positions_clockwise: [Vec2; 3],
-        vertex_colors_clockwise: [Color; 3]
+        polygon: &Polygon,
+        offset: V,
+        paint: &Paint
     )
     {
-        self.renderer.draw_triangle_three_color(
-            vertex_positions_clockwise,
-            vertex_colors_clockwise
-        );
+        self.with_paint(paint, |graphics, color| {
+            graphics.draw_polygon(polygon, offset, color)
+        });
     }
 
-    /// Draws part of an image, tinted with the provided colors, at the
-    /// specified location. The sub-image will be scaled to fill the
-    /// triangle described by the vertices in `vertex_positions_clockwise`.
-    ///
-    /// The coordinates in `image_coords_normalized` should be in the range
-    /// `0.0` to `1.0`, and define the portion of the source image which
-    /// should be drawn.
+    /// Draws the outline of the provided path, using the line width, joins and
+    /// caps specified in `style`.
     ///
-    /// The tinting is performed by for each pixel by multiplying each color
-    /// component in the image pixel by the corresponding color component in
-    /// the `color` parameter.
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::{LineJoin, Path, StrokeStyle};
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let path = Path::new()
+    ///     .move_to((100.0, 100.0))
+    ///     .line_to((200.0, 100.0))
+    ///     .cubic_to((250.0, 100.0), (250.0, 200.0), (200.0, 200.0));
+    ///
+    /// let style = StrokeStyle::new(5.0).with_line_join(LineJoin::Round);
+    ///
+    /// graphics.stroke_path(&path, &style, Color::BLUE);
+    /// # }
+    /// ```
+    pub fn stroke_path(&mut self, path: &shape::Path, style: &StrokeStyle, color: Color)
+    {
+        self.renderer
+            .draw_polygon(&path.to_stroke_polygon(style), Vec2::ZERO, color)
+    }
+
+    /// Draws a line between the given points, specified in pixels, using the
+    /// width, line caps and dash pattern specified in `style`.
     ///
-    /// The vertex positions (and associated colors and image coordinates) must
-    /// be provided in clockwise order.
-    pub fn draw_triangle_image_tinted_three_color(
+    /// For a solid line with no caps, [Graphics2D::draw_line] may be used
+    /// instead.
+    pub fn stroke_line(
         &mut self,
-        vertex_positions_clockwise: [Vec2; 3],
-        vertex_colors: [Color; 3],
-        image_coords_normalized: [Vec2; 3],
-        image: &ImageHandle
+        start_position: impl Into<Vec2>,
+        end_position: impl Into<Vec2>,
+        style: &StrokeStyle,
+        color: Color
     )
     {
-        self.renderer.draw_triangle_image_tinted(
-            vertex_positions_clockwise,
-            vertex_colors,
-            image_coords_normalized,
-            image
-        );
+        self.stroke_path(
+            &shape::Path::new()
+                .move_to(start_position)
+                .line_to(end_position),
+            style,
+            color
+        )
     }
 
-    /// Draws a triangle with the specified color.
-    ///
-    /// The vertex positions must be provided in clockwise order.
-    #[inline]
-    pub fn draw_triangle(&mut self, vertex_positions_clockwise: [Vec2; 3], color: Color)
-    {
+    /// Draws the outline of the specified rectangle, using the width, joins
+    /// and dash pattern specified in `style`. The outline is centered on the
+    /// edges of the rectangle.
+    pub fn stroke_rectangle(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        style: &StrokeStyle,
+        color: Color
+    )
+    {
+        self.stroke_path(&shape::Path::from_rectangle(rect), style, color)
+    }
+
+    /// Draws the outline of the specified rounded rectangle, using the width
+    /// and dash pattern specified in `style`. The outline is centered on the
+    /// edges of the rounded rectangle.
+    pub fn stroke_rounded_rectangle(
+        &mut self,
+        round_rect: impl AsRef<RoundedRectangle>,
+        style: &StrokeStyle,
+        color: Color
+    )
+    {
+        self.stroke_path(
+            &shape::Path::from_rounded_rectangle(round_rect),
+            style,
+            color
+        )
+    }
+
+    /// Draws the outline of a circle, using the width and dash pattern
+    /// specified in `style`. The outline is centered on the edge of the
+    /// circle.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::StrokeStyle;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let dashed = StrokeStyle::new(2.0).with_dash_pattern(&[8.0, 4.0], 0.0);
+    ///
+    /// graphics.stroke_circle((100.0, 100.0), 50.0, &dashed, Color::BLACK);
+    /// # }
+    /// ```
+    pub fn stroke_circle(
+        &mut self,
+        center_position: impl Into<Vec2>,
+        radius: f32,
+        style: &StrokeStyle,
+        color: Color
+    )
+    {
+        self.stroke_path(
+            &shape::Path::from_circle(center_position, radius),
+            style,
+            color
+        )
+    }
+
+    /// Fills the interior of the provided path with a single color.
+    ///
+    /// All subpaths are treated as closed. The first subpath is the outline of
+    /// the shape, and any further subpaths are treated as holes.
+    pub fn fill_path(&mut self, path: &shape::Path, color: Color)
+    {
+        self.renderer
+            .draw_polygon(&path.to_fill_polygon(), Vec2::ZERO, color)
+    }
+
+    /// Draws a triangle with the specified colors (one color for each corner).
+    ///
+    /// The vertex positions (and associated colors) must be provided in
+    /// clockwise order.
+    pub fn draw_triangle_three_color(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3]
+    )
+    {
+        self.renderer.draw_triangle_three_color(
+            vertex_positions_clockwise,
+            vertex_colors_clockwise
+        );
+    }
+
+    /// Draws part of an image, tinted with the provided colors, at the
+    /// specified location. The sub-image will be scaled to fill the
+    /// triangle described by the vertices in `vertex_positions_clockwise`.
+    ///
+    /// The coordinates in `image_coords_normalized` should be in the range
+    /// `0.0` to `1.0`, and define the portion of the source image which
+    /// should be drawn.
+    ///
+    /// The tinting is performed by for each pixel by multiplying each color
+    /// component in the image pixel by the corresponding color component in
+    /// the `color` parameter.
+    ///
+    /// The vertex positions (and associated colors and image coordinates) must
+    /// be provided in clockwise order.
+    pub fn draw_triangle_image_tinted_three_color(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors: [Color; 3],
+        image_coords_normalized: [Vec2; 3],
+        image: &ImageHandle
+    )
+    {
+        self.renderer.draw_triangle_image_tinted(
+            vertex_positions_clockwise,
+            vertex_colors,
+            image_coords_normalized,
+            image
+        );
+    }
+
+    /// Draws a triangle with the specified color.
+    ///
+    /// The vertex positions must be provided in clockwise order.
+    #[inline]
+    pub fn draw_triangle(&mut self, vertex_positions_clockwise: [Vec2; 3], color: Color)
+    {
         self.draw_triangle_three_color(vertex_positions_clockwise, [color, color, color]);
     }
 
@@ -1023,6 +1732,94 @@ impl Graphics2D
         self.draw_rectangle_image_tinted(rect, Color::WHITE, image);
     }
 
+    /// Fills the provided rectangle with copies of an image, tinted with the
+    /// provided color, repeated horizontally and vertically.
+    ///
+    /// Each copy of the image is drawn at its original size multiplied by
+    /// `scale`. The copies are positioned so that the top left corner of one
+    /// of them is at `offset` pixels from the top left of the rectangle.
+    ///
+    /// The image must have been created with [image::ImageWrapMode::Repeat]
+    /// or [image::ImageWrapMode::MirroredRepeat]. Otherwise, the edges of the
+    /// image are stretched to fill the rectangle instead.
+    ///
+    /// If either component of `scale` is zero, nothing is drawn.
+    ///
+    /// The tinting is performed by for each pixel by multiplying each color
+    /// component in the image pixel by the corresponding color component in
+    /// the `color` parameter.
+    #[inline]
+    pub fn draw_rectangle_image_tiled_tinted(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        scale: impl Into<Vec2>,
+        offset: impl Into<Vec2>,
+        color: Color,
+        image: &ImageHandle
+    )
+    {
+        let rect = rect.as_ref();
+        let offset = offset.into();
+        let scale = scale.into();
+        let image_size = image.size().into_f32();
+        let tile_size = Vec2::new(image_size.x * scale.x, image_size.y * scale.y);
+
+        if tile_size.x == 0.0 || tile_size.y == 0.0 {
+            return;
+        }
+
+        let normalize =
+            |point: Vec2| Vec2::new(point.x / tile_size.x, point.y / tile_size.y);
+
+        self.draw_rectangle_image_subset_tinted(
+            rect,
+            color,
+            Rectangle::new(
+                normalize(Vec2::ZERO - offset),
+                normalize(rect.size() - offset)
+            ),
+            image
+        );
+    }
+
+    /// Fills the provided rectangle with copies of an image, repeated
+    /// horizontally and vertically. See
+    /// [Graphics2D::draw_rectangle_image_tiled_tinted] for details.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::image::{ImageDataType, ImageOptions, ImageSmoothingMode};
+    /// # use speedy2d::image::ImageWrapMode;
+    /// # use speedy2d::shape::Rectangle;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let options = ImageOptions::new(ImageSmoothingMode::Linear)
+    ///     .with_wrap_mode(ImageWrapMode::Repeat);
+    ///
+    /// let floor = graphics
+    ///     .create_image_from_file_path(None, options, "floor.png")
+    ///     .unwrap();
+    ///
+    /// // Covers the rectangle with copies of the image at half size
+    /// graphics.draw_rectangle_image_tiled(
+    ///     Rectangle::from_tuples((0.0, 300.0), (800.0, 600.0)),
+    ///     (0.5, 0.5),
+    ///     (0.0, 0.0),
+    ///     &floor
+    /// );
+    /// # }
+    /// ```
+    #[inline]
+    pub fn draw_rectangle_image_tiled(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        scale: impl Into<Vec2>,
+        offset: impl Into<Vec2>,
+        image: &ImageHandle
+    )
+    {
+        self.draw_rectangle_image_tiled_tinted(rect, scale, offset, Color::WHITE, image);
+    }
+
     /// Draws an image at the specified pixel location. The image will be
     /// drawn at its original size with no scaling.
     #[inline]
@@ -1036,6 +1833,61 @@ impl Graphics2D
         );
     }
 
+    /// Draws a [SubImageHandle] from a [texture_atlas::TextureAtlas],
+    /// tinted with the provided color, at the specified location. The
+    /// sub-image will be scaled to fill the pixel coordinates in the provided
+    /// rectangle.
+    ///
+    /// The tinting is performed by for each pixel by multiplying each color
+    /// component in the image pixel by the corresponding color component in
+    /// the `color` parameter.
+    #[inline]
+    pub fn draw_rectangle_sub_image_tinted(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        color: Color,
+        sub_image: &SubImageHandle
+    )
+    {
+        self.draw_rectangle_image_subset_tinted(
+            rect,
+            color,
+            sub_image.texture_coords_normalized(),
+            sub_image.image()
+        );
+    }
+
+    /// Draws a [SubImageHandle] from a [texture_atlas::TextureAtlas] at the
+    /// specified location. The sub-image will be scaled to fill the pixel
+    /// coordinates in the provided rectangle.
+    #[inline]
+    pub fn draw_rectangle_sub_image(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        sub_image: &SubImageHandle
+    )
+    {
+        self.draw_rectangle_sub_image_tinted(rect, Color::WHITE, sub_image);
+    }
+
+    /// Draws a [SubImageHandle] from a [texture_atlas::TextureAtlas] at the
+    /// specified pixel location. The sub-image will be drawn at its original
+    /// size with no scaling.
+    #[inline]
+    pub fn draw_sub_image<P: Into<Vec2>>(
+        &mut self,
+        position: P,
+        sub_image: &SubImageHandle
+    )
+    {
+        let position = position.into();
+
+        self.draw_rectangle_sub_image(
+            Rectangle::new(position, position + sub_image.size().into_f32()),
+            sub_image
+        );
+    }
+
     /// Draws a single-color rectangle at the specified location. The
     /// coordinates of the rectangle are specified in pixels.
     #[inline]
@@ -1054,6 +1906,53 @@ impl Graphics2D
         );
     }
 
+    /// Draws a rectangle at the specified location, filled using the specified
+    /// [Paint]. The coordinates of the rectangle are specified in pixels.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::paint::{Gradient, Paint};
+    /// # use speedy2d::shape::Rectangle;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let paint = Paint::Gradient(
+    ///     Gradient::linear((0.0, 0.0), (200.0, 0.0))
+    ///         .with_stop(0.0, Color::RED)
+    ///         .with_stop(0.5, Color::YELLOW)
+    ///         .with_stop(1.0, Color::GREEN)
+    /// );
+    ///
+    /// graphics.draw_rectangle_with_paint(
+    ///     Rectangle::from_tuples((0.0, 0.0), (200.0, 50.0)),
+    ///     &paint
+    /// );
+    /// # }
+    /// ```
+    pub fn draw_rectangle_with_paint(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        paint: &Paint
+    )
+    {
+        self.with_paint(paint, |graphics, color| {
+            graphics.draw_rectangle(rect, color)
+        });
+    }
+
+    /// Draws a rounded rectangle at the specified location, filled using the
+    /// specified [Paint]. The coordinates of the rounded rectangle are
+    /// specified in pixels.
+    pub fn draw_rounded_rectangle_with_paint(
+        &mut self,
+        round_rect: impl AsRef<RoundedRectangle>,
+        paint: &Paint
+    )
+    {
+        self.with_paint(paint, |graphics, color| {
+            graphics.draw_rounded_rectangle(round_rect, color)
+        });
+    }
+
     /// Draws a single-color rounded rectangle at the specified location. The
     /// coordinates of the rounded rectangle are specified in pixels.
     #[inline]
@@ -1202,100 +2101,612 @@ impl Graphics2D
         );
     }
 
-    /// Draws a single-color line between the given points, specified in pixels.
-    ///
-    /// # Pixel alignment
-    ///
-    /// On a display with square pixels, an integer-valued coordinate is located
-    /// at the boundary between two pixels, rather than the center of the
-    /// pixel. For example:
-    ///
-    ///  * `(0.0, 0.0)` = Top left of pixel
-    ///  * `(0.5, 0.5)` = Center of pixel
-    ///  * `(1.0, 1.0)` = Bottom right of pixel
+    /// Draws the outline of a rectangle, with a single color, at the specified
+    /// location. The coordinates of the rectangle are specified in pixels.
     ///
-    /// If drawing a line of odd-numbered thickness, it is advisable to locate
-    /// the start and end of the line at the centers of pixels, rather than
-    /// the edges.
+    /// The outline is drawn inside the edges of the rectangle. If `thickness`
+    /// is large enough to cover the whole rectangle, the rectangle is filled.
     ///
-    /// For example, a one-pixel-thick line between `(0.0, 10.0)` and `(100.0,
-    /// 10.0)` will be drawn as a rectangle with corners `(0.0, 9.5)` and
-    /// `(100.0, 10.5)`, meaning that the line's thickness will actually
-    /// span two half-pixels. Drawing the same line between `(0.0, 10.5)`
-    /// and `(100.0, 10.5)` will result in a pixel-aligned rectangle between
-    /// `(0.0, 10.0)` and `(100.0, 11.0)`.
-    pub fn draw_line<VStart: Into<Vec2>, VEnd: Into<Vec2>>(
+    /// To draw an outline centered on the edges of the rectangle, or a dashed
+    /// outline, use [Graphics2D::stroke_rectangle].
+    pub fn draw_rectangle_outline(
         &mut self,
-        start_position: VStart,
-        end_position: VEnd,
+        rect: impl AsRef<Rectangle>,
         thickness: f32,
         color: Color
     )
     {
-        let start_position = start_position.into();
-        let end_position = end_position.into();
-
-        let gradient_normalized = match (end_position - start_position).normalize() {
-            None => return,
-            Some(gradient) => gradient
-        };
+        let rect = rect.as_ref();
 
-        let gradient_thickness = gradient_normalized * (thickness / 2.0);
+        if thickness * 2.0 >= rect.width().min(rect.height()) {
+            self.draw_rectangle(rect, color);
+            return;
+        }
 
-        let offset_anticlockwise = gradient_thickness.rotate_90_degrees_anticlockwise();
-        let offset_clockwise = gradient_thickness.rotate_90_degrees_clockwise();
+        // Top and bottom edges span the whole width
+        self.draw_rectangle(
+            Rectangle::new(
+                *rect.top_left(),
+                Vec2::new(rect.right(), rect.top() + thickness)
+            ),
+            color
+        );
 
-        let start_anticlockwise = start_position + offset_anticlockwise;
-        let start_clockwise = start_position + offset_clockwise;
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(rect.left(), rect.bottom() - thickness),
+                *rect.bottom_right()
+            ),
+            color
+        );
 
-        let end_anticlockwise = end_position + offset_anticlockwise;
-        let end_clockwise = end_position + offset_clockwise;
+        // Left and right edges fit between them
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(rect.left(), rect.top() + thickness),
+                Vec2::new(rect.left() + thickness, rect.bottom() - thickness)
+            ),
+            color
+        );
 
-        self.draw_quad(
-            [
-                start_anticlockwise,
-                end_anticlockwise,
-                end_clockwise,
-                start_clockwise
-            ],
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(rect.right() - thickness, rect.top() + thickness),
+                Vec2::new(rect.right(), rect.bottom() - thickness)
+            ),
             color
         );
     }
 
-    /// Draws a circle, filled with a single color, at the specified pixel
-    /// location.
-    pub fn draw_circle<V: Into<Vec2>>(
+    /// Draws the outline of a rounded rectangle, with a single color, at the
+    /// specified location. The coordinates of the rounded rectangle are
+    /// specified in pixels.
+    ///
+    /// The outline is drawn inside the edges of the rounded rectangle. If
+    /// `thickness` is large enough to cover the whole rounded rectangle, it is
+    /// filled.
+    ///
+    /// To draw an outline centered on the edges of the rounded rectangle, or a
+    /// dashed outline, use [Graphics2D::stroke_rounded_rectangle].
+    pub fn draw_rounded_rectangle_outline(
         &mut self,
-        center_position: V,
-        radius: f32,
+        round_rect: impl AsRef<RoundedRectangle>,
+        thickness: f32,
         color: Color
     )
     {
-        let center_position = center_position.into();
+        let round_rect = round_rect.as_ref();
 
-        let top_left = center_position + Vec2::new(-radius, -radius);
-        let top_right = center_position + Vec2::new(radius, -radius);
-        let bottom_right = center_position + Vec2::new(radius, radius);
-        let bottom_left = center_position + Vec2::new(-radius, radius);
+        if thickness * 2.0 >= round_rect.width().min(round_rect.height()) {
+            self.draw_rounded_rectangle(round_rect, color);
+            return;
+        }
 
-        self.renderer.draw_circle_section(
-            [top_left, top_right, bottom_right],
-            [color, color, color],
-            [
-                Vec2::new(-1.0, -1.0),
-                Vec2::new(1.0, -1.0),
-                Vec2::new(1.0, 1.0)
-            ]
-        );
+        let radius = round_rect.radius();
 
-        self.renderer.draw_circle_section(
-            [bottom_right, bottom_left, top_left],
-            [color, color, color],
+        let top = round_rect.top();
+        let bottom = round_rect.bottom();
+        let left = round_rect.left();
+        let right = round_rect.right();
+
+        // The corners are each a square of size `radius`, textured with a
+        // quarter of a ring.
+        self.draw_ring_section(
             [
-                Vec2::new(1.0, 1.0),
+                *round_rect.top_left(),
+                Vec2::new(left + radius, top),
+                Vec2::new(left + radius, top + radius),
+                Vec2::new(left, top + radius)
+            ],
+            [
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(0.0, -1.0),
+                Vec2::new(0.0, 0.0),
+                Vec2::new(-1.0, 0.0)
+            ],
+            radius,
+            thickness,
+            color
+        );
+
+        self.draw_ring_section(
+            [
+                Vec2::new(right - radius, top),
+                round_rect.top_right(),
+                Vec2::new(right, top + radius),
+                Vec2::new(right - radius, top + radius)
+            ],
+            [
+                Vec2::new(0.0, -1.0),
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 0.0),
+                Vec2::new(0.0, 0.0)
+            ],
+            radius,
+            thickness,
+            color
+        );
+
+        self.draw_ring_section(
+            [
+                Vec2::new(right - radius, bottom - radius),
+                Vec2::new(right, bottom - radius),
+                *round_rect.bottom_right(),
+                Vec2::new(right - radius, bottom)
+            ],
+            [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 0.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(0.0, 1.0)
+            ],
+            radius,
+            thickness,
+            color
+        );
+
+        self.draw_ring_section(
+            [
+                Vec2::new(left, bottom - radius),
+                Vec2::new(left + radius, bottom - radius),
+                Vec2::new(left + radius, bottom),
+                round_rect.bottom_left()
+            ],
+            [
+                Vec2::new(-1.0, 0.0),
+                Vec2::new(0.0, 0.0),
+                Vec2::new(0.0, 1.0),
+                Vec2::new(-1.0, 1.0)
+            ],
+            radius,
+            thickness,
+            color
+        );
+
+        // The top and bottom edges span the gap between the corners
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(left + radius, top),
+                Vec2::new(right - radius, top + thickness)
+            ),
+            color
+        );
+
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(left + radius, bottom - thickness),
+                Vec2::new(right - radius, bottom)
+            ),
+            color
+        );
+
+        // The left and right edges fit between the corners, or between the
+        // top and bottom edges if the outline is thicker than the radius
+        let edge_inset = radius.max(thickness);
+
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(left, top + edge_inset),
+                Vec2::new(left + thickness, bottom - edge_inset)
+            ),
+            color
+        );
+
+        self.draw_rectangle(
+            Rectangle::new(
+                Vec2::new(right - thickness, top + edge_inset),
+                Vec2::new(right, bottom - edge_inset)
+            ),
+            color
+        );
+
+        if thickness > radius {
+            // Fill the gaps below and above the corners
+            let gap_height = thickness - radius;
+
+            for (x, y) in [
+                (left, top + radius),
+                (right - radius, top + radius),
+                (left, bottom - thickness),
+                (right - radius, bottom - thickness)
+            ] {
+                self.draw_rectangle(
+                    Rectangle::new(
+                        Vec2::new(x, y),
+                        Vec2::new(x + radius, y + gap_height)
+                    ),
+                    color
+                );
+            }
+        }
+    }
+
+    /// Draws a single-color line between the given points, specified in pixels.
+    ///
+    /// # Pixel alignment
+    ///
+    /// On a display with square pixels, an integer-valued coordinate is located
+    /// at the boundary between two pixels, rather than the center of the
+    /// pixel. For example:
+    ///
+    ///  * `(0.0, 0.0)` = Top left of pixel
+    ///  * `(0.5, 0.5)` = Center of pixel
+    ///  * `(1.0, 1.0)` = Bottom right of pixel
+    ///
+    /// If drawing a line of odd-numbered thickness, it is advisable to locate
+    /// the start and end of the line at the centers of pixels, rather than
+    /// the edges.
+    ///
+    /// For example, a one-pixel-thick line between `(0.0, 10.0)` and `(100.0,
+    /// 10.0)` will be drawn as a rectangle with corners `(0.0, 9.5)` and
+    /// `(100.0, 10.5)`, meaning that the line's thickness will actually
+    /// span two half-pixels. Drawing the same line between `(0.0, 10.5)`
+    /// and `(100.0, 10.5)` will result in a pixel-aligned rectangle between
+    /// `(0.0, 10.0)` and `(100.0, 11.0)`.
+    ///
+    /// To draw a dashed line, or a line with caps, use
+    /// [Graphics2D::stroke_line].
+    pub fn draw_line<VStart: Into<Vec2>, VEnd: Into<Vec2>>(
+        &mut self,
+        start_position: VStart,
+        end_position: VEnd,
+        thickness: f32,
+        color: Color
+    )
+    {
+        let start_position = start_position.into();
+        let end_position = end_position.into();
+
+        let gradient_normalized = match (end_position - start_position).normalize() {
+            None => return,
+            Some(gradient) => gradient
+        };
+
+        let gradient_thickness = gradient_normalized * (thickness / 2.0);
+
+        let offset_anticlockwise = gradient_thickness.rotate_90_degrees_anticlockwise();
+        let offset_clockwise = gradient_thickness.rotate_90_degrees_clockwise();
+
+        let start_anticlockwise = start_position + offset_anticlockwise;
+        let start_clockwise = start_position + offset_clockwise;
+
+        let end_anticlockwise = end_position + offset_anticlockwise;
+        let end_clockwise = end_position + offset_clockwise;
+
+        self.draw_quad(
+            [
+                start_anticlockwise,
+                end_anticlockwise,
+                end_clockwise,
+                start_clockwise
+            ],
+            color
+        );
+    }
+
+    /// Draws a circle, filled with a single color, at the specified pixel
+    /// location.
+    pub fn draw_circle<V: Into<Vec2>>(
+        &mut self,
+        center_position: V,
+        radius: f32,
+        color: Color
+    )
+    {
+        let center_position = center_position.into();
+
+        let top_left = center_position + Vec2::new(-radius, -radius);
+        let top_right = center_position + Vec2::new(radius, -radius);
+        let bottom_right = center_position + Vec2::new(radius, radius);
+        let bottom_left = center_position + Vec2::new(-radius, radius);
+
+        self.renderer.draw_circle_section(
+            [top_left, top_right, bottom_right],
+            [color, color, color],
+            [
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 1.0)
+            ],
+            Vec2::ZERO
+        );
+
+        self.renderer.draw_circle_section(
+            [bottom_right, bottom_left, top_left],
+            [color, color, color],
+            [
+                Vec2::new(1.0, 1.0),
                 Vec2::new(-1.0, 1.0),
                 Vec2::new(-1.0, -1.0)
-            ]
+            ],
+            Vec2::ZERO
+        );
+    }
+
+    /// Draws a circle, filled using the specified [Paint], at the specified
+    /// pixel location.
+    pub fn draw_circle_with_paint<V: Into<Vec2>>(
+        &mut self,
+        center_position: V,
+        radius: f32,
+        paint: &Paint
+    )
+    {
+        self.with_paint(paint, |graphics, color| {
+            graphics.draw_circle(center_position, radius, color)
+        });
+    }
+
+    /// Draws the outline of a circle, with a single color, at the specified
+    /// pixel location.
+    ///
+    /// The outline is drawn inside the edge of the circle, so the outer edge
+    /// matches the circle drawn by [Graphics2D::draw_circle] with the same
+    /// radius. If `thickness` is greater than or equal to `radius`, the circle
+    /// is filled.
+    ///
+    /// To draw an outline centered on the edge of the circle, or a dashed
+    /// outline, use [Graphics2D::stroke_circle].
+    pub fn draw_circle_outline<V: Into<Vec2>>(
+        &mut self,
+        center_position: V,
+        radius: f32,
+        thickness: f32,
+        color: Color
+    )
+    {
+        let center_position = center_position.into();
+
+        self.draw_ring_section(
+            [
+                center_position + Vec2::new(-radius, -radius),
+                center_position + Vec2::new(radius, -radius),
+                center_position + Vec2::new(radius, radius),
+                center_position + Vec2::new(-radius, radius)
+            ],
+            [
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(-1.0, 1.0)
+            ],
+            radius,
+            thickness,
+            color
+        );
+    }
+
+    /// Draws an ellipse, filled with a single color.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Ellipse;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// graphics.draw_ellipse(
+    ///     Ellipse::new((200.0, 150.0), (100.0, 50.0)).with_rotation(0.5),
+    ///     Color::MAGENTA
+    /// );
+    /// # }
+    /// ```
+    pub fn draw_ellipse(&mut self, ellipse: impl AsRef<Ellipse>, color: Color)
+    {
+        self.draw_ellipse_quad(ellipse.as_ref(), Vec2::ZERO, color);
+    }
+
+    /// Draws the outline of an ellipse, with a single color.
+    ///
+    /// The outline is drawn inside the edge of the ellipse, and is
+    /// `thickness` pixels wide at the ends of each axis.
+    pub fn draw_ellipse_outline(
+        &mut self,
+        ellipse: impl AsRef<Ellipse>,
+        thickness: f32,
+        color: Color
+    )
+    {
+        let ellipse = ellipse.as_ref();
+
+        self.draw_ellipse_quad(ellipse, ellipse.normalized_inner_radii(thickness), color);
+    }
+
+    /// Draws a pie slice of an ellipse, filled with a single color.
+    ///
+    /// The slice extends clockwise from `start_angle` to `end_angle`, which
+    /// are specified in radians, where zero points to the right. If
+    /// `end_angle` is less than `start_angle`, the slice extends anticlockwise
+    /// instead.
+    ///
+    /// For rotated or non-circular ellipses, the angles are measured before
+    /// the ellipse is rotated and stretched to its radii.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Ellipse;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// use std::f32::consts::PI;
+    ///
+    /// // The top right quarter of a circle
+    /// graphics.draw_pie_slice(
+    ///     Ellipse::circle((200.0, 150.0), 100.0),
+    ///     -PI / 2.0,
+    ///     0.0,
+    ///     Color::BLUE
+    /// );
+    /// # }
+    /// ```
+    pub fn draw_pie_slice(
+        &mut self,
+        ellipse: impl AsRef<Ellipse>,
+        start_angle: f32,
+        end_angle: f32,
+        color: Color
+    )
+    {
+        self.draw_ellipse_wedges(
+            ellipse.as_ref(),
+            start_angle,
+            end_angle,
+            Vec2::ZERO,
+            color
+        );
+    }
+
+    /// Draws an arc around the edge of an ellipse, with a single color. This
+    /// is useful for progress rings and gauges.
+    ///
+    /// The arc is drawn inside the edge of the ellipse, with the specified
+    /// thickness, and has straight ends. The angles are interpreted as
+    /// described in [Graphics2D::draw_pie_slice].
+    pub fn draw_arc(
+        &mut self,
+        ellipse: impl AsRef<Ellipse>,
+        start_angle: f32,
+        end_angle: f32,
+        thickness: f32,
+        color: Color
+    )
+    {
+        let ellipse = ellipse.as_ref();
+
+        self.draw_ellipse_wedges(
+            ellipse,
+            start_angle,
+            end_angle,
+            ellipse.normalized_inner_radii(thickness),
+            color
+        );
+    }
+
+    /// Draws the bounding quad of an ellipse, textured with a circle.
+    fn draw_ellipse_quad(
+        &mut self,
+        ellipse: &Ellipse,
+        normalized_inner_radius: Vec2,
+        color: Color
+    )
+    {
+        let coords = [
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 1.0)
+        ];
+
+        let positions = coords.map(|coord| ellipse.map_from_unit_circle(coord));
+
+        self.renderer.draw_circle_section(
+            [positions[0], positions[1], positions[2]],
+            [color; 3],
+            [coords[0], coords[1], coords[2]],
+            normalized_inner_radius
+        );
+
+        self.renderer.draw_circle_section(
+            [positions[2], positions[3], positions[0]],
+            [color; 3],
+            [coords[2], coords[3], coords[0]],
+            normalized_inner_radius
+        );
+    }
+
+    /// Draws a pie slice of an ellipse as a series of triangles, each
+    /// covering at most a quarter of the ellipse. The curved edge of each
+    /// triangle is provided by the circle shader.
+    fn draw_ellipse_wedges(
+        &mut self,
+        ellipse: &Ellipse,
+        start_angle: f32,
+        end_angle: f32,
+        normalized_inner_radius: Vec2,
+        color: Color
+    )
+    {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        let sweep_angle = (end_angle - start_angle).clamp(-2.0 * PI, 2.0 * PI);
+
+        if sweep_angle == 0.0 || !sweep_angle.is_finite() {
+            return;
+        }
+
+        let wedge_count = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+        let wedge_angle = sweep_angle / wedge_count as f32;
+
+        // The outer edge of each triangle is tangent to the circle at the
+        // middle of the wedge, so that the whole arc is inside the triangle
+        let outer_distance = 1.0 / (wedge_angle / 2.0).cos();
+
+        let center = ellipse.center();
+
+        for i in 0..wedge_count {
+            let angle_1 = start_angle + wedge_angle * i as f32;
+            let angle_2 = angle_1 + wedge_angle;
+
+            let coord_1 = Vec2::new(angle_1.cos(), angle_1.sin()) * outer_distance;
+            let coord_2 = Vec2::new(angle_2.cos(), angle_2.sin()) * outer_distance;
+
+            self.renderer.draw_circle_section(
+                [
+                    center,
+                    ellipse.map_from_unit_circle(coord_1),
+                    ellipse.map_from_unit_circle(coord_2)
+                ],
+                [color; 3],
+                [Vec2::ZERO, coord_1, coord_2],
+                normalized_inner_radius
+            );
+        }
+    }
+
+    /// Draws a quad textured with a region of a ring, where the ring has the
+    /// specified outer radius and thickness in pixels.
+    fn draw_ring_section(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 4],
+        vertex_circle_coords_normalized: [Vec2; 4],
+        radius: f32,
+        thickness: f32,
+        color: Color
+    )
+    {
+        let inner_radius = if radius > 0.0 {
+            ((radius - thickness) / radius).max(0.0)
+        } else {
+            0.0
+        };
+
+        let inner_radius = Vec2::new(inner_radius, inner_radius);
+
+        self.renderer.draw_circle_section(
+            [
+                vertex_positions_clockwise[0],
+                vertex_positions_clockwise[1],
+                vertex_positions_clockwise[2]
+            ],
+            [color; 3],
+            [
+                vertex_circle_coords_normalized[0],
+                vertex_circle_coords_normalized[1],
+                vertex_circle_coords_normalized[2]
+            ],
+            inner_radius
+        );
+
+        self.renderer.draw_circle_section(
+            [
+                vertex_positions_clockwise[2],
+                vertex_positions_clockwise[3],
+                vertex_positions_clockwise[0]
+            ],
+            [color; 3],
+            [
+                vertex_circle_coords_normalized[2],
+                vertex_circle_coords_normalized[3],
+                vertex_circle_coords_normalized[0]
+            ],
+            inner_radius
         );
     }
 
@@ -1343,18 +2754,582 @@ impl Graphics2D
         self.renderer.draw_circle_section(
             vertex_positions_clockwise,
             vertex_colors,
-            vertex_circle_coords_normalized
+            vertex_circle_coords_normalized,
+            Vec2::ZERO
         );
     }
 
+    /// Pushes a transformation onto the transform stack. All subsequent
+    /// drawing operations, including text and images, will be transformed by
+    /// `transform`, followed by any transformations which were already on the
+    /// stack.
+    ///
+    /// Each call to `push_transform()` must be matched by a call to
+    /// [Graphics2D::pop_transform]. Alternatively, use
+    /// [Graphics2D::with_transform]. The stack is cleared at the end of each
+    /// frame.
+    ///
+    /// Note that text is rasterized before it is transformed, so scaled text
+    /// may appear blurry. The clip area set using [Graphics2D::set_clip] is
+    /// not affected by the transform, but clip areas pushed using
+    /// [Graphics2D::push_clip] are.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Rectangle;
+    /// # use speedy2d::transform::Transform;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// graphics.push_transform(Transform::rotate_around(0.3, (150.0, 150.0)));
+    ///
+    /// graphics.draw_rectangle(
+    ///     Rectangle::from_tuples((100.0, 100.0), (200.0, 200.0)),
+    ///     Color::BLUE
+    /// );
+    ///
+    /// graphics.pop_transform();
+    /// # }
+    /// ```
+    pub fn push_transform(&mut self, transform: Transform)
+    {
+        self.renderer.push_transform(&transform);
+    }
+
+    /// Removes the most recently pushed transformation from the transform
+    /// stack. See [Graphics2D::push_transform].
+    pub fn pop_transform(&mut self)
+    {
+        self.renderer.pop_transform();
+    }
+
+    /// Runs the provided callback with the specified transformation pushed
+    /// onto the transform stack, and pops it afterwards. See
+    /// [Graphics2D::push_transform].
+    pub fn with_transform<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        transform: Transform,
+        callback: F
+    ) -> R
+    {
+        self.push_transform(transform);
+        let result = callback(self);
+        self.pop_transform();
+        result
+    }
+
+    /// Returns the combination of all transformations currently on the
+    /// transform stack. This maps from the coordinates passed to drawing
+    /// operations, to pixels in the render window.
+    pub fn current_transform(&self) -> Transform
+    {
+        self.renderer.current_transform()
+    }
+
+    /// Compiles a [CustomShader] from the provided fragment shader source,
+    /// which must be written in the language returned by
+    /// [Graphics2D::shader_language]. See [CustomShader] for the inputs
+    /// available to the shader.
+    ///
+    /// An error is returned if the shader's `sampler2D` uniforms need more
+    /// texture units than the GPU supports. One unit is always reserved for
+    /// `in_Texture`.
+    ///
+    /// The returned [CustomShader] is valid only for the current graphics
+    /// context.
+    pub fn create_custom_shader(
+        &mut self,
+        fragment_shader_source: &str
+    ) -> Result<CustomShader, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_custom_shader(fragment_shader_source)
+    }
+
+    /// Returns the shading language which custom fragment shaders must be
+    /// written in.
+    pub fn shader_language(&self) -> ShaderLanguage
+    {
+        match self.renderer.gl_version() {
+            GLVersion::OpenGL2_0 => ShaderLanguage::Glsl110,
+            GLVersion::WebGL2_0 => ShaderLanguage::GlslEs300
+        }
+    }
+
+    /// Runs the provided callback, drawing everything within it using the
+    /// specified [CustomShader] instead of the built-in shader. The current
+    /// values of the shader's uniforms are used.
+    ///
+    /// Calls to this function may be nested, in which case the innermost
+    /// shader is used.
+    pub fn with_shader<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        shader: &CustomShader,
+        callback: F
+    ) -> R
+    {
+        let previous = self
+            .renderer
+            .set_custom_shader(Some(Rc::new(shader.clone())));
+
+        let result = callback(self);
+        self.renderer.set_custom_shader(previous);
+        result
+    }
+
+    /// Sets the [BlendMode] used to draw subsequent items. This may be changed
+    /// at any time between draw calls. The blend mode is reset to
+    /// [BlendMode::Normal] at the end of each frame.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::Graphics2D;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::paint::BlendMode;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// graphics.set_blend_mode(BlendMode::Additive);
+    /// graphics.draw_circle((100.0, 100.0), 50.0, Color::from_rgba(1.0, 0.5, 0.0, 0.5));
+    /// graphics.draw_circle((140.0, 100.0), 50.0, Color::from_rgba(1.0, 0.5, 0.0, 0.5));
+    /// graphics.set_blend_mode(BlendMode::Normal);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode)
+    {
+        self.renderer.set_blend_mode(blend_mode);
+    }
+
+    /// Returns the current [BlendMode].
+    #[inline]
+    pub fn blend_mode(&self) -> BlendMode
+    {
+        self.renderer.blend_mode()
+    }
+
+    /// Runs the provided callback, drawing everything within it using the
+    /// specified [BlendMode]. The previous blend mode is restored afterwards.
+    pub fn with_blend_mode<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        blend_mode: BlendMode,
+        callback: F
+    ) -> R
+    {
+        let previous = self.renderer.blend_mode();
+        self.renderer.set_blend_mode(blend_mode);
+
+        let result = callback(self);
+        self.renderer.set_blend_mode(previous);
+        result
+    }
+
+    /// Sets the [BatchingMode], which controls whether items may be drawn in a
+    /// different order to reduce the number of draw calls. The default is
+    /// [BatchingMode::Ordered]. This setting is retained between frames.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::{BatchingMode, Graphics2D};
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::font::FormattedTextBlock as Block;
+    /// # use speedy2d::image::ImageHandle;
+    /// # fn draw(graphics: &mut Graphics2D, icon: &ImageHandle, labels: &[Block]) {
+    /// graphics.set_batching_mode(BatchingMode::Grouped);
+    ///
+    /// // The icons and the labels are each drawn with a single draw call
+    /// for (i, label) in labels.iter().enumerate() {
+    ///     let y = i as f32 * 40.0;
+    ///     graphics.draw_image((0.0, y), icon);
+    ///     graphics.draw_text((40.0, y), Color::BLACK, label);
+    /// }
+    /// # }
+    /// ```
+    pub fn set_batching_mode(&mut self, batching_mode: BatchingMode)
+    {
+        self.renderer.set_batching_mode(batching_mode);
+    }
+
+    /// Returns the current [BatchingMode].
+    #[inline]
+    pub fn batching_mode(&self) -> BatchingMode
+    {
+        self.renderer.batching_mode()
+    }
+
+    /// Sets the layer of subsequent items. When using
+    /// [BatchingMode::Grouped], items on a higher layer are drawn on top of
+    /// items on a lower layer, even if they were submitted earlier. Items on
+    /// the same layer are drawn in the order they were submitted. The layer
+    /// is ignored when using [BatchingMode::Ordered].
+    ///
+    /// Items are only reordered within the same clip area and render target,
+    /// and static meshes and sprite batches are always drawn immediately.
+    /// The layer is reset to `0` at the end of each frame.
+    #[inline]
+    pub fn set_layer(&mut self, layer: i32)
+    {
+        self.renderer.set_layer(layer);
+    }
+
+    /// Returns the current layer. See [Graphics2D::set_layer].
+    #[inline]
+    pub fn layer(&self) -> i32
+    {
+        self.renderer.layer()
+    }
+
+    /// Runs the provided callback, drawing everything within it on the
+    /// specified layer. The previous layer is restored afterwards. See
+    /// [Graphics2D::set_layer].
+    pub fn with_layer<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        layer: i32,
+        callback: F
+    ) -> R
+    {
+        let previous = self.renderer.layer();
+        self.renderer.set_layer(layer);
+
+        let result = callback(self);
+        self.renderer.set_layer(previous);
+        result
+    }
+
+    /// Returns statistics about the rendering work done during the previous
+    /// frame. This includes any drawing into render targets since the frame
+    /// before that.
+    ///
+    /// The number of draw calls is useful when deciding whether
+    /// [BatchingMode::Grouped] is beneficial.
+    #[inline]
+    pub fn last_frame_statistics(&self) -> FrameStatistics
+    {
+        self.renderer.last_frame_statistics()
+    }
+
+    /// Draws into the specified [RenderTarget] instead of the window. The
+    /// callback is given this `Graphics2D` object, and all drawing operations
+    /// within the callback are redirected into the render target, in
+    /// coordinates measured in pixels from its top left corner.
+    ///
+    /// While drawing to the render target, the transform stack and clip area
+    /// start out empty, and any virtual resolution is ignored. They are
+    /// restored when the callback returns. Calls to this function may be
+    /// nested.
+    ///
+    /// The render target's own image must not be drawn into it.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::image::ImageSmoothingMode;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let minimap = graphics
+    ///     .create_render_target(ImageSmoothingMode::Linear, (200, 150))
+    ///     .unwrap();
+    ///
+    /// graphics.draw_to_render_target(&minimap, |graphics| {
+    ///     graphics.clear_screen(Color::DARK_GRAY);
+    ///     graphics.draw_circle((100.0, 75.0), 10.0, Color::RED);
+    /// });
+    ///
+    /// graphics.draw_image((10.0, 10.0), minimap.image());
+    /// # }
+    /// ```
+    pub fn draw_to_render_target<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        target: &RenderTarget,
+        callback: F
+    ) -> R
+    {
+        let saved = self.renderer.begin_render_target(target);
+        let result = callback(self);
+        self.renderer.end_render_target(saved);
+        result
+    }
+
+    /// Creates a [StaticMesh] containing the shapes and images drawn by the
+    /// callback, which are uploaded to the GPU once instead of being drawn.
+    /// The mesh can then be drawn many times using
+    /// [Graphics2D::draw_static_mesh].
+    ///
+    /// Within the callback, the transform stack starts out empty, so that
+    /// the mesh is positioned relative to its own origin. The paint and any
+    /// transforms used for each shape are included in the mesh, but the
+    /// blend mode, custom shader, and clip area are those in effect when the
+    /// mesh is drawn.
+    ///
+    /// Text can't be added to a mesh. Clearing the screen and changing the
+    /// clip area (using [Graphics2D::set_clip], [Graphics2D::push_clip], or
+    /// [Graphics2D::pop_clip]) aren't recorded either. These operations are
+    /// ignored inside the callback, and a warning is logged.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Rectangle;
+    /// # use speedy2d::transform::Transform;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let tiles = graphics
+    ///     .create_static_mesh(|graphics| {
+    ///         for y in 0..100 {
+    ///             for x in 0..100 {
+    ///                 let color = match (x + y) % 2 {
+    ///                     0 => Color::GRAY,
+    ///                     _ => Color::WHITE
+    ///                 };
+    ///
+    ///                 graphics.draw_rectangle(
+    ///                     Rectangle::from_tuples(
+    ///                         (x as f32 * 16.0, y as f32 * 16.0),
+    ///                         ((x + 1) as f32 * 16.0, (y + 1) as f32 * 16.0)
+    ///                     ),
+    ///                     color
+    ///                 );
+    ///             }
+    ///         }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// // Then, each frame
+    /// graphics.draw_static_mesh_transformed_tinted(
+    ///     &tiles,
+    ///     Transform::translate((-200.0, -100.0)),
+    ///     Color::from_gray(0.8)
+    /// );
+    /// # }
+    /// ```
+    pub fn create_static_mesh<F: FnOnce(&mut Graphics2D)>(
+        &mut self,
+        callback: F
+    ) -> Result<StaticMesh, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.begin_mesh();
+        callback(self);
+        Ok(StaticMesh::new(self.renderer.end_mesh()?))
+    }
+
+    /// Draws all the sprites in a [SpriteBatch], relative to the current
+    /// transform. This uses far fewer draw calls, and much less CPU time,
+    /// than drawing each sprite using
+    /// [Graphics2D::draw_rectangle_image_subset_tinted]. See [SpriteBatch].
+    #[inline]
+    pub fn draw_sprite_batch(&mut self, batch: &SpriteBatch)
+    {
+        self.renderer.draw_sprites(batch.image(), batch.sprites());
+    }
+
+    /// Draws a [StaticMesh], relative to the current transform. See
+    /// [Graphics2D::create_static_mesh].
+    #[inline]
+    pub fn draw_static_mesh(&mut self, mesh: &StaticMesh)
+    {
+        self.draw_static_mesh_transformed_tinted(mesh, Transform::IDENTITY, Color::WHITE);
+    }
+
+    /// Draws a [StaticMesh], with the specified transform applied before the
+    /// current transform. The color of the mesh is multiplied by `tint`.
+    #[inline]
+    pub fn draw_static_mesh_transformed_tinted(
+        &mut self,
+        mesh: &StaticMesh,
+        transform: Transform,
+        tint: Color
+    )
+    {
+        self.renderer.draw_mesh(&mesh.buffers, &transform, tint);
+    }
+
+    /// Sets a logical coordinate space which is scaled to fit the window,
+    /// using the specified [viewport::ScalingMode]. Drawing operations are
+    /// then specified in logical coordinates, regardless of the window size.
+    /// Pass `None` to draw in window pixels again.
+    ///
+    /// The setting persists across frames, and the scaling is updated
+    /// automatically when the window is resized. Any area of the window
+    /// outside the logical space is covered by letterbox bars at the end of
+    /// each frame.
+    ///
+    /// Calling this function resets the transform stack (see
+    /// [Graphics2D::push_transform]). The clip area set using
+    /// [Graphics2D::set_clip] is still specified in window pixels.
+    ///
+    /// To convert mouse positions into logical coordinates, use
+    /// [Graphics2D::window_to_logical] or
+    /// [VirtualResolution::window_to_logical].
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::viewport::{ScalingMode, VirtualResolution};
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// graphics.set_virtual_resolution(Some(VirtualResolution::new(
+    ///     (320.0, 180.0),
+    ///     ScalingMode::IntegerScale
+    /// )));
+    ///
+    /// // Draws in the center of the logical area, whatever the window size
+    /// graphics.draw_circle((160.0, 90.0), 20.0, Color::RED);
+    /// # }
+    /// ```
+    pub fn set_virtual_resolution(
+        &mut self,
+        virtual_resolution: Option<VirtualResolution>
+    )
+    {
+        self.renderer.set_virtual_resolution(virtual_resolution);
+    }
+
+    /// Returns the logical coordinate space set using
+    /// [Graphics2D::set_virtual_resolution], if any.
+    pub fn virtual_resolution(&self) -> Option<&VirtualResolution>
+    {
+        self.renderer.virtual_resolution()
+    }
+
+    /// Converts a position in window pixels (such as the mouse position) into
+    /// the logical coordinate space set using
+    /// [Graphics2D::set_virtual_resolution]. If no virtual resolution is set,
+    /// the position is returned unchanged.
+    pub fn window_to_logical(&self, position: impl Into<Vec2>) -> Vec2
+    {
+        match self.renderer.virtual_resolution() {
+            None => position.into(),
+            Some(resolution) => resolution
+                .window_to_logical(self.renderer.viewport_size_pixels(), position)
+        }
+    }
+
     /// Sets the current clip to the rectangle specified by the given
     /// coordinates. Rendering operations have no effect outside of the
     /// clipping area.
+    ///
+    /// This replaces any clip rectangle set by an earlier call to `set_clip()`
+    /// or [Graphics2D::push_clip]. To combine nested clip areas, use
+    /// [Graphics2D::push_clip] instead.
     pub fn set_clip(&mut self, rect: Option<Rectangle<i32>>)
     {
         self.renderer.set_clip(rect);
     }
 
+    /// Pushes a clip area onto the clip stack. Subsequent drawing operations
+    /// only affect pixels inside both the new shape and all the clip areas
+    /// which were already on the stack.
+    ///
+    /// The shape is transformed by the current transform, like any other
+    /// drawing operation. Axis-aligned rectangles are clipped using the
+    /// scissor test, and other shapes (such as rounded rectangles, circles,
+    /// polygons, and paths) are clipped using the stencil buffer. If the
+    /// window or render target has no stencil buffer, clipping to those shapes
+    /// has no effect.
+    ///
+    /// Each call to `push_clip()` must be matched by a call to
+    /// [Graphics2D::pop_clip]. Alternatively, use [Graphics2D::with_clip]. The
+    /// stack is cleared at the end of each frame.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::image::ImageHandle;
+    /// # use speedy2d::shape::{Ellipse, Rectangle};
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D, avatar: &ImageHandle) {
+    /// // A circular avatar inside a scrolling panel
+    /// graphics.push_clip(Rectangle::from_tuples((0.0, 0.0), (300.0, 200.0)));
+    /// graphics.push_clip(Ellipse::circle((80.0, 80.0), 64.0));
+    ///
+    /// graphics.draw_image((16.0, 16.0), avatar);
+    ///
+    /// graphics.pop_clip();
+    /// graphics.pop_clip();
+    /// # }
+    /// ```
+    pub fn push_clip(&mut self, shape: impl Into<ClipShape>)
+    {
+        let shape = shape.into();
+
+        // The shape would be added to the mesh instead of the stencil buffer
+        if self.renderer.is_building_mesh() {
+            log::warn!(
+                "push_clip() can't be used while building a static mesh, ignoring"
+            );
+            return;
+        }
+
+        if let ClipShape::Rectangle(rect) = &shape {
+            if self.renderer.push_clip_rectangle(rect) {
+                return;
+            }
+        }
+
+        // The shape is drawn into the stencil buffer using the built-in
+        // shader, so that custom shaders can't discard any of its pixels.
+        let shader = self.renderer.set_custom_shader(None);
+        self.renderer.begin_stencil_clip();
+
+        match &shape {
+            ClipShape::Rectangle(rect) => self.draw_rectangle(rect, Color::WHITE),
+            ClipShape::RoundedRectangle(round_rect) => {
+                self.draw_rounded_rectangle(round_rect, Color::WHITE)
+            }
+            ClipShape::Ellipse(ellipse) => self.draw_ellipse(ellipse, Color::WHITE),
+            ClipShape::Polygon(polygon) => {
+                self.draw_polygon(polygon, Vec2::ZERO, Color::WHITE)
+            }
+            ClipShape::Path(path) => self.fill_path(path, Color::WHITE)
+        }
+
+        self.renderer.end_stencil_clip();
+        self.renderer.set_custom_shader(shader);
+    }
+
+    /// Removes the most recently pushed clip area from the clip stack. See
+    /// [Graphics2D::push_clip].
+    pub fn pop_clip(&mut self)
+    {
+        self.renderer.pop_clip();
+    }
+
+    /// Runs the provided callback with the specified clip area pushed onto
+    /// the clip stack, and pops it afterwards. See [Graphics2D::push_clip].
+    pub fn with_clip<F: FnOnce(&mut Graphics2D) -> R, R>(
+        &mut self,
+        shape: impl Into<ClipShape>,
+        callback: F
+    ) -> R
+    {
+        self.push_clip(shape);
+        let result = callback(self);
+        self.pop_clip();
+        result
+    }
+
+    /// Records the drawing operations performed by the callback into a
+    /// [DrawList], which can be replayed later using [Graphics2D::replay], or
+    /// saved to a file. The operations are also drawn as normal.
+    ///
+    /// When the callback returns, the pixels of any images which were drawn
+    /// are read back from the GPU, so that they can be saved with the list.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::Graphics2D;
+    /// # fn draw(graphics: &mut Graphics2D) {
+    /// let recording = graphics.record(|graphics| {
+    ///     graphics.draw_circle((100.0, 100.0), 75.0, Color::BLUE);
+    /// });
+    ///
+    /// recording.save_to_file_path("frame.s2dl").unwrap();
+    /// # }
+    /// ```
+    pub fn record<F: FnOnce(&mut Graphics2D)>(&mut self, callback: F) -> DrawList
+    {
+        self.renderer.begin_recording();
+        callback(self);
+        self.renderer.end_recording()
+    }
+
+    /// Draws the operations in the specified [DrawList], relative to the
+    /// current transform. See [DrawList] for details.
+    pub fn replay(&mut self, list: &DrawList)
+    {
+        list.replay(&mut self.renderer);
+    }
+
     /// Captures a screenshot of the render window. The returned data contains
     /// the color of each pixel. Pixels are represented using a `u8` for each
     /// component (red, green, blue, and alpha). Use the `format` parameter to
@@ -1363,6 +3338,71 @@ impl Graphics2D
     {
         self.renderer.capture(format)
     }
+
+    /// Captures part of the render window, measured in pixels from the top
+    /// left. The area is cropped to the window. See [Graphics2D::capture].
+    ///
+    /// When drawing into a [RenderTarget], this captures part of the render
+    /// target instead. As with [Graphics2D::read_render_target_pixels], the
+    /// returned colors don't have premultiplied alpha.
+    ///
+    /// The returned data can be saved to a file using
+    /// [RawBitmapData::save_to_file].
+    pub fn capture_region(
+        &mut self,
+        region: impl AsRef<URect>,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        self.renderer.capture_region(region.as_ref(), format)
+    }
+
+    /// Reads back the current contents of an image. The returned data
+    /// contains the color of each pixel, from the top left of the image,
+    /// in the layout specified by `format`.
+    ///
+    /// Anything drawn into the image earlier in the frame (for example, using
+    /// [Graphics2D::draw_to_render_target]) is included.
+    ///
+    /// The returned colors never have premultiplied alpha. Render targets
+    /// store their contents with premultiplied alpha, so these are converted
+    /// back when read, which may lose some precision for translucent pixels.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::image::{ImageDataType, ImageHandle};
+    /// # use speedy2d::Graphics2D;
+    /// # fn inspect(graphics: &mut Graphics2D, texture: &ImageHandle) {
+    /// let pixels = graphics
+    ///     .read_image_pixels(texture, ImageDataType::RGBA)
+    ///     .unwrap();
+    ///
+    /// // The red component of the top left pixel
+    /// let red = pixels.data()[0];
+    /// # }
+    /// ```
+    pub fn read_image_pixels(
+        &mut self,
+        image: &ImageHandle,
+        format: ImageDataType
+    ) -> Result<RawBitmapData, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.read_image(image, format)
+    }
+
+    /// Reads back the current contents of a render target. This is the same
+    /// as calling [Graphics2D::read_image_pixels] with the target's image,
+    /// but reuses the target's existing framebuffer.
+    ///
+    /// The target's contents are stored with premultiplied alpha, but the
+    /// returned colors are converted back to non-premultiplied alpha.
+    pub fn read_render_target_pixels(
+        &mut self,
+        render_target: &RenderTarget,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        self.renderer.read_render_target(render_target, format)
+    }
 }
 
 /// Struct representing a window.
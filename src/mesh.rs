@@ -0,0 +1,220 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::renderer2d::MeshBuffers;
+
+/// A collection of shapes and images which is uploaded to the GPU once, and
+/// can then be drawn many times with very little overhead. This is useful
+/// for large amounts of content which rarely changes, such as tilemaps,
+/// charts, and map layers.
+///
+/// Create a mesh using [crate::Graphics2D::create_static_mesh], and draw it
+/// using [crate::Graphics2D::draw_static_mesh] or
+/// [crate::Graphics2D::draw_static_mesh_transformed_tinted].
+///
+/// Text can't be added to a mesh, and should be drawn separately. Static
+/// meshes aren't included when recording a [crate::draw_list::DrawList].
+///
+/// Note: this handle can only be used in the graphics context in which it was
+/// created.
+#[derive(Clone)]
+pub struct StaticMesh
+{
+    pub(crate) buffers: Rc<MeshBuffers>
+}
+
+impl StaticMesh
+{
+    pub(crate) fn new(buffers: MeshBuffers) -> Self
+    {
+        StaticMesh {
+            buffers: Rc::new(buffers)
+        }
+    }
+
+    /// Returns the number of vertices in the mesh. Each triangle has three
+    /// vertices.
+    #[inline]
+    pub fn vertex_count(&self) -> usize
+    {
+        self.buffers.vertex_count()
+    }
+
+    /// Returns true if the mesh contains nothing to draw.
+    #[inline]
+    pub fn is_empty(&self) -> bool
+    {
+        self.buffers.vertex_count() == 0
+    }
+}
+
+impl Debug for StaticMesh
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_struct("StaticMesh")
+            .field("vertex_count", &self.vertex_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use crate::color::Color;
+    use crate::image::{ImageDataType, ImageSmoothingMode};
+    use crate::paint::Gradient;
+    use crate::shape::{Ellipse, Rectangle};
+    use crate::test_utils::{pixel, render_frame};
+    use crate::transform::Transform;
+    use crate::GLRenderer;
+
+    #[test]
+    fn transform_and_tint()
+    {
+        let mut renderer = GLRenderer::new_software((40, 40)).unwrap();
+
+        let image = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::BLACK);
+
+            let mesh = graphics
+                .create_static_mesh(|graphics| {
+                    graphics.draw_rectangle(
+                        Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                        Color::WHITE
+                    );
+                })
+                .unwrap();
+
+            assert_eq!(6, mesh.vertex_count());
+
+            graphics.draw_static_mesh(&mesh);
+
+            graphics.with_transform(Transform::translate((20.0, 0.0)), |graphics| {
+                graphics.draw_static_mesh_transformed_tinted(
+                    &mesh,
+                    Transform::translate((0.0, 20.0)),
+                    Color::RED
+                );
+            });
+
+            // Ordinary drawing is unaffected by the mesh transform and tint
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 30.0), (10.0, 40.0)),
+                Color::BLUE
+            );
+
+            graphics.capture(ImageDataType::RGBA)
+        });
+
+        assert_eq!([255, 255, 255, 255], pixel(&image, 5, 5));
+        assert_eq!([255, 0, 0, 255], pixel(&image, 25, 25));
+        assert_eq!([0, 0, 0, 255], pixel(&image, 25, 5));
+        assert_eq!([0, 0, 0, 255], pixel(&image, 5, 25));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 5, 35));
+    }
+
+    #[test]
+    fn textures_and_gradients()
+    {
+        let mut renderer = GLRenderer::new_software((30, 10)).unwrap();
+
+        let image = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::BLACK);
+
+            let green = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGB,
+                    ImageSmoothingMode::NearestNeighbor,
+                    (1, 1),
+                    &[0, 255, 0]
+                )
+                .unwrap();
+
+            let mesh = graphics
+                .create_static_mesh(|graphics| {
+                    graphics.draw_rectangle_image(
+                        Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                        &green
+                    );
+                    graphics.draw_rectangle_with_paint(
+                        Rectangle::from_tuples((10.0, 0.0), (20.0, 10.0)),
+                        &Gradient::linear((10.0, 0.0), (20.0, 0.0))
+                            .with_stop(0.0, Color::BLUE)
+                            .with_stop(1.0, Color::BLUE)
+                            .into()
+                    );
+                    graphics.draw_rectangle(
+                        Rectangle::from_tuples((20.0, 0.0), (30.0, 10.0)),
+                        Color::RED
+                    );
+                })
+                .unwrap();
+
+            graphics.draw_static_mesh(&mesh);
+            graphics.capture(ImageDataType::RGBA)
+        });
+
+        assert_eq!([0, 255, 0, 255], pixel(&image, 5, 5));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 15, 5));
+        assert_eq!([255, 0, 0, 255], pixel(&image, 25, 5));
+    }
+
+    #[test]
+    fn clear_and_clip_are_ignored()
+    {
+        let mut renderer = GLRenderer::new_software((20, 10)).unwrap();
+
+        let (mesh, image) = render_frame(&mut renderer, Color::BLACK, |graphics| {
+            let mesh = graphics
+                .create_static_mesh(|graphics| {
+                    graphics.clear_screen(Color::RED);
+                    graphics.set_clip(Some(Rectangle::from_tuples((0, 0), (1, 1))));
+
+                    graphics.with_clip(Ellipse::circle((5.0, 5.0), 1.0), |graphics| {
+                        graphics.with_clip(
+                            Rectangle::from_tuples((0.0, 0.0), (1.0, 1.0)),
+                            |graphics| {
+                                graphics.draw_rectangle(
+                                    Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                                    Color::WHITE
+                                );
+                            }
+                        );
+                    });
+                })
+                .unwrap();
+
+            graphics.draw_static_mesh(&mesh);
+
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((10.0, 0.0), (20.0, 10.0)),
+                Color::BLUE
+            );
+
+            mesh
+        });
+
+        // The clip shape isn't added to the mesh
+        assert_eq!(6, mesh.vertex_count());
+
+        assert_eq!([255, 255, 255, 255], pixel(&image, 9, 9));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 15, 5));
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::color::Color;
+use crate::dimen::Vec2;
+
+/// Describes how the interior of a shape (or a block of text) is colored.
+///
+/// A `Paint` may be created from a [Color] or a [Gradient] using `into()`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Paint
+{
+    /// The shape is filled with a single color.
+    Solid(Color),
+
+    /// The shape is filled with a gradient.
+    Gradient(Gradient)
+}
+
+impl From<Color> for Paint
+{
+    fn from(color: Color) -> Self
+    {
+        Paint::Solid(color)
+    }
+}
+
+impl From<Gradient> for Paint
+{
+    fn from(gradient: Gradient) -> Self
+    {
+        Paint::Gradient(gradient)
+    }
+}
+
+/// Describes how the colors of newly drawn items are combined with the colors
+/// already present on the screen (or render target). Set the blend mode using
+/// [crate::Graphics2D::set_blend_mode].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum BlendMode
+{
+    /// Items are drawn over the existing contents, using their alpha
+    /// component to determine their opacity. This is the default.
+    #[default]
+    Normal,
+
+    /// The color of the item is added to the existing contents, scaled by its
+    /// alpha component. This brightens the area, and is useful for particle
+    /// effects and lights.
+    Additive,
+
+    /// The existing contents are multiplied by the color of the item. This
+    /// darkens the area, and is useful for shadows and lighting masks.
+    Multiply,
+
+    /// The inverse of the existing contents is multiplied by the inverse of
+    /// the color of the item. This brightens the area, but unlike
+    /// [BlendMode::Additive], never saturates to white unless one of the
+    /// colors is white.
+    Screen,
+
+    /// Like [BlendMode::Normal], but the item's colors are assumed to have
+    /// already been multiplied by their alpha component. This applies to the
+    /// colors of shapes, tint colors, and the pixels of images, which are
+    /// all drawn unchanged and blended using `ONE, ONE_MINUS_SRC_ALPHA`.
+    Premultiplied,
+
+    /// The item replaces the existing contents, including the alpha
+    /// component, without any blending.
+    Replace
+}
+
+impl BlendMode
+{
+    /// Returns true if the shader must multiply its output color by its alpha
+    /// component for this blend mode to work correctly. Normal and additive
+    /// blending use premultiplied output when drawing a texture which is
+    /// already premultiplied, such as a render target.
+    pub(crate) fn requires_premultiplied_output(
+        &self,
+        texture_premultiplied: bool
+    ) -> bool
+    {
+        match self {
+            BlendMode::Multiply | BlendMode::Screen => true,
+            BlendMode::Normal | BlendMode::Additive => texture_premultiplied,
+            BlendMode::Premultiplied | BlendMode::Replace => false
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum GradientShape
+{
+    Linear
+    {
+        start: Vec2, end: Vec2
+    },
+    Radial
+    {
+        center: Vec2, radius: f32
+    }
+}
+
+/// A color gradient, with multiple color stops.
+///
+/// Gradient coordinates are specified in pixels, in the same coordinate space
+/// as the shapes being drawn. Beyond the first and last stops, the color of
+/// the nearest stop is used.
+///
+/// Up to [Gradient::MAX_STOPS] stops are supported. If more stops are added,
+/// the extra stops are ignored. At least one stop is required: a gradient
+/// without any stops is drawn in solid black, and a warning is logged.
+///
+/// ```rust
+/// use speedy2d::color::Color;
+/// use speedy2d::paint::Gradient;
+///
+/// let sunset = Gradient::linear((0.0, 0.0), (0.0, 480.0))
+///     .with_stop(0.0, Color::from_hex_rgb(0x1b2a6b))
+///     .with_stop(0.6, Color::from_hex_rgb(0xf26b38))
+///     .with_stop(1.0, Color::from_hex_rgb(0xffd56b));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Gradient
+{
+    pub(crate) shape: GradientShape,
+    pub(crate) stops: Vec<(f32, Color)>
+}
+
+impl Gradient
+{
+    /// The maximum number of color stops in a gradient.
+    pub const MAX_STOPS: usize = 8;
+
+    /// Creates a linear gradient, which varies along the line from `start`
+    /// (at offset `0.0`) to `end` (at offset `1.0`). The color is constant
+    /// along lines perpendicular to this.
+    #[inline]
+    #[must_use]
+    pub fn linear(start: impl Into<Vec2>, end: impl Into<Vec2>) -> Self
+    {
+        Gradient {
+            shape: GradientShape::Linear {
+                start: start.into(),
+                end: end.into()
+            },
+            stops: Vec::new()
+        }
+    }
+
+    /// Creates a radial gradient, which varies from the center (at offset
+    /// `0.0`) to the edge of a circle with the specified radius (at offset
+    /// `1.0`).
+    #[inline]
+    #[must_use]
+    pub fn radial(center: impl Into<Vec2>, radius: f32) -> Self
+    {
+        Gradient {
+            shape: GradientShape::Radial {
+                center: center.into(),
+                radius
+            },
+            stops: Vec::new()
+        }
+    }
+
+    /// Adds a color stop at the specified offset, which should be in the
+    /// range `0.0` to `1.0`. Stops may be added in any order.
+    #[inline]
+    #[must_use]
+    pub fn with_stop(mut self, offset: f32, color: Color) -> Self
+    {
+        if self.stops.len() >= Gradient::MAX_STOPS {
+            log::warn!(
+                "Ignoring gradient stop: the maximum is {}",
+                Gradient::MAX_STOPS
+            );
+            return self;
+        }
+
+        let offset = offset.clamp(0.0, 1.0);
+
+        let index = self
+            .stops
+            .iter()
+            .position(|(existing, _)| *existing > offset)
+            .unwrap_or(self.stops.len());
+
+        self.stops.insert(index, (offset, color));
+        self
+    }
+
+    /// Returns the color stops in this gradient, sorted by offset.
+    #[inline]
+    pub fn stops(&self) -> &[(f32, Color)]
+    {
+        &self.stops
+    }
+
+    /// The value of the `GradientType` shader attribute for this gradient.
+    pub(crate) fn shader_type(&self) -> f32
+    {
+        match self.shape {
+            GradientShape::Linear { .. } => 1.0,
+            GradientShape::Radial { .. } => 2.0
+        }
+    }
+
+    /// Returns the gradient coordinate for the specified position. For linear
+    /// gradients, the X component is the offset along the gradient. For
+    /// radial gradients, the offset is the magnitude of the coordinate.
+    pub(crate) fn shader_coord(&self, position: Vec2) -> Vec2
+    {
+        match &self.shape {
+            GradientShape::Linear { start, end } => {
+                let direction = *end - *start;
+                let length_squared = direction.magnitude_squared();
+
+                if length_squared == 0.0 {
+                    return Vec2::ZERO;
+                }
+
+                let relative = position - *start;
+
+                Vec2::new(
+                    (relative.x * direction.x + relative.y * direction.y)
+                        / length_squared,
+                    0.0
+                )
+            }
+
+            GradientShape::Radial { center, radius } => {
+                if *radius <= 0.0 {
+                    return Vec2::new(1.0, 0.0);
+                }
+
+                (position - *center) / *radius
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::shape::Rectangle;
+    use crate::test_utils::{pixel, render};
+
+    #[test]
+    fn stops_are_sorted()
+    {
+        let gradient = Gradient::linear((0.0, 0.0), (10.0, 0.0))
+            .with_stop(1.0, Color::BLUE)
+            .with_stop(0.0, Color::RED)
+            .with_stop(2.0, Color::WHITE)
+            .with_stop(0.5, Color::GREEN);
+
+        assert_eq!(
+            &[
+                (0.0, Color::RED),
+                (0.5, Color::GREEN),
+                (1.0, Color::BLUE),
+                (1.0, Color::WHITE)
+            ],
+            gradient.stops()
+        );
+    }
+
+    #[test]
+    fn gradient_without_stops()
+    {
+        let image = render(10, 10, |graphics| {
+            graphics.draw_rectangle_with_paint(
+                Rectangle::from_tuples((0.0, 0.0), (5.0, 10.0)),
+                &Gradient::linear((0.0, 0.0), (5.0, 0.0)).into()
+            );
+        });
+
+        assert_eq!([0, 0, 0, 255], pixel(&image, 2, 5));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 7, 5));
+    }
+
+    #[test]
+    fn shader_coords()
+    {
+        let linear = Gradient::linear((10.0, 10.0), (10.0, 30.0));
+
+        assert_eq!(
+            Vec2::new(0.5, 0.0),
+            linear.shader_coord(Vec2::new(50.0, 20.0))
+        );
+        assert_eq!(
+            Vec2::new(-0.5, 0.0),
+            linear.shader_coord(Vec2::new(0.0, 0.0))
+        );
+
+        let radial = Gradient::radial((100.0, 100.0), 50.0);
+
+        assert_eq!(
+            Vec2::new(0.0, -0.5),
+            radial.shader_coord(Vec2::new(100.0, 75.0))
+        );
+    }
+}
@@ -14,6 +14,8 @@
  *  limitations under the License.
  */
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 #[cfg(any(feature = "image-loading", doc, doctest))]
@@ -26,13 +28,28 @@ use {
 };
 
 use crate::color::Color;
-use crate::dimen::{UVec2, Vec2};
+use crate::dimen::{IVec2, UVec2, Vec2};
+use crate::draw_list::{DrawCommand, DrawList};
 use crate::error::{BacktraceError, Context, ErrorMessage};
 use crate::font::{FormattedGlyph, FormattedTextBlock};
 use crate::font_cache::GlyphCache;
 use crate::glwrapper::*;
-use crate::image::{ImageDataType, ImageHandle, ImageSmoothingMode};
-use crate::{Polygon, RawBitmapData, Rect, Rectangle};
+use crate::image::{
+    ImageDataType,
+    ImageHandle,
+    ImageOptions,
+    ImageSmoothingMode,
+    ImageWrapMode,
+    RenderTarget
+};
+use crate::paint::{BlendMode, Gradient};
+use crate::shader::{CustomShader, ShaderUniform};
+use crate::shape::URect;
+use crate::sprite::Sprite;
+use crate::time::Stopwatch;
+use crate::transform::Transform;
+use crate::viewport::VirtualResolution;
+use crate::{BatchingMode, FrameStatistics, Polygon, RawBitmapData, Rect, Rectangle};
 
 struct AttributeBuffers
 {
@@ -41,12 +58,18 @@ struct AttributeBuffers
     texture_coord: Vec<f32>,
     texture_mix: Vec<f32>,
     circle_mix: Vec<f32>,
+    circle_inner_radius: Vec<f32>,
+    gradient_coord: Vec<f32>,
+    gradient_type: Vec<f32>,
 
     glbuf_position: GLBuffer,
     glbuf_color: GLBuffer,
     glbuf_texture_coord: GLBuffer,
     glbuf_texture_mix: GLBuffer,
-    glbuf_circle_mix: GLBuffer
+    glbuf_circle_mix: GLBuffer,
+    glbuf_circle_inner_radius: GLBuffer,
+    glbuf_gradient_coord: GLBuffer,
+    glbuf_gradient_type: GLBuffer
 }
 
 impl AttributeBuffers
@@ -62,6 +85,9 @@ impl AttributeBuffers
             texture_coord: Vec::new(),
             texture_mix: Vec::new(),
             circle_mix: Vec::new(),
+            circle_inner_radius: Vec::new(),
+            gradient_coord: Vec::new(),
+            gradient_type: Vec::new(),
 
             glbuf_position: context
                 .new_buffer(
@@ -111,7 +137,37 @@ impl AttributeBuffers
                         .get_attribute_handle(Renderer2D::ATTR_NAME_CIRCLE_MIX)
                         .context("Failed to get attribute CIRCLE_MIX")?
                 )
-                .context("Failed to create buffer for attribute CIRCLE_MIX")?
+                .context("Failed to create buffer for attribute CIRCLE_MIX")?,
+
+            glbuf_circle_inner_radius: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    2,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_CIRCLE_INNER_RADIUS)
+                        .context("Failed to get attribute CIRCLE_INNER_RADIUS")?
+                )
+                .context("Failed to create buffer for attribute CIRCLE_INNER_RADIUS")?,
+
+            glbuf_gradient_coord: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    2,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_GRADIENT_COORD)
+                        .context("Failed to get attribute GRADIENT_COORD")?
+                )
+                .context("Failed to create buffer for attribute GRADIENT_COORD")?,
+
+            glbuf_gradient_type: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    1,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_GRADIENT_TYPE)
+                        .context("Failed to get attribute GRADIENT_TYPE")?
+                )
+                .context("Failed to create buffer for attribute GRADIENT_TYPE")?
         })
     }
 
@@ -123,15 +179,56 @@ impl AttributeBuffers
 
     pub fn upload_and_clear(&mut self, context: &GLContextManager)
     {
+        context.count_vertices_uploaded(self.get_vertex_count());
         self.glbuf_position.set_data(context, &self.position);
         self.glbuf_color.set_data(context, &self.color);
         self.glbuf_texture_coord
             .set_data(context, &self.texture_coord);
         self.glbuf_texture_mix.set_data(context, &self.texture_mix);
         self.glbuf_circle_mix.set_data(context, &self.circle_mix);
+        self.glbuf_circle_inner_radius
+            .set_data(context, &self.circle_inner_radius);
+        self.glbuf_gradient_coord
+            .set_data(context, &self.gradient_coord);
+        self.glbuf_gradient_type
+            .set_data(context, &self.gradient_type);
+        self.clear();
+    }
+
+    /// Uploads the vertices for drawing many times, using `bind()`.
+    pub fn upload_static_and_clear(&mut self, context: &GLContextManager)
+    {
+        context.count_vertices_uploaded(self.get_vertex_count());
+        self.glbuf_position.set_static_data(context, &self.position);
+        self.glbuf_color.set_static_data(context, &self.color);
+        self.glbuf_texture_coord
+            .set_static_data(context, &self.texture_coord);
+        self.glbuf_texture_mix
+            .set_static_data(context, &self.texture_mix);
+        self.glbuf_circle_mix
+            .set_static_data(context, &self.circle_mix);
+        self.glbuf_circle_inner_radius
+            .set_static_data(context, &self.circle_inner_radius);
+        self.glbuf_gradient_coord
+            .set_static_data(context, &self.gradient_coord);
+        self.glbuf_gradient_type
+            .set_static_data(context, &self.gradient_type);
         self.clear();
     }
 
+    /// Uses the previously uploaded vertices for subsequent draw calls.
+    pub fn bind(&self, context: &GLContextManager)
+    {
+        self.glbuf_position.bind(context);
+        self.glbuf_color.bind(context);
+        self.glbuf_texture_coord.bind(context);
+        self.glbuf_texture_mix.bind(context);
+        self.glbuf_circle_mix.bind(context);
+        self.glbuf_circle_inner_radius.bind(context);
+        self.glbuf_gradient_coord.bind(context);
+        self.glbuf_gradient_type.bind(context);
+    }
+
     pub fn clear(&mut self)
     {
         self.position.clear();
@@ -139,23 +236,25 @@ impl AttributeBuffers
         self.texture_coord.clear();
         self.texture_mix.clear();
         self.circle_mix.clear();
+        self.circle_inner_radius.clear();
+        self.gradient_coord.clear();
+        self.gradient_type.clear();
     }
 
     #[inline]
-    pub fn append(
-        &mut self,
-        position: &Vec2,
-        color: &Color,
-        texture_coord: &Vec2,
-        texture_mix: f32,
-        circle_mix: f32
-    )
+    pub fn append(&mut self, vertex: &Renderer2DVertex)
     {
-        AttributeBuffers::push_vec2(&mut self.position, position);
-        AttributeBuffers::push_color(&mut self.color, color);
-        AttributeBuffers::push_vec2(&mut self.texture_coord, texture_coord);
-        self.texture_mix.push(texture_mix);
-        self.circle_mix.push(circle_mix);
+        AttributeBuffers::push_vec2(&mut self.position, &vertex.position);
+        AttributeBuffers::push_color(&mut self.color, &vertex.color);
+        AttributeBuffers::push_vec2(&mut self.texture_coord, &vertex.texture_coord);
+        self.texture_mix.push(vertex.texture_mix);
+        self.circle_mix.push(vertex.circle_mix);
+        AttributeBuffers::push_vec2(
+            &mut self.circle_inner_radius,
+            &vertex.circle_inner_radius
+        );
+        AttributeBuffers::push_vec2(&mut self.gradient_coord, &vertex.gradient_coord);
+        self.gradient_type.push(vertex.gradient_type);
     }
 
     #[inline]
@@ -179,16 +278,36 @@ struct Uniforms
 {
     scale_x: GLUniformHandle,
     scale_y: GLUniformHandle,
-    texture: GLUniformHandle
+    offset_y: GLUniformHandle,
+    transform_x: GLUniformHandle,
+    transform_y: GLUniformHandle,
+    tint: GLUniformHandle,
+
+    // The fragment shader uniforms are optional for custom shaders, which
+    // might not use them.
+    texture: Option<GLUniformHandle>,
+    gradient_stop_count: Option<GLUniformHandle>,
+    gradient_stop_offsets: Option<GLUniformHandle>,
+    gradient_stop_colors: Option<GLUniformHandle>,
+    premultiply_alpha: Option<GLUniformHandle>,
+    texture_premultiplied: Option<GLUniformHandle>
 }
 
 impl Uniforms
 {
     fn new(
         context: &GLContextManager,
-        program: &Rc<GLProgram>
+        program: &Rc<GLProgram>,
+        require_fragment_uniforms: bool
     ) -> Result<Uniforms, BacktraceError<ErrorMessage>>
     {
+        let fragment_uniform =
+            |name: &str| match program.get_uniform_handle(context, name) {
+                Ok(handle) => Ok(Some(handle)),
+                Err(err) if require_fragment_uniforms => Err(err),
+                Err(_) => Ok(None)
+            };
+
         Ok(Uniforms {
             scale_x: program
                 .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_SCALE_X)
@@ -196,37 +315,343 @@ impl Uniforms
             scale_y: program
                 .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_SCALE_Y)
                 .context("Failed to find SCALE_Y uniform")?,
-            texture: program
-                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_TEXTURE)
-                .context("Failed to find TEXTURE uniform")?
+            offset_y: program
+                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_OFFSET_Y)
+                .context("Failed to find OFFSET_Y uniform")?,
+            transform_x: program
+                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_TRANSFORM_X)
+                .context("Failed to find TRANSFORM_X uniform")?,
+            transform_y: program
+                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_TRANSFORM_Y)
+                .context("Failed to find TRANSFORM_Y uniform")?,
+            tint: program
+                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_TINT)
+                .context("Failed to find TINT uniform")?,
+            texture: fragment_uniform(Renderer2D::UNIFORM_NAME_TEXTURE)
+                .context("Failed to find TEXTURE uniform")?,
+            gradient_stop_count: fragment_uniform(
+                Renderer2D::UNIFORM_NAME_GRADIENT_STOP_COUNT
+            )
+            .context("Failed to find GRADIENT_STOP_COUNT uniform")?,
+            gradient_stop_offsets: fragment_uniform(
+                Renderer2D::UNIFORM_NAME_GRADIENT_STOP_OFFSETS
+            )
+            .context("Failed to find GRADIENT_STOP_OFFSETS uniform")?,
+            gradient_stop_colors: fragment_uniform(
+                Renderer2D::UNIFORM_NAME_GRADIENT_STOP_COLORS
+            )
+            .context("Failed to find GRADIENT_STOP_COLORS uniform")?,
+            premultiply_alpha: fragment_uniform(
+                Renderer2D::UNIFORM_NAME_PREMULTIPLY_ALPHA
+            )
+            .context("Failed to find PREMULTIPLY_ALPHA uniform")?,
+            texture_premultiplied: fragment_uniform(
+                Renderer2D::UNIFORM_NAME_TEXTURE_PREMULTIPLIED
+            )
+            .context("Failed to find TEXTURE_PREMULTIPLIED uniform")?
         })
     }
 
-    fn set_viewport_size_pixels(
+    fn set_projection(&self, context: &GLContextManager, projection: Projection)
+    {
+        // Render targets are drawn upside down, so that the first row of the
+        // texture is at the top.
+        let (scale_y, offset_y) = match projection.flip_vertical {
+            false => (-2.0, 1.0),
+            true => (2.0, -1.0)
+        };
+
+        self.scale_x
+            .set_value_float(context, 2.0 / projection.size.x as f32);
+        self.scale_y
+            .set_value_float(context, scale_y / projection.size.y as f32);
+        self.offset_y.set_value_float(context, offset_y);
+    }
+
+    fn set_model(&self, context: &GLContextManager, model: Model)
+    {
+        let [a, b, c, d, e, f] = model.transform.to_matrix();
+
+        self.transform_x.set_value_vec3(context, [a, c, e]);
+        self.transform_y.set_value_vec3(context, [b, d, f]);
+
+        let tint = model.tint;
+
+        self.tint
+            .set_value_vec4_array(context, &[tint.r(), tint.g(), tint.b(), tint.a()]);
+    }
+
+    fn set_blend_mode(
         &self,
         context: &GLContextManager,
-        viewport_size_pixels: UVec2
+        blend_mode: BlendMode,
+        texture_premultiplied: bool
     )
     {
-        self.scale_x
-            .set_value_float(context, 2.0 / viewport_size_pixels.x as f32);
-        self.scale_y
-            .set_value_float(context, -2.0 / viewport_size_pixels.y as f32);
+        let as_float = |value: bool| if value { 1.0 } else { 0.0 };
+
+        if let Some(handle) = &self.premultiply_alpha {
+            handle.set_value_float(
+                context,
+                as_float(blend_mode.requires_premultiplied_output(texture_premultiplied))
+            );
+        }
+
+        if let Some(handle) = &self.texture_premultiplied {
+            handle.set_value_float(context, as_float(texture_premultiplied));
+        }
     }
 
     fn set_texture_unit(&self, context: &GLContextManager, texture_unit: i32)
     {
-        self.texture.set_value_int(context, texture_unit);
+        if let Some(texture) = &self.texture {
+            texture.set_value_int(context, texture_unit);
+        }
+    }
+
+    fn set_gradient(&self, context: &GLContextManager, gradient: &Gradient)
+    {
+        let mut offsets = [0.0; Gradient::MAX_STOPS];
+        let mut colors = [0.0; Gradient::MAX_STOPS * 4];
+
+        let stops = gradient.stops.iter().take(Gradient::MAX_STOPS);
+
+        for (i, (offset, color)) in stops.enumerate() {
+            offsets[i] = *offset;
+            colors[i * 4] = color.r();
+            colors[i * 4 + 1] = color.g();
+            colors[i * 4 + 2] = color.b();
+            colors[i * 4 + 3] = color.a();
+        }
+
+        if let Some(handle) = &self.gradient_stop_count {
+            let count = gradient.stops.len().min(Gradient::MAX_STOPS);
+            handle.set_value_int(context, count as i32);
+        }
+
+        if let Some(handle) = &self.gradient_stop_offsets {
+            handle.set_value_float_array(context, &offsets);
+        }
+
+        if let Some(handle) = &self.gradient_stop_colors {
+            handle.set_value_vec4_array(context, &colors);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Projection
+{
+    size: UVec2,
+    flip_vertical: bool
+}
+
+/// The transform and tint applied to vertices on the GPU. This is only used
+/// for static meshes, as other vertices are transformed before they're
+/// uploaded.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Model
+{
+    transform: Transform,
+    tint: Color
+}
+
+impl Model
+{
+    const IDENTITY: Model = Model {
+        transform: Transform::IDENTITY,
+        tint: Color::WHITE
+    };
+}
+
+/// A linked shader program, along with the locations of its uniforms. All
+/// programs share the built-in vertex shader, and the same attribute
+/// locations.
+pub(crate) struct ShaderProgram
+{
+    program: Rc<GLProgram>,
+    uniforms: Uniforms,
+    current_projection: Cell<Option<Projection>>,
+    current_model: Cell<Option<Model>>,
+    current_blend_mode: Cell<Option<(BlendMode, bool)>>,
+    custom_uniforms: RefCell<HashMap<String, Option<GLUniformHandle>>>,
+    max_texture_units: usize
+}
+
+impl ShaderProgram
+{
+    fn new(
+        context: &GLContextManager,
+        fragment_shader_src: &str,
+        require_fragment_uniforms: bool
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let vertex_shader_src = match context.version() {
+            GLVersion::OpenGL2_0 => include_str!("shaders/r2d_vertex_v110.glsl"),
+            GLVersion::WebGL2_0 => include_str!("shaders/r2d_vertex_v300es.glsl")
+        };
+
+        log::info!("Creating vertex shader");
+
+        let vertex_shader = context
+            .new_shader(GLShaderType::Vertex, vertex_shader_src)
+            .context("Failed to create vertex shader")?;
+
+        log::info!("Creating fragment shader");
+
+        let fragment_shader = context
+            .new_shader(GLShaderType::Fragment, fragment_shader_src)
+            .context("Failed to create fragment shader")?;
+
+        log::info!("Compiling program");
+
+        let program = context
+            .new_program(
+                &vertex_shader,
+                &fragment_shader,
+                &Renderer2D::ALL_ATTRIBUTES
+            )
+            .context("Failed to link program")?;
+
+        let uniforms = Uniforms::new(context, &program, require_fragment_uniforms)?;
+
+        context.use_program(&program);
+        uniforms.set_texture_unit(context, 0);
+
+        Ok(ShaderProgram {
+            program,
+            uniforms,
+            current_projection: Cell::new(None),
+            current_model: Cell::new(None),
+            current_blend_mode: Cell::new(None),
+            custom_uniforms: RefCell::new(HashMap::new()),
+            max_texture_units: context.max_texture_units()
+        })
+    }
+
+    fn activate(
+        &self,
+        context: &GLContextManager,
+        projection: Projection,
+        model: Model,
+        blend_mode: BlendMode,
+        texture_premultiplied: bool
+    )
+    {
+        context.use_program(&self.program);
+
+        if self.current_projection.get() != Some(projection) {
+            self.uniforms.set_projection(context, projection);
+            self.current_projection.set(Some(projection));
+        }
+
+        if self.current_model.get() != Some(model) {
+            self.uniforms.set_model(context, model);
+            self.current_model.set(Some(model));
+        }
+
+        self.set_blend_mode(context, blend_mode, texture_premultiplied);
+    }
+
+    /// Updates the blend mode of the active program. The shader output
+    /// depends on whether the colors of the texture being drawn are
+    /// premultiplied.
+    fn set_blend_mode(
+        &self,
+        context: &GLContextManager,
+        blend_mode: BlendMode,
+        texture_premultiplied: bool
+    )
+    {
+        let key = Some((blend_mode, texture_premultiplied));
+
+        if self.current_blend_mode.get() != key {
+            self.uniforms
+                .set_blend_mode(context, blend_mode, texture_premultiplied);
+            self.current_blend_mode.set(key);
+        }
+    }
+
+    fn set_custom_uniforms(
+        &self,
+        context: &GLContextManager,
+        values: &[(String, ShaderUniform)]
+    )
+    {
+        let mut handles = self.custom_uniforms.borrow_mut();
+
+        // Texture unit 0 is used for the texture of the item being drawn
+        let mut next_texture_unit = 1;
+
+        for (name, value) in values {
+            let handle = handles.entry(name.clone()).or_insert_with(|| {
+                let handle = self.program.get_uniform_handle(context, name).ok();
+
+                if handle.is_none() {
+                    log::warn!("Custom shader uniform '{}' not found", name);
+                }
+
+                handle
+            });
+
+            let handle = match handle {
+                None => continue,
+                Some(handle) => handle
+            };
+
+            match value {
+                ShaderUniform::Float(value) => handle.set_value_float(context, *value),
+                ShaderUniform::Vec2(value) => {
+                    handle.set_value_vec2(context, [value.x, value.y])
+                }
+                ShaderUniform::Vec3(x, y, z) => {
+                    handle.set_value_vec3(context, [*x, *y, *z])
+                }
+                ShaderUniform::Vec4(x, y, z, w) => {
+                    handle.set_value_vec4_array(context, &[*x, *y, *z, *w])
+                }
+                ShaderUniform::Color(color) => handle.set_value_vec4_array(
+                    context,
+                    &[color.r(), color.g(), color.b(), color.a()]
+                ),
+                ShaderUniform::Texture(image) => {
+                    if next_texture_unit as usize >= self.max_texture_units {
+                        log::warn!(
+                            "No texture unit available for custom shader uniform '{}'",
+                            name
+                        );
+                        continue;
+                    }
+
+                    context.bind_texture_to_unit(next_texture_unit, &image.texture);
+                    handle.set_value_int(context, next_texture_unit as i32);
+                    next_texture_unit += 1;
+                }
+            }
+        }
     }
 }
 
+/// The state shared by all vertices in the current batch.
+#[derive(Default)]
+struct BatchState
+{
+    texture: Option<GLTexture>,
+    gradient: Option<Rc<Gradient>>,
+    shader: Option<Rc<CustomShader>>,
+    blend_mode: BlendMode
+}
+
 pub(crate) struct Renderer2DVertex
 {
     pub position: Vec2,
     pub texture_coord: Vec2,
     pub color: Color,
     pub texture_mix: f32,
-    pub circle_mix: f32
+    pub circle_mix: f32,
+    pub circle_inner_radius: Vec2,
+    pub gradient_coord: Vec2,
+    pub gradient_type: f32
 }
 
 impl Renderer2DVertex
@@ -234,13 +659,7 @@ impl Renderer2DVertex
     #[inline]
     fn append_to_attribute_buffers(&self, attribute_buffers: &mut AttributeBuffers)
     {
-        attribute_buffers.append(
-            &self.position,
-            &self.color,
-            &self.texture_coord,
-            self.texture_mix,
-            self.circle_mix
-        );
+        attribute_buffers.append(self);
     }
 }
 
@@ -271,6 +690,42 @@ impl Renderer2DAction
         }
     }
 
+    /// Returns the smallest rectangle containing the triangle.
+    fn bounds(&self) -> Rect
+    {
+        let [a, b, c] = &self.vertices_clockwise;
+
+        Rect::new(
+            Vec2::new(
+                a.position.x.min(b.position.x).min(c.position.x),
+                a.position.y.min(b.position.y).min(c.position.y)
+            ),
+            Vec2::new(
+                a.position.x.max(b.position.x).max(c.position.x),
+                a.position.y.max(b.position.y).max(c.position.y)
+            )
+        )
+    }
+
+    #[inline]
+    fn apply_transform(&mut self, transform: &Transform)
+    {
+        for vertex in self.vertices_clockwise.iter_mut() {
+            vertex.position = transform.transform_point(vertex.position);
+        }
+    }
+
+    #[inline]
+    fn apply_gradient(&mut self, gradient: &Gradient)
+    {
+        let gradient_type = gradient.shader_type();
+
+        for vertex in self.vertices_clockwise.iter_mut() {
+            vertex.gradient_coord = gradient.shader_coord(vertex.position);
+            vertex.gradient_type = gradient_type;
+        }
+    }
+
     #[inline]
     fn append_to_attribute_buffers(&self, attribute_buffers: &mut AttributeBuffers)
     {
@@ -280,6 +735,103 @@ impl Renderer2DAction
     }
 }
 
+struct RenderQueueEntry
+{
+    item: RenderQueueItem,
+    gradient: Option<Rc<Gradient>>,
+    shader: Option<Rc<CustomShader>>,
+    blend_mode: BlendMode,
+    transform: Option<Transform>,
+    layer: i32
+}
+
+/// A group of triangles sharing the same state, which are drawn together when
+/// using [BatchingMode::Grouped].
+struct GroupedBatch
+{
+    state: BatchState,
+    layer: i32,
+    bounds: Rect,
+    actions: Vec<Renderer2DAction>
+}
+
+impl GroupedBatch
+{
+    /// The number of preceding batches which are searched for one which can
+    /// accept a triangle.
+    const MAX_LOOKBEHIND: usize = 32;
+
+    fn new(entry: &RenderQueueEntry, action: Renderer2DAction) -> Self
+    {
+        GroupedBatch {
+            state: BatchState {
+                texture: action.texture.clone(),
+                gradient: entry.gradient.clone(),
+                shader: entry.shader.clone(),
+                blend_mode: entry.blend_mode
+            },
+            layer: entry.layer,
+            bounds: action.bounds(),
+            actions: vec![action]
+        }
+    }
+
+    fn accepts(&self, entry: &RenderQueueEntry, action: &Renderer2DAction) -> bool
+    {
+        let texture_matches = match (&self.state.texture, &action.texture) {
+            (Some(current), Some(texture)) => current == texture,
+            _ => true
+        };
+
+        let gradient_matches = match (&self.state.gradient, &entry.gradient) {
+            (Some(current), Some(gradient)) => **current == **gradient,
+            _ => true
+        };
+
+        self.layer == entry.layer
+            && self.state.shader == entry.shader
+            && self.state.blend_mode == entry.blend_mode
+            && texture_matches
+            && gradient_matches
+    }
+
+    fn overlaps(&self, bounds: &Rect) -> bool
+    {
+        // Touching edges count as overlapping, as antialiasing may affect the
+        // pixels on both sides.
+        self.bounds.left() <= bounds.right()
+            && bounds.left() <= self.bounds.right()
+            && self.bounds.top() <= bounds.bottom()
+            && bounds.top() <= self.bounds.bottom()
+    }
+
+    fn add(&mut self, entry: &RenderQueueEntry, action: Renderer2DAction)
+    {
+        if self.state.texture.is_none() {
+            self.state.texture.clone_from(&action.texture);
+        }
+
+        if self.state.gradient.is_none() {
+            self.state.gradient.clone_from(&entry.gradient);
+        }
+
+        let bounds = action.bounds();
+
+        self.bounds = Rect::new(
+            Vec2::new(
+                self.bounds.left().min(bounds.left()),
+                self.bounds.top().min(bounds.top())
+            ),
+            Vec2::new(
+                self.bounds.right().max(bounds.right()),
+                self.bounds.bottom().max(bounds.bottom())
+            )
+        );
+
+        self.actions.push(action);
+    }
+}
+
 enum RenderQueueItem
 {
     FormattedTextBlock
@@ -301,7 +853,8 @@ enum RenderQueueItem
     {
         vertex_positions_clockwise: [Vec2; 3],
         vertex_colors_clockwise: [Color; 3],
-        vertex_normalized_circle_coords_clockwise: [Vec2; 3]
+        vertex_normalized_circle_coords_clockwise: [Vec2; 3],
+        normalized_inner_radius: Vec2
     },
 
     TriangleColored
@@ -361,7 +914,8 @@ impl RenderQueueItem
             RenderQueueItem::CircleSectionColored {
                 vertex_positions_clockwise,
                 vertex_colors_clockwise,
-                vertex_normalized_circle_coords_clockwise
+                vertex_normalized_circle_coords_clockwise,
+                normalized_inner_radius
             } => runner(Renderer2DAction {
                 texture: None,
                 vertices_clockwise: [
@@ -370,21 +924,30 @@ impl RenderQueueItem
                         texture_coord: vertex_normalized_circle_coords_clockwise[0],
                         color: vertex_colors_clockwise[0],
                         texture_mix: 0.0,
-                        circle_mix: 1.0
+                        circle_mix: 1.0,
+                        circle_inner_radius: *normalized_inner_radius,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[1],
                         texture_coord: vertex_normalized_circle_coords_clockwise[1],
                         color: vertex_colors_clockwise[1],
                         texture_mix: 0.0,
-                        circle_mix: 1.0
+                        circle_mix: 1.0,
+                        circle_inner_radius: *normalized_inner_radius,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[2],
                         texture_coord: vertex_normalized_circle_coords_clockwise[2],
                         color: vertex_colors_clockwise[2],
                         texture_mix: 0.0,
-                        circle_mix: 1.0
+                        circle_mix: 1.0,
+                        circle_inner_radius: *normalized_inner_radius,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     }
                 ]
             }),
@@ -400,21 +963,30 @@ impl RenderQueueItem
                         texture_coord: Vec2::ZERO,
                         color: vertex_colors_clockwise[0],
                         texture_mix: 0.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        circle_inner_radius: Vec2::ZERO,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[1],
                         texture_coord: Vec2::ZERO,
                         color: vertex_colors_clockwise[1],
                         texture_mix: 0.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        circle_inner_radius: Vec2::ZERO,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[2],
                         texture_coord: Vec2::ZERO,
                         color: vertex_colors_clockwise[2],
                         texture_mix: 0.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        circle_inner_radius: Vec2::ZERO,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     }
                 ]
             }),
@@ -432,21 +1004,30 @@ impl RenderQueueItem
                         texture_coord: vertex_texture_coords_clockwise[0],
                         color: vertex_colors_clockwise[0],
                         texture_mix: 1.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        circle_inner_radius: Vec2::ZERO,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[1],
                         texture_coord: vertex_texture_coords_clockwise[1],
                         color: vertex_colors_clockwise[1],
                         texture_mix: 1.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        circle_inner_radius: Vec2::ZERO,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[2],
                         texture_coord: vertex_texture_coords_clockwise[2],
                         color: vertex_colors_clockwise[2],
                         texture_mix: 1.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        circle_inner_radius: Vec2::ZERO,
+                        gradient_coord: Vec2::ZERO,
+                        gradient_type: 0.0
                     }
                 ]
             })
@@ -454,120 +1035,700 @@ impl RenderQueueItem
     }
 }
 
-pub struct Renderer2D
+/// A range of vertices in a static mesh which share a texture and gradient.
+struct MeshSegment
 {
-    context: GLContextManager,
+    first_vertex: usize,
+    vertex_count: usize,
+    texture: Option<GLTexture>,
+    gradient: Option<Rc<Gradient>>
+}
 
-    program: Rc<GLProgram>,
+impl MeshSegment
+{
+    /// Returns true if the vertices of an action with the specified texture
+    /// and gradient can be added to this segment.
+    fn try_add(
+        &mut self,
+        texture: &Option<GLTexture>,
+        gradient: &Option<Rc<Gradient>>
+    ) -> bool
+    {
+        let texture_matches = match (&self.texture, texture) {
+            (Some(current), Some(texture)) => current == texture,
+            _ => true
+        };
 
-    render_queue: Vec<RenderQueueItem>,
+        let gradient_matches = match (&self.gradient, gradient) {
+            (Some(current), Some(gradient)) => **current == **gradient,
+            _ => true
+        };
 
-    glyph_cache: GlyphCache,
+        if !texture_matches || !gradient_matches {
+            return false;
+        }
+
+        if self.texture.is_none() {
+            self.texture.clone_from(texture);
+        }
+
+        if self.gradient.is_none() {
+            self.gradient.clone_from(gradient);
+        }
+
+        true
+    }
+}
+
+/// Vertices which have been uploaded to the GPU once, to be drawn many
+/// times.
+pub(crate) struct MeshBuffers
+{
     attribute_buffers: AttributeBuffers,
-    current_texture: Option<GLTexture>,
+    segments: Vec<MeshSegment>,
+    vertex_count: usize
+}
 
-    #[allow(dead_code)]
-    uniforms: Uniforms
+impl MeshBuffers
+{
+    #[inline]
+    pub(crate) fn vertex_count(&self) -> usize
+    {
+        self.vertex_count
+    }
 }
 
-impl Renderer2D
+/// The buffers used to draw sprite batches, which are created the first time
+/// a batch is drawn.
+struct SpriteBuffers
 {
-    const ATTR_NAME_POSITION: &'static str = "in_Position";
-    const ATTR_NAME_COLOR: &'static str = "in_Color";
-    const ATTR_NAME_TEXTURE_COORD: &'static str = "in_TextureCoord";
-    const ATTR_NAME_TEXTURE_MIX: &'static str = "in_TextureMix";
-    const ATTR_NAME_CIRCLE_MIX: &'static str = "in_CircleMix";
+    vertices: GLInterleavedBuffer,
+    indices: GLIndexBuffer,
+    vertex_data: Vec<f32>
+}
 
-    const UNIFORM_NAME_SCALE_X: &'static str = "in_ScaleX";
-    const UNIFORM_NAME_SCALE_Y: &'static str = "in_ScaleY";
-    const UNIFORM_NAME_TEXTURE: &'static str = "in_Texture";
+impl SpriteBuffers
+{
+    /// The number of values in each interleaved vertex.
+    const VERTEX_SIZE: usize = 15;
 
-    const ALL_ATTRIBUTES: [&'static str; 5] = [
-        Renderer2D::ATTR_NAME_POSITION,
-        Renderer2D::ATTR_NAME_COLOR,
-        Renderer2D::ATTR_NAME_TEXTURE_COORD,
-        Renderer2D::ATTR_NAME_TEXTURE_MIX,
-        Renderer2D::ATTR_NAME_CIRCLE_MIX
-    ];
+    /// The maximum number of sprites drawn in one draw call, which limits the
+    /// size of the buffers.
+    const MAX_SPRITES_PER_DRAW: usize = 16384;
 
-    pub fn new(
+    fn new(
         context: &GLContextManager,
-        viewport_size_pixels: UVec2
+        program: &GLProgram
     ) -> Result<Self, BacktraceError<ErrorMessage>>
     {
-        log::info!("Creating vertex shader");
+        let components = [2, 4, 2, 1, 1, 2, 2, 1];
+        let mut attributes = Vec::new();
 
-        let (vertex_shader_src, fragment_shader_src) = match context.version() {
-            GLVersion::OpenGL2_0 => {
-                log::info!("Using OpenGL 2.0 shaders");
-                (
-                    include_str!("shaders/r2d_vertex_v110.glsl"),
-                    include_str!("shaders/r2d_fragment_v110.glsl")
-                )
-            }
-            GLVersion::WebGL2_0 => {
-                log::info!("Using WebGL 2.0 shaders");
-                (
-                    include_str!("shaders/r2d_vertex_v300es.glsl"),
-                    include_str!("shaders/r2d_fragment_v300es.glsl")
-                )
-            }
-        };
+        for (name, components) in Renderer2D::ALL_ATTRIBUTES.iter().zip(components) {
+            let handle = program
+                .get_attribute_handle(name)
+                .context(format!("Failed to get attribute {}", name))?;
 
-        let vertex_shader = context
-            .new_shader(GLShaderType::Vertex, vertex_shader_src)
-            .context("Failed to create Renderer2D vertex shader")?;
+            attributes.push((handle, components));
+        }
 
-        log::info!("Creating fragment shader");
+        let vertices = context
+            .new_interleaved_buffer(attributes)
+            .context("Failed to create sprite vertex buffer")?;
 
-        let fragment_shader = context
-            .new_shader(GLShaderType::Fragment, fragment_shader_src)
-            .context("Failed to create Renderer2D fragment shader")?;
+        let mut indices = context
+            .new_index_buffer()
+            .context("Failed to create sprite index buffer")?;
 
-        log::info!("Compiling program");
+        // Every sprite is a quad made from two triangles, so the indices never
+        // change
+        let index_data: Vec<u32> = (0..SpriteBuffers::MAX_SPRITES_PER_DRAW as u32)
+            .flat_map(|sprite| {
+                let first = sprite * 4;
+                [first, first + 1, first + 2, first + 2, first + 3, first]
+            })
+            .collect();
 
-        let program = context
-            .new_program(
-                &vertex_shader,
-                &fragment_shader,
-                &Renderer2D::ALL_ATTRIBUTES
-            )
-            .context("Failed to create Renderer2D program")?;
+        indices.set_data(context, &index_data);
 
-        let attribute_buffers = AttributeBuffers::new(context, &program)?;
-        let uniforms = Uniforms::new(context, &program)?;
+        Ok(SpriteBuffers {
+            vertices,
+            indices,
+            vertex_data: Vec::new()
+        })
+    }
 
-        context.use_program(&program);
+    #[inline]
+    fn append(&mut self, sprite: &Sprite, image_size: Vec2)
+    {
+        let tint = sprite.tint();
+        let texture_coords = sprite.texture_coords(image_size);
+
+        for (position, texture_coord) in sprite.corners().iter().zip(texture_coords) {
+            self.vertex_data.extend_from_slice(&[
+                position.x,
+                position.y,
+                tint.r(),
+                tint.g(),
+                tint.b(),
+                tint.a(),
+                texture_coord.x,
+                texture_coord.y,
+                1.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0
+            ]);
+        }
+    }
+}
 
-        uniforms.set_texture_unit(context, 0);
+/// The items collected for a static mesh which is being built, and the
+/// transform state to restore afterwards.
+struct MeshBuilder
+{
+    entries: Vec<RenderQueueEntry>,
+    saved_transform: Transform,
+    saved_transform_stack: Vec<Transform>
+}
+
+#[derive(Clone)]
+struct RenderTargetBinding
+{
+    framebuffer: GLFramebuffer,
+    size: UVec2,
+
+    /// The target's texture, if its mipmaps must be regenerated after
+    /// drawing into it.
+    mipmapped_texture: Option<GLTexture>
+}
+
+/// An entry in the clip stack, describing how to undo the clip.
+enum ClipStackEntry
+{
+    /// The scissor rectangle was narrowed from the specified value.
+    Scissor(Option<Rectangle<i32>>),
+
+    /// A shape was added to the stencil buffer.
+    Stencil
+}
+
+/// The state which is replaced while drawing to a render target, and restored
+/// afterwards.
+pub(crate) struct SavedRenderState
+{
+    render_target: Option<RenderTargetBinding>,
+    current_transform: Transform,
+    transform_stack: Vec<Transform>,
+    clip: Option<Rectangle<i32>>,
+    clip_stack: Vec<ClipStackEntry>,
+    stencil_depth: u8
+}
+
+/// The drawing state which is reset to its defaults by
+/// `reset_drawing_state()`, and restored afterwards.
+pub(crate) struct SavedDrawingState
+{
+    paint_gradient: Option<Rc<Gradient>>,
+    shader: Option<Rc<CustomShader>>,
+    blend_mode: BlendMode,
+    virtual_resolution: Option<VirtualResolution>,
+    current_transform: Transform,
+    transform_stack: Vec<Transform>,
+    clip: Option<Rectangle<i32>>,
+    clip_stack: Vec<ClipStackEntry>,
+    stencil_depth: u8
+}
+
+pub struct Renderer2D
+{
+    context: GLContextManager,
+
+    program: ShaderProgram,
+    projection: Projection,
+
+    render_queue: Vec<RenderQueueEntry>,
+    current_paint_gradient: Option<Rc<Gradient>>,
+    current_shader: Option<Rc<CustomShader>>,
+    current_blend_mode: BlendMode,
+    current_transform: Transform,
+    transform_stack: Vec<Transform>,
+
+    viewport_size_pixels: UVec2,
+    virtual_resolution: Option<VirtualResolution>,
+    render_target: Option<RenderTargetBinding>,
+    current_clip: Option<Rectangle<i32>>,
+    clip_stack: Vec<ClipStackEntry>,
+    stencil_depth: u8,
+    recordings: Vec<DrawList>,
+    mesh_builders: Vec<MeshBuilder>,
+    sprite_buffers: Option<SpriteBuffers>,
+    batching_mode: BatchingMode,
+    current_layer: i32,
+    stopwatch: Option<Stopwatch>,
+    flush_time_secs: f64,
+    last_frame_statistics: FrameStatistics,
+    readback_framebuffer: Option<GLFramebuffer>,
+
+    glyph_cache: GlyphCache,
+    attribute_buffers: AttributeBuffers,
+    batch: BatchState
+}
+
+impl Renderer2D
+{
+    pub(crate) const ATTR_NAME_POSITION: &'static str = "in_Position";
+    pub(crate) const ATTR_NAME_COLOR: &'static str = "in_Color";
+    pub(crate) const ATTR_NAME_TEXTURE_COORD: &'static str = "in_TextureCoord";
+    pub(crate) const ATTR_NAME_TEXTURE_MIX: &'static str = "in_TextureMix";
+    pub(crate) const ATTR_NAME_CIRCLE_MIX: &'static str = "in_CircleMix";
+    pub(crate) const ATTR_NAME_CIRCLE_INNER_RADIUS: &'static str = "in_CircleInnerRadius";
+    pub(crate) const ATTR_NAME_GRADIENT_COORD: &'static str = "in_GradientCoord";
+    pub(crate) const ATTR_NAME_GRADIENT_TYPE: &'static str = "in_GradientType";
+
+    pub(crate) const UNIFORM_NAME_SCALE_X: &'static str = "in_ScaleX";
+    pub(crate) const UNIFORM_NAME_SCALE_Y: &'static str = "in_ScaleY";
+    pub(crate) const UNIFORM_NAME_OFFSET_Y: &'static str = "in_OffsetY";
+    pub(crate) const UNIFORM_NAME_TRANSFORM_X: &'static str = "in_TransformX";
+    pub(crate) const UNIFORM_NAME_TRANSFORM_Y: &'static str = "in_TransformY";
+    pub(crate) const UNIFORM_NAME_TINT: &'static str = "in_Tint";
+    pub(crate) const UNIFORM_NAME_TEXTURE: &'static str = "in_Texture";
+    pub(crate) const UNIFORM_NAME_GRADIENT_STOP_COUNT: &'static str =
+        "in_GradientStopCount";
+    pub(crate) const UNIFORM_NAME_GRADIENT_STOP_OFFSETS: &'static str =
+        "in_GradientStopOffsets";
+    pub(crate) const UNIFORM_NAME_GRADIENT_STOP_COLORS: &'static str =
+        "in_GradientStopColors";
+    pub(crate) const UNIFORM_NAME_PREMULTIPLY_ALPHA: &'static str = "in_PremultiplyAlpha";
+    pub(crate) const UNIFORM_NAME_TEXTURE_PREMULTIPLIED: &'static str =
+        "in_TexturePremultiplied";
+
+    const ALL_ATTRIBUTES: [&'static str; 8] = [
+        Renderer2D::ATTR_NAME_POSITION,
+        Renderer2D::ATTR_NAME_COLOR,
+        Renderer2D::ATTR_NAME_TEXTURE_COORD,
+        Renderer2D::ATTR_NAME_TEXTURE_MIX,
+        Renderer2D::ATTR_NAME_CIRCLE_MIX,
+        Renderer2D::ATTR_NAME_CIRCLE_INNER_RADIUS,
+        Renderer2D::ATTR_NAME_GRADIENT_COORD,
+        Renderer2D::ATTR_NAME_GRADIENT_TYPE
+    ];
+
+    pub fn new(
+        context: &GLContextManager,
+        viewport_size_pixels: UVec2
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let fragment_shader_src = match context.version() {
+            GLVersion::OpenGL2_0 => {
+                log::info!("Using OpenGL 2.0 shaders");
+                include_str!("shaders/r2d_fragment_v110.glsl")
+            }
+            GLVersion::WebGL2_0 => {
+                log::info!("Using WebGL 2.0 shaders");
+                include_str!("shaders/r2d_fragment_v300es.glsl")
+            }
+        };
+
+        let program = ShaderProgram::new(context, fragment_shader_src, true)
+            .context("Failed to create Renderer2D program")?;
+
+        let attribute_buffers = AttributeBuffers::new(context, &program.program)?;
+
+        let projection = Projection {
+            size: viewport_size_pixels,
+            flip_vertical: false
+        };
 
-        uniforms.set_viewport_size_pixels(context, viewport_size_pixels);
+        program.activate(
+            context,
+            projection,
+            Model::IDENTITY,
+            BlendMode::Normal,
+            false
+        );
 
         context.set_viewport_size(viewport_size_pixels);
 
+        let stopwatch = match Stopwatch::new() {
+            Ok(stopwatch) => Some(stopwatch),
+            Err(err) => {
+                log::warn!("Failed to create stopwatch for frame statistics: {:?}", err);
+                None
+            }
+        };
+
         Ok(Renderer2D {
             context: context.clone(),
             program,
+            projection,
             render_queue: Vec::new(),
+            current_paint_gradient: None,
+            current_shader: None,
+            current_blend_mode: BlendMode::Normal,
+            current_transform: Transform::IDENTITY,
+            transform_stack: Vec::new(),
+            viewport_size_pixels,
+            virtual_resolution: None,
+            render_target: None,
+            current_clip: None,
+            clip_stack: Vec::new(),
+            stencil_depth: 0,
+            recordings: Vec::new(),
+            mesh_builders: Vec::new(),
+            sprite_buffers: None,
+            batching_mode: BatchingMode::Ordered,
+            current_layer: 0,
+            stopwatch,
+            flush_time_secs: 0.0,
+            last_frame_statistics: FrameStatistics::default(),
+            readback_framebuffer: None,
             glyph_cache: GlyphCache::new(),
             attribute_buffers,
-            current_texture: None,
-            uniforms
+            batch: BatchState::default()
         })
     }
 
-    pub fn set_viewport_size_pixels(&self, viewport_size_pixels: UVec2)
+    pub fn set_viewport_size_pixels(&mut self, viewport_size_pixels: UVec2)
+    {
+        self.viewport_size_pixels = viewport_size_pixels;
+
+        // If a render target is active, the new size takes effect once
+        // drawing returns to the window.
+        if self.render_target.is_none() {
+            self.apply_render_target();
+            self.reset_transform();
+        }
+    }
+
+    /// Binds the active render target (or the window, if there is none), and
+    /// sets up the viewport to match its size.
+    fn apply_render_target(&mut self)
+    {
+        let (framebuffer, size) = match &self.render_target {
+            None => (None, self.viewport_size_pixels),
+            Some(target) => (Some(&target.framebuffer), target.size)
+        };
+
+        self.context.bind_framebuffer(framebuffer);
+        self.context.set_viewport_size(size);
+
+        // Each shader program is updated to match when it is next used
+        self.projection = Projection {
+            size,
+            flip_vertical: framebuffer.is_some()
+        };
+    }
+
+    /// Redirects subsequent drawing operations into the specified render
+    /// target. The transform stack and clip area are reset while drawing to
+    /// the target, and restored by `end_render_target()`.
+    pub(crate) fn begin_render_target(
+        &mut self,
+        target: &RenderTarget
+    ) -> SavedRenderState
+    {
+        self.record(|list| {
+            DrawCommand::BeginRenderTarget(list.render_target_index(target))
+        });
+        self.switch_to_render_target(target)
+    }
+
+    /// Returns to drawing into the render target (or window) which was active
+    /// before the matching call to `begin_render_target()`.
+    pub(crate) fn end_render_target(&mut self, saved: SavedRenderState)
+    {
+        self.record(|_| DrawCommand::EndRenderTarget);
+        self.restore_render_target(saved);
+    }
+
+    fn switch_to_render_target(&mut self, target: &RenderTarget) -> SavedRenderState
+    {
+        self.flush_render_queue();
+
+        let saved = SavedRenderState {
+            render_target: self.render_target.replace(RenderTargetBinding {
+                framebuffer: target.framebuffer.clone(),
+                size: *target.size(),
+                mipmapped_texture: Some(target.image().texture.clone())
+                    .filter(|_| target.image().mipmaps)
+            }),
+            current_transform: self.current_transform,
+            transform_stack: std::mem::take(&mut self.transform_stack),
+            clip: self.current_clip.clone(),
+            clip_stack: std::mem::take(&mut self.clip_stack),
+            stencil_depth: std::mem::replace(&mut self.stencil_depth, 0)
+        };
+
+        self.apply_clip(None);
+        self.apply_stencil_depth();
+        self.apply_render_target();
+        self.reset_transform();
+
+        saved
+    }
+
+    fn restore_render_target(&mut self, saved: SavedRenderState)
+    {
+        self.flush_render_queue();
+
+        if let Some(texture) = self
+            .render_target
+            .as_ref()
+            .and_then(|binding| binding.mipmapped_texture.as_ref())
+        {
+            texture.generate_mipmaps(&self.context);
+        }
+
+        if !self.transform_stack.is_empty() {
+            log::warn!(
+                "{} transform(s) were pushed but not popped in the render target",
+                self.transform_stack.len()
+            );
+        }
+
+        if !self.clip_stack.is_empty() {
+            log::warn!(
+                "{} clip(s) were pushed but not popped in the render target",
+                self.clip_stack.len()
+            );
+        }
+
+        self.render_target = saved.render_target;
+        self.current_transform = saved.current_transform;
+        self.transform_stack = saved.transform_stack;
+        self.clip_stack = saved.clip_stack;
+        self.stencil_depth = saved.stencil_depth;
+
+        self.apply_render_target();
+        self.apply_stencil_depth();
+        self.apply_clip(saved.clip);
+    }
+
+    /// Resets the paint, shader, blend mode, virtual resolution, transform
+    /// stack, and clip area to their defaults, so that subsequent items are
+    /// drawn in pixels, unaffected by any state left active by the caller.
+    /// The previous state is restored by `restore_drawing_state()`.
+    pub(crate) fn reset_drawing_state(&mut self) -> SavedDrawingState
+    {
+        self.flush_render_queue();
+
+        let saved = SavedDrawingState {
+            paint_gradient: self.current_paint_gradient.take(),
+            shader: self.current_shader.take(),
+            blend_mode: std::mem::replace(
+                &mut self.current_blend_mode,
+                BlendMode::Normal
+            ),
+            virtual_resolution: self.virtual_resolution.take(),
+            current_transform: self.current_transform,
+            transform_stack: std::mem::take(&mut self.transform_stack),
+            clip: self.current_clip.clone(),
+            clip_stack: std::mem::take(&mut self.clip_stack),
+            stencil_depth: std::mem::replace(&mut self.stencil_depth, 0)
+        };
+
+        self.apply_clip(None);
+        self.apply_stencil_depth();
+        self.reset_transform();
+
+        saved
+    }
+
+    /// Restores the state which was active before the matching call to
+    /// `reset_drawing_state()`.
+    pub(crate) fn restore_drawing_state(&mut self, saved: SavedDrawingState)
+    {
+        self.flush_render_queue();
+
+        self.current_paint_gradient = saved.paint_gradient;
+        self.current_shader = saved.shader;
+        self.current_blend_mode = saved.blend_mode;
+        self.virtual_resolution = saved.virtual_resolution;
+        self.current_transform = saved.current_transform;
+        self.transform_stack = saved.transform_stack;
+        self.clip_stack = saved.clip_stack;
+        self.stencil_depth = saved.stencil_depth;
+
+        self.apply_stencil_depth();
+        self.apply_clip(saved.clip);
+    }
+
+    /// Sets the logical coordinate space used for subsequent drawing. This
+    /// resets the transform stack.
+    pub(crate) fn set_virtual_resolution(
+        &mut self,
+        virtual_resolution: Option<VirtualResolution>
+    )
+    {
+        self.virtual_resolution = virtual_resolution;
+        self.reset_transform();
+    }
+
+    #[inline]
+    pub(crate) fn virtual_resolution(&self) -> Option<&VirtualResolution>
+    {
+        self.virtual_resolution.as_ref()
+    }
+
+    /// Returns the size of the area being drawn into, in the coordinates used
+    /// by drawing operations before the current transform is applied.
+    pub(crate) fn drawable_size(&self) -> Vec2
+    {
+        match (&self.render_target, &self.virtual_resolution) {
+            (Some(target), _) => target.size.into_f32(),
+            (None, Some(virtual_resolution)) => virtual_resolution.logical_size(),
+            (None, None) => self.viewport_size_pixels.into_f32()
+        }
+    }
+
+    #[inline]
+    pub(crate) fn viewport_size_pixels(&self) -> UVec2
+    {
+        self.viewport_size_pixels
+    }
+
+    /// The transform at the bottom of the transform stack. Render targets are
+    /// always drawn in pixels, regardless of the virtual resolution.
+    fn base_transform(&self) -> Transform
+    {
+        match (&self.render_target, &self.virtual_resolution) {
+            (None, Some(resolution)) => {
+                resolution.transform_for_window_size(self.viewport_size_pixels)
+            }
+            _ => Transform::IDENTITY
+        }
+    }
+
+    fn reset_transform(&mut self)
     {
-        self.uniforms
-            .set_viewport_size_pixels(&self.context, viewport_size_pixels);
+        self.transform_stack.clear();
+        self.current_transform = self.base_transform();
+    }
+
+    fn draw_letterbox_bars(&mut self)
+    {
+        let resolution = match &self.virtual_resolution {
+            None => return,
+            Some(resolution) => resolution
+        };
+
+        let color = resolution.letterbox_color();
+        let window = self.viewport_size_pixels.into_f32();
+        let inner = resolution.viewport_for_window_size(self.viewport_size_pixels);
+
+        let bars = [
+            Rectangle::from_tuples((0.0, 0.0), (window.x, inner.top())),
+            Rectangle::from_tuples((0.0, inner.bottom()), (window.x, window.y)),
+            Rectangle::from_tuples((0.0, inner.top()), (inner.left(), inner.bottom())),
+            Rectangle::from_tuples(
+                (inner.right(), inner.top()),
+                (window.x, inner.bottom())
+            )
+        ];
+
+        // The bars are drawn in window coordinates, over the top of anything
+        // which strayed outside the logical area. The clip area is restored
+        // afterwards, so that drawing the bars leaves it unchanged.
+        let clip = self.current_clip.clone();
+
+        if clip.is_some() {
+            self.apply_clip(None);
+        }
+
+        self.current_paint_gradient = None;
+        self.current_transform = Transform::IDENTITY;
+
+        for bar in bars
+            .iter()
+            .filter(|bar| bar.width() > 0.0 && bar.height() > 0.0)
+        {
+            let (tl, tr) = (*bar.top_left(), bar.top_right());
+            let (br, bl) = (*bar.bottom_right(), bar.bottom_left());
 
-        self.context.set_viewport_size(viewport_size_pixels);
+            self.draw_triangle_three_color([tl, tr, br], [color, color, color]);
+            self.draw_triangle_three_color([br, bl, tl], [color, color, color]);
+        }
+
+        if clip.is_some() {
+            self.apply_clip(clip);
+        }
     }
 
     pub fn finish_frame(&mut self)
     {
         self.flush_render_queue();
+
+        if !self.clip_stack.is_empty() {
+            log::warn!(
+                "{} clip(s) were pushed but not popped during the frame",
+                self.clip_stack.len()
+            );
+
+            self.clip_stack.clear();
+            self.stencil_depth = 0;
+            self.apply_stencil_depth();
+        }
+
+        self.current_blend_mode = BlendMode::Normal;
+        self.current_layer = 0;
+        self.draw_letterbox_bars();
+        self.flush_render_queue();
         self.glyph_cache.on_new_frame_start();
+
+        let gl_statistics = self.context.take_statistics();
+        let glyph_statistics = self.glyph_cache.take_statistics();
+
+        self.last_frame_statistics = FrameStatistics {
+            draw_calls: gl_statistics.draw_calls,
+            vertices_uploaded: gl_statistics.vertices_uploaded,
+            texture_binds: gl_statistics.texture_binds,
+            glyph_cache_hits: glyph_statistics.hits,
+            glyph_cache_misses: glyph_statistics.misses,
+            glyph_textures_allocated: glyph_statistics.textures_allocated,
+            flush_time_secs: std::mem::take(&mut self.flush_time_secs)
+        };
+
+        if !self.transform_stack.is_empty() {
+            log::warn!(
+                "{} transform(s) were pushed but not popped during the frame",
+                self.transform_stack.len()
+            );
+        }
+
+        self.reset_transform();
+    }
+
+    /// Combines the specified transform with the current one. Subsequent items
+    /// will be transformed by `transform`, followed by the previously active
+    /// transform.
+    #[inline]
+    pub(crate) fn push_transform(&mut self, transform: &Transform)
+    {
+        self.record(|_| DrawCommand::PushTransform(*transform));
+        self.transform_stack.push(self.current_transform);
+        self.current_transform = transform.then(&self.current_transform);
+    }
+
+    /// Restores the transform which was active before the most recent call to
+    /// `push_transform()`.
+    #[inline]
+    pub(crate) fn pop_transform(&mut self)
+    {
+        self.record(|_| DrawCommand::PopTransform);
+        match self.transform_stack.pop() {
+            Some(transform) => self.current_transform = transform,
+            None => log::warn!("pop_transform() called with no transform pushed")
+        }
+    }
+
+    #[inline]
+    pub(crate) fn current_transform(&self) -> Transform
+    {
+        self.current_transform
     }
 
     fn flush_render_queue(&mut self)
@@ -576,12 +1737,23 @@ impl Renderer2D
             return;
         }
 
+        let start_secs = self.stopwatch.as_ref().map(Stopwatch::secs_elapsed);
+
+        self.draw_render_queue();
+
+        if let (Some(stopwatch), Some(start_secs)) = (&self.stopwatch, start_secs) {
+            self.flush_time_secs += stopwatch.secs_elapsed() - start_secs;
+        }
+    }
+
+    fn draw_render_queue(&mut self)
+    {
         self.attribute_buffers.clear();
 
         let mut has_text = false;
 
-        for item in &self.render_queue {
-            match item {
+        for entry in &self.render_queue {
+            match &entry.item {
                 RenderQueueItem::FormattedTextBlock {
                     block, position, ..
                 } => {
@@ -616,27 +1788,102 @@ impl Renderer2D
             }
         }
 
+        if self.batching_mode == BatchingMode::Grouped {
+            let grouped_batches =
+                Renderer2D::group_render_queue(&self.render_queue, &self.glyph_cache);
+
+            self.render_queue.clear();
+
+            for grouped in grouped_batches {
+                self.batch = grouped.state;
+
+                for action in &grouped.actions {
+                    action.append_to_attribute_buffers(&mut self.attribute_buffers);
+                }
+
+                Renderer2D::draw_buffers(
+                    &self.context,
+                    &self.program,
+                    self.projection,
+                    &mut self.attribute_buffers,
+                    &mut self.batch
+                );
+            }
+
+            return;
+        }
+
         {
-            let current_texture = &mut self.current_texture;
+            let batch = &mut self.batch;
             let context = &self.context;
             let program = &self.program;
+            let projection = self.projection;
             let attribute_buffers = &mut self.attribute_buffers;
 
-            for item in &self.render_queue {
-                item.generate_actions(&self.glyph_cache, &mut |action| {
-                    if !action.update_current_texture_if_empty(current_texture) {
-                        Renderer2D::draw_buffers(
-                            context,
-                            program,
-                            attribute_buffers,
-                            current_texture
-                        );
+            for entry in &self.render_queue {
+                let gradient = &entry.gradient;
+                let transform = &entry.transform;
+
+                if entry.shader != batch.shader || entry.blend_mode != batch.blend_mode {
+                    Renderer2D::draw_buffers(
+                        context,
+                        program,
+                        projection,
+                        attribute_buffers,
+                        batch
+                    );
+
+                    batch.shader.clone_from(&entry.shader);
+                    batch.blend_mode = entry.blend_mode;
+                }
 
-                        current_texture.clone_from(&action.texture);
-                    }
+                entry
+                    .item
+                    .generate_actions(&self.glyph_cache, &mut |mut action| {
+                        // Gradients are in the same coordinate space as the
+                        // shape, so they're applied before the transform
+                        if let Some(gradient) = gradient {
+                            action.apply_gradient(gradient);
+
+                            let gradient_changed = match &batch.gradient {
+                                None => false,
+                                Some(current) => **current != **gradient
+                            };
+
+                            if gradient_changed {
+                                Renderer2D::draw_buffers(
+                                    context,
+                                    program,
+                                    projection,
+                                    attribute_buffers,
+                                    batch
+                                );
+                            }
+
+                            if batch.gradient.is_none() {
+                                batch.gradient = Some(gradient.clone());
+                            }
+                        }
 
-                    action.append_to_attribute_buffers(attribute_buffers);
-                });
+                        if let Some(transform) = transform {
+                            action.apply_transform(transform);
+                        }
+
+                        if !action.update_current_texture_if_empty(&mut batch.texture) {
+                            Renderer2D::draw_buffers(
+                                context,
+                                program,
+                                projection,
+                                attribute_buffers,
+                                batch
+                            );
+
+                            batch.texture.clone_from(&action.texture);
+                            batch.gradient.clone_from(gradient);
+                        }
+
+                        action.append_to_attribute_buffers(attribute_buffers);
+                    });
             }
         }
 
@@ -645,16 +1892,77 @@ impl Renderer2D
         Renderer2D::draw_buffers(
             &self.context,
             &self.program,
+            self.projection,
             &mut self.attribute_buffers,
-            &mut self.current_texture
+            &mut self.batch
         );
     }
 
+    /// Splits the triangles of the queued items into batches which can each be
+    /// drawn with a single draw call. Items are sorted by layer, and a
+    /// triangle is only moved ahead of the triangles it doesn't overlap.
+    fn group_render_queue(
+        render_queue: &[RenderQueueEntry],
+        glyph_cache: &GlyphCache
+    ) -> Vec<GroupedBatch>
+    {
+        let mut order: Vec<&RenderQueueEntry> = render_queue.iter().collect();
+        order.sort_by_key(|entry| entry.layer);
+
+        let mut batches: Vec<GroupedBatch> = Vec::new();
+
+        for entry in order {
+            entry.item.generate_actions(glyph_cache, &mut |mut action| {
+                if let Some(gradient) = &entry.gradient {
+                    action.apply_gradient(gradient);
+                }
+
+                if let Some(transform) = &entry.transform {
+                    action.apply_transform(transform);
+                }
+
+                let bounds = action.bounds();
+                let mut target = None;
+
+                for (index, batch) in batches
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .take(GroupedBatch::MAX_LOOKBEHIND)
+                {
+                    if batch.layer != entry.layer {
+                        break;
+                    }
+
+                    if batch.accepts(entry, &action) {
+                        target = Some(index);
+                        break;
+                    }
+
+                    if batch.overlaps(&bounds) {
+                        break;
+                    }
+                }
+
+                match target {
+                    Some(index) => batches[index].add(entry, action),
+                    None => batches.push(GroupedBatch::new(entry, action))
+                }
+            });
+        }
+
+        batches
+    }
+
+    /// Draws the vertices in `attribute_buffers`. The texture and gradient of
+    /// the batch are cleared afterwards, but the shader and blend mode are
+    /// retained.
     fn draw_buffers(
         context: &GLContextManager,
-        program: &Rc<GLProgram>,
+        builtin_program: &ShaderProgram,
+        projection: Projection,
         attribute_buffers: &mut AttributeBuffers,
-        current_texture: &mut Option<GLTexture>
+        batch: &mut BatchState
     )
     {
         let vertex_count = attribute_buffers.get_vertex_count();
@@ -663,19 +1971,43 @@ impl Renderer2D
             return;
         }
 
-        context.use_program(program);
+        let program = match &batch.shader {
+            None => builtin_program,
+            Some(shader) => &shader.program
+        };
 
-        attribute_buffers.upload_and_clear(context);
+        let current_texture = batch.texture.take();
 
-        let current_texture = current_texture.take();
+        let texture_premultiplied = matches!(
+            &current_texture,
+            Some(texture) if texture.has_premultiplied_alpha()
+        );
 
-        match &current_texture {
-            None => context.unbind_texture(),
-            Some(texture) => context.bind_texture(texture)
+        program.activate(
+            context,
+            projection,
+            Model::IDENTITY,
+            batch.blend_mode,
+            texture_premultiplied
+        );
+
+        if let Some(shader) = &batch.shader {
+            program.set_custom_uniforms(context, &shader.uniforms);
+        }
+
+        attribute_buffers.upload_and_clear(context);
+
+        match &current_texture {
+            None => context.unbind_texture(),
+            Some(texture) => context.bind_texture(texture)
+        }
+
+        if let Some(gradient) = batch.gradient.take() {
+            program.uniforms.set_gradient(context, &gradient);
         }
 
         context.draw_triangles(
-            GLBlendEnabled::Enabled(GLBlendMode::OneMinusSrcAlpha),
+            GLBlendEnabled::new(batch.blend_mode, texture_premultiplied),
             vertex_count
         );
     }
@@ -683,57 +2015,202 @@ impl Renderer2D
     pub(crate) fn create_image_from_raw_pixels<S: Into<UVec2>>(
         &self,
         data_type: ImageDataType,
-        smoothing_mode: ImageSmoothingMode,
+        options: ImageOptions,
         size: S,
         data: &[u8]
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
         let size = size.into();
 
-        let pixel_bytes = match data_type {
-            ImageDataType::RGB => 3,
-            ImageDataType::RGBA => 4
-        };
+        Renderer2D::check_image_data_len(data_type, size, data)?;
 
-        {
-            let expected_bytes = pixel_bytes * size.x as usize * size.y as usize;
-
-            if expected_bytes != data.len() {
-                return Err(ErrorMessage::msg(format!(
-                    "Expecting {} bytes ({}x{}x{}), got {}",
-                    expected_bytes,
-                    size.x,
-                    size.y,
-                    pixel_bytes,
-                    data.len()
-                )));
+        let gl_format = data_type.into();
+
+        let texture = self
+            .context
+            .new_texture()
+            .context("Failed to create GPU texture")?;
+
+        texture
+            .set_image_data(
+                &self.context,
+                gl_format,
+                Renderer2D::gl_smoothing(options),
+                Renderer2D::gl_wrap(options.wrap_mode()),
+                &size,
+                Some(data)
+            )
+            .context("Failed to upload image data")?;
+
+        Ok(ImageHandle {
+            size,
+            smoothing_mode: options.smoothing_mode(),
+            wrap_mode: options.wrap_mode(),
+            mipmaps: options.mipmaps(),
+            texture
+        })
+    }
+
+    pub(crate) fn update_image_region(
+        &mut self,
+        image: &ImageHandle,
+        data_type: ImageDataType,
+        region: &URect,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        let top_left = region.top_left();
+        let bottom_right = region.bottom_right();
+
+        if top_left.x > bottom_right.x || top_left.y > bottom_right.y {
+            return Err(ErrorMessage::msg(format!("Invalid region {:?}", region)));
+        }
+
+        if bottom_right.x > image.size.x || bottom_right.y > image.size.y {
+            return Err(ErrorMessage::msg(format!(
+                "Region {:?} is outside the {}x{} image",
+                region, image.size.x, image.size.y
+            )));
+        }
+
+        let size = region.size();
+
+        Renderer2D::check_image_data_len(data_type, size, data)?;
+
+        if size.x == 0 || size.y == 0 {
+            return Ok(());
+        }
+
+        // Items already queued must be drawn using the previous contents
+        self.flush_render_queue();
+
+        image
+            .texture
+            .set_sub_image_data(
+                &self.context,
+                data_type.into(),
+                region.top_left(),
+                &size,
+                data
+            )
+            .context("Failed to upload image data")?;
+
+        if image.mipmaps {
+            image.texture.generate_mipmaps(&self.context);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_image_data_len(
+        data_type: ImageDataType,
+        size: UVec2,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        let pixel_bytes = data_type.bytes_per_pixel();
+
+        let expected_bytes = pixel_bytes * size.x as usize * size.y as usize;
+
+        if expected_bytes != data.len() {
+            return Err(ErrorMessage::msg(format!(
+                "Expecting {} bytes ({}x{}x{}), got {}",
+                expected_bytes,
+                size.x,
+                size.y,
+                pixel_bytes,
+                data.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn gl_smoothing(options: ImageOptions) -> GLTextureSmoothing
+    {
+        match (options.smoothing_mode(), options.mipmaps()) {
+            (ImageSmoothingMode::NearestNeighbor, false) => {
+                GLTextureSmoothing::NearestNeighbour
+            }
+            (ImageSmoothingMode::NearestNeighbor, true) => {
+                GLTextureSmoothing::NearestNeighbourMipmap
             }
+            (ImageSmoothingMode::Linear, false) => GLTextureSmoothing::Linear,
+            (ImageSmoothingMode::Linear, true) => GLTextureSmoothing::LinearMipmap,
+            (ImageSmoothingMode::Trilinear, _) => GLTextureSmoothing::Trilinear
         }
+    }
 
-        let gl_format = data_type.into();
+    fn gl_wrap(wrap_mode: ImageWrapMode) -> GLTextureWrap
+    {
+        match wrap_mode {
+            ImageWrapMode::ClampToEdge => GLTextureWrap::ClampToEdge,
+            ImageWrapMode::Repeat => GLTextureWrap::Repeat,
+            ImageWrapMode::MirroredRepeat => GLTextureWrap::MirroredRepeat
+        }
+    }
 
-        let gl_smoothing = match smoothing_mode {
-            ImageSmoothingMode::NearestNeighbor => GLTextureSmoothing::NearestNeighbour,
-            ImageSmoothingMode::Linear => GLTextureSmoothing::Linear
-        };
+    pub(crate) fn create_render_target(
+        &mut self,
+        size: UVec2,
+        options: ImageOptions
+    ) -> Result<RenderTarget, BacktraceError<ErrorMessage>>
+    {
+        if size.x == 0 || size.y == 0 {
+            return Err(ErrorMessage::msg(format!(
+                "Invalid render target size {}x{}",
+                size.x, size.y
+            )));
+        }
 
+        // Drawing translucent colors into a transparent target produces
+        // premultiplied colors
         let texture = self
             .context
             .new_texture()
-            .context("Failed to create GPU texture")?;
+            .context("Failed to create GPU texture")?
+            .with_premultiplied_alpha();
 
         texture
-            .set_image_data(&self.context, gl_format, gl_smoothing, &size, data)
-            .context("Failed to upload image data")?;
+            .set_image_data(
+                &self.context,
+                GLTextureImageFormatU8::RGBA,
+                Renderer2D::gl_smoothing(options),
+                Renderer2D::gl_wrap(options.wrap_mode()),
+                &size,
+                None
+            )
+            .context("Failed to allocate render target texture")?;
+
+        let framebuffer = self
+            .context
+            .new_framebuffer(&texture, &size)
+            .context("Failed to create framebuffer")?;
+
+        let target = RenderTarget::new(
+            ImageHandle {
+                size,
+                smoothing_mode: options.smoothing_mode(),
+                wrap_mode: options.wrap_mode(),
+                mipmaps: options.mipmaps(),
+                texture
+            },
+            framebuffer
+        );
 
-        Ok(ImageHandle { size, texture })
+        // The initial contents of the texture are undefined
+        let saved = self.switch_to_render_target(&target);
+        self.context.clear_screen(Color::TRANSPARENT);
+        self.restore_render_target(saved);
+
+        Ok(target)
     }
 
     #[cfg(any(feature = "image-loading", doc, doctest))]
     pub fn create_image_from_file_path<P: AsRef<Path>>(
         &mut self,
         data_type: Option<ImageFileFormat>,
-        smoothing_mode: ImageSmoothingMode,
+        options: ImageOptions,
         path: P
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
@@ -742,14 +2219,14 @@ impl Renderer2D
             path.as_ref()
         ))?;
 
-        self.create_image_from_file_bytes(data_type, smoothing_mode, BufReader::new(file))
+        self.create_image_from_file_bytes(data_type, options, BufReader::new(file))
     }
 
     #[cfg(any(feature = "image-loading", doc, doctest))]
     pub fn create_image_from_file_bytes<R: Seek + BufRead>(
         &mut self,
         data_type: Option<ImageFileFormat>,
-        smoothing_mode: ImageSmoothingMode,
+        options: ImageOptions,
         file_bytes: R
     ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
     {
@@ -761,20 +2238,7 @@ impl Renderer2D
                     .with_guessed_format()
                     .context("Could not guess file format")?
             }
-            Some(format) => reader.set_format(match format {
-                ImageFileFormat::PNG => image::ImageFormat::Png,
-                ImageFileFormat::JPEG => image::ImageFormat::Jpeg,
-                ImageFileFormat::GIF => image::ImageFormat::Gif,
-                ImageFileFormat::BMP => image::ImageFormat::Bmp,
-                ImageFileFormat::ICO => image::ImageFormat::Ico,
-                ImageFileFormat::TIFF => image::ImageFormat::Tiff,
-                ImageFileFormat::WebP => image::ImageFormat::WebP,
-                ImageFileFormat::AVIF => image::ImageFormat::Avif,
-                ImageFileFormat::PNM => image::ImageFormat::Pnm,
-                ImageFileFormat::DDS => image::ImageFormat::Dds,
-                ImageFileFormat::TGA => image::ImageFormat::Tga,
-                ImageFileFormat::Farbfeld => image::ImageFormat::Farbfeld
-            })
+            Some(format) => reader.set_format(format.to_image_format())
         }
 
         let image = reader.decode().context("Failed to parse image data")?;
@@ -785,7 +2249,7 @@ impl Renderer2D
 
         self.create_image_from_raw_pixels(
             ImageDataType::RGBA,
-            smoothing_mode,
+            options,
             dimensions,
             bytes_rgba8.as_slice()
         )
@@ -794,6 +2258,12 @@ impl Renderer2D
     #[inline]
     pub(crate) fn clear_screen(&mut self, color: Color)
     {
+        if self.ignore_while_building_mesh("clear_screen()") {
+            return;
+        }
+
+        self.record(|_| DrawCommand::ClearScreen(color));
+
         if color.a() < 1.0 {
             self.flush_render_queue();
         } else {
@@ -803,145 +2273,1664 @@ impl Renderer2D
         self.context.clear_screen(color);
     }
 
-    #[inline]
-    fn add_to_render_queue(&mut self, item: RenderQueueItem)
+    /// Starts recording subsequent drawing operations into a new [DrawList],
+    /// which is returned by the matching call to `end_recording()`.
+    /// Recordings may be nested, in which case each operation is added to
+    /// every active recording.
+    pub(crate) fn begin_recording(&mut self)
     {
-        self.render_queue.push(item);
+        let mut list = DrawList::new();
 
-        if self.render_queue.len() > 100000 {
-            self.flush_render_queue();
+        // The list starts with the current state, so that it's replayed with
+        // the same appearance.
+        list.push(DrawCommand::SetBlendMode(self.current_blend_mode));
+
+        if let Some(shader) = &self.current_shader {
+            let shader = Some(list.shader_index(shader));
+            list.push(DrawCommand::SetShader(shader));
+        }
+
+        if self.current_paint_gradient.is_some() {
+            list.push(DrawCommand::SetGradient(
+                self.current_paint_gradient.clone()
+            ));
         }
+
+        self.recordings.push(list);
+    }
+
+    pub(crate) fn end_recording(&mut self) -> DrawList
+    {
+        let mut list = self.recordings.pop().unwrap_or_default();
+        list.read_image_pixels(self);
+        list
     }
 
     #[inline]
-    pub(crate) fn draw_polygon<V: Into<Vec2>>(
-        &mut self,
-        polygon: &Polygon,
-        offset: V,
-        color: Color
-    )
+    fn record<F: FnMut(&mut DrawList) -> DrawCommand>(&mut self, mut command: F)
     {
-        let color = [color; 3];
-        let offset = offset.into();
+        for list in self.recordings.iter_mut() {
+            let command = command(list);
+            list.push(command);
+        }
+    }
 
-        for triangle in polygon.triangles.iter() {
-            let triangle = triangle.map(|vertex| vertex + offset);
+    #[inline]
+    fn add_to_render_queue(&mut self, item: RenderQueueItem)
+    {
+        let transform = if self.current_transform.is_identity() {
+            None
+        } else {
+            Some(self.current_transform)
+        };
 
-            self.draw_triangle_three_color(triangle, color);
+        let entry = RenderQueueEntry {
+            item,
+            gradient: self.current_paint_gradient.clone(),
+            shader: self.current_shader.clone(),
+            blend_mode: self.current_blend_mode,
+            transform,
+            layer: self.current_layer
+        };
+
+        if let Some(builder) = self.mesh_builders.last_mut() {
+            builder.entries.push(entry);
+            return;
+        }
+
+        self.render_queue.push(entry);
+
+        if self.render_queue.len() > 100000 {
+            self.flush_render_queue();
         }
     }
 
-    #[inline]
-    pub(crate) fn draw_triangle_three_color(
-        &mut self,
-        vertex_positions_clockwise: [Vec2; 3],
-        vertex_colors_clockwise: [Color; 3]
-    )
+    /// Returns true if a static mesh is being built, in which case the
+    /// specified operation can't take effect, and a warning is logged.
+    fn ignore_while_building_mesh(&self, operation: &str) -> bool
     {
-        self.add_to_render_queue(RenderQueueItem::TriangleColored {
-            vertex_positions_clockwise,
-            vertex_colors_clockwise
-        })
+        if self.mesh_builders.is_empty() {
+            return false;
+        }
+
+        log::warn!(
+            "{} can't be used while building a static mesh, ignoring",
+            operation
+        );
+        true
     }
 
     #[inline]
-    pub(crate) fn draw_triangle_image_tinted(
-        &mut self,
-        vertex_positions_clockwise: [Vec2; 3],
-        vertex_colors_clockwise: [Color; 3],
-        vertex_texture_coords_clockwise: [Vec2; 3],
-        image: &ImageHandle
-    )
+    pub(crate) fn is_building_mesh(&self) -> bool
     {
-        self.add_to_render_queue(RenderQueueItem::TriangleTextured {
-            vertex_positions_clockwise,
-            vertex_colors_clockwise,
-            vertex_texture_coords_clockwise,
-            texture: image.texture.clone()
-        })
+        !self.mesh_builders.is_empty()
     }
 
-    #[inline]
-    pub(crate) fn draw_text<V: Into<Vec2>>(
-        &mut self,
-        position: V,
-        color: Color,
-        text: &FormattedTextBlock
-    )
+    /// Starts collecting subsequent shapes and images into a static mesh,
+    /// instead of drawing them. The mesh is created by the matching call to
+    /// `end_mesh()`. While the mesh is being built, the transform stack
+    /// starts out empty.
+    pub(crate) fn begin_mesh(&mut self)
     {
-        self.add_to_render_queue(RenderQueueItem::FormattedTextBlock {
-            position: position.into(),
-            color,
-            block: text.clone()
-        })
+        let saved_transform =
+            std::mem::replace(&mut self.current_transform, Transform::IDENTITY);
+
+        self.mesh_builders.push(MeshBuilder {
+            entries: Vec::new(),
+            saved_transform,
+            saved_transform_stack: std::mem::take(&mut self.transform_stack)
+        });
     }
 
-    #[inline]
-    pub(crate) fn draw_text_cropped<V: Into<Vec2>>(
-        &mut self,
-        position: V,
-        crop_window: Rect,
-        color: Color,
-        text: &FormattedTextBlock
-    )
+    pub(crate) fn end_mesh(&mut self)
+        -> Result<MeshBuffers, BacktraceError<ErrorMessage>>
     {
-        let position = position.into();
+        let builder = self
+            .mesh_builders
+            .pop()
+            .ok_or_else(|| ErrorMessage::msg("No mesh is being built"))?;
 
-        for line in text.iter_lines() {
-            for glyph in line.iter_glyphs() {
-                if let Some(glyph_outline) = glyph.pixel_bounding_box() {
-                    let glyph_outline = glyph_outline.with_offset(position);
-                    if glyph_outline.intersect(&crop_window).is_some() {
-                        self.add_to_render_queue(RenderQueueItem::FormattedTextGlyph {
-                            position,
-                            color,
-                            glyph: glyph.clone(),
-                            crop_window: crop_window.clone()
-                        })
-                    }
+        self.current_transform = builder.saved_transform;
+        self.transform_stack = builder.saved_transform_stack;
+
+        let mut attribute_buffers =
+            AttributeBuffers::new(&self.context, &self.program.program)?;
+
+        let mut segments: Vec<MeshSegment> = Vec::new();
+
+        for entry in builder.entries.iter() {
+            match &entry.item {
+                RenderQueueItem::FormattedTextBlock { .. }
+                | RenderQueueItem::FormattedTextGlyph { .. } => {
+                    log::warn!("Text can't be added to a static mesh, ignoring");
+                    continue;
                 }
+                RenderQueueItem::CircleSectionColored { .. }
+                | RenderQueueItem::TriangleColored { .. }
+                | RenderQueueItem::TriangleTextured { .. } => {}
             }
+
+            entry
+                .item
+                .generate_actions(&self.glyph_cache, &mut |mut action| {
+                    if let Some(gradient) = &entry.gradient {
+                        action.apply_gradient(gradient);
+                    }
+
+                    if let Some(transform) = &entry.transform {
+                        action.apply_transform(transform);
+                    }
+
+                    let added = match segments.last_mut() {
+                        None => false,
+                        Some(segment) => segment.try_add(&action.texture, &entry.gradient)
+                    };
+
+                    if !added {
+                        segments.push(MeshSegment {
+                            first_vertex: attribute_buffers.get_vertex_count(),
+                            vertex_count: 0,
+                            texture: action.texture.clone(),
+                            gradient: entry.gradient.clone()
+                        });
+                    }
+
+                    action.append_to_attribute_buffers(&mut attribute_buffers);
+                    segments.last_mut().unwrap().vertex_count += 3;
+                });
         }
+
+        let vertex_count = attribute_buffers.get_vertex_count();
+        attribute_buffers.upload_static_and_clear(&self.context);
+
+        Ok(MeshBuffers {
+            attribute_buffers,
+            segments,
+            vertex_count
+        })
     }
 
-    #[inline]
-    pub(crate) fn draw_circle_section(
+    /// Draws a static mesh, with the specified transform applied before the
+    /// current transform, and its colors multiplied by `tint`.
+    pub(crate) fn draw_mesh(
         &mut self,
-        vertex_positions_clockwise: [Vec2; 3],
-        vertex_colors_clockwise: [Color; 3],
-        vertex_normalized_circle_coords_clockwise: [Vec2; 3]
+        mesh: &MeshBuffers,
+        transform: &Transform,
+        tint: Color
     )
     {
-        self.add_to_render_queue(RenderQueueItem::CircleSectionColored {
-            vertex_positions_clockwise,
-            vertex_colors_clockwise,
-            vertex_normalized_circle_coords_clockwise
-        })
-    }
+        if !self.mesh_builders.is_empty() {
+            log::warn!("Static meshes can't be added to another static mesh, ignoring");
+            return;
+        }
 
-    #[inline]
-    pub(crate) fn set_clip(&mut self, rect: Option<Rectangle<i32>>)
-    {
-        // If we change the clip area, we need to draw everything in a queue
-        // through the current clip before setting new one.
         self.flush_render_queue();
-        match rect {
-            None => self.context.set_enable_scissor(false),
-            Some(rect) => {
-                self.context.set_enable_scissor(true);
-                self.context.set_clip(
-                    rect.top_left().x,
-                    rect.top_left().y,
-                    rect.width(),
-                    rect.height()
-                )
+
+        let program = match &self.current_shader {
+            None => &self.program,
+            Some(shader) => &shader.program
+        };
+
+        let model = Model {
+            transform: transform.then(&self.current_transform),
+            tint
+        };
+
+        program.activate(
+            &self.context,
+            self.projection,
+            model,
+            self.current_blend_mode,
+            false
+        );
+
+        if let Some(shader) = &self.current_shader {
+            program.set_custom_uniforms(&self.context, &shader.uniforms);
+        }
+
+        mesh.attribute_buffers.bind(&self.context);
+
+        for segment in mesh.segments.iter() {
+            match &segment.texture {
+                None => self.context.unbind_texture(),
+                Some(texture) => self.context.bind_texture(texture)
+            }
+
+            let texture_premultiplied = matches!(
+                &segment.texture,
+                Some(texture) if texture.has_premultiplied_alpha()
+            );
+
+            program.set_blend_mode(
+                &self.context,
+                self.current_blend_mode,
+                texture_premultiplied
+            );
+
+            if let Some(gradient) = &segment.gradient {
+                program.uniforms.set_gradient(&self.context, gradient);
             }
+
+            self.context.draw_triangles_from(
+                GLBlendEnabled::new(self.current_blend_mode, texture_premultiplied),
+                segment.first_vertex,
+                segment.vertex_count
+            );
         }
     }
 
-    pub(crate) fn capture(&mut self, format: ImageDataType) -> RawBitmapData
+    /// Draws the sprites in order of increasing depth, using interleaved
+    /// vertices and an index buffer, with one draw call for every
+    /// `MAX_SPRITES_PER_DRAW` sprites.
+    pub(crate) fn draw_sprites(&mut self, image: &ImageHandle, sprites: &[Sprite])
     {
+        if sprites.is_empty() {
+            return;
+        }
+
+        let is_sorted = sprites
+            .windows(2)
+            .all(|pair| pair[0].depth() <= pair[1].depth());
+
+        let mut sorted: Vec<&Sprite> = sprites.iter().collect();
+
+        if !is_sorted {
+            sorted.sort_by(|a, b| a.depth().total_cmp(&b.depth()));
+        }
+
+        let image_size = image.size().into_f32();
+
+        // Recordings and meshes need the individual triangles
+        if !self.recordings.is_empty() || !self.mesh_builders.is_empty() {
+            for sprite in sorted {
+                let [tl, tr, br, bl] = sprite.corners();
+                let [tl_tex, tr_tex, br_tex, bl_tex] = sprite.texture_coords(image_size);
+                let colors = [sprite.tint(); 3];
+
+                self.draw_triangle_image_tinted(
+                    [tl, tr, br],
+                    colors,
+                    [tl_tex, tr_tex, br_tex],
+                    image
+                );
+                self.draw_triangle_image_tinted(
+                    [br, bl, tl],
+                    colors,
+                    [br_tex, bl_tex, tl_tex],
+                    image
+                );
+            }
+
+            return;
+        }
+
         self.flush_render_queue();
-        self.context.capture(format)
+
+        if self.sprite_buffers.is_none() {
+            match SpriteBuffers::new(&self.context, &self.program.program) {
+                Ok(buffers) => self.sprite_buffers = Some(buffers),
+                Err(err) => {
+                    log::error!("Failed to create sprite buffers: {:?}", err);
+                    return;
+                }
+            }
+        }
+
+        let buffers = self.sprite_buffers.as_mut().unwrap();
+
+        let program = match &self.current_shader {
+            None => &self.program,
+            Some(shader) => &shader.program
+        };
+
+        let model = Model {
+            transform: self.current_transform,
+            tint: Color::WHITE
+        };
+
+        let texture_premultiplied = image.texture.has_premultiplied_alpha();
+
+        program.activate(
+            &self.context,
+            self.projection,
+            model,
+            self.current_blend_mode,
+            texture_premultiplied
+        );
+
+        if let Some(shader) = &self.current_shader {
+            program.set_custom_uniforms(&self.context, &shader.uniforms);
+        }
+
+        self.context.bind_texture(&image.texture);
+
+        for chunk in sorted.chunks(SpriteBuffers::MAX_SPRITES_PER_DRAW) {
+            buffers.vertex_data.clear();
+            buffers
+                .vertex_data
+                .reserve(chunk.len() * 4 * SpriteBuffers::VERTEX_SIZE);
+
+            for sprite in chunk {
+                buffers.append(sprite, image_size);
+            }
+
+            buffers
+                .vertices
+                .set_data(&self.context, &buffers.vertex_data);
+
+            self.context.count_vertices_uploaded(chunk.len() * 4);
+
+            self.context.draw_indexed_triangles(
+                GLBlendEnabled::new(self.current_blend_mode, texture_premultiplied),
+                &buffers.indices,
+                chunk.len() * 6
+            );
+        }
+    }
+
+    /// Sets the custom shader used to draw subsequent items, or `None` to use
+    /// the built-in shader. Returns the previous shader.
+    #[inline]
+    pub(crate) fn set_custom_shader(
+        &mut self,
+        shader: Option<Rc<CustomShader>>
+    ) -> Option<Rc<CustomShader>>
+    {
+        self.record(|list| {
+            DrawCommand::SetShader(
+                shader.as_ref().map(|shader| list.shader_index(shader))
+            )
+        });
+
+        std::mem::replace(&mut self.current_shader, shader)
+    }
+
+    #[inline]
+    pub(crate) fn custom_shader(&self) -> Option<Rc<CustomShader>>
+    {
+        self.current_shader.clone()
+    }
+
+    /// Sets the blend mode used to draw subsequent items.
+    #[inline]
+    pub(crate) fn set_blend_mode(&mut self, blend_mode: BlendMode)
+    {
+        self.record(|_| DrawCommand::SetBlendMode(blend_mode));
+        self.current_blend_mode = blend_mode;
+    }
+
+    #[inline]
+    pub(crate) fn blend_mode(&self) -> BlendMode
+    {
+        self.current_blend_mode
+    }
+
+    /// Sets the batching mode. Any queued items are drawn first, using the
+    /// previous mode.
+    pub(crate) fn set_batching_mode(&mut self, batching_mode: BatchingMode)
+    {
+        if self.batching_mode != batching_mode {
+            self.flush_render_queue();
+            self.batching_mode = batching_mode;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn batching_mode(&self) -> BatchingMode
+    {
+        self.batching_mode
+    }
+
+    /// Sets the layer of subsequent items.
+    #[inline]
+    pub(crate) fn set_layer(&mut self, layer: i32)
+    {
+        self.record(|_| DrawCommand::SetLayer(layer));
+        self.current_layer = layer;
+    }
+
+    #[inline]
+    pub(crate) fn layer(&self) -> i32
+    {
+        self.current_layer
+    }
+
+    #[inline]
+    pub(crate) fn last_frame_statistics(&self) -> FrameStatistics
+    {
+        self.last_frame_statistics
+    }
+
+    pub(crate) fn create_custom_shader(
+        &self,
+        fragment_shader_src: &str
+    ) -> Result<CustomShader, BacktraceError<ErrorMessage>>
+    {
+        let program = ShaderProgram::new(&self.context, fragment_shader_src, false)
+            .context("Failed to create custom shader")?;
+
+        Renderer2D::check_texture_units(
+            &program.program.get_sampler_uniforms(&self.context),
+            program.max_texture_units
+        )?;
+
+        Ok(CustomShader::new(Rc::new(program), fragment_shader_src))
+    }
+
+    /// Returns an error if the shader's `sampler2D` uniforms need more
+    /// texture units than are available. Unit 0 is always reserved for the
+    /// texture of the item being drawn.
+    fn check_texture_units(
+        samplers: &[(String, usize)],
+        available: usize
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        let required = 1 + samplers
+            .iter()
+            .filter(|(name, _)| name != Renderer2D::UNIFORM_NAME_TEXTURE)
+            .map(|(_, size)| size)
+            .sum::<usize>();
+
+        if required > available {
+            return Err(ErrorMessage::msg(format!(
+                "Custom shader needs {required} texture units, but only {available} are \
+                 available"
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn gl_version(&self) -> GLVersion
+    {
+        self.context.version()
+    }
+
+    /// Sets the gradient used to paint subsequent items, or `None` to paint
+    /// them using only their vertex colors.
+    #[inline]
+    pub(crate) fn set_paint_gradient(&mut self, gradient: Option<Rc<Gradient>>)
+    {
+        self.record(|_| DrawCommand::SetGradient(gradient.clone()));
+        self.current_paint_gradient = gradient;
+    }
+
+    #[inline]
+    pub(crate) fn paint_gradient(&self) -> Option<Rc<Gradient>>
+    {
+        self.current_paint_gradient.clone()
+    }
+
+    #[inline]
+    pub(crate) fn draw_polygon<V: Into<Vec2>>(
+        &mut self,
+        polygon: &Polygon,
+        offset: V,
+        color: Color
+    )
+    {
+        let color = [color; 3];
+        let offset = offset.into();
+
+        for triangle in polygon.triangles.iter() {
+            let triangle = triangle.map(|vertex| vertex + offset);
+
+            self.draw_triangle_three_color(triangle, color);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn draw_triangle_three_color(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3]
+    )
+    {
+        self.record(|_| DrawCommand::Triangle {
+            vertex_positions_clockwise,
+            vertex_colors_clockwise
+        });
+
+        self.add_to_render_queue(RenderQueueItem::TriangleColored {
+            vertex_positions_clockwise,
+            vertex_colors_clockwise
+        })
+    }
+
+    #[inline]
+    pub(crate) fn draw_triangle_image_tinted(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3],
+        vertex_texture_coords_clockwise: [Vec2; 3],
+        image: &ImageHandle
+    )
+    {
+        self.record(|list| DrawCommand::TriangleImage {
+            vertex_positions_clockwise,
+            vertex_colors_clockwise,
+            vertex_texture_coords_clockwise,
+            image: list.image_index(image)
+        });
+
+        self.add_to_render_queue(RenderQueueItem::TriangleTextured {
+            vertex_positions_clockwise,
+            vertex_colors_clockwise,
+            vertex_texture_coords_clockwise,
+            texture: image.texture.clone()
+        })
+    }
+
+    #[inline]
+    pub(crate) fn draw_text<V: Into<Vec2>>(
+        &mut self,
+        position: V,
+        color: Color,
+        text: &FormattedTextBlock
+    )
+    {
+        let position = position.into();
+
+        self.record(|_| DrawCommand::Text {
+            position,
+            color,
+            text: text.clone()
+        });
+
+        self.add_to_render_queue(RenderQueueItem::FormattedTextBlock {
+            position,
+            color,
+            block: text.clone()
+        })
+    }
+
+    #[inline]
+    pub(crate) fn draw_text_cropped<V: Into<Vec2>>(
+        &mut self,
+        position: V,
+        crop_window: Rect,
+        color: Color,
+        text: &FormattedTextBlock
+    )
+    {
+        let position = position.into();
+
+        self.record(|_| DrawCommand::TextCropped {
+            position,
+            crop_window: crop_window.clone(),
+            color,
+            text: text.clone()
+        });
+
+        for line in text.iter_lines() {
+            for glyph in line.iter_glyphs() {
+                if let Some(glyph_outline) = glyph.pixel_bounding_box() {
+                    let glyph_outline = glyph_outline.with_offset(position);
+                    if glyph_outline.intersect(&crop_window).is_some() {
+                        self.add_to_render_queue(RenderQueueItem::FormattedTextGlyph {
+                            position,
+                            color,
+                            glyph: glyph.clone(),
+                            crop_window: crop_window.clone()
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn draw_circle_section(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3],
+        vertex_normalized_circle_coords_clockwise: [Vec2; 3],
+        normalized_inner_radius: Vec2
+    )
+    {
+        self.record(|_| DrawCommand::CircleSection {
+            vertex_positions_clockwise,
+            vertex_colors_clockwise,
+            vertex_normalized_circle_coords_clockwise,
+            normalized_inner_radius
+        });
+
+        self.add_to_render_queue(RenderQueueItem::CircleSectionColored {
+            vertex_positions_clockwise,
+            vertex_colors_clockwise,
+            vertex_normalized_circle_coords_clockwise,
+            normalized_inner_radius
+        })
+    }
+
+    #[inline]
+    pub(crate) fn set_clip(&mut self, rect: Option<Rectangle<i32>>)
+    {
+        if self.ignore_while_building_mesh("set_clip()") {
+            return;
+        }
+
+        self.record(|_| DrawCommand::SetClip(rect.clone()));
+        self.apply_clip(rect);
+    }
+
+    fn apply_clip(&mut self, rect: Option<Rectangle<i32>>)
+    {
+        // If we change the clip area, we need to draw everything in a queue
+        // through the current clip before setting new one.
+        self.flush_render_queue();
+        self.current_clip = rect.clone();
+        match rect {
+            None => self.context.set_enable_scissor(false),
+            Some(rect) => {
+                self.context.set_enable_scissor(true);
+                self.context.set_clip(
+                    rect.top_left().x,
+                    rect.top_left().y,
+                    rect.width(),
+                    rect.height()
+                )
+            }
+        }
+    }
+
+    /// Intersects the clip area with `rect`, after transforming it by the
+    /// current transform. Returns false without changing anything if the
+    /// transformed rectangle wouldn't be axis-aligned, in which case the
+    /// stencil buffer must be used instead.
+    ///
+    /// While a static mesh is being built, the clip area can't change, so
+    /// this returns true without doing anything.
+    pub(crate) fn push_clip_rectangle(&mut self, rect: &Rect) -> bool
+    {
+        if self.ignore_while_building_mesh("push_clip()") {
+            return true;
+        }
+
+        let [_, skew_y, skew_x, _, _, _] = self.current_transform.to_matrix();
+
+        if skew_x != 0.0 || skew_y != 0.0 {
+            return false;
+        }
+
+        let top_left = self.current_transform.transform_point(*rect.top_left());
+        let bottom_right = self.current_transform.transform_point(*rect.bottom_right());
+
+        // Pixels are inside the clip area if their centers are, so the edges
+        // are rounded to the nearest pixel boundary.
+        let pixels = Rectangle::new(
+            IVec2::new(
+                top_left.x.min(bottom_right.x).round() as i32,
+                top_left.y.min(bottom_right.y).round() as i32
+            ),
+            IVec2::new(
+                top_left.x.max(bottom_right.x).round() as i32,
+                top_left.y.max(bottom_right.y).round() as i32
+            )
+        );
+
+        let clip = match &self.current_clip {
+            None => pixels,
+            Some(current) => current
+                .intersect(&pixels)
+                .unwrap_or_else(|| Rectangle::new(IVec2::ZERO, IVec2::ZERO))
+        };
+
+        self.clip_stack
+            .push(ClipStackEntry::Scissor(self.current_clip.clone()));
+        self.apply_clip(Some(clip));
+
+        self.record(|_| DrawCommand::PushClipRectangle(rect.clone()));
+
+        true
+    }
+
+    /// Prepares to add a shape to the stencil buffer. Items drawn until the
+    /// matching call to `end_stencil_clip()` are not visible, and instead
+    /// narrow the clip area to the pixels they cover.
+    pub(crate) fn begin_stencil_clip(&mut self)
+    {
+        self.record(|_| DrawCommand::BeginStencilClip);
+        self.flush_render_queue();
+
+        // The scissor test would prevent pixels outside the clip rectangle
+        // from being updated, leaving them out of step with the rest of the
+        // stencil buffer.
+        self.context.set_enable_scissor(false);
+
+        // Stencil values are only meaningful while a stencil clip is active,
+        // so the buffer is cleared when the first one is pushed.
+        if self.stencil_depth == 0 {
+            self.context.clear_stencil();
+        }
+
+        self.context
+            .set_stencil_mode(GLStencilMode::Increment(self.stencil_depth));
+    }
+
+    pub(crate) fn end_stencil_clip(&mut self)
+    {
+        self.record(|_| DrawCommand::EndStencilClip);
+        self.flush_render_queue();
+
+        match self.stencil_depth.checked_add(1) {
+            Some(depth) => {
+                self.stencil_depth = depth;
+                self.clip_stack.push(ClipStackEntry::Stencil);
+            }
+            None => {
+                log::error!("Too many nested clips, ignoring push_clip()");
+                self.clip_stack
+                    .push(ClipStackEntry::Scissor(self.current_clip.clone()));
+            }
+        }
+
+        self.apply_stencil_depth();
+        self.context.set_enable_scissor(self.current_clip.is_some());
+    }
+
+    /// Removes the most recently pushed clip.
+    pub(crate) fn pop_clip(&mut self)
+    {
+        if self.ignore_while_building_mesh("pop_clip()") {
+            return;
+        }
+
+        self.record(|_| DrawCommand::PopClip);
+
+        match self.clip_stack.pop() {
+            None => log::warn!("Ignoring pop_clip(): the clip stack is empty"),
+
+            Some(ClipStackEntry::Scissor(previous)) => self.apply_clip(previous),
+
+            Some(ClipStackEntry::Stencil) => {
+                self.flush_render_queue();
+                self.stencil_depth -= 1;
+
+                // Pixels inside the removed shape have a higher stencil value
+                // than the rest of the parent clip area, so they're reduced to
+                // match. This isn't necessary for the outermost clip, since
+                // the buffer is cleared before it's next used.
+                if self.stencil_depth > 0 {
+                    self.context.set_enable_scissor(false);
+                    self.context
+                        .set_stencil_mode(GLStencilMode::ReduceTo(self.stencil_depth));
+
+                    self.draw_viewport_quad();
+                    self.flush_render_queue();
+
+                    self.context.set_enable_scissor(self.current_clip.is_some());
+                }
+
+                self.apply_stencil_depth();
+            }
+        }
+    }
+
+    fn apply_stencil_depth(&self)
+    {
+        self.context.set_stencil_mode(match self.stencil_depth {
+            0 => GLStencilMode::Disabled,
+            depth => GLStencilMode::Test(depth)
+        });
+    }
+
+    /// Queues a quad covering the whole viewport, using the built-in shader
+    /// and ignoring the current transform.
+    fn draw_viewport_quad(&mut self)
+    {
+        let size = self.projection.size.into_f32();
+        let color = [Color::WHITE; 3];
+
+        let (tl, tr) = (Vec2::ZERO, Vec2::new(size.x, 0.0));
+        let (br, bl) = (size, Vec2::new(0.0, size.y));
+
+        for vertex_positions_clockwise in [[tl, tr, br], [br, bl, tl]] {
+            self.render_queue.push(RenderQueueEntry {
+                item: RenderQueueItem::TriangleColored {
+                    vertex_positions_clockwise,
+                    vertex_colors_clockwise: color
+                },
+                gradient: None,
+                shader: None,
+                blend_mode: BlendMode::Normal,
+                transform: None,
+                layer: 0
+            });
+        }
+    }
+
+    pub(crate) fn capture(&mut self, format: ImageDataType) -> RawBitmapData
+    {
+        match &self.render_target {
+            None => {
+                self.flush_render_queue();
+                self.context.capture(format)
+            }
+            Some(target) => {
+                let region = URect::new(UVec2::ZERO, target.size);
+                self.capture_region(&region, format)
+            }
+        }
+    }
+
+    /// Render targets store premultiplied alpha, which is converted back when
+    /// capturing them.
+    pub(crate) fn capture_region(
+        &mut self,
+        region: &URect,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        self.flush_render_queue();
+
+        match self.render_target {
+            None => self.context.capture_region(region, format),
+            Some(_) => self
+                .context
+                .capture_region(region, ImageDataType::RGBA)
+                .unpremultiply_alpha(format)
+        }
+    }
+
+    pub(crate) fn read_image(
+        &mut self,
+        image: &ImageHandle,
+        format: ImageDataType
+    ) -> Result<RawBitmapData, BacktraceError<ErrorMessage>>
+    {
+        if image.size.x == 0 || image.size.y == 0 {
+            return Ok(RawBitmapData::new(vec![], image.size, format));
+        }
+
+        self.flush_render_queue();
+
+        // Images can only be read by attaching them to a framebuffer, as
+        // glGetTexImage is unavailable in GLES and WebGL
+        let framebuffer = match &self.readback_framebuffer {
+            Some(framebuffer) => framebuffer.clone(),
+            None => {
+                let framebuffer = self
+                    .context
+                    .new_readback_framebuffer()
+                    .context("Failed to create framebuffer for reading image")?;
+
+                self.readback_framebuffer = Some(framebuffer.clone());
+                framebuffer
+            }
+        };
+
+        if !image.texture.has_premultiplied_alpha() {
+            return self
+                .context
+                .read_texture(&framebuffer, &image.texture, image.size, format)
+                .context("Failed to read image");
+        }
+
+        Ok(self
+            .context
+            .read_texture(
+                &framebuffer,
+                &image.texture,
+                image.size,
+                ImageDataType::RGBA
+            )
+            .context("Failed to read image")?
+            .unpremultiply_alpha(format))
+    }
+
+    pub(crate) fn read_render_target(
+        &mut self,
+        render_target: &RenderTarget,
+        format: ImageDataType
+    ) -> RawBitmapData
+    {
+        self.flush_render_queue();
+
+        self.context
+            .read_framebuffer(
+                &render_target.framebuffer,
+                *render_target.size(),
+                ImageDataType::RGBA
+            )
+            .unpremultiply_alpha(format)
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use crate::color::Color;
+    use crate::dimen::UVec2;
+    use crate::font::{Font, TextLayout, TextOptions};
+    use crate::image::{
+        ImageDataType,
+        ImageOptions,
+        ImageSmoothingMode,
+        ImageWrapMode,
+        RawBitmapData
+    };
+    use crate::paint::{BlendMode, Gradient, Paint};
+    use crate::renderer2d::Renderer2D;
+    use crate::shape::{Rectangle, URect};
+    use crate::test_utils::{pixel, render, render_frame};
+    use crate::viewport::{ScalingMode, VirtualResolution};
+    use crate::{BatchingMode, GLRenderer, Graphics2D};
+
+    /// Renders the action with the specified batching mode, returning the
+    /// image and the number of draw calls.
+    fn render_with_batching<F: FnOnce(&mut Graphics2D)>(
+        batching_mode: BatchingMode,
+        action: F
+    ) -> (RawBitmapData, usize)
+    {
+        let mut renderer = GLRenderer::new_software((100, 100)).unwrap();
+
+        let (_, image) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.set_batching_mode(batching_mode);
+            action(graphics);
+        });
+
+        (image, renderer.last_frame_statistics().draw_calls())
+    }
+
+    fn draw_interleaved(graphics: &mut Graphics2D)
+    {
+        let font =
+            Font::new(include_bytes!("../assets/fonts/NotoSans-Regular.ttf")).unwrap();
+
+        let label = font.layout_text("Ab", 12.0, TextOptions::new());
+
+        let image = graphics
+            .create_image_from_raw_pixels(
+                ImageDataType::RGB,
+                ImageSmoothingMode::NearestNeighbor,
+                (1, 1),
+                &[0, 0, 255]
+            )
+            .unwrap();
+
+        for i in 0..5 {
+            let y = i as f32 * 20.0;
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((0.0, y), (10.0, y + 10.0)),
+                &image
+            );
+            graphics.draw_text((20.0, y), Color::BLACK, &label);
+            graphics.draw_circle((60.0, y + 5.0), 4.0, Color::RED);
+        }
+
+        // Overlaps the text and images, so must be drawn after them
+        graphics.draw_rectangle(
+            Rectangle::from_tuples((5.0, 0.0), (25.0, 100.0)),
+            Color::from_rgba(0.0, 1.0, 0.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn grouped_matches_ordered()
+    {
+        let (ordered, ordered_draw_calls) =
+            render_with_batching(BatchingMode::Ordered, draw_interleaved);
+
+        let (grouped, grouped_draw_calls) =
+            render_with_batching(BatchingMode::Grouped, draw_interleaved);
+
+        assert_eq!(ordered.data(), grouped.data());
+
+        // Untextured shapes can join either batch, so only the images and
+        // the text require separate draw calls
+        assert_eq!(2, grouped_draw_calls);
+        assert_eq!(10, ordered_draw_calls);
+    }
+
+    #[test]
+    fn statistics()
+    {
+        let font =
+            Font::new(include_bytes!("../assets/fonts/NotoSans-Regular.ttf")).unwrap();
+
+        let label = font.layout_text("Abc", 12.0, TextOptions::new());
+
+        let mut renderer = GLRenderer::new_software((100, 100)).unwrap();
+
+        for frame in 0..2 {
+            renderer.draw_frame(|graphics| {
+                graphics.draw_text((0.0, 0.0), Color::BLACK, &label);
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 50.0), (10.0, 60.0)),
+                    Color::RED
+                );
+            });
+
+            let statistics = renderer.last_frame_statistics();
+
+            assert_eq!(1, statistics.draw_calls());
+            assert_eq!(3 * 6 + 6, statistics.vertices_uploaded());
+            assert!(statistics.flush_time_secs() > 0.0);
+
+            if frame == 0 {
+                assert_eq!(1, statistics.texture_binds());
+                assert_eq!(0, statistics.glyph_cache_hits());
+                assert_eq!(3, statistics.glyph_cache_misses());
+                assert_eq!(1, statistics.glyph_textures_allocated());
+            } else {
+                assert_eq!(0, statistics.texture_binds());
+                assert_eq!(3, statistics.glyph_cache_hits());
+                assert_eq!(0, statistics.glyph_cache_misses());
+                assert_eq!(0, statistics.glyph_textures_allocated());
+            }
+        }
+    }
+
+    #[test]
+    fn layers()
+    {
+        let draw = |graphics: &mut Graphics2D| {
+            graphics.with_layer(1, |graphics| {
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (50.0, 50.0)),
+                    Color::RED
+                );
+            });
+
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((25.0, 25.0), (75.0, 75.0)),
+                Color::BLUE
+            );
+        };
+
+        let (ordered, _) = render_with_batching(BatchingMode::Ordered, draw);
+        let (grouped, _) = render_with_batching(BatchingMode::Grouped, draw);
+
+        assert_eq!([0, 0, 255, 255], pixel(&ordered, 30, 30));
+        assert_eq!([255, 0, 0, 255], pixel(&grouped, 30, 30));
+        assert_eq!([0, 0, 255, 255], pixel(&grouped, 60, 60));
+    }
+
+    #[test]
+    fn tiled_textures()
+    {
+        let image = render(40, 30, |graphics| {
+            let create_image = |graphics: &mut Graphics2D, wrap_mode| {
+                graphics
+                    .create_image_from_raw_pixels(
+                        ImageDataType::RGB,
+                        ImageOptions::new(ImageSmoothingMode::NearestNeighbor)
+                            .with_wrap_mode(wrap_mode),
+                        (2, 1),
+                        &[255, 0, 0, 0, 255, 0]
+                    )
+                    .unwrap()
+            };
+
+            let repeat = create_image(graphics, ImageWrapMode::Repeat);
+            let mirrored = create_image(graphics, ImageWrapMode::MirroredRepeat);
+
+            graphics.draw_rectangle_image_tiled(
+                Rectangle::from_tuples((0.0, 0.0), (40.0, 10.0)),
+                (5.0, 10.0),
+                (0.0, 0.0),
+                &repeat
+            );
+
+            graphics.draw_rectangle_image_tiled(
+                Rectangle::from_tuples((0.0, 10.0), (40.0, 20.0)),
+                (5.0, 10.0),
+                (0.0, 0.0),
+                &mirrored
+            );
+
+            graphics.draw_rectangle_image_tiled(
+                Rectangle::from_tuples((0.0, 20.0), (40.0, 30.0)),
+                (5.0, 10.0),
+                (2.0, 0.0),
+                &repeat
+            );
+
+            // A zero scale draws nothing
+            graphics.draw_rectangle_image_tiled(
+                Rectangle::from_tuples((0.0, 0.0), (40.0, 30.0)),
+                (0.0, 10.0),
+                (0.0, 0.0),
+                &repeat
+            );
+        });
+
+        let red = [255, 0, 0, 255];
+        let green = [0, 255, 0, 255];
+
+        for (x, expected) in [(2, red), (7, green), (12, red), (17, green), (37, green)] {
+            assert_eq!(expected, pixel(&image, x, 5));
+        }
+
+        for (x, expected) in [(2, red), (7, green), (12, green), (17, red), (22, red)] {
+            assert_eq!(expected, pixel(&image, x, 15));
+        }
+
+        for (x, expected) in [(0, green), (3, red), (8, green), (13, red), (38, green)] {
+            assert_eq!(expected, pixel(&image, x, 25));
+        }
+    }
+
+    #[test]
+    fn update_image()
+    {
+        let image = render(40, 20, |graphics| {
+            let handle = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGB,
+                    ImageSmoothingMode::NearestNeighbor,
+                    (2, 2),
+                    &[255, 0, 0].repeat(4)
+                )
+                .unwrap();
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((0.0, 0.0), (20.0, 20.0)),
+                &handle
+            );
+
+            graphics
+                .update_image_region(
+                    &handle,
+                    ImageDataType::RGBA,
+                    URect::from_tuples((1, 0), (2, 2)),
+                    &[0, 255, 0, 255, 0, 0, 255, 255]
+                )
+                .unwrap();
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((20.0, 0.0), (40.0, 20.0)),
+                &handle
+            );
+
+            assert!(graphics
+                .update_image_region(
+                    &handle,
+                    ImageDataType::RGB,
+                    URect::from_tuples((1, 1), (3, 2)),
+                    &[0; 6]
+                )
+                .is_err());
+
+            assert!(graphics
+                .update_image(&handle, ImageDataType::RGB, &[0; 9])
+                .is_err());
+        });
+
+        // Drawn before the update
+        assert_eq!([255, 0, 0, 255], pixel(&image, 15, 5));
+        assert_eq!([255, 0, 0, 255], pixel(&image, 15, 15));
+
+        assert_eq!([255, 0, 0, 255], pixel(&image, 25, 5));
+        assert_eq!([0, 255, 0, 255], pixel(&image, 35, 5));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 35, 15));
+    }
+
+    #[test]
+    fn read_image_pixels()
+    {
+        let pixels = [
+            255, 0, 0, 0, 255, 0, 0, 0, 255, //
+            10, 20, 30, 40, 50, 60, 70, 80, 90
+        ];
+
+        let mut renderer = GLRenderer::new_software((20, 20)).unwrap();
+
+        let image = renderer
+            .create_image_from_raw_pixels(
+                ImageDataType::RGB,
+                ImageSmoothingMode::NearestNeighbor,
+                UVec2::new(3, 2),
+                &pixels
+            )
+            .unwrap();
+
+        let target = renderer
+            .create_render_target(ImageSmoothingMode::NearestNeighbor, UVec2::new(4, 4))
+            .unwrap();
+
+        let (rgb, rgba, target_pixels, screen) = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::WHITE);
+
+            let rgb = graphics
+                .read_image_pixels(&image, ImageDataType::RGB)
+                .unwrap();
+
+            let rgba = graphics
+                .read_image_pixels(&image, ImageDataType::RGBA)
+                .unwrap();
+
+            graphics.draw_to_render_target(&target, |graphics| {
+                graphics.clear_screen(Color::BLUE);
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (2.0, 1.0)),
+                    Color::RED
+                );
+
+                // Reading another image leaves the target bound
+                graphics
+                    .read_image_pixels(&image, ImageDataType::RGB)
+                    .unwrap();
+
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 3.0), (1.0, 4.0)),
+                    Color::GREEN
+                );
+            });
+
+            let target_pixels =
+                graphics.read_render_target_pixels(&target, ImageDataType::RGBA);
+
+            (
+                rgb,
+                rgba,
+                target_pixels,
+                graphics.capture(ImageDataType::RGBA)
+            )
+        });
+
+        assert_eq!(UVec2::new(3, 2), rgb.size());
+        assert_eq!(&pixels[..], &rgb.data()[..]);
+
+        assert_eq!([70, 80, 90, 255], pixel(&rgba, 2, 1));
+        assert_eq!(24, rgba.data().len());
+
+        assert_eq!([255, 0, 0, 255], pixel(&target_pixels, 1, 0));
+        assert_eq!([0, 0, 255, 255], pixel(&target_pixels, 2, 0));
+        assert_eq!([0, 255, 0, 255], pixel(&target_pixels, 0, 3));
+
+        assert_eq!([255, 255, 255, 255], pixel(&screen, 0, 0));
+    }
+
+    #[test]
+    fn read_translucent_render_target()
+    {
+        let mut renderer = GLRenderer::new_software((20, 20)).unwrap();
+
+        let target = renderer
+            .create_render_target(ImageSmoothingMode::NearestNeighbor, UVec2::new(4, 4))
+            .unwrap();
+
+        let (target_pixels, image_pixels, captured) = renderer.draw_frame(|graphics| {
+            let captured = graphics.draw_to_render_target(&target, |graphics| {
+                graphics.clear_screen(Color::TRANSPARENT);
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (4.0, 4.0)),
+                    Color::from_rgba(1.0, 0.5, 0.0, 0.5)
+                );
+
+                graphics.capture(ImageDataType::RGBA)
+            });
+
+            (
+                graphics.read_render_target_pixels(&target, ImageDataType::RGBA),
+                graphics
+                    .read_image_pixels(target.image(), ImageDataType::RGB)
+                    .unwrap(),
+                captured
+            )
+        });
+
+        // The premultiplied contents of the target are converted back
+        let assert_close = |expected: &[u8], actual: &[u8]| {
+            assert!(
+                expected
+                    .iter()
+                    .zip(actual.iter())
+                    .all(|(a, b)| (*a as i32 - *b as i32).abs() <= 1),
+                "Expected {:?}, got {:?}",
+                expected,
+                actual
+            );
+        };
+
+        assert_close(&[255, 128, 0, 128], &pixel(&target_pixels, 1, 1));
+        assert_close(&[255, 128, 0, 128], &pixel(&captured, 2, 3));
+        assert_close(&[255, 128, 0], &image_pixels.data()[..3]);
+        assert_eq!(48, image_pixels.data().len());
+    }
+
+    #[test]
+    fn capture_region()
+    {
+        let mut renderer = GLRenderer::new_software((40, 30)).unwrap();
+
+        let target = renderer
+            .create_render_target(ImageSmoothingMode::NearestNeighbor, UVec2::new(8, 8))
+            .unwrap();
+
+        let (region, cropped, target_region) = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::WHITE);
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((10.0, 5.0), (20.0, 10.0)),
+                Color::RED
+            );
+
+            let region = graphics
+                .capture_region(URect::from_tuples((5, 0), (15, 20)), ImageDataType::RGB);
+
+            let cropped = graphics.capture_region(
+                URect::from_tuples((30, 20), (50, 50)),
+                ImageDataType::RGBA
+            );
+
+            let target_region = graphics.draw_to_render_target(&target, |graphics| {
+                graphics.clear_screen(Color::BLUE);
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 6.0), (8.0, 8.0)),
+                    Color::GREEN
+                );
+                graphics.capture_region(
+                    URect::from_tuples((2, 4), (4, 8)),
+                    ImageDataType::RGBA
+                )
+            });
+
+            (region, cropped, target_region)
+        });
+
+        let rgb_pixel = |x: u32, y: u32| {
+            let start = ((y * region.size().x + x) * 3) as usize;
+            &region.data()[start..start + 3]
+        };
+
+        assert_eq!(UVec2::new(10, 20), region.size());
+        assert_eq!(&[255, 255, 255], rgb_pixel(4, 4));
+        assert_eq!(&[255, 0, 0], rgb_pixel(5, 5));
+        assert_eq!(&[255, 0, 0], rgb_pixel(9, 9));
+        assert_eq!(&[255, 255, 255], rgb_pixel(9, 10));
+
+        assert_eq!(UVec2::new(10, 10), cropped.size());
+        assert_eq!(400, cropped.data().len());
+
+        assert_eq!(UVec2::new(2, 4), target_region.size());
+        assert_eq!([0, 0, 255, 255], pixel(&target_region, 0, 1));
+        assert_eq!([0, 255, 0, 255], pixel(&target_region, 1, 2));
+    }
+
+    #[test]
+    fn mipmaps()
+    {
+        // A checkerboard of single black and white pixels
+        let checkerboard: Vec<u8> = (0..64 * 64)
+            .flat_map(|i| {
+                let value = if (i % 64 + i / 64) % 2 == 0 { 255 } else { 0 };
+                [value, value, value]
+            })
+            .collect();
+
+        let image = render(40, 20, |graphics| {
+            let mut create_image = |options: ImageOptions| {
+                graphics
+                    .create_image_from_raw_pixels(
+                        ImageDataType::RGB,
+                        options,
+                        (64, 64),
+                        &checkerboard
+                    )
+                    .unwrap()
+            };
+
+            let nearest =
+                create_image(ImageOptions::new(ImageSmoothingMode::NearestNeighbor));
+
+            let nearest_mipmap = create_image(
+                ImageOptions::new(ImageSmoothingMode::NearestNeighbor).with_mipmaps(true)
+            );
+
+            let trilinear =
+                create_image(ImageOptions::new(ImageSmoothingMode::Trilinear));
+            let updated = create_image(ImageOptions::new(ImageSmoothingMode::Trilinear));
+
+            assert!(!nearest.has_mipmaps());
+            assert!(nearest_mipmap.has_mipmaps());
+            assert!(trilinear.has_mipmaps());
+
+            graphics
+                .update_image(&updated, ImageDataType::RGB, &[255, 0, 0].repeat(64 * 64))
+                .unwrap();
+
+            let target = graphics
+                .create_render_target(ImageSmoothingMode::Trilinear, (64, 64))
+                .unwrap();
+
+            // Alternating red and blue columns, which average to purple
+            graphics.draw_to_render_target(&target, |graphics| {
+                graphics.clear_screen(Color::BLUE);
+
+                for x in (0..64).step_by(2) {
+                    graphics.draw_rectangle(
+                        Rectangle::from_tuples((x as f32, 0.0), (x as f32 + 1.0, 64.0)),
+                        Color::RED
+                    );
+                }
+            });
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((0.0, 0.0), (8.0, 8.0)),
+                &nearest
+            );
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((10.0, 0.0), (18.0, 8.0)),
+                &nearest_mipmap
+            );
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((20.0, 0.0), (44.0, 24.0)),
+                &trilinear
+            );
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((0.0, 10.0), (8.0, 18.0)),
+                &updated
+            );
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((10.0, 10.0), (18.0, 18.0)),
+                target.image()
+            );
+        });
+
+        let is_gray =
+            |pixel: [u8; 4]| pixel[..3].iter().all(|value| (120..=135).contains(value));
+
+        // Without mipmaps, every pixel samples the same color of the checkerboard
+        let first = pixel(&image, 0, 0);
+        assert!(first == [0, 0, 0, 255] || first == [255, 255, 255, 255]);
+
+        for (x, y) in [(1, 0), (0, 1), (7, 7), (3, 5)] {
+            assert_eq!(first, pixel(&image, x, y));
+        }
+
+        // With mipmaps, the pixels are averaged
+        assert!(is_gray(pixel(&image, 10, 0)));
+        assert!(is_gray(pixel(&image, 17, 7)));
+        assert!(is_gray(pixel(&image, 25, 5)));
+        assert!(is_gray(pixel(&image, 39, 15)));
+
+        // Mipmaps are regenerated after the contents change
+        assert_eq!([255, 0, 0, 255], pixel(&image, 4, 14));
+        let purple = pixel(&image, 14, 14);
+        assert!((120..=135).contains(&purple[0]), "{:?}", purple);
+        assert_eq!(0, purple[1]);
+        assert!((120..=135).contains(&purple[2]), "{:?}", purple);
+    }
+
+    #[test]
+    fn gradient_with_too_many_stops()
+    {
+        let mut gradient = Gradient::linear((0.0, 0.0), (10.0, 0.0));
+
+        // Gradients created without using with_stop() may exceed the limit
+        for i in 0..=Gradient::MAX_STOPS {
+            gradient.stops.push((i as f32 / 10.0, Color::RED));
+        }
+
+        let image = render(10, 10, |graphics| {
+            graphics.draw_rectangle_with_paint(
+                Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                &Paint::Gradient(gradient)
+            );
+        });
+
+        assert_eq!([255, 0, 0, 255], pixel(&image, 5, 5));
+    }
+
+    #[test]
+    fn letterbox_bars_keep_clip()
+    {
+        let mut renderer = GLRenderer::new_software((40, 20)).unwrap();
+        let clip = Rectangle::from_tuples((10, 0), (20, 20));
+
+        renderer.set_virtual_resolution(Some(VirtualResolution::new(
+            (20.0, 20.0),
+            ScalingMode::Fit
+        )));
+
+        let (_, image) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            graphics.set_clip(Some(clip.clone()));
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 0.0), (20.0, 20.0)),
+                Color::RED
+            );
+        });
+
+        assert_eq!([255, 0, 0, 255], pixel(&image, 15, 10));
+        assert_eq!([255, 255, 255, 255], pixel(&image, 25, 10));
+
+        // Drawing the letterbox bars at the end of the frame leaves the clip
+        // area unchanged
+        assert_eq!(Some(clip), renderer.renderer.renderer.current_clip);
+    }
+
+    #[test]
+    fn premultiplied_blend_mode()
+    {
+        let image = render(20, 10, |graphics| {
+            // A fully transparent pixel with a non-zero color is added to the
+            // existing contents
+            let glow = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGBA,
+                    ImageSmoothingMode::NearestNeighbor,
+                    (1, 1),
+                    &[0, 0, 255, 0]
+                )
+                .unwrap();
+
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((10.0, 0.0), (20.0, 10.0)),
+                Color::BLACK
+            );
+
+            graphics.with_blend_mode(BlendMode::Premultiplied, |graphics| {
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                    Color::from_rgba(0.5, 0.0, 0.0, 0.5)
+                );
+
+                graphics.draw_rectangle_image(
+                    Rectangle::from_tuples((10.0, 0.0), (20.0, 10.0)),
+                    &glow
+                );
+            });
+        });
+
+        assert_eq!([255, 128, 128, 255], pixel(&image, 5, 5));
+        assert_eq!([0, 0, 255, 255], pixel(&image, 15, 5));
+    }
+
+    #[test]
+    fn render_target_translucent_content()
+    {
+        let translucent = Color::from_rgba(1.0, 0.0, 0.0, 0.5);
+
+        let image = render(30, 10, |graphics| {
+            let target = graphics
+                .create_render_target(ImageSmoothingMode::NearestNeighbor, (10, 10))
+                .unwrap();
+
+            graphics.draw_to_render_target(&target, |graphics| {
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                    translucent
+                );
+            });
+
+            // Drawn directly, through the render target, and through the
+            // render target with a tint
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 0.0), (10.0, 10.0)),
+                translucent
+            );
+
+            graphics.draw_rectangle_image(
+                Rectangle::from_tuples((10.0, 0.0), (20.0, 10.0)),
+                target.image()
+            );
+
+            graphics.draw_rectangle_image_tinted(
+                Rectangle::from_tuples((20.0, 0.0), (30.0, 10.0)),
+                Color::from_rgba(1.0, 1.0, 1.0, 0.5),
+                target.image()
+            );
+        });
+
+        // The render target stores 8-bit colors, so allow for rounding
+        let assert_close = |expected: [u8; 4], actual: [u8; 4]| {
+            assert!(
+                expected
+                    .iter()
+                    .zip(actual.iter())
+                    .all(|(a, b)| (*a as i32 - *b as i32).abs() <= 1),
+                "Expected {:?}, got {:?}",
+                expected,
+                actual
+            );
+        };
+
+        assert_eq!([255, 128, 128, 255], pixel(&image, 5, 5));
+        assert_close([255, 128, 128, 255], pixel(&image, 15, 5));
+        assert_close([255, 191, 191, 255], pixel(&image, 25, 5));
+    }
+
+    #[test]
+    fn check_texture_units()
+    {
+        let samplers = |list: &[(&str, usize)]| -> Vec<(String, usize)> {
+            list.iter()
+                .map(|(name, size)| (name.to_string(), *size))
+                .collect()
+        };
+
+        assert!(Renderer2D::check_texture_units(&samplers(&[]), 1).is_ok());
+
+        assert!(Renderer2D::check_texture_units(
+            &samplers(&[("in_Texture", 1), ("in_Mask", 1), ("in_Layers[0]", 6)]),
+            8
+        )
+        .is_ok());
+
+        assert!(Renderer2D::check_texture_units(
+            &samplers(&[("in_Mask", 1), ("in_Layers[0]", 7)]),
+            8
+        )
+        .is_err());
+
+        // The software renderer supports the built-in shader
+        let renderer = GLRenderer::new_software((10, 10)).unwrap();
+        let context = &renderer.renderer.renderer.context;
+        let program = &renderer.renderer.renderer.program;
+
+        assert_eq!(8, program.max_texture_units);
+        assert_eq!(
+            samplers(&[("in_Texture", 1)]),
+            program.program.get_sampler_uniforms(context)
+        );
     }
 }
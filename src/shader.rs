@@ -0,0 +1,274 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::color::Color;
+use crate::dimen::Vec2;
+use crate::image::ImageHandle;
+use crate::renderer2d::ShaderProgram;
+
+/// The shading language version which custom fragment shaders must be written
+/// in. This depends on the type of graphics context in use, and can be
+/// obtained using [crate::Graphics2D::shader_language].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum ShaderLanguage
+{
+    /// GLSL version 1.10, as used with OpenGL 2.0. Inputs are declared using
+    /// `varying`, and the output is written to `gl_FragColor`.
+    Glsl110,
+
+    /// GLSL ES version 3.00, as used with WebGL 2.0. The source must begin
+    /// with `#version 300 es` and declare a float precision. Inputs are
+    /// declared using `in`, and the output is written to a variable declared
+    /// as `out vec4 out_FragColor`.
+    GlslEs300
+}
+
+/// The value of a uniform variable in a [CustomShader].
+#[derive(Debug, PartialEq, Clone)]
+pub enum ShaderUniform
+{
+    /// A `float` uniform.
+    Float(f32),
+
+    /// A `vec2` uniform.
+    Vec2(Vec2),
+
+    /// A `vec3` uniform.
+    Vec3(f32, f32, f32),
+
+    /// A `vec4` uniform.
+    Vec4(f32, f32, f32, f32),
+
+    /// A `vec4` uniform, containing the red, green, blue, and alpha
+    /// components of the color.
+    Color(Color),
+
+    /// A `sampler2D` uniform. Each texture uses one of the GPU's texture
+    /// units, the number of which depends on the GPU. One unit is reserved
+    /// for `in_Texture`.
+    Texture(ImageHandle)
+}
+
+impl From<f32> for ShaderUniform
+{
+    fn from(value: f32) -> Self
+    {
+        ShaderUniform::Float(value)
+    }
+}
+
+impl From<Vec2> for ShaderUniform
+{
+    fn from(value: Vec2) -> Self
+    {
+        ShaderUniform::Vec2(value)
+    }
+}
+
+impl From<Color> for ShaderUniform
+{
+    fn from(value: Color) -> Self
+    {
+        ShaderUniform::Color(value)
+    }
+}
+
+impl From<ImageHandle> for ShaderUniform
+{
+    fn from(value: ImageHandle) -> Self
+    {
+        ShaderUniform::Texture(value)
+    }
+}
+
+impl From<&ImageHandle> for ShaderUniform
+{
+    fn from(value: &ImageHandle) -> Self
+    {
+        ShaderUniform::Texture(value.clone())
+    }
+}
+
+/// A user-supplied fragment shader, which can be used in place of the
+/// built-in shader to draw shapes, images, and text. Create a custom shader
+/// using [crate::Graphics2D::create_custom_shader], and draw with it using
+/// [crate::Graphics2D::with_shader].
+///
+/// Custom shaders are compiled against the built-in vertex shader. The source
+/// must be written in the [ShaderLanguage] for the current context.
+///
+/// # Inputs
+///
+/// The built-in vertex shader receives the following attributes for each
+/// vertex. Apart from `in_Position`, each one is passed unchanged to the
+/// fragment shader in a variable with the `pass_` prefix instead of `in_`
+/// (for example, `in_Color` becomes `pass_Color`).
+///
+/// * `vec2 in_Position`: the position in pixels, after transformation.
+/// * `vec4 in_Color`: the vertex color.
+/// * `vec2 in_TextureCoord`: the texture coordinate, or for circles, the
+///   position relative to the center, where the edge has a magnitude of `1.0`.
+/// * `float in_TextureMix`: `1.0` if the texture should be sampled, otherwise
+///   `0.0`.
+/// * `float in_CircleMix`: `1.0` if the vertex is part of a circle or ellipse,
+///   otherwise `0.0`.
+/// * `vec2 in_CircleInnerRadius`: the inner radius of a ring, relative to the
+///   outer radius.
+/// * `vec2 in_GradientCoord`: the position within the current gradient.
+/// * `float in_GradientType`: `0.0` for no gradient, `1.0` for a linear
+///   gradient, or `2.0` for a radial gradient.
+///
+/// The following uniforms are also available:
+///
+/// * `uniform sampler2D in_Texture`: the texture for the image or text being
+///   drawn.
+/// * `uniform int in_GradientStopCount`, `uniform float
+///   in_GradientStopOffsets[8]`, and `uniform vec4 in_GradientStopColors[8]`:
+///   the stops of the current gradient.
+/// * `uniform float in_PremultiplyAlpha`: `1.0` if the output color should be
+///   multiplied by its own alpha component, as required by
+///   [crate::paint::BlendMode::Multiply] and [crate::paint::BlendMode::Screen],
+///   otherwise `0.0`.
+/// * `uniform float in_TexturePremultiplied`: `1.0` if the colors of
+///   `in_Texture` have already been multiplied by their alpha component, as
+///   with the image of a [crate::image::RenderTarget], otherwise `0.0`. In this
+///   case, the alpha of the texture must not be applied to the output color a
+///   second time.
+///
+/// Any other uniforms may be set using [CustomShader::set_uniform].
+///
+/// Circles, ellipses, and rings are drawn as quads, and it is the fragment
+/// shader's responsibility to discard the pixels outside the shape. See the
+/// built-in shaders in the `src/shaders` directory for an example.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use speedy2d::Graphics2D;
+/// # use speedy2d::image::ImageHandle;
+/// # fn draw(graphics: &mut Graphics2D, image: &ImageHandle) {
+/// // A shader which fades images to grayscale
+/// let mut shader = graphics
+///     .create_custom_shader(
+///         r#"
+///         uniform sampler2D in_Texture;
+///         uniform float in_Amount;
+///
+///         varying vec4 pass_Color;
+///         varying vec2 pass_TextureCoord;
+///         varying float pass_TextureMix;
+///
+///         void main(void) {
+///             vec4 texColor = texture2D(in_Texture, pass_TextureCoord);
+///             vec4 color = pass_Color * mix(vec4(1.0), texColor, pass_TextureMix);
+///             float gray = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+///             gl_FragColor = vec4(mix(color.rgb, vec3(gray), in_Amount), color.a);
+///         }
+///         "#
+///     )
+///     .unwrap();
+///
+/// shader.set_uniform("in_Amount", 0.8);
+///
+/// graphics.with_shader(&shader, |graphics| {
+///     graphics.draw_image((100.0, 100.0), image);
+/// });
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CustomShader
+{
+    pub(crate) program: Rc<ShaderProgram>,
+    pub(crate) source: Rc<str>,
+    pub(crate) uniforms: Vec<(String, ShaderUniform)>
+}
+
+impl CustomShader
+{
+    pub(crate) fn new(program: Rc<ShaderProgram>, source: &str) -> Self
+    {
+        CustomShader {
+            program,
+            source: Rc::from(source),
+            uniforms: Vec::new()
+        }
+    }
+
+    /// Sets the value of the uniform with the specified name. The value is
+    /// captured when [crate::Graphics2D::with_shader] is called, so changing
+    /// it afterwards does not affect items which have already been drawn.
+    pub fn set_uniform<S, V>(&mut self, name: S, value: V)
+    where
+        S: AsRef<str>,
+        V: Into<ShaderUniform>
+    {
+        let name = name.as_ref();
+        let value = value.into();
+
+        match self
+            .uniforms
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.uniforms.push((name.to_string(), value))
+        }
+    }
+
+    /// Sets the value of the uniform with the specified name. See
+    /// [CustomShader::set_uniform].
+    #[inline]
+    #[must_use]
+    pub fn with_uniform<S, V>(mut self, name: S, value: V) -> Self
+    where
+        S: AsRef<str>,
+        V: Into<ShaderUniform>
+    {
+        self.set_uniform(name, value);
+        self
+    }
+
+    /// Returns the value of the uniform with the specified name, if it has
+    /// been set.
+    pub fn uniform<S: AsRef<str>>(&self, name: S) -> Option<&ShaderUniform>
+    {
+        self.uniforms
+            .iter()
+            .find(|(existing, _)| existing == name.as_ref())
+            .map(|(_, value)| value)
+    }
+}
+
+impl PartialEq for CustomShader
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        Rc::ptr_eq(&self.program, &other.program) && self.uniforms == other.uniforms
+    }
+}
+
+impl Debug for CustomShader
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_struct("CustomShader")
+            .field("uniforms", &self.uniforms)
+            .finish()
+    }
+}
@@ -20,13 +20,54 @@ precision mediump float;
 
 uniform sampler2D in_Texture;
 
+#define MAX_GRADIENT_STOPS 8
+
+uniform int in_GradientStopCount;
+uniform float in_GradientStopOffsets[MAX_GRADIENT_STOPS];
+uniform vec4 in_GradientStopColors[MAX_GRADIENT_STOPS];
+
+// 1.0 if the output color should be multiplied by its alpha component, as
+// required by some blend modes, otherwise 0.0.
+uniform float in_PremultiplyAlpha;
+
+// 1.0 if the colors of the texture have already been multiplied by their
+// alpha component, as with render targets, otherwise 0.0.
+uniform float in_TexturePremultiplied;
+
 in vec4 pass_Color;
 in vec2 pass_TextureCoord;
 in float pass_TextureMix;
 in float pass_CircleMix;
+in vec2 pass_CircleInnerRadius;
+in vec2 pass_GradientCoord;
+in float pass_GradientType;
 
 out vec4 out_FragColor;
 
+// Returns the color of the current gradient at the specified offset. Each
+// stop after the first blends towards its own color, so the result is the
+// interpolation between the two stops surrounding the offset.
+vec4 gradientColor(float offset) {
+
+    vec4 result = in_GradientStopColors[0];
+
+    for (int i = 1; i < MAX_GRADIENT_STOPS; i++) {
+        if (i < in_GradientStopCount) {
+            float previousOffset = in_GradientStopOffsets[i - 1];
+            float segmentLength = in_GradientStopOffsets[i] - previousOffset;
+
+            float blend = clamp(
+                    (offset - previousOffset) / max(segmentLength, 0.00001),
+                    0.0,
+                    1.0);
+
+            result = mix(result, in_GradientStopColors[i], blend);
+        }
+    }
+
+    return result;
+}
+
 void main(void) {
 
     vec4 texCol = texture(in_Texture, pass_TextureCoord);
@@ -34,10 +75,43 @@ void main(void) {
     float texCoordMagSquared = pass_TextureCoord.x * pass_TextureCoord.x
             + pass_TextureCoord.y * pass_TextureCoord.y;
 
-    float circleAlpha = 1.0 - step(1.0, texCoordMagSquared);
+    // Pixels inside the inner ellipse are transparent. This is calculated
+    // without division, so that an inner radius of zero gives no hole.
+    vec2 innerScaledCoord = pass_TextureCoord * pass_CircleInnerRadius.yx;
+    float innerRadiusProduct = pass_CircleInnerRadius.x * pass_CircleInnerRadius.y;
+
+    float innerAlpha = step(
+            innerRadiusProduct * innerRadiusProduct,
+            dot(innerScaledCoord, innerScaledCoord));
+
+    float circleAlpha = (1.0 - step(1.0, texCoordMagSquared)) * innerAlpha;
+
+    // Pixels outside the circle aren't part of the shape, so they must not be
+    // drawn even when blending is disabled
+    if (pass_CircleMix * (1.0 - circleAlpha) > 0.5) {
+        discard;
+    }
 
-    out_FragColor = pass_Color * (
+    // Gradient type 0 is no gradient, 1 is linear, and 2 is radial
+    float gradientMix = step(0.5, pass_GradientType);
+    float gradientOffset = mix(
+            pass_GradientCoord.x,
+            length(pass_GradientCoord),
+            step(1.5, pass_GradientType));
+
+    vec4 paintColor = mix(vec4(1.0), gradientColor(gradientOffset), gradientMix);
+
+    vec4 color = pass_Color * paintColor * (
             vec4(1.0 - pass_TextureMix - pass_CircleMix)
                     + (texCol * pass_TextureMix)
                     + (vec4(vec3(1.0), circleAlpha)) * pass_CircleMix);
+
+    // The alpha of a premultiplied texture has already been applied to its
+    // colors, so only the remaining alpha is applied here
+    float alphaFactor = mix(
+            color.a,
+            pass_Color.a * paintColor.a * mix(1.0, circleAlpha, pass_CircleMix),
+            in_TexturePremultiplied);
+
+    out_FragColor = vec4(color.rgb * mix(1.0, alphaFactor, in_PremultiplyAlpha), color.a);
 }
\ No newline at end of file
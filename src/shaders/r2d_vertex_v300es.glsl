@@ -21,25 +21,46 @@ in vec4 in_Color;
 in vec2 in_TextureCoord;
 in float in_TextureMix;
 in float in_CircleMix;
+in vec2 in_CircleInnerRadius;
+in vec2 in_GradientCoord;
+in float in_GradientType;
 
 uniform float in_ScaleX;
 uniform float in_ScaleY;
+uniform float in_OffsetY;
+
+// The rows of the transform applied to each vertex, and a color which is
+// multiplied with the vertex color. These are used for drawing static meshes,
+// and are the identity transform and white otherwise.
+uniform vec3 in_TransformX;
+uniform vec3 in_TransformY;
+uniform vec4 in_Tint;
 
 out vec4 pass_Color;
 out vec2 pass_TextureCoord;
 out float pass_TextureMix;
 out float pass_CircleMix;
+out vec2 pass_CircleInnerRadius;
+out vec2 pass_GradientCoord;
+out float pass_GradientType;
 
 void main(void) {
 
+    vec2 position = vec2(
+            dot(in_TransformX, vec3(in_Position, 1.0)),
+            dot(in_TransformY, vec3(in_Position, 1.0)));
+
     gl_Position = vec4(
-            in_Position.x * in_ScaleX - 1.0,
-            in_Position.y * in_ScaleY + 1.0,
+            position.x * in_ScaleX - 1.0,
+            position.y * in_ScaleY + in_OffsetY,
             0.0,
             1.0);
 
-    pass_Color = in_Color;
+    pass_Color = in_Color * in_Tint;
     pass_TextureCoord = in_TextureCoord;
     pass_TextureMix = in_TextureMix;
     pass_CircleMix = in_CircleMix;
+    pass_CircleInnerRadius = in_CircleInnerRadius;
+    pass_GradientCoord = in_GradientCoord;
+    pass_GradientType = in_GradientType;
 }
\ No newline at end of file
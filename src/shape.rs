@@ -18,6 +18,7 @@ use num_traits::Zero;
 
 use crate::dimen::{Vec2, Vector2};
 use crate::numeric::{max, min, PrimitiveZero};
+use crate::tessellate;
 
 /// A struct representing an axis-aligned rectangle. Two points are stored: the
 /// top left vertex, and the bottom right vertex.
@@ -363,7 +364,8 @@ impl Polygon
 #[cfg(test)]
 mod test
 {
-    use crate::shape::URect;
+    use crate::dimen::Vec2;
+    use crate::shape::{Ellipse, URect};
 
     #[test]
     pub fn test_intersect_1()
@@ -397,6 +399,23 @@ mod test
 
         assert_eq!(None, r1.intersect(&r2));
     }
+
+    #[test]
+    pub fn test_ellipse_contains()
+    {
+        let ellipse = Ellipse::new((100.0, 100.0), (50.0, 20.0));
+
+        assert!(ellipse.contains(Vec2::new(145.0, 100.0)));
+        assert!(!ellipse.contains(Vec2::new(100.0, 125.0)));
+
+        let rotated = ellipse.with_rotation(std::f32::consts::FRAC_PI_2);
+
+        assert!(!rotated.contains(Vec2::new(145.0, 100.0)));
+        assert!(rotated.contains(Vec2::new(100.0, 145.0)));
+
+        let point = rotated.map_from_unit_circle(Vec2::new(1.0, 0.0));
+        assert!((point - Vec2::new(100.0, 150.0)).magnitude() < 0.001);
+    }
 }
 
 ///////////////////////////////////
@@ -718,3 +737,646 @@ impl<T: num_traits::AsPrimitive<f32> + Copy> RoundedRectangle<T>
         )
     }
 }
+
+/// A struct representing an ellipse, which may be rotated around its center.
+/// A circle can be constructed using [Ellipse::circle].
+///
+/// Angles are specified in radians. An angle of zero points to the right
+/// (along the positive X axis), and angles increase clockwise on the screen.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ellipse
+{
+    center: Vec2,
+    radii: Vec2,
+    rotation: f32
+}
+
+impl AsRef<Ellipse> for Ellipse
+{
+    fn as_ref(&self) -> &Self
+    {
+        self
+    }
+}
+
+impl Ellipse
+{
+    /// Constructs a new axis-aligned `Ellipse`, with the specified horizontal
+    /// and vertical radii.
+    #[inline]
+    pub fn new(center: impl Into<Vec2>, radii: impl Into<Vec2>) -> Self
+    {
+        Ellipse {
+            center: center.into(),
+            radii: radii.into(),
+            rotation: 0.0
+        }
+    }
+
+    /// Constructs a new circle with the specified radius.
+    #[inline]
+    pub fn circle(center: impl Into<Vec2>, radius: f32) -> Self
+    {
+        Ellipse::new(center, Vec2::new(radius, radius))
+    }
+
+    /// Returns a new `Ellipse`, rotated clockwise around its center by the
+    /// specified angle in radians.
+    #[inline]
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: f32) -> Self
+    {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Returns the center of the ellipse.
+    #[inline]
+    pub fn center(&self) -> Vec2
+    {
+        self.center
+    }
+
+    /// Returns the horizontal and vertical radii of the ellipse, before
+    /// rotation.
+    #[inline]
+    pub fn radii(&self) -> Vec2
+    {
+        self.radii
+    }
+
+    /// Returns the clockwise rotation of the ellipse in radians.
+    #[inline]
+    pub fn rotation(&self) -> f32
+    {
+        self.rotation
+    }
+
+    /// Returns true if the specified point is inside this ellipse.
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool
+    {
+        let normalized = self.map_to_unit_circle(point);
+        normalized.magnitude_squared() <= 1.0
+    }
+
+    /// Converts a point relative to a circle of radius `1.0` centered on the
+    /// origin, into the equivalent point on this ellipse.
+    pub(crate) fn map_from_unit_circle(&self, point: Vec2) -> Vec2
+    {
+        let (sin, cos) = self.rotation.sin_cos();
+        let scaled = Vec2::new(point.x * self.radii.x, point.y * self.radii.y);
+
+        self.center
+            + Vec2::new(
+                scaled.x * cos - scaled.y * sin,
+                scaled.x * sin + scaled.y * cos
+            )
+    }
+
+    /// The inverse of [Ellipse::map_from_unit_circle].
+    fn map_to_unit_circle(&self, point: Vec2) -> Vec2
+    {
+        let (sin, cos) = self.rotation.sin_cos();
+        let offset = point - self.center;
+
+        Vec2::new(
+            (offset.x * cos + offset.y * sin) / self.radii.x,
+            (-offset.x * sin + offset.y * cos) / self.radii.y
+        )
+    }
+
+    /// Returns the radii of an ellipse inset from this one by `thickness`
+    /// pixels, relative to the radii of this ellipse.
+    pub(crate) fn normalized_inner_radii(&self, thickness: f32) -> Vec2
+    {
+        let inner = |radius: f32| {
+            if radius > 0.0 {
+                ((radius - thickness) / radius).max(0.0)
+            } else {
+                0.0
+            }
+        };
+
+        Vec2::new(inner(self.radii.x), inner(self.radii.y))
+    }
+}
+
+///////////////////////////////////
+
+/// The shape used at the corners where two segments of a stroked path meet.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum LineJoin
+{
+    /// The outer edges of the two segments are extended until they meet at a
+    /// sharp point. If the point would be further away than the miter limit
+    /// allows (see [StrokeStyle::with_miter_limit]), a `Bevel` join is used
+    /// instead.
+    Miter,
+
+    /// The corner is rounded off with a circular arc.
+    Round,
+
+    /// The corner is cut off with a straight line between the outer edges of
+    /// the two segments.
+    Bevel
+}
+
+/// The shape used at the start and end of an open stroked path.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum LineCap
+{
+    /// The stroke ends exactly at the end point of the path.
+    Butt,
+
+    /// The stroke ends with a semicircle, centered on the end point of the
+    /// path.
+    Round,
+
+    /// The stroke is extended past the end point of the path by half the
+    /// stroke width.
+    Square
+}
+
+/// Describes how the outline of a [Path] should be drawn.
+///
+/// This is used by [crate::Graphics2D::stroke_path] and the other `stroke_*()`
+/// functions. The simpler `draw_*_outline()` functions only take a thickness:
+/// see [crate::Graphics2D] for how the two differ.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StrokeStyle
+{
+    pub(crate) width: f32,
+    pub(crate) line_join: LineJoin,
+    pub(crate) line_cap: LineCap,
+    pub(crate) miter_limit: f32,
+    pub(crate) dash_pattern: Vec<f32>,
+    pub(crate) dash_phase: f32
+}
+
+impl StrokeStyle
+{
+    /// Instantiates a new `StrokeStyle` with the specified width in pixels.
+    ///
+    /// By default, segments are connected using [LineJoin::Miter], and open
+    /// paths end with [LineCap::Butt]. The default miter limit is `4.0`, and
+    /// the stroke is solid (not dashed).
+    #[inline]
+    #[must_use]
+    pub fn new(width: f32) -> Self
+    {
+        StrokeStyle {
+            width,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+            miter_limit: 4.0,
+            dash_pattern: Vec::new(),
+            dash_phase: 0.0
+        }
+    }
+
+    /// Sets the shape used where two segments of the path meet.
+    #[inline]
+    #[must_use]
+    pub fn with_line_join(mut self, line_join: LineJoin) -> Self
+    {
+        self.line_join = line_join;
+        self
+    }
+
+    /// Sets the shape used at the start and end of each open subpath.
+    #[inline]
+    #[must_use]
+    pub fn with_line_cap(mut self, line_cap: LineCap) -> Self
+    {
+        self.line_cap = line_cap;
+        self
+    }
+
+    /// Sets the miter limit, which is the maximum allowed ratio between the
+    /// length of a [LineJoin::Miter] join and the stroke width. Sharper
+    /// corners than this will be drawn using [LineJoin::Bevel].
+    ///
+    /// The default is `4.0`.
+    #[inline]
+    #[must_use]
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self
+    {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Sets the dash pattern used to draw the stroke.
+    ///
+    /// The values in `lengths` are the alternating lengths (in pixels) of the
+    /// dashes and the gaps between them, starting with a dash. If an odd
+    /// number of lengths is provided, the list is repeated to make it even.
+    /// For example, `&[10.0, 5.0]` draws dashes of 10 pixels, separated by
+    /// gaps of 5 pixels.
+    ///
+    /// The `phase` is the distance into the pattern at which the start of
+    /// each subpath begins.
+    ///
+    /// The pattern continues around corners, and around the start of closed
+    /// subpaths. Each dash has the line caps specified by
+    /// [StrokeStyle::with_line_cap], so a dotted line can be drawn using
+    /// zero-length dashes with [LineCap::Round].
+    ///
+    /// If `lengths` is empty, contains negative values, or does not contain
+    /// any positive values, the stroke is drawn solid. Positive lengths
+    /// shorter than `0.01` pixels are treated as `0.01` pixels.
+    ///
+    /// ```rust
+    /// use speedy2d::shape::{LineCap, StrokeStyle};
+    ///
+    /// let dotted = StrokeStyle::new(4.0)
+    ///     .with_line_cap(LineCap::Round)
+    ///     .with_dash_pattern(&[0.0, 10.0], 0.0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_dash_pattern(mut self, lengths: &[f32], phase: f32) -> Self
+    {
+        self.dash_pattern = lengths.to_vec();
+        self.dash_phase = phase;
+        self
+    }
+
+    /// Returns the width of the stroke in pixels.
+    #[inline]
+    pub fn width(&self) -> f32
+    {
+        self.width
+    }
+
+    /// Returns the shape used where two segments of the path meet.
+    #[inline]
+    pub fn line_join(&self) -> LineJoin
+    {
+        self.line_join
+    }
+
+    /// Returns the shape used at the start and end of each open subpath.
+    #[inline]
+    pub fn line_cap(&self) -> LineCap
+    {
+        self.line_cap
+    }
+
+    /// Returns the miter limit.
+    #[inline]
+    pub fn miter_limit(&self) -> f32
+    {
+        self.miter_limit
+    }
+
+    pub(crate) fn is_dashed(&self) -> bool
+    {
+        self.dash_pattern.iter().any(|length| *length > 0.0)
+            && self
+                .dash_pattern
+                .iter()
+                .all(|length| length.is_finite() && *length >= 0.0)
+    }
+
+    /// Returns the lengths of the dashes and gaps in the dash pattern. This
+    /// will be empty if the stroke is solid.
+    #[inline]
+    pub fn dash_pattern(&self) -> &[f32]
+    {
+        &self.dash_pattern
+    }
+
+    /// Returns the offset into the dash pattern at which each subpath begins.
+    #[inline]
+    pub fn dash_phase(&self) -> f32
+    {
+        self.dash_phase
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum PathSegment
+{
+    MoveTo(Vec2),
+    LineTo(Vec2),
+    QuadTo(Vec2, Vec2),
+    CubicTo(Vec2, Vec2, Vec2),
+    ArcTo
+    {
+        radii: Vec2,
+        x_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        end: Vec2
+    },
+    Close
+}
+
+/// A struct representing a path made up of straight lines, Bézier curves, and
+/// elliptical arcs. A path may contain multiple subpaths, each of which begins
+/// with [Path::move_to].
+///
+/// Paths are constructed using a builder pattern, for example:
+///
+/// ```rust
+/// use speedy2d::shape::Path;
+///
+/// let path = Path::new()
+///     .move_to((100.0, 100.0))
+///     .line_to((200.0, 100.0))
+///     .quad_to((250.0, 150.0), (200.0, 200.0))
+///     .close();
+/// ```
+///
+/// A path can be drawn using [crate::Graphics2D::stroke_path] or
+/// [crate::Graphics2D::fill_path].
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Path
+{
+    pub(crate) segments: Vec<PathSegment>
+}
+
+impl Path
+{
+    /// Instantiates a new, empty `Path`.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Path {
+            segments: Vec::new()
+        }
+    }
+
+    /// Starts a new subpath at the specified point.
+    #[inline]
+    #[must_use]
+    pub fn move_to(mut self, point: impl Into<Vec2>) -> Self
+    {
+        self.segments.push(PathSegment::MoveTo(point.into()));
+        self
+    }
+
+    /// Adds a straight line from the current point to the specified point.
+    ///
+    /// If there is no current point, this is equivalent to [Path::move_to].
+    #[inline]
+    #[must_use]
+    pub fn line_to(mut self, point: impl Into<Vec2>) -> Self
+    {
+        self.segments.push(PathSegment::LineTo(point.into()));
+        self
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to `end`, using
+    /// the specified control point.
+    #[inline]
+    #[must_use]
+    pub fn quad_to(mut self, control: impl Into<Vec2>, end: impl Into<Vec2>) -> Self
+    {
+        self.segments
+            .push(PathSegment::QuadTo(control.into(), end.into()));
+        self
+    }
+
+    /// Adds a cubic Bézier curve from the current point to `end`, using the
+    /// two specified control points.
+    #[inline]
+    #[must_use]
+    pub fn cubic_to(
+        mut self,
+        control_1: impl Into<Vec2>,
+        control_2: impl Into<Vec2>,
+        end: impl Into<Vec2>
+    ) -> Self
+    {
+        self.segments.push(PathSegment::CubicTo(
+            control_1.into(),
+            control_2.into(),
+            end.into()
+        ));
+        self
+    }
+
+    /// Adds an elliptical arc from the current point to `end`, using the same
+    /// parameters as the SVG `A` path command.
+    ///
+    /// * `radii`: the horizontal and vertical radius of the ellipse.
+    /// * `x_rotation`: the rotation of the ellipse, in radians.
+    /// * `large_arc`: if `true`, the arc which spans more than 180 degrees is
+    ///   chosen, otherwise the smaller arc is chosen.
+    /// * `sweep`: if `true`, the arc is drawn in the direction of increasing
+    ///   angle (clockwise on the screen), otherwise it is drawn anticlockwise.
+    ///
+    /// If the radii are too small to connect the two points, they are scaled
+    /// up until the arc fits. If either radius is zero, a straight line is
+    /// drawn instead.
+    #[inline]
+    #[must_use]
+    pub fn arc_to(
+        mut self,
+        radii: impl Into<Vec2>,
+        x_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        end: impl Into<Vec2>
+    ) -> Self
+    {
+        self.segments.push(PathSegment::ArcTo {
+            radii: radii.into(),
+            x_rotation,
+            large_arc,
+            sweep,
+            end: end.into()
+        });
+        self
+    }
+
+    /// Closes the current subpath by adding a straight line back to its
+    /// starting point.
+    #[inline]
+    #[must_use]
+    pub fn close(mut self) -> Self
+    {
+        self.segments.push(PathSegment::Close);
+        self
+    }
+
+    /// Creates a closed path around the edges of the specified rectangle,
+    /// starting at the top left corner and proceeding clockwise.
+    #[must_use]
+    pub fn from_rectangle(rect: impl AsRef<Rectangle>) -> Self
+    {
+        let rect = rect.as_ref();
+
+        Path::new()
+            .move_to(*rect.top_left())
+            .line_to(rect.top_right())
+            .line_to(*rect.bottom_right())
+            .line_to(rect.bottom_left())
+            .close()
+    }
+
+    /// Creates a closed path around the edges of the specified rounded
+    /// rectangle, starting at the end of the top left corner and proceeding
+    /// clockwise.
+    #[must_use]
+    pub fn from_rounded_rectangle(round_rect: impl AsRef<RoundedRectangle>) -> Self
+    {
+        let round_rect = round_rect.as_ref();
+        let radius = round_rect.radius();
+        let radii = Vec2::new(radius, radius);
+
+        Path::new()
+            .move_to(round_rect.top_left() + Vec2::new(radius, 0.0))
+            .line_to(round_rect.top_right() + Vec2::new(-radius, 0.0))
+            .arc_to(
+                radii,
+                0.0,
+                false,
+                true,
+                round_rect.top_right() + Vec2::new(0.0, radius)
+            )
+            .line_to(round_rect.bottom_right() + Vec2::new(0.0, -radius))
+            .arc_to(
+                radii,
+                0.0,
+                false,
+                true,
+                round_rect.bottom_right() + Vec2::new(-radius, 0.0)
+            )
+            .line_to(round_rect.bottom_left() + Vec2::new(radius, 0.0))
+            .arc_to(
+                radii,
+                0.0,
+                false,
+                true,
+                round_rect.bottom_left() + Vec2::new(0.0, -radius)
+            )
+            .line_to(round_rect.top_left() + Vec2::new(0.0, radius))
+            .arc_to(
+                radii,
+                0.0,
+                false,
+                true,
+                round_rect.top_left() + Vec2::new(radius, 0.0)
+            )
+            .close()
+    }
+
+    /// Creates a closed path around the edge of the specified circle,
+    /// starting at the rightmost point and proceeding clockwise.
+    #[must_use]
+    pub fn from_circle(center: impl Into<Vec2>, radius: f32) -> Self
+    {
+        let center = center.into();
+        let radii = Vec2::new(radius, radius);
+
+        Path::new()
+            .move_to(center + Vec2::new(radius, 0.0))
+            .arc_to(radii, 0.0, false, true, center + Vec2::new(-radius, 0.0))
+            .arc_to(radii, 0.0, false, true, center + Vec2::new(radius, 0.0))
+            .close()
+    }
+
+    /// Returns `true` if no segments have been added to this path.
+    #[inline]
+    pub fn is_empty(&self) -> bool
+    {
+        self.segments.is_empty()
+    }
+
+    pub(crate) fn to_stroke_polygon(&self, style: &StrokeStyle) -> Polygon
+    {
+        let subpaths = tessellate::flatten(self, tessellate::DEFAULT_TOLERANCE);
+
+        let subpaths = if style.is_dashed() {
+            tessellate::dash(&subpaths, &style.dash_pattern, style.dash_phase)
+        } else {
+            subpaths
+        };
+
+        Polygon {
+            triangles: tessellate::stroke(&subpaths, style)
+        }
+    }
+
+    pub(crate) fn to_fill_polygon(&self) -> Polygon
+    {
+        Polygon {
+            triangles: tessellate::fill(&tessellate::flatten(
+                self,
+                tessellate::DEFAULT_TOLERANCE
+            ))
+        }
+    }
+}
+
+///////////////////////////////////
+
+/// The area within which drawing takes place, as set using
+/// [crate::Graphics2D::push_clip]. A `ClipShape` may be created from any of
+/// the supported shapes using `into()`.
+///
+/// Clipping to an axis-aligned rectangle uses the GPU's scissor test. All
+/// other shapes (including rectangles which have been rotated or skewed by
+/// the current transform) require a stencil buffer.
+#[derive(Debug, Clone)]
+pub enum ClipShape
+{
+    /// A rectangle.
+    Rectangle(Rectangle),
+
+    /// A rectangle with rounded corners.
+    RoundedRectangle(RoundedRectangle),
+
+    /// An ellipse or circle.
+    Ellipse(Ellipse),
+
+    /// A polygon.
+    Polygon(Polygon),
+
+    /// The interior of a path, as drawn by [crate::Graphics2D::fill_path].
+    Path(Path)
+}
+
+impl From<Rectangle> for ClipShape
+{
+    fn from(value: Rectangle) -> Self
+    {
+        ClipShape::Rectangle(value)
+    }
+}
+
+impl From<RoundedRectangle> for ClipShape
+{
+    fn from(value: RoundedRectangle) -> Self
+    {
+        ClipShape::RoundedRectangle(value)
+    }
+}
+
+impl From<Ellipse> for ClipShape
+{
+    fn from(value: Ellipse) -> Self
+    {
+        ClipShape::Ellipse(value)
+    }
+}
+
+impl From<Polygon> for ClipShape
+{
+    fn from(value: Polygon) -> Self
+    {
+        ClipShape::Polygon(value)
+    }
+}
+
+impl From<Path> for ClipShape
+{
+    fn from(value: Path) -> Self
+    {
+        ClipShape::Path(value)
+    }
+}
@@ -0,0 +1,401 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::color::Color;
+use crate::dimen::Vec2;
+use crate::image::ImageHandle;
+use crate::shape::Rect;
+
+/// A single sprite in a [SpriteBatch], showing part of the batch's image.
+///
+/// ```rust
+/// use speedy2d::color::Color;
+/// use speedy2d::shape::Rectangle;
+/// use speedy2d::sprite::Sprite;
+///
+/// // The second 16x16 frame of a sprite sheet, rotated around its center
+/// let frame = Rectangle::from_tuples((16.0, 0.0), (32.0, 16.0));
+///
+/// let sprite = Sprite::new(frame, (100.0, 50.0))
+///     .with_origin((8.0, 8.0))
+///     .with_rotation(std::f32::consts::FRAC_PI_4)
+///     .with_scale((2.0, 2.0))
+///     .with_tint(Color::from_rgba(1.0, 1.0, 1.0, 0.5));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Sprite
+{
+    source: Rect,
+    position: Vec2,
+    origin: Vec2,
+    rotation: f32,
+    scale: Vec2,
+    tint: Color,
+    depth: f32
+}
+
+impl Sprite
+{
+    /// Creates a sprite showing the area `source` of the image, measured in
+    /// pixels from the top left of the image. The top left corner of the
+    /// sprite is drawn at `position`, and by default it has the same size
+    /// as `source`.
+    #[inline]
+    #[must_use]
+    pub fn new(source: impl AsRef<Rect>, position: impl Into<Vec2>) -> Self
+    {
+        Sprite {
+            source: source.as_ref().clone(),
+            position: position.into(),
+            origin: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::new(1.0, 1.0),
+            tint: Color::WHITE,
+            depth: 0.0
+        }
+    }
+
+    /// Sets the point within the sprite which is drawn at its position, and
+    /// which it is rotated and scaled around. This is measured in pixels from
+    /// the top left of `source`, before scaling. The default is the top left
+    /// corner.
+    #[inline]
+    #[must_use]
+    pub fn with_origin(mut self, origin: impl Into<Vec2>) -> Self
+    {
+        self.origin = origin.into();
+        self
+    }
+
+    /// Sets the clockwise rotation of the sprite around its origin, in
+    /// radians.
+    #[inline]
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: f32) -> Self
+    {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the horizontal and vertical scale factors of the sprite.
+    #[inline]
+    #[must_use]
+    pub fn with_scale(mut self, scale: impl Into<Vec2>) -> Self
+    {
+        self.scale = scale.into();
+        self
+    }
+
+    /// Sets the color which each pixel of the sprite is multiplied by. The
+    /// default is white, which leaves the image unchanged.
+    #[inline]
+    #[must_use]
+    pub fn with_tint(mut self, tint: Color) -> Self
+    {
+        self.tint = tint;
+        self
+    }
+
+    /// Sets the depth of the sprite. Sprites with a greater depth are drawn
+    /// on top of those with a lower depth, and sprites with the same depth
+    /// are drawn in the order they were added to the batch. The default is
+    /// `0.0`.
+    #[inline]
+    #[must_use]
+    pub fn with_depth(mut self, depth: f32) -> Self
+    {
+        self.depth = depth;
+        self
+    }
+
+    /// Returns the positions of the corners of the sprite, clockwise from the
+    /// top left.
+    pub(crate) fn corners(&self) -> [Vec2; 4]
+    {
+        let size = self.source.size();
+        let (sin, cos) = self.rotation.sin_cos();
+
+        let corner = |x: f32, y: f32| {
+            let x = (x - self.origin.x) * self.scale.x;
+            let y = (y - self.origin.y) * self.scale.y;
+
+            Vec2::new(
+                self.position.x + x * cos - y * sin,
+                self.position.y + x * sin + y * cos
+            )
+        };
+
+        [
+            corner(0.0, 0.0),
+            corner(size.x, 0.0),
+            corner(size.x, size.y),
+            corner(0.0, size.y)
+        ]
+    }
+
+    /// Returns the normalized texture coordinates of the corners of the
+    /// sprite, in the same order as `corners()`.
+    pub(crate) fn texture_coords(&self, image_size: Vec2) -> [Vec2; 4]
+    {
+        let normalize =
+            |point: &Vec2| Vec2::new(point.x / image_size.x, point.y / image_size.y);
+
+        let top_left = normalize(self.source.top_left());
+        let bottom_right = normalize(self.source.bottom_right());
+
+        [
+            top_left,
+            Vec2::new(bottom_right.x, top_left.y),
+            bottom_right,
+            Vec2::new(top_left.x, bottom_right.y)
+        ]
+    }
+
+    #[inline]
+    pub(crate) fn tint(&self) -> Color
+    {
+        self.tint
+    }
+
+    #[inline]
+    pub(crate) fn depth(&self) -> f32
+    {
+        self.depth
+    }
+}
+
+/// A collection of sprites which share the same image, and are drawn
+/// together using [crate::Graphics2D::draw_sprite_batch]. This is much faster
+/// than drawing each sprite as a separate image, and is intended for scenes
+/// containing thousands of sprites, such as particle effects.
+///
+/// The batch can be kept and modified between frames, or rebuilt each frame.
+///
+/// ```rust,no_run
+/// # use speedy2d::image::ImageHandle;
+/// # use speedy2d::shape::Rectangle;
+/// # use speedy2d::sprite::{Sprite, SpriteBatch};
+/// # use speedy2d::Graphics2D;
+/// # fn draw(graphics: &mut Graphics2D, particle_image: &ImageHandle) {
+/// let mut batch = SpriteBatch::new(particle_image);
+///
+/// for i in 0..10000 {
+///     let position = ((i % 100) as f32 * 8.0, (i / 100) as f32 * 8.0);
+///     batch.push(Sprite::new(Rectangle::from_tuples((0.0, 0.0), (8.0, 8.0)), position));
+/// }
+///
+/// graphics.draw_sprite_batch(&batch);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpriteBatch
+{
+    image: ImageHandle,
+    sprites: Vec<Sprite>
+}
+
+impl SpriteBatch
+{
+    /// Creates an empty batch, for sprites showing parts of the specified
+    /// image.
+    #[inline]
+    #[must_use]
+    pub fn new(image: &ImageHandle) -> Self
+    {
+        SpriteBatch {
+            image: image.clone(),
+            sprites: Vec::new()
+        }
+    }
+
+    /// Returns the image which the sprites show.
+    #[inline]
+    pub fn image(&self) -> &ImageHandle
+    {
+        &self.image
+    }
+
+    /// Adds a sprite to the batch.
+    #[inline]
+    pub fn push(&mut self, sprite: Sprite)
+    {
+        self.sprites.push(sprite);
+    }
+
+    /// Removes all sprites from the batch, so that it can be reused.
+    #[inline]
+    pub fn clear(&mut self)
+    {
+        self.sprites.clear();
+    }
+
+    /// Returns the sprites in the batch.
+    #[inline]
+    pub fn sprites(&self) -> &[Sprite]
+    {
+        &self.sprites
+    }
+
+    /// Returns the sprites in the batch, allowing them to be modified.
+    #[inline]
+    pub fn sprites_mut(&mut self) -> &mut [Sprite]
+    {
+        &mut self.sprites
+    }
+
+    /// Returns the number of sprites in the batch.
+    #[inline]
+    pub fn len(&self) -> usize
+    {
+        self.sprites.len()
+    }
+
+    /// Returns true if the batch contains no sprites.
+    #[inline]
+    pub fn is_empty(&self) -> bool
+    {
+        self.sprites.is_empty()
+    }
+}
+
+impl Extend<Sprite> for SpriteBatch
+{
+    fn extend<T: IntoIterator<Item = Sprite>>(&mut self, iter: T)
+    {
+        self.sprites.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::image::{ImageDataType, ImageSmoothingMode, RawBitmapData};
+    use crate::shape::Rectangle;
+    use crate::test_utils::{pixel, render_frame};
+    use crate::transform::Transform;
+    use crate::{GLRenderer, Graphics2D};
+
+    fn render<F: FnOnce(&mut Graphics2D, &ImageHandle)>(action: F) -> RawBitmapData
+    {
+        let mut renderer = GLRenderer::new_software((40, 40)).unwrap();
+
+        render_frame(&mut renderer, Color::BLACK, |graphics| {
+            // Red on the left half, green on the right half
+            let image = graphics
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGB,
+                    ImageSmoothingMode::NearestNeighbor,
+                    (2, 1),
+                    &[255, 0, 0, 0, 255, 0]
+                )
+                .unwrap();
+
+            action(graphics, &image);
+        })
+        .1
+    }
+
+    #[test]
+    fn corners()
+    {
+        let sprite =
+            Sprite::new(Rectangle::from_tuples((0.0, 0.0), (4.0, 2.0)), (10.0, 10.0))
+                .with_origin((2.0, 1.0))
+                .with_scale((2.0, 3.0))
+                .with_rotation(std::f32::consts::FRAC_PI_2);
+
+        let corners = sprite.corners();
+        let expected = [(13.0, 6.0), (13.0, 14.0), (7.0, 14.0), (7.0, 6.0)];
+
+        for (corner, expected) in corners.iter().zip(expected) {
+            assert!((*corner - Vec2::from(expected)).magnitude() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn draw_batch()
+    {
+        let image = render(|graphics, image| {
+            let mut batch = SpriteBatch::new(image);
+
+            let green = Rectangle::from_tuples((1.0, 0.0), (2.0, 1.0));
+            let red = Rectangle::from_tuples((0.0, 0.0), (1.0, 1.0));
+
+            batch.push(Sprite::new(&green, (0.0, 0.0)).with_scale((10.0, 10.0)));
+
+            // Drawn underneath the first sprite, despite being added later
+            batch.push(
+                Sprite::new(&red, (5.0, 5.0))
+                    .with_scale((10.0, 10.0))
+                    .with_depth(-1.0)
+            );
+
+            batch.push(
+                Sprite::new(&red, (30.0, 30.0))
+                    .with_origin((0.5, 0.5))
+                    .with_scale((10.0, 10.0))
+                    .with_tint(Color::from_rgb(1.0, 1.0, 0.0))
+            );
+
+            graphics.with_transform(Transform::translate((20.0, 0.0)), |graphics| {
+                graphics.draw_sprite_batch(&batch);
+            });
+        });
+
+        assert_eq!([0, 255, 0, 255], pixel(&image, 27, 7));
+        assert_eq!([255, 0, 0, 255], pixel(&image, 32, 12));
+        assert_eq!([0, 0, 0, 255], pixel(&image, 5, 5));
+
+        // The third sprite is outside the window after translation
+        assert_eq!([0, 0, 0, 255], pixel(&image, 30, 30));
+    }
+
+    #[test]
+    fn recorded_batch_matches()
+    {
+        let create_batch = |image: &ImageHandle| {
+            let mut batch = SpriteBatch::new(image);
+
+            for i in 0..10 {
+                batch.push(
+                    Sprite::new(
+                        Rectangle::from_tuples((0.0, 0.0), (2.0, 1.0)),
+                        (20.0, 20.0)
+                    )
+                    .with_origin((1.0, 0.5))
+                    .with_scale((8.0, 8.0))
+                    .with_rotation(i as f32 * 0.3)
+                    .with_depth((i % 3) as f32)
+                );
+            }
+
+            batch
+        };
+
+        let direct = render(|graphics, image| {
+            let batch = create_batch(image);
+            graphics.draw_sprite_batch(&batch);
+        });
+
+        let recorded = render(|graphics, image| {
+            let batch = create_batch(image);
+            graphics.record(|graphics| graphics.draw_sprite_batch(&batch));
+        });
+
+        assert_eq!(direct.data(), recorded.data());
+    }
+}
@@ -0,0 +1,1201 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::f32::consts::PI;
+
+use crate::dimen::Vec2;
+use crate::shape::{LineCap, LineJoin, Path, PathSegment, StrokeStyle};
+
+/// The maximum distance in pixels between a curve and the straight line
+/// segments used to approximate it.
+pub(crate) const DEFAULT_TOLERANCE: f32 = 0.25;
+
+/// Upper bound on the number of line segments generated for a single curve or
+/// arc, to avoid pathological cases with huge coordinates.
+const MAX_SEGMENTS_PER_CURVE: usize = 1024;
+
+/// Positive dash and gap lengths shorter than this are lengthened, as they
+/// are too small to be visible.
+const MIN_DASH_LENGTH: f32 = 0.01;
+
+/// Upper bound on the number of dashes and gaps generated for a single line
+/// segment, to avoid pathological cases with huge coordinates.
+const MAX_DASHES_PER_EDGE: usize = 100_000;
+
+/// A single subpath, approximated using straight line segments.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct FlattenedSubpath
+{
+    pub(crate) points: Vec<Vec2>,
+    pub(crate) closed: bool
+}
+
+#[inline]
+fn dot(a: Vec2, b: Vec2) -> f32
+{
+    a.x * b.x + a.y * b.y
+}
+
+#[inline]
+fn cross(a: Vec2, b: Vec2) -> f32
+{
+    a.x * b.y - a.y * b.x
+}
+
+#[inline]
+fn rotate(v: Vec2, angle: f32) -> Vec2
+{
+    let (sin, cos) = angle.sin_cos();
+    Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+#[inline]
+fn lerp(a: Vec2, b: Vec2, t: f32) -> Vec2
+{
+    a + (b - a) * t
+}
+
+#[inline]
+fn is_same_point(a: Vec2, b: Vec2) -> bool
+{
+    (a - b).magnitude_squared() < 1.0e-10
+}
+
+/// Returns the number of segments required to approximate an arc of the given
+/// radius and angle, so that the error is no larger than `tolerance`.
+pub(crate) fn arc_segment_count(radius: f32, angle: f32, tolerance: f32) -> usize
+{
+    if radius <= tolerance {
+        return 1;
+    }
+
+    let max_step = 2.0 * (1.0 - tolerance / radius).clamp(-1.0, 1.0).acos();
+    let count = (angle.abs() / max_step).ceil();
+
+    if count.is_nan() {
+        1
+    } else {
+        (count as usize).clamp(1, MAX_SEGMENTS_PER_CURVE)
+    }
+}
+
+fn curve_segment_count(second_difference: f32, tolerance: f32) -> usize
+{
+    let count = (second_difference / tolerance).sqrt().ceil();
+
+    if count.is_nan() {
+        1
+    } else {
+        (count as usize).clamp(1, MAX_SEGMENTS_PER_CURVE)
+    }
+}
+
+struct SubpathBuilder
+{
+    result: Vec<FlattenedSubpath>,
+    points: Vec<Vec2>,
+    has_segments: bool,
+    start: Option<Vec2>
+}
+
+impl SubpathBuilder
+{
+    fn current_point(&self) -> Option<Vec2>
+    {
+        self.points.last().copied()
+    }
+
+    fn finish(&mut self, closed: bool)
+    {
+        let mut points = std::mem::take(&mut self.points);
+
+        if self.has_segments && !points.is_empty() {
+            if closed
+                && points.len() > 1
+                && is_same_point(points[0], *points.last().unwrap())
+            {
+                points.pop();
+            }
+
+            self.result.push(FlattenedSubpath { points, closed });
+        }
+
+        self.has_segments = false;
+    }
+
+    fn move_to(&mut self, point: Vec2)
+    {
+        self.finish(false);
+        self.points.push(point);
+        self.start = Some(point);
+    }
+
+    /// Returns the current point, starting a new subpath at `fallback` if
+    /// there isn't one.
+    fn ensure_started(&mut self, fallback: Vec2) -> Vec2
+    {
+        match self.current_point() {
+            Some(point) => point,
+            None => {
+                self.move_to(fallback);
+                fallback
+            }
+        }
+    }
+
+    fn line_to(&mut self, point: Vec2)
+    {
+        self.has_segments = true;
+
+        if let Some(last) = self.current_point() {
+            if is_same_point(last, point) {
+                return;
+            }
+        }
+
+        self.points.push(point);
+    }
+
+    fn close(&mut self)
+    {
+        let start = self.start;
+        self.finish(true);
+
+        // Any further segments continue from the start of the closed subpath
+        if let Some(start) = start {
+            self.points.push(start);
+        }
+    }
+}
+
+fn flatten_quad(
+    builder: &mut SubpathBuilder,
+    from: Vec2,
+    control: Vec2,
+    end: Vec2,
+    tolerance: f32
+)
+{
+    let second_difference = (from - control * 2.0 + end).magnitude();
+    let count = curve_segment_count(second_difference / 4.0, tolerance);
+
+    for i in 1..=count {
+        let t = i as f32 / count as f32;
+        builder.line_to(lerp(lerp(from, control, t), lerp(control, end, t), t));
+    }
+}
+
+fn flatten_cubic(
+    builder: &mut SubpathBuilder,
+    from: Vec2,
+    control_1: Vec2,
+    control_2: Vec2,
+    end: Vec2,
+    tolerance: f32
+)
+{
+    let second_difference = (from - control_1 * 2.0 + control_2)
+        .magnitude()
+        .max((control_1 - control_2 * 2.0 + end).magnitude());
+
+    let count = curve_segment_count(second_difference * 0.75, tolerance);
+
+    for i in 1..=count {
+        let t = i as f32 / count as f32;
+
+        let a = lerp(from, control_1, t);
+        let b = lerp(control_1, control_2, t);
+        let c = lerp(control_2, end, t);
+
+        builder.line_to(lerp(lerp(a, b, t), lerp(b, c, t), t));
+    }
+}
+
+/// Converts an SVG-style endpoint arc into a series of line segments. See
+/// section F.6.5 of the SVG 1.1 specification.
+#[allow(clippy::too_many_arguments)]
+fn flatten_arc(
+    builder: &mut SubpathBuilder,
+    from: Vec2,
+    radii: Vec2,
+    x_rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    end: Vec2,
+    tolerance: f32
+)
+{
+    if is_same_point(from, end) {
+        return;
+    }
+
+    let mut rx = radii.x.abs();
+    let mut ry = radii.y.abs();
+
+    if rx < 1.0e-6 || ry < 1.0e-6 {
+        builder.line_to(end);
+        return;
+    }
+
+    let (sin_phi, cos_phi) = x_rotation.sin_cos();
+
+    let half_diff = (from - end) / 2.0;
+
+    let x1p = cos_phi * half_diff.x + sin_phi * half_diff.y;
+    let y1p = -sin_phi * half_diff.x + cos_phi * half_diff.y;
+
+    let lambda = (x1p * x1p) / (rx * rx) + (y1p * y1p) / (ry * ry);
+
+    if lambda > 1.0 {
+        let scale = lambda.sqrt();
+        rx *= scale;
+        ry *= scale;
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1p * y1p - ry * ry * x1p * x1p;
+    let denominator = rx * rx * y1p * y1p + ry * ry * x1p * x1p;
+
+    let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+
+    if large_arc == sweep {
+        coefficient = -coefficient;
+    }
+
+    let cxp = coefficient * rx * y1p / ry;
+    let cyp = -coefficient * ry * x1p / rx;
+
+    let center = Vec2::new(
+        cos_phi * cxp - sin_phi * cyp + (from.x + end.x) / 2.0,
+        sin_phi * cxp + cos_phi * cyp + (from.y + end.y) / 2.0
+    );
+
+    let start_vector = Vec2::new((x1p - cxp) / rx, (y1p - cyp) / ry);
+    let end_vector = Vec2::new((-x1p - cxp) / rx, (-y1p - cyp) / ry);
+
+    let start_angle = start_vector.y.atan2(start_vector.x);
+
+    let mut sweep_angle =
+        cross(start_vector, end_vector).atan2(dot(start_vector, end_vector));
+
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    }
+
+    let count = arc_segment_count(rx.max(ry), sweep_angle, tolerance);
+
+    for i in 1..count {
+        let angle = start_angle + sweep_angle * (i as f32 / count as f32);
+        let (sin, cos) = angle.sin_cos();
+
+        builder.line_to(Vec2::new(
+            center.x + rx * cos * cos_phi - ry * sin * sin_phi,
+            center.y + rx * cos * sin_phi + ry * sin * cos_phi
+        ));
+    }
+
+    builder.line_to(end);
+}
+
+/// Approximates each subpath of the provided path using straight lines.
+pub(crate) fn flatten(path: &Path, tolerance: f32) -> Vec<FlattenedSubpath>
+{
+    let mut builder = SubpathBuilder {
+        result: Vec::new(),
+        points: Vec::new(),
+        has_segments: false,
+        start: None
+    };
+
+    for segment in &path.segments {
+        match segment {
+            PathSegment::MoveTo(point) => builder.move_to(*point),
+
+            PathSegment::LineTo(point) => {
+                builder.ensure_started(*point);
+                builder.line_to(*point);
+            }
+
+            PathSegment::QuadTo(control, end) => {
+                let from = builder.ensure_started(*control);
+                flatten_quad(&mut builder, from, *control, *end, tolerance);
+            }
+
+            PathSegment::CubicTo(control_1, control_2, end) => {
+                let from = builder.ensure_started(*control_1);
+                flatten_cubic(
+                    &mut builder,
+                    from,
+                    *control_1,
+                    *control_2,
+                    *end,
+                    tolerance
+                );
+            }
+
+            PathSegment::ArcTo {
+                radii,
+                x_rotation,
+                large_arc,
+                sweep,
+                end
+            } => {
+                let from = builder.ensure_started(*end);
+                flatten_arc(
+                    &mut builder,
+                    from,
+                    *radii,
+                    *x_rotation,
+                    *large_arc,
+                    *sweep,
+                    *end,
+                    tolerance
+                );
+            }
+
+            PathSegment::Close => builder.close()
+        }
+    }
+
+    builder.finish(false);
+    builder.result
+}
+
+/// Splits the provided subpaths into a series of dashes, using the
+/// specified dash pattern. The pattern must contain at least one positive
+/// length, and no negative lengths.
+pub(crate) fn dash(
+    subpaths: &[FlattenedSubpath],
+    pattern: &[f32],
+    phase: f32
+) -> Vec<FlattenedSubpath>
+{
+    let mut pattern: Vec<f32> = pattern
+        .iter()
+        .map(|length| match *length {
+            length if length > 0.0 => length.max(MIN_DASH_LENGTH),
+            length => length
+        })
+        .collect();
+
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_within(..);
+    }
+
+    let pattern_length: f32 = pattern.iter().sum();
+
+    let mut result = Vec::new();
+
+    for subpath in subpaths {
+        // Find the starting position within the pattern
+        let mut index = 0;
+        let mut remaining = pattern[0];
+        let mut offset = phase.rem_euclid(pattern_length);
+
+        while offset > 0.0 {
+            if offset < remaining {
+                remaining -= offset;
+                offset = 0.0;
+            } else {
+                offset -= remaining;
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+        }
+
+        // Even indices in the pattern are dashes, odd indices are gaps
+        let starts_on = index & 1 == 0;
+        let first_dash_index = result.len();
+
+        let mut current: Option<Vec<Vec2>> = None;
+
+        if starts_on {
+            current = Some(vec![subpath.points[0]]);
+        }
+
+        let edge_count = if subpath.closed {
+            subpath.points.len()
+        } else {
+            subpath.points.len() - 1
+        };
+
+        for i in 0..edge_count {
+            let start = subpath.points[i];
+            let end = subpath.points[(i + 1) % subpath.points.len()];
+            let length = (end - start).magnitude();
+
+            if length <= 0.0 {
+                continue;
+            }
+
+            let mut position = 0.0;
+            let mut dash_count = 0;
+
+            while length - position >= remaining {
+                let next_position = position + remaining;
+
+                // If the position stops increasing due to rounding, or there
+                // are too many dashes, the current dash or gap continues to
+                // the end of the edge.
+                if dash_count == MAX_DASHES_PER_EDGE
+                    || (remaining > 0.0 && next_position <= position)
+                {
+                    position = length;
+                    remaining = 0.0;
+                    break;
+                }
+
+                position = next_position;
+                dash_count += 1;
+                let point = lerp(start, end, position / length);
+
+                match current.take() {
+                    Some(mut points) => {
+                        if !is_same_point(*points.last().unwrap(), point) {
+                            points.push(point);
+                        }
+
+                        result.push(FlattenedSubpath {
+                            points,
+                            closed: false
+                        });
+                    }
+                    None => current = Some(vec![point])
+                }
+
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+
+            remaining -= length - position;
+
+            if let Some(points) = &mut current {
+                if !is_same_point(*points.last().unwrap(), end) {
+                    points.push(end);
+                }
+            }
+        }
+
+        if let Some(mut points) = current {
+            if subpath.closed && starts_on {
+                if result.len() == first_dash_index {
+                    // The whole subpath is covered by a single dash
+                    points.pop();
+                    result.push(FlattenedSubpath {
+                        points,
+                        closed: true
+                    });
+                } else {
+                    // Join the last dash to the first, so that the corner at
+                    // the start of the subpath is drawn correctly
+                    let first = &mut result[first_dash_index].points;
+                    points.extend_from_slice(&first[1..]);
+                    *first = points;
+                }
+            } else {
+                result.push(FlattenedSubpath {
+                    points,
+                    closed: false
+                });
+            }
+        }
+    }
+
+    result
+}
+
+fn push_quad(out: &mut Vec<[Vec2; 3]>, quad: [Vec2; 4])
+{
+    out.push([quad[0], quad[1], quad[2]]);
+    out.push([quad[2], quad[3], quad[0]]);
+}
+
+/// Adds a fan of triangles around `center`, starting at `center +
+/// start_offset` and rotating by `sweep_angle` radians.
+fn push_fan(
+    out: &mut Vec<[Vec2; 3]>,
+    center: Vec2,
+    start_offset: Vec2,
+    sweep_angle: f32,
+    tolerance: f32
+)
+{
+    push_fan_from(out, center, center, start_offset, sweep_angle, tolerance);
+}
+
+/// Adds triangles from `apex` to each edge of an arc around `center`,
+/// starting at `center + start_offset` and rotating by `sweep_angle` radians.
+fn push_fan_from(
+    out: &mut Vec<[Vec2; 3]>,
+    apex: Vec2,
+    center: Vec2,
+    start_offset: Vec2,
+    sweep_angle: f32,
+    tolerance: f32
+)
+{
+    let count = arc_segment_count(start_offset.magnitude(), sweep_angle, tolerance);
+
+    let mut previous = center + start_offset;
+
+    for i in 1..=count {
+        let next = center + rotate(start_offset, sweep_angle * (i as f32 / count as f32));
+        out.push([apex, previous, next]);
+        previous = next;
+    }
+}
+
+/// The offsets from a point where two segments of a stroke meet.
+struct JoinOffsets
+{
+    /// The corners of the incoming and outgoing segments on the outer side
+    /// of the turn.
+    outer_in: Vec2,
+    outer_out: Vec2,
+
+    /// Where the edges on the inner side of the turn cross, if both segments
+    /// are long enough for them to meet there.
+    inner: Option<Vec2>,
+
+    /// True if the inner side is the one in the direction of each segment's
+    /// normal, which points clockwise from its direction.
+    inner_on_normal_side: bool
+}
+
+/// Returns `None` if the segments continue in the same direction, in which
+/// case no join is needed.
+fn join_offsets(
+    incoming: Vec2,
+    outgoing: Vec2,
+    incoming_length: f32,
+    outgoing_length: f32,
+    half_width: f32
+) -> Option<JoinOffsets>
+{
+    let turn = cross(incoming, outgoing);
+    let alignment = dot(incoming, outgoing);
+
+    if turn.abs() < 1.0e-6 && alignment > 0.0 {
+        return None;
+    }
+
+    let normal_in = incoming.rotate_90_degrees_clockwise() * half_width;
+    let normal_out = outgoing.rotate_90_degrees_clockwise() * half_width;
+
+    // The join is added to the outer side of the corner
+    let (outer_in, outer_out) = if turn > 0.0 {
+        (normal_in * -1.0, normal_out * -1.0)
+    } else {
+        (normal_in, normal_out)
+    };
+
+    // The inner edges of the segments cross at this distance before and
+    // after the point, which is half_width * tan(theta / 2)
+    let inner_extent = half_width * turn.abs() / (1.0 + alignment);
+
+    let inner = if alignment > -0.99
+        && inner_extent <= incoming_length / 2.0
+        && inner_extent <= outgoing_length / 2.0
+    {
+        Some((outer_in + outer_out) * (-1.0 / (1.0 + alignment)))
+    } else {
+        None
+    };
+
+    Some(JoinOffsets {
+        outer_in,
+        outer_out,
+        inner,
+        inner_on_normal_side: turn > 0.0
+    })
+}
+
+/// Adds the part of a join outside the two segments. When the segments
+/// share an inner corner, the triangles start from that corner, so that they
+/// don't overlap the segments.
+fn push_join(
+    out: &mut Vec<[Vec2; 3]>,
+    point: Vec2,
+    incoming: Vec2,
+    offsets: &JoinOffsets,
+    style: &StrokeStyle,
+    tolerance: f32
+)
+{
+    let half_width = style.width / 2.0;
+    let apex = point + offsets.inner.unwrap_or(Vec2::ZERO);
+    let (outer_in, outer_out) = (offsets.outer_in, offsets.outer_out);
+
+    let bisector = outer_in + outer_out;
+    let bisector_length_squared = bisector.magnitude_squared();
+
+    match style.line_join {
+        LineJoin::Bevel => out.push([apex, point + outer_in, point + outer_out]),
+
+        LineJoin::Miter => {
+            // The ratio between the miter length and the stroke width is
+            // 1/sin(theta/2), which simplifies to the following.
+            let miter_ratio_squared =
+                (4.0 * half_width * half_width) / bisector_length_squared;
+
+            if bisector_length_squared < 1.0e-10
+                || miter_ratio_squared > style.miter_limit * style.miter_limit
+            {
+                out.push([apex, point + outer_in, point + outer_out]);
+            } else {
+                let tip = point
+                    + bisector
+                        * (2.0 * half_width * half_width / bisector_length_squared);
+
+                out.push([apex, point + outer_in, tip]);
+                out.push([apex, tip, point + outer_out]);
+            }
+        }
+
+        LineJoin::Round => {
+            let sweep_angle = if bisector_length_squared < 1.0e-10 {
+                // A complete reversal: bulge out in the original direction
+                PI * cross(outer_in, incoming).signum()
+            } else {
+                cross(outer_in, outer_out).atan2(dot(outer_in, outer_out))
+            };
+
+            push_fan_from(out, apex, point, outer_in, sweep_angle, tolerance);
+        }
+    }
+}
+
+/// Adds a cap to the end of a stroke, where `direction` is the normalized
+/// direction pointing away from the stroke.
+fn push_cap(
+    out: &mut Vec<[Vec2; 3]>,
+    point: Vec2,
+    direction: Vec2,
+    style: &StrokeStyle,
+    tolerance: f32
+)
+{
+    let half_width = style.width / 2.0;
+    let normal = direction.rotate_90_degrees_clockwise() * half_width;
+
+    match style.line_cap {
+        LineCap::Butt => {}
+
+        LineCap::Square => {
+            let extension = direction * half_width;
+
+            push_quad(
+                out,
+                [
+                    point + normal,
+                    point + normal + extension,
+                    point - normal + extension,
+                    point - normal
+                ]
+            );
+        }
+
+        LineCap::Round => push_fan(
+            out,
+            point,
+            normal,
+            PI * cross(normal, direction).signum(),
+            tolerance
+        )
+    }
+}
+
+fn stroke_subpath(
+    out: &mut Vec<[Vec2; 3]>,
+    subpath: &FlattenedSubpath,
+    style: &StrokeStyle,
+    tolerance: f32
+)
+{
+    let points = &subpath.points;
+    let half_width = style.width / 2.0;
+
+    if points.len() == 1 {
+        if !subpath.closed {
+            // A zero-length subpath only has caps
+            match style.line_cap {
+                LineCap::Butt => {}
+                LineCap::Round => push_fan(
+                    out,
+                    points[0],
+                    Vec2::new(half_width, 0.0),
+                    2.0 * PI,
+                    tolerance
+                ),
+                LineCap::Square => push_quad(
+                    out,
+                    [
+                        points[0] + Vec2::new(-half_width, -half_width),
+                        points[0] + Vec2::new(half_width, -half_width),
+                        points[0] + Vec2::new(half_width, half_width),
+                        points[0] + Vec2::new(-half_width, half_width)
+                    ]
+                )
+            }
+        }
+
+        return;
+    }
+
+    let segment_count = if subpath.closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+
+    let directions: Vec<Vec2> = (0..segment_count)
+        .map(|i| {
+            (points[(i + 1) % points.len()] - points[i])
+                .normalize()
+                .unwrap_or(Vec2::new(1.0, 0.0))
+        })
+        .collect();
+
+    let lengths: Vec<f32> = (0..segment_count)
+        .map(|i| (points[(i + 1) % points.len()] - points[i]).magnitude())
+        .collect();
+
+    // The offsets of the corners on each side at the start and end of each
+    // segment, with the normal side first. Where segments share an inner corner,
+    // these are moved so that the segments don't overlap.
+    let mut starts: Vec<(Vec2, Vec2)> = Vec::with_capacity(segment_count);
+    let mut ends: Vec<(Vec2, Vec2)> = Vec::with_capacity(segment_count);
+
+    for direction in &directions {
+        let normal = direction.rotate_90_degrees_clockwise() * half_width;
+        starts.push((normal, normal * -1.0));
+        ends.push((normal, normal * -1.0));
+    }
+
+    // Each join is between the segment before the point and the one after
+    let join_points: Vec<usize> = if subpath.closed {
+        (0..points.len()).collect()
+    } else {
+        (1..points.len() - 1).collect()
+    };
+
+    let mut joins = Vec::with_capacity(join_points.len());
+
+    for i in join_points {
+        let before = (i + segment_count - 1) % segment_count;
+
+        let offsets = match join_offsets(
+            directions[before],
+            directions[i],
+            lengths[before],
+            lengths[i],
+            half_width
+        ) {
+            None => continue,
+            Some(offsets) => offsets
+        };
+
+        if let Some(inner) = offsets.inner {
+            if offsets.inner_on_normal_side {
+                ends[before].0 = inner;
+                starts[i].0 = inner;
+            } else {
+                ends[before].1 = inner;
+                starts[i].1 = inner;
+            }
+        }
+
+        joins.push((i, before, offsets));
+    }
+
+    for i in 0..segment_count {
+        let start = points[i];
+        let end = points[(i + 1) % points.len()];
+
+        push_quad(
+            out,
+            [
+                start + starts[i].0,
+                end + ends[i].0,
+                end + ends[i].1,
+                start + starts[i].1
+            ]
+        );
+    }
+
+    for (i, before, offsets) in joins {
+        push_join(
+            out,
+            points[i],
+            directions[before],
+            &offsets,
+            style,
+            tolerance
+        );
+    }
+
+    if !subpath.closed {
+        push_cap(out, points[0], directions[0] * -1.0, style, tolerance);
+        push_cap(
+            out,
+            *points.last().unwrap(),
+            *directions.last().unwrap(),
+            style,
+            tolerance
+        );
+    }
+}
+
+/// Generates the triangles which make up the outline of the provided
+/// subpaths.
+pub(crate) fn stroke(subpaths: &[FlattenedSubpath], style: &StrokeStyle)
+    -> Vec<[Vec2; 3]>
+{
+    let mut out = Vec::new();
+
+    if style.width.is_nan() || style.width <= 0.0 {
+        return out;
+    }
+
+    for subpath in subpaths {
+        stroke_subpath(&mut out, subpath, style, DEFAULT_TOLERANCE);
+    }
+
+    out
+}
+
+/// Generates the triangles which make up the interior of the provided
+/// subpaths. The first subpath is treated as the outline of the shape, and any
+/// further subpaths are treated as holes.
+pub(crate) fn fill(subpaths: &[FlattenedSubpath]) -> Vec<[Vec2; 3]>
+{
+    let mut vertices = Vec::new();
+    let mut flattened = Vec::new();
+    let mut hole_indices = Vec::new();
+
+    for subpath in subpaths.iter().filter(|subpath| subpath.points.len() >= 3) {
+        if !vertices.is_empty() {
+            hole_indices.push(vertices.len());
+        }
+
+        for point in &subpath.points {
+            vertices.push(*point);
+            flattened.push(point.x);
+            flattened.push(point.y);
+        }
+    }
+
+    if vertices.is_empty() {
+        return Vec::new();
+    }
+
+    earcutr::earcut(&flattened, &hole_indices, 2)
+        .chunks_exact(3)
+        .map(|triangle| {
+            [
+                vertices[triangle[0]],
+                vertices[triangle[1]],
+                vertices[triangle[2]]
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::color::Color;
+    use crate::test_utils::{pixel, render};
+
+    fn triangles_area(triangles: &[[Vec2; 3]]) -> f32
+    {
+        triangles
+            .iter()
+            .map(|[a, b, c]| (cross(*b - *a, *c - *a) / 2.0).abs())
+            .sum()
+    }
+
+    #[test]
+    fn flatten_lines_and_close()
+    {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((10.0, 0.0))
+            .line_to((10.0, 0.0))
+            .line_to((10.0, 10.0))
+            .close()
+            .line_to((0.0, 20.0));
+
+        assert_eq!(
+            vec![
+                FlattenedSubpath {
+                    points: vec![
+                        Vec2::new(0.0, 0.0),
+                        Vec2::new(10.0, 0.0),
+                        Vec2::new(10.0, 10.0)
+                    ],
+                    closed: true
+                },
+                FlattenedSubpath {
+                    points: vec![Vec2::new(0.0, 0.0), Vec2::new(0.0, 20.0)],
+                    closed: false
+                }
+            ],
+            flatten(&path, DEFAULT_TOLERANCE)
+        );
+    }
+
+    #[test]
+    fn flatten_curves_end_at_end_point()
+    {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .quad_to((50.0, 100.0), (100.0, 0.0))
+            .cubic_to((100.0, 50.0), (0.0, 50.0), (0.0, 100.0))
+            .arc_to((50.0, 50.0), 0.0, false, true, (100.0, 100.0));
+
+        let subpaths = flatten(&path, DEFAULT_TOLERANCE);
+
+        assert_eq!(1, subpaths.len());
+        assert!(subpaths[0].points.len() > 10);
+        assert_eq!(Vec2::new(100.0, 100.0), *subpaths[0].points.last().unwrap());
+
+        // A clockwise semicircle between (0, 100) and (100, 100) passes
+        // through (50, 50)
+        assert!(subpaths[0]
+            .points
+            .iter()
+            .any(|point| (*point - Vec2::new(50.0, 50.0)).magnitude() < 1.0));
+    }
+
+    #[test]
+    fn stroke_rectangle_joins()
+    {
+        let square = flatten(
+            &Path::new()
+                .move_to((0.0, 0.0))
+                .line_to((100.0, 0.0))
+                .line_to((100.0, 100.0))
+                .line_to((0.0, 100.0))
+                .close(),
+            DEFAULT_TOLERANCE
+        );
+
+        let miter = stroke(&square, &StrokeStyle::new(10.0));
+        let bevel = stroke(
+            &square,
+            &StrokeStyle::new(10.0).with_line_join(LineJoin::Bevel)
+        );
+
+        // A 110x110 square with a 90x90 hole, without any overlapping
+        // triangles (or missing half of each 5x5 corner for bevel joins)
+        assert!((triangles_area(&miter) - 4000.0).abs() < 0.01);
+        assert!((triangles_area(&bevel) - 3950.0).abs() < 0.01);
+
+        // A sharper corner than the limit falls back to a bevel
+        let limited = stroke(&square, &StrokeStyle::new(10.0).with_miter_limit(1.0));
+        assert!((triangles_area(&limited) - 3950.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn stroke_caps()
+    {
+        let line = flatten(
+            &Path::new().move_to((0.0, 0.0)).line_to((100.0, 0.0)),
+            DEFAULT_TOLERANCE
+        );
+
+        let butt = stroke(&line, &StrokeStyle::new(10.0));
+        let square = stroke(
+            &line,
+            &StrokeStyle::new(10.0).with_line_cap(LineCap::Square)
+        );
+        let round = stroke(&line, &StrokeStyle::new(10.0).with_line_cap(LineCap::Round));
+
+        assert!((triangles_area(&butt) - 1000.0).abs() < 0.01);
+        assert!((triangles_area(&square) - 1100.0).abs() < 0.01);
+
+        // The caps are approximated by polygons inside the circle
+        let round_area = triangles_area(&round);
+        let circle_area = PI * 25.0;
+        assert!(round_area > 1000.0 + circle_area * 0.9);
+        assert!(round_area < 1000.0 + circle_area);
+    }
+
+    #[test]
+    fn dash_continues_around_corners()
+    {
+        let square = flatten(
+            &Path::new()
+                .move_to((0.0, 0.0))
+                .line_to((100.0, 0.0))
+                .line_to((100.0, 100.0))
+                .line_to((0.0, 100.0))
+                .close(),
+            DEFAULT_TOLERANCE
+        );
+
+        let dashes = dash(&square, &[30.0, 20.0], 10.0);
+
+        // The first dash is 20px long, and the last dash (10px long) joins
+        // onto it at the top left corner
+        assert_eq!(8, dashes.len());
+
+        assert_eq!(
+            FlattenedSubpath {
+                points: vec![
+                    Vec2::new(0.0, 10.0),
+                    Vec2::new(0.0, 0.0),
+                    Vec2::new(20.0, 0.0)
+                ],
+                closed: false
+            },
+            dashes[0]
+        );
+
+        assert_eq!(
+            FlattenedSubpath {
+                points: vec![
+                    Vec2::new(90.0, 0.0),
+                    Vec2::new(100.0, 0.0),
+                    Vec2::new(100.0, 20.0)
+                ],
+                closed: false
+            },
+            dashes[2]
+        );
+    }
+
+    #[test]
+    fn dash_zero_length_and_odd_patterns()
+    {
+        let line = flatten(
+            &Path::new().move_to((0.0, 0.0)).line_to((25.0, 0.0)),
+            DEFAULT_TOLERANCE
+        );
+
+        let dots = dash(&line, &[0.0, 10.0], 0.0);
+
+        assert_eq!(
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(10.0, 0.0),
+                Vec2::new(20.0, 0.0)
+            ],
+            dots.iter()
+                .map(|subpath| {
+                    assert_eq!(1, subpath.points.len());
+                    subpath.points[0]
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // An odd pattern is repeated: [5, 5, 5, 5] would give 3 dashes
+        assert_eq!(3, dash(&line, &[5.0], 0.0).len());
+    }
+
+    #[test]
+    fn dash_tiny_lengths_terminate()
+    {
+        let line = flatten(
+            &Path::new().move_to((0.0, 0.0)).line_to((1.0e7, 0.0)),
+            DEFAULT_TOLERANCE
+        );
+
+        let dashes = dash(&line, &[1.0e-6, 1.0e-6], 0.0);
+
+        assert!(dashes.len() <= MAX_DASHES_PER_EDGE / 2 + 1);
+        assert_eq!(
+            Vec2::new(1.0e7, 0.0),
+            *dashes.last().unwrap().points.last().unwrap()
+        );
+
+        // Positive lengths are clamped, so short dashes still cover the line
+        let short = flatten(
+            &Path::new().move_to((0.0, 0.0)).line_to((1.0, 0.0)),
+            DEFAULT_TOLERANCE
+        );
+
+        let short_dashes = dash(&short, &[1.0e-6, 1.0e-6], 0.0).len();
+        assert!((50..=51).contains(&short_dashes));
+    }
+
+    #[test]
+    fn fill_with_hole()
+    {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((100.0, 0.0))
+            .line_to((100.0, 100.0))
+            .line_to((0.0, 100.0))
+            .close()
+            .move_to((25.0, 25.0))
+            .line_to((75.0, 25.0))
+            .line_to((75.0, 75.0))
+            .line_to((25.0, 75.0))
+            .close();
+
+        let triangles = fill(&flatten(&path, DEFAULT_TOLERANCE));
+
+        assert!((triangles_area(&triangles) - 7500.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn translucent_curves_have_uniform_alpha()
+    {
+        for line_join in [LineJoin::Miter, LineJoin::Bevel, LineJoin::Round] {
+            let style = StrokeStyle::new(8.0).with_line_join(line_join);
+
+            let image = render(120, 160, |graphics| {
+                let color = Color::from_rgba(0.0, 0.0, 0.0, 0.5);
+
+                graphics.stroke_path(
+                    &Path::from_circle((50.0, 50.0), 30.0),
+                    &style,
+                    color
+                );
+                graphics.stroke_path(
+                    &Path::new().move_to((10.0, 130.0)).cubic_to(
+                        (40.0, 90.0),
+                        (80.0, 170.0),
+                        (110.0, 130.0)
+                    ),
+                    &style,
+                    color
+                );
+            });
+
+            // Overlapping triangles would be blended twice, and darker
+            let darkest = image
+                .data()
+                .chunks_exact(4)
+                .map(|pixel| pixel[0])
+                .min()
+                .unwrap();
+
+            assert!(
+                (127..=128).contains(&darkest),
+                "{} with {:?}",
+                darkest,
+                line_join
+            );
+            assert!((127..=128).contains(&pixel(&image, 80, 50)[0]));
+            assert!((127..=128).contains(&pixel(&image, 60, 130)[0]));
+        }
+    }
+}
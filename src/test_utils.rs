@@ -0,0 +1,56 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::color::Color;
+use crate::image::{ImageDataType, RawBitmapData};
+use crate::{GLRenderer, Graphics2D};
+
+/// Draws a frame which is cleared to `background` and then drawn by
+/// `action`, returning the result of the action and the contents of the
+/// screen.
+pub(crate) fn render_frame<F: FnOnce(&mut Graphics2D) -> R, R>(
+    renderer: &mut GLRenderer,
+    background: Color,
+    action: F
+) -> (R, RawBitmapData)
+{
+    renderer.draw_frame(|graphics| {
+        graphics.clear_screen(background);
+        let result = action(graphics);
+        (result, graphics.capture(ImageDataType::RGBA))
+    })
+}
+
+/// Draws a single frame on a white background, using a new software renderer
+/// of the specified size.
+pub(crate) fn render<F: FnOnce(&mut Graphics2D)>(
+    width: u32,
+    height: u32,
+    action: F
+) -> RawBitmapData
+{
+    let mut renderer = GLRenderer::new_software((width, height)).unwrap();
+    render_frame(&mut renderer, Color::WHITE, action).1
+}
+
+/// Returns the RGBA components of a pixel in an image captured using
+/// [ImageDataType::RGBA].
+pub(crate) fn pixel(image: &RawBitmapData, x: u32, y: u32) -> [u8; 4]
+{
+    let start = ((y * image.size().x + x) * 4) as usize;
+    let data = &image.data()[start..start + 4];
+    [data[0], data[1], data[2], data[3]]
+}
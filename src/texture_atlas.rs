@@ -0,0 +1,446 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::dimen::{UVec2, Vec2};
+use crate::error::{BacktraceError, Context, ErrorMessage};
+use crate::image::{ImageDataType, ImageHandle, ImageOptions};
+use crate::renderer2d::Renderer2D;
+use crate::shape::{Rect, URect};
+use crate::texture_packer::TexturePacker;
+use crate::Graphics2D;
+
+/// A part of a larger image, returned when adding an image to a
+/// [TextureAtlas]. It can be drawn using [Graphics2D::draw_sub_image] and
+/// related functions, in the same way as an [ImageHandle].
+///
+/// Consecutive draws of sub-images on the same atlas page share a texture,
+/// and so are batched into a single draw call.
+///
+/// Note: this handle can only be used in the graphics context in which it was
+/// created.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SubImageHandle
+{
+    image: ImageHandle,
+    region: URect
+}
+
+impl SubImageHandle
+{
+    /// Returns the atlas page containing this sub-image.
+    #[inline]
+    pub fn image(&self) -> &ImageHandle
+    {
+        &self.image
+    }
+
+    /// Returns the area of the atlas page covered by this sub-image, in
+    /// pixels. This may be used as the source of a [crate::sprite::Sprite]
+    /// in a [crate::sprite::SpriteBatch] created for the page.
+    #[inline]
+    pub fn region(&self) -> &URect
+    {
+        &self.region
+    }
+
+    /// Returns the size of the sub-image in pixels.
+    #[inline]
+    pub fn size(&self) -> UVec2
+    {
+        self.region.size()
+    }
+
+    /// Returns the area of the atlas page covered by this sub-image, in
+    /// normalized texture coordinates from `0.0` to `1.0`.
+    pub fn texture_coords_normalized(&self) -> Rect
+    {
+        let page_size = self.image.size().into_f32();
+        let region = self.region.as_f32();
+
+        let normalize =
+            |point: &Vec2| Vec2::new(point.x / page_size.x, point.y / page_size.y);
+
+        Rect::new(
+            normalize(region.top_left()),
+            normalize(region.bottom_right())
+        )
+    }
+}
+
+#[derive(Debug)]
+struct TextureAtlasPage
+{
+    image: ImageHandle,
+    packer: TexturePacker
+}
+
+/// Packs many small images into one or more larger images, known as pages.
+///
+/// Drawing lots of separate small images requires switching textures
+/// between each one, which prevents them from being batched together. When
+/// the images are added to an atlas instead, each one is returned as a
+/// [SubImageHandle], and any sub-images on the same page can be drawn with a
+/// single draw call.
+///
+/// A new page is created when an image doesn't fit on the existing pages.
+/// Each image is surrounded by a one-pixel border containing a copy of its
+/// edges, so that neighbouring images don't bleed into each other when
+/// drawn with [crate::image::ImageSmoothingMode::Linear].
+///
+/// ```rust,no_run
+/// # use speedy2d::image::{ImageDataType, ImageSmoothingMode};
+/// # use speedy2d::texture_atlas::TextureAtlas;
+/// # use speedy2d::Graphics2D;
+/// # fn draw(graphics: &mut Graphics2D, icons: &[Vec<u8>]) {
+/// let mut atlas = TextureAtlas::new((1024, 1024), ImageSmoothingMode::Linear);
+///
+/// let icons: Vec<_> = icons
+///     .iter()
+///     .map(|pixels| {
+///         atlas
+///             .add_image(graphics, ImageDataType::RGBA, (32, 32), pixels)
+///             .unwrap()
+///     })
+///     .collect();
+///
+/// // All the icons are drawn with a single draw call
+/// for (i, icon) in icons.iter().enumerate() {
+///     graphics.draw_sub_image((i as f32 * 32.0, 0.0), icon);
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TextureAtlas
+{
+    page_size: UVec2,
+    options: ImageOptions,
+    pages: Vec<TextureAtlasPage>
+}
+
+impl TextureAtlas
+{
+    /// Creates a new, empty atlas. Pages are created on demand with the
+    /// specified size in pixels, using the specified options.
+    ///
+    /// The `options` may be an [crate::image::ImageSmoothingMode], or
+    /// [ImageOptions]. The wrap mode has no effect on sub-images.
+    ///
+    /// Mipmaps, including those used by
+    /// [crate::image::ImageSmoothingMode::Trilinear], aren't supported: the
+    /// smaller mipmap levels would blend neighbouring images together despite
+    /// the border. Adding an image to an atlas created with them returns an
+    /// error.
+    pub fn new(page_size: impl Into<UVec2>, options: impl Into<ImageOptions>) -> Self
+    {
+        TextureAtlas {
+            page_size: page_size.into(),
+            options: options.into(),
+            pages: Vec::new()
+        }
+    }
+
+    /// Returns the size of each page in pixels.
+    #[inline]
+    pub fn page_size(&self) -> UVec2
+    {
+        self.page_size
+    }
+
+    /// Returns the pages created so far, in the order they were created.
+    pub fn pages(&self) -> impl Iterator<Item = &ImageHandle>
+    {
+        self.pages.iter().map(|page| &page.image)
+    }
+
+    /// Adds an image to the atlas, creating a new page if there isn't
+    /// enough space on the existing ones. The data provided in the `data`
+    /// parameter must be in the format specified by `data_type`.
+    ///
+    /// An error is returned if the image is empty, is too large to fit on a
+    /// page along with its border, or if the atlas uses mipmaps.
+    pub fn add_image(
+        &mut self,
+        graphics: &mut Graphics2D,
+        data_type: ImageDataType,
+        size: impl Into<UVec2>,
+        data: &[u8]
+    ) -> Result<SubImageHandle, BacktraceError<ErrorMessage>>
+    {
+        let size = size.into();
+
+        if self.options.mipmaps() {
+            return Err(ErrorMessage::msg(
+                "Cannot add an image to an atlas which uses mipmaps"
+            ));
+        }
+
+        if size.x == 0 || size.y == 0 {
+            return Err(ErrorMessage::msg("Cannot add an empty image to an atlas"));
+        }
+
+        if size.x.saturating_add(2) > self.page_size.x
+            || size.y.saturating_add(2) > self.page_size.y
+        {
+            return Err(ErrorMessage::msg(format!(
+                "Image size {}x{} is too large for atlas page size {}x{}",
+                size.x, size.y, self.page_size.x, self.page_size.y
+            )));
+        }
+
+        Renderer2D::check_image_data_len(data_type, size, data)?;
+
+        // The space is allocated from a copy of the page's packer, so that it
+        // isn't lost if the upload fails
+        let existing = self.pages.iter().enumerate().find_map(|(index, page)| {
+            let mut packer = page.packer.clone();
+
+            packer
+                .try_allocate(size)
+                .ok()
+                .map(|region| (index, packer, region))
+        });
+
+        match existing {
+            Some((index, packer, region)) => {
+                let page = &mut self.pages[index];
+                Self::upload(graphics, &page.image, data_type, &region, data)?;
+                page.packer = packer;
+
+                Ok(SubImageHandle {
+                    image: page.image.clone(),
+                    region
+                })
+            }
+
+            None => {
+                let mut page = self.create_page(graphics)?;
+
+                let region = page
+                    .packer
+                    .try_allocate(size)
+                    .map_err(|_| ErrorMessage::msg("Image does not fit on a new page"))?;
+
+                Self::upload(graphics, &page.image, data_type, &region, data)?;
+
+                let image = page.image.clone();
+                self.pages.push(page);
+
+                Ok(SubImageHandle { image, region })
+            }
+        }
+    }
+
+    /// Uploads the image, surrounded by its border, into the specified
+    /// region of a page.
+    fn upload(
+        graphics: &mut Graphics2D,
+        page: &ImageHandle,
+        data_type: ImageDataType,
+        region: &URect,
+        data: &[u8]
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        let bordered_region = URect::new(
+            region.top_left() - UVec2::new(1, 1),
+            region.bottom_right() + UVec2::new(1, 1)
+        );
+
+        graphics
+            .update_image_region(
+                page,
+                data_type,
+                &bordered_region,
+                &Self::add_border(region.size(), data_type.bytes_per_pixel(), data)
+            )
+            .context("Failed to upload image to atlas page")
+    }
+
+    fn create_page(
+        &self,
+        graphics: &mut Graphics2D
+    ) -> Result<TextureAtlasPage, BacktraceError<ErrorMessage>>
+    {
+        let transparent =
+            vec![0; self.page_size.x as usize * self.page_size.y as usize * 4];
+
+        let image = graphics
+            .create_image_from_raw_pixels(
+                ImageDataType::RGBA,
+                self.options,
+                self.page_size,
+                &transparent
+            )
+            .context("Failed to create atlas page")?;
+
+        Ok(TextureAtlasPage {
+            image,
+            packer: TexturePacker::new(self.page_size.x, self.page_size.y)
+        })
+    }
+
+    /// Returns a copy of the image surrounded by a one-pixel border, which
+    /// repeats the pixels at the edge of the image.
+    fn add_border(size: UVec2, pixel_bytes: usize, data: &[u8]) -> Vec<u8>
+    {
+        let width = size.x as usize;
+        let height = size.y as usize;
+        let row_bytes = width * pixel_bytes;
+
+        let mut result = Vec::with_capacity((width + 2) * (height + 2) * pixel_bytes);
+
+        let mut push_row = |row: &[u8]| {
+            result.extend_from_slice(&row[..pixel_bytes]);
+            result.extend_from_slice(row);
+            result.extend_from_slice(&row[row_bytes - pixel_bytes..]);
+        };
+
+        push_row(&data[..row_bytes]);
+
+        for row in data.chunks_exact(row_bytes) {
+            push_row(row);
+        }
+
+        push_row(&data[data.len() - row_bytes..]);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::color::Color;
+    use crate::image::ImageSmoothingMode;
+    use crate::test_utils::{pixel, render_frame};
+    use crate::GLRenderer;
+
+    #[test]
+    fn add_border()
+    {
+        let data = [1, 2, 3, 4, 5, 6];
+        let result = TextureAtlas::add_border(UVec2::new(3, 2), 1, &data);
+
+        assert_eq!(
+            vec![
+                1, 1, 2, 3, 3, //
+                1, 1, 2, 3, 3, //
+                4, 4, 5, 6, 6, //
+                4, 4, 5, 6, 6
+            ],
+            result
+        );
+    }
+
+    #[test]
+    fn pack_and_draw()
+    {
+        let mut renderer = GLRenderer::new_software((200, 100)).unwrap();
+        let mut atlas = TextureAtlas::new((64, 64), ImageSmoothingMode::NearestNeighbor);
+
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+        let (images, pixels) = render_frame(&mut renderer, Color::WHITE, |graphics| {
+            let images: Vec<SubImageHandle> = (0..12)
+                .map(|i| {
+                    atlas
+                        .add_image(
+                            graphics,
+                            ImageDataType::RGBA,
+                            (20, 20),
+                            &colors[i % 3].repeat(400)
+                        )
+                        .unwrap()
+                })
+                .collect();
+
+            for (i, image) in images.iter().enumerate() {
+                graphics.draw_sub_image((i as f32 * 10.0, 0.0), image);
+            }
+
+            images
+        });
+
+        // Only four 22x22 images fit on each page
+        assert_eq!(3, atlas.pages().count());
+        assert_eq!(images[0].image(), images[3].image());
+        assert_ne!(images[0].image(), images[4].image());
+        assert_eq!(UVec2::new(20, 20), images[5].size());
+
+        // Each image is partly covered by the next one
+        assert_eq!(colors[0], pixel(&pixels, 5, 10));
+        assert_eq!(colors[1], pixel(&pixels, 15, 10));
+        assert_eq!(colors[2], pixel(&pixels, 25, 10));
+        assert_eq!(colors[2], pixel(&pixels, 125, 10));
+        assert_eq!([255, 255, 255, 255], pixel(&pixels, 5, 50));
+
+        renderer.draw_frame(|graphics| {
+            // One draw call for each page
+            assert_eq!(3, graphics.last_frame_statistics().draw_calls());
+        });
+    }
+
+    #[test]
+    fn errors()
+    {
+        let mut renderer = GLRenderer::new_software((100, 100)).unwrap();
+        let mut atlas = TextureAtlas::new((32, 32), ImageSmoothingMode::Linear);
+
+        renderer.draw_frame(|graphics| {
+            assert!(atlas
+                .add_image(graphics, ImageDataType::RGBA, (31, 2), &[0; 248])
+                .is_err());
+
+            assert!(atlas
+                .add_image(graphics, ImageDataType::RGBA, (0, 2), &[])
+                .is_err());
+
+            assert!(atlas
+                .add_image(graphics, ImageDataType::RGB, (2, 2), &[0; 16])
+                .is_err());
+
+            assert!(atlas
+                .add_image(graphics, ImageDataType::RGBA, (u32::MAX, 2), &[])
+                .is_err());
+
+            let image = atlas
+                .add_image(graphics, ImageDataType::RGB, (30, 30), &[0; 2700])
+                .unwrap();
+
+            assert_eq!(
+                Rect::from_tuples((1.0 / 32.0, 1.0 / 32.0), (31.0 / 32.0, 31.0 / 32.0)),
+                image.texture_coords_normalized()
+            );
+        });
+
+        assert_eq!(1, atlas.pages().count());
+    }
+
+    #[test]
+    fn mipmaps_are_rejected()
+    {
+        let mut renderer = GLRenderer::new_software((100, 100)).unwrap();
+        let mut atlas = TextureAtlas::new((32, 32), ImageSmoothingMode::Trilinear);
+
+        renderer.draw_frame(|graphics| {
+            assert!(atlas
+                .add_image(graphics, ImageDataType::RGBA, (2, 2), &[0; 16])
+                .is_err());
+        });
+
+        assert_eq!(0, atlas.pages().count());
+    }
+}
@@ -18,7 +18,7 @@ use crate::dimen::UVec2;
 use crate::shape::Rectangle;
 use crate::texture_packer::TexturePackerError::NotEnoughSpace;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct FreeRegion
 {
     rect: Rectangle<u32>
@@ -45,7 +45,7 @@ pub(crate) enum TexturePackerError
     NotEnoughSpace
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct TexturePacker
 {
     areas: Vec<FreeRegion>
@@ -0,0 +1,250 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::dimen::Vec2;
+
+/// A 2D affine transformation, such as a translation, rotation, scale, or
+/// skew, or any combination of these.
+///
+/// Transformations can be combined using [Transform::then]. For example, to
+/// rotate around the point `(100, 100)`:
+///
+/// ```rust
+/// use speedy2d::dimen::Vec2;
+/// use speedy2d::transform::Transform;
+///
+/// let transform = Transform::translate((-100.0, -100.0))
+///     .then(&Transform::rotate(std::f32::consts::FRAC_PI_2))
+///     .then(&Transform::translate((100.0, 100.0)));
+///
+/// let point = transform.transform_point((200.0, 100.0));
+///
+/// assert!((point - Vec2::new(100.0, 200.0)).magnitude() < 0.001);
+/// ```
+///
+/// Angles are specified in radians, and positive angles rotate clockwise on
+/// the screen.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Transform
+{
+    // The matrix is:
+    //
+    // | a c e |
+    // | b d f |
+    // | 0 0 1 |
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32
+}
+
+impl Default for Transform
+{
+    fn default() -> Self
+    {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform
+{
+    /// The identity transformation, which leaves all points unchanged.
+    pub const IDENTITY: Transform = Transform::from_matrix(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    /// Creates a transformation from the components of a 3x2 matrix. A point
+    /// `(x, y)` is transformed to `(a*x + c*y + e, b*x + d*y + f)`.
+    #[inline]
+    pub const fn from_matrix(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self
+    {
+        Transform { a, b, c, d, e, f }
+    }
+
+    /// Creates a transformation which moves points by the specified offset.
+    #[inline]
+    pub fn translate(offset: impl Into<Vec2>) -> Self
+    {
+        let offset = offset.into();
+        Transform::from_matrix(1.0, 0.0, 0.0, 1.0, offset.x, offset.y)
+    }
+
+    /// Creates a transformation which rotates points clockwise around the
+    /// origin by the specified angle in radians.
+    #[inline]
+    pub fn rotate(angle: f32) -> Self
+    {
+        let (sin, cos) = angle.sin_cos();
+        Transform::from_matrix(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// Creates a transformation which rotates points clockwise around
+    /// `center` by the specified angle in radians.
+    #[inline]
+    pub fn rotate_around(angle: f32, center: impl Into<Vec2>) -> Self
+    {
+        let center = center.into();
+
+        Transform::translate(center * -1.0)
+            .then(&Transform::rotate(angle))
+            .then(&Transform::translate(center))
+    }
+
+    /// Creates a transformation which scales points away from the origin, by
+    /// the specified horizontal and vertical factors.
+    #[inline]
+    pub fn scale(factor: impl Into<Vec2>) -> Self
+    {
+        let factor = factor.into();
+        Transform::from_matrix(factor.x, 0.0, 0.0, factor.y, 0.0, 0.0)
+    }
+
+    /// Creates a transformation which scales points away from the origin
+    /// equally in both directions.
+    #[inline]
+    pub fn scale_uniform(factor: f32) -> Self
+    {
+        Transform::scale((factor, factor))
+    }
+
+    /// Creates a skew (shear) transformation. Vertical lines are tilted by
+    /// `x_angle`, and horizontal lines are tilted by `y_angle`. Both angles
+    /// are in radians.
+    #[inline]
+    pub fn skew(x_angle: f32, y_angle: f32) -> Self
+    {
+        Transform::from_matrix(1.0, y_angle.tan(), x_angle.tan(), 1.0, 0.0, 0.0)
+    }
+
+    /// Returns a transformation which applies `self`, followed by `next`.
+    #[inline]
+    #[must_use]
+    pub fn then(&self, next: &Transform) -> Transform
+    {
+        Transform {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f
+        }
+    }
+
+    /// Returns the inverse of this transformation, or `None` if it cannot be
+    /// inverted (for example, if it scales by zero).
+    pub fn inverse(&self) -> Option<Transform>
+    {
+        let determinant = self.a * self.d - self.b * self.c;
+
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let a = self.d / determinant;
+        let b = -self.b / determinant;
+        let c = -self.c / determinant;
+        let d = self.a / determinant;
+
+        Some(Transform {
+            a,
+            b,
+            c,
+            d,
+            e: -(a * self.e + c * self.f),
+            f: -(b * self.e + d * self.f)
+        })
+    }
+
+    /// Applies this transformation to the specified point.
+    #[inline]
+    pub fn transform_point(&self, point: impl Into<Vec2>) -> Vec2
+    {
+        let point = point.into();
+
+        Vec2::new(
+            self.a * point.x + self.c * point.y + self.e,
+            self.b * point.x + self.d * point.y + self.f
+        )
+    }
+
+    /// Returns true if this transformation leaves all points unchanged.
+    #[inline]
+    pub fn is_identity(&self) -> bool
+    {
+        *self == Transform::IDENTITY
+    }
+
+    /// Returns the components of the 3x2 matrix for this transformation, in
+    /// the same order as [Transform::from_matrix].
+    #[inline]
+    pub fn to_matrix(&self) -> [f32; 6]
+    {
+        [self.a, self.b, self.c, self.d, self.e, self.f]
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    fn assert_near(expected: Vec2, actual: Vec2)
+    {
+        assert!(
+            (expected - actual).magnitude() < 0.0001,
+            "Expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn combine_transforms()
+    {
+        let transform = Transform::scale((2.0, 3.0))
+            .then(&Transform::rotate(std::f32::consts::FRAC_PI_2))
+            .then(&Transform::translate((10.0, 20.0)));
+
+        // (1, 1) -> (2, 3) -> (-3, 2) -> (7, 22)
+        assert_near(Vec2::new(7.0, 22.0), transform.transform_point((1.0, 1.0)));
+
+        let around = Transform::rotate_around(std::f32::consts::PI, (5.0, 5.0));
+        assert_near(Vec2::new(10.0, 10.0), around.transform_point((0.0, 0.0)));
+
+        let skew = Transform::skew(std::f32::consts::FRAC_PI_4, 0.0);
+        assert_near(Vec2::new(3.0, 2.0), skew.transform_point((1.0, 2.0)));
+    }
+
+    #[test]
+    fn inverse()
+    {
+        let transform = Transform::skew(0.3, 0.1)
+            .then(&Transform::scale((2.0, 0.5)))
+            .then(&Transform::rotate(1.0))
+            .then(&Transform::translate((-7.0, 3.0)));
+
+        let inverse = transform.inverse().unwrap();
+        let point = Vec2::new(12.0, -4.0);
+
+        assert_near(
+            point,
+            inverse.transform_point(transform.transform_point(point))
+        );
+
+        assert_eq!(None, Transform::scale((0.0, 1.0)).inverse());
+    }
+}
@@ -0,0 +1,280 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::color::Color;
+use crate::dimen::{UVec2, Vec2};
+use crate::shape::Rect;
+use crate::transform::Transform;
+
+/// Describes how a [VirtualResolution] is scaled to fill the window.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ScalingMode
+{
+    /// The logical area is stretched to fill the whole window. The aspect
+    /// ratio is not preserved.
+    Stretch,
+
+    /// The logical area is scaled as much as possible while preserving the
+    /// aspect ratio, and centered in the window. Any remaining space is
+    /// filled with letterbox bars.
+    Fit,
+
+    /// Like [ScalingMode::Fit], but the scale factor is always a whole
+    /// number, so that each logical pixel covers the same number of window
+    /// pixels. This is useful for pixel art.
+    ///
+    /// If the window is smaller than the logical area, this falls back to
+    /// [ScalingMode::Fit].
+    IntegerScale
+}
+
+/// A logical coordinate space which is scaled to fit the window, regardless
+/// of the window's actual size. This allows a game to be written for a fixed
+/// resolution, such as 320x180.
+///
+/// Set this using [crate::Graphics2D::set_virtual_resolution] or
+/// [crate::GLRenderer::set_virtual_resolution]. To convert mouse positions
+/// from window pixels into logical coordinates, use
+/// [VirtualResolution::window_to_logical].
+///
+/// ```rust
+/// use speedy2d::dimen::Vec2;
+/// use speedy2d::viewport::{ScalingMode, VirtualResolution};
+///
+/// let resolution = VirtualResolution::new((320.0, 180.0), ScalingMode::Fit);
+///
+/// // A 1280x1024 window is scaled by 4x, with bars above and below
+/// let position = resolution.window_to_logical((1280, 1024), (640.0, 512.0));
+///
+/// assert_eq!(Vec2::new(160.0, 90.0), position);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct VirtualResolution
+{
+    logical_size: Vec2,
+    scaling_mode: ScalingMode,
+    letterbox_color: Color
+}
+
+impl VirtualResolution
+{
+    /// Creates a logical coordinate space of the specified size, which is
+    /// scaled to the window using `scaling_mode`. Letterbox bars are black by
+    /// default.
+    #[inline]
+    #[must_use]
+    pub fn new(logical_size: impl Into<Vec2>, scaling_mode: ScalingMode) -> Self
+    {
+        VirtualResolution {
+            logical_size: logical_size.into(),
+            scaling_mode,
+            letterbox_color: Color::BLACK
+        }
+    }
+
+    /// Sets the color of the letterbox bars, which fill any part of the
+    /// window outside the logical area.
+    #[inline]
+    #[must_use]
+    pub fn with_letterbox_color(mut self, color: Color) -> Self
+    {
+        self.letterbox_color = color;
+        self
+    }
+
+    /// Returns the size of the logical coordinate space.
+    #[inline]
+    pub fn logical_size(&self) -> Vec2
+    {
+        self.logical_size
+    }
+
+    /// Returns the scaling mode.
+    #[inline]
+    pub fn scaling_mode(&self) -> ScalingMode
+    {
+        self.scaling_mode
+    }
+
+    /// Returns the color of the letterbox bars.
+    #[inline]
+    pub fn letterbox_color(&self) -> Color
+    {
+        self.letterbox_color
+    }
+
+    fn scale_and_offset(&self, window_size: UVec2) -> (Vec2, Vec2)
+    {
+        let window_size = window_size.into_f32();
+
+        if self.logical_size.x <= 0.0 || self.logical_size.y <= 0.0 {
+            return (Vec2::new(1.0, 1.0), Vec2::ZERO);
+        }
+
+        let stretch = Vec2::new(
+            window_size.x / self.logical_size.x,
+            window_size.y / self.logical_size.y
+        );
+
+        let fit = stretch.x.min(stretch.y);
+
+        let scale = match self.scaling_mode {
+            ScalingMode::Stretch => return (stretch, Vec2::ZERO),
+            ScalingMode::Fit => fit,
+            ScalingMode::IntegerScale if fit >= 1.0 => fit.floor(),
+            ScalingMode::IntegerScale => fit
+        };
+
+        // Snap the offset to whole pixels, to keep edges sharp
+        let offset = (window_size - self.logical_size * scale) / 2.0;
+
+        (
+            Vec2::new(scale, scale),
+            Vec2::new(offset.x.floor(), offset.y.floor())
+        )
+    }
+
+    /// Returns the transformation from logical coordinates to window pixels,
+    /// for a window of the specified size.
+    pub fn transform_for_window_size(&self, window_size: impl Into<UVec2>) -> Transform
+    {
+        let (scale, offset) = self.scale_and_offset(window_size.into());
+        Transform::scale(scale).then(&Transform::translate(offset))
+    }
+
+    /// Returns the area of the window, in pixels, which is covered by the
+    /// logical coordinate space. Everything outside this area is covered by
+    /// letterbox bars.
+    pub fn viewport_for_window_size(&self, window_size: impl Into<UVec2>) -> Rect
+    {
+        let (scale, offset) = self.scale_and_offset(window_size.into());
+
+        Rect::new(
+            offset,
+            offset
+                + Vec2::new(self.logical_size.x * scale.x, self.logical_size.y * scale.y)
+        )
+    }
+
+    /// Converts a position in window pixels (such as the mouse position) into
+    /// logical coordinates. The result may lie outside the logical area if
+    /// the position is on a letterbox bar.
+    ///
+    /// If the window has zero width or height (for example, when it is
+    /// minimized), the origin is returned.
+    pub fn window_to_logical(
+        &self,
+        window_size: impl Into<UVec2>,
+        position: impl Into<Vec2>
+    ) -> Vec2
+    {
+        let (scale, offset) = self.scale_and_offset(window_size.into());
+
+        if scale.x <= 0.0 || scale.y <= 0.0 {
+            return Vec2::ZERO;
+        }
+
+        let position = position.into() - offset;
+
+        Vec2::new(position.x / scale.x, position.y / scale.y)
+    }
+
+    /// Converts a position in logical coordinates into window pixels.
+    pub fn logical_to_window(
+        &self,
+        window_size: impl Into<UVec2>,
+        position: impl Into<Vec2>
+    ) -> Vec2
+    {
+        self.transform_for_window_size(window_size)
+            .transform_point(position)
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn scaling_modes()
+    {
+        let window = UVec2::new(1000, 500);
+
+        let stretch = VirtualResolution::new((100.0, 100.0), ScalingMode::Stretch);
+
+        assert_eq!(
+            Rect::from_tuples((0.0, 0.0), (1000.0, 500.0)),
+            stretch.viewport_for_window_size(window)
+        );
+
+        let fit = VirtualResolution::new((100.0, 100.0), ScalingMode::Fit);
+
+        assert_eq!(
+            Rect::from_tuples((250.0, 0.0), (750.0, 500.0)),
+            fit.viewport_for_window_size(window)
+        );
+
+        let integer = VirtualResolution::new((120.0, 120.0), ScalingMode::IntegerScale);
+
+        assert_eq!(
+            Rect::from_tuples((260.0, 10.0), (740.0, 490.0)),
+            integer.viewport_for_window_size(window)
+        );
+
+        assert_eq!(
+            Rect::from_tuples((10.0, 10.0), (490.0, 490.0)),
+            integer.viewport_for_window_size((500, 500))
+        );
+
+        let small = integer.viewport_for_window_size((60, 80));
+        assert_eq!(Vec2::new(60.0, 60.0), small.size());
+    }
+
+    #[test]
+    fn window_to_logical()
+    {
+        let window = UVec2::new(1000, 500);
+        let fit = VirtualResolution::new((100.0, 100.0), ScalingMode::Fit);
+
+        assert_eq!(
+            Vec2::new(0.0, 0.0),
+            fit.window_to_logical(window, (250.0, 0.0))
+        );
+        assert_eq!(
+            Vec2::new(50.0, 100.0),
+            fit.window_to_logical(window, (500.0, 500.0))
+        );
+        assert_eq!(
+            Vec2::new(-10.0, 50.0),
+            fit.window_to_logical(window, (200.0, 250.0))
+        );
+
+        let point = Vec2::new(33.0, 71.0);
+        let stretch = VirtualResolution::new((100.0, 100.0), ScalingMode::Stretch);
+
+        assert_eq!(
+            point,
+            stretch.window_to_logical(window, stretch.logical_to_window(window, point))
+        );
+
+        assert_eq!(Vec2::ZERO, fit.window_to_logical((0, 0), (10.0, 10.0)));
+        assert_eq!(
+            Vec2::ZERO,
+            stretch.window_to_logical((0, 500), (10.0, 10.0))
+        );
+    }
+}
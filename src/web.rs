@@ -472,9 +472,13 @@ impl WebCanvasElement
             viewport_size_pixels
         );
 
+        // The stencil buffer is used for clipping to arbitrary shapes
+        let attributes = web_sys::WebGlContextAttributes::new();
+        attributes.set_stencil(true);
+
         let context = self
             .canvas
-            .get_context("webgl2")
+            .get_context_with_context_options("webgl2", &attributes)
             .map_err(|err| {
                 GLRendererCreationError::msg(format!(
                     "Failed to get WebGL2 context: '{err:?}'"
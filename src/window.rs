@@ -17,6 +17,7 @@
 use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
 
+use crate::debug_overlay::DebugOverlay;
 use crate::dimen::{IVec2, UVec2, Vec2};
 use crate::error::{BacktraceError, ErrorMessage};
 use crate::{GLRenderer, Graphics2D};
@@ -411,7 +412,13 @@ where
         let renderer = &mut self.renderer;
         let window_handler = &mut self.window_handler;
 
-        renderer.draw_frame(|graphics| window_handler.on_draw(helper, graphics))
+        renderer.draw_frame(|graphics| {
+            window_handler.on_draw(helper, graphics);
+
+            if let Some(overlay) = &mut helper.debug_overlay {
+                overlay.draw(graphics);
+            }
+        })
     }
 
     #[inline]
@@ -506,14 +513,18 @@ pub struct WindowHelper<UserEventType = ()>
 where
     UserEventType: 'static
 {
-    inner: WindowHelperInnerType<UserEventType>
+    inner: WindowHelperInnerType<UserEventType>,
+    debug_overlay: Option<DebugOverlay>
 }
 
 impl<UserEventType> WindowHelper<UserEventType>
 {
     pub(crate) fn new(inner: WindowHelperInnerType<UserEventType>) -> Self
     {
-        WindowHelper { inner }
+        WindowHelper {
+            inner,
+            debug_overlay: None
+        }
     }
 
     #[inline]
@@ -674,6 +685,23 @@ impl<UserEventType> WindowHelper<UserEventType>
     {
         self.inner.create_user_event_sender()
     }
+
+    /// Enables a [DebugOverlay], which is drawn on top of everything else at
+    /// the end of each call to [WindowHandler::on_draw]. Pass `None` to
+    /// disable the overlay.
+    ///
+    /// To get an accurate frame rate, [WindowHelper::request_redraw] should
+    /// be called on every frame.
+    pub fn set_debug_overlay(&mut self, overlay: Option<DebugOverlay>)
+    {
+        self.debug_overlay = overlay;
+    }
+
+    /// Returns the active [DebugOverlay], if any.
+    pub fn debug_overlay(&self) -> Option<&DebugOverlay>
+    {
+        self.debug_overlay.as_ref()
+    }
 }
 
 #[cfg(any(doc, doctest, not(target_arch = "wasm32")))]
@@ -711,7 +711,8 @@ fn create_best_context<UserEventType>(
     for multisampling in &[options.multisampling, 16, 8, 4, 2, 1, 0] {
         log::info!("Trying multisampling={}...", multisampling);
 
-        let mut template = ConfigTemplateBuilder::new();
+        // The stencil buffer is used for clipping to arbitrary shapes
+        let mut template = ConfigTemplateBuilder::new().with_stencil_size(8);
 
         if *multisampling > 1 {
             template = template.with_multisampling(
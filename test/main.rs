@@ -20,19 +20,8 @@
 compile_error!("The automated tests currently support Linux x86_64 only");
 
 use std::convert::TryInto;
-use std::ffi::CString;
 use std::sync::mpsc::channel;
 
-use glutin::config::ConfigTemplateBuilder;
-use glutin::context::{
-    ContextApi,
-    ContextAttributesBuilder,
-    NotCurrentGlContext,
-    Version
-};
-use glutin::display::{GetGlDisplay, GlDisplay};
-use glutin::surface::{PbufferSurface, SurfaceAttributesBuilder};
-use glutin_winit::DisplayBuilder;
 use image::{ColorType, GenericImageView, ImageFormat};
 use num_traits::ToPrimitive;
 use speedy2d::color::Color;
@@ -41,7 +30,6 @@ use speedy2d::font::{Font, TextAlignment, TextLayout, TextOptions};
 use speedy2d::image::{ImageDataType, ImageSmoothingMode};
 use speedy2d::shape::{Polygon, Rect, Rectangle};
 use speedy2d::GLRenderer;
-use winit::event_loop::EventLoop;
 
 const NOTO_SANS_REGULAR_BYTES: &[u8] =
     include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
@@ -77,53 +65,7 @@ fn write_framebuffer_to_png<S: AsRef<str>>(name: S, width: u32, height: u32, dat
     write_rgba_to_png(name, width, height, data);
 }
 
-fn create_context_and_run<R, F>(
-    event_loop: &EventLoop<()>,
-    width: u32,
-    height: u32,
-    action: F
-) -> R
-where
-    F: FnOnce(&mut GLRenderer) -> R
-{
-    let (_window, config) = DisplayBuilder::new()
-        .build(event_loop, ConfigTemplateBuilder::new(), |mut configs| {
-            configs.next().unwrap()
-        })
-        .unwrap();
-
-    let gl_display = config.display();
-
-    let context_attr = ContextAttributesBuilder::new()
-        .with_context_api(ContextApi::OpenGl(Some(Version::new(2, 0))))
-        .build(None);
-
-    let context = unsafe { gl_display.create_context(&config, &context_attr) }.unwrap();
-
-    let surface = unsafe {
-        config.display().create_pbuffer_surface(
-            &config,
-            &SurfaceAttributesBuilder::<PbufferSurface>::new()
-                .build(width.try_into().unwrap(), height.try_into().unwrap())
-        )
-    }
-    .unwrap();
-
-    let _context = context.make_current(&surface).unwrap();
-
-    let mut renderer = unsafe {
-        GLRenderer::new_for_gl_context((width, height), |name| {
-            gl_display.get_proc_address(CString::new(name).unwrap().as_c_str())
-                as *const _
-        })
-        .unwrap()
-    };
-
-    action(&mut renderer)
-}
-
 fn run_test_with_new_context<S: AsRef<str>, F: FnOnce(&mut GLRenderer)>(
-    event_loop: &EventLoop<()>,
     expected_image_name: S,
     width: u32,
     height: u32,
@@ -132,25 +74,23 @@ fn run_test_with_new_context<S: AsRef<str>, F: FnOnce(&mut GLRenderer)>(
 {
     let expected_image = read_png_argb8(expected_image_name.as_ref());
 
-    let actual_image = create_context_and_run(event_loop, width, height, |renderer| {
-        action(renderer);
+    let mut renderer = GLRenderer::new_headless((width, height)).unwrap();
 
-        let actual_image =
-            renderer.draw_frame(|graphics| graphics.capture(ImageDataType::RGBA));
+    action(&mut renderer);
 
-        if expected_image.is_none()
-            || (&expected_image).as_ref().unwrap() != actual_image.data()
-        {
-            write_framebuffer_to_png(
-                format!("{}_ACTUAL", expected_image_name.as_ref()),
-                width,
-                height,
-                actual_image.data().as_slice()
-            );
-        }
+    let actual_image =
+        renderer.draw_frame(|graphics| graphics.capture(ImageDataType::RGBA));
 
-        actual_image
-    });
+    if expected_image.is_none()
+        || (&expected_image).as_ref().unwrap() != actual_image.data()
+    {
+        write_framebuffer_to_png(
+            format!("{}_ACTUAL", expected_image_name.as_ref()),
+            width,
+            height,
+            actual_image.data().as_slice()
+        );
+    }
 
     assert!(expected_image.is_some(), "Expected image does not exist");
 
@@ -188,8 +128,6 @@ fn main()
 {
     simple_logger::SimpleLogger::new().init().unwrap();
 
-    let event_loop = EventLoop::new().unwrap();
-
     let mut tests = Vec::new();
 
     tests.push(GLTest {
@@ -1317,13 +1255,7 @@ fn main()
     for test in tests {
         log::info!("Running test {}", test.name);
 
-        run_test_with_new_context(
-            &event_loop,
-            test.name,
-            test.width,
-            test.height,
-            test.action
-        );
+        run_test_with_new_context(test.name, test.width, test.height, test.action);
     }
 
     log::info!("All tests succeeded");